@@ -0,0 +1,430 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use anyhow::Result;
+use anyhow::anyhow;
+use codex_common::CliConfigOverrides;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::config::find_codex_home;
+use codex_core::config::find_project_config;
+use codex_core::config::load_config_as_toml;
+use codex_core::config::load_layered_config_as_toml;
+use codex_core::config_check::ConfigFile;
+use codex_core::config_check::check_config_files;
+use codex_core::config_check::config_toml_schema;
+use codex_core::protocol::SandboxPolicy;
+use serde::Serialize;
+use toml::Table;
+use toml::Value as TomlValue;
+
+/// Inspect and validate Codex configuration.
+///
+/// Subcommands:
+/// - `check`  — report unknown keys, type errors and undefined references
+/// - `show`   — print the merged config files, or the effective configuration
+/// - `schema` — print the JSON Schema of `config.toml`
+#[derive(Debug, clap::Parser)]
+pub struct ConfigCli {
+    #[clap(flatten)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub cmd: ConfigSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum ConfigSubcommand {
+    /// Check `~/.codex/config.toml` and the project config for unknown keys,
+    /// values of the wrong type and undefined profiles or model providers.
+    Check,
+
+    /// Print the configuration.
+    Show(ShowArgs),
+
+    /// Print the JSON Schema of `config.toml`, for editor completion.
+    Schema,
+}
+
+#[derive(Debug, clap::Parser)]
+pub struct ShowArgs {
+    /// Print the fully resolved configuration, defaults included, instead of
+    /// the merged config files.
+    #[arg(long)]
+    pub effective: bool,
+
+    /// Configuration profile to resolve.
+    #[arg(long, short = 'p', requires = "effective")]
+    pub profile: Option<String>,
+}
+
+impl ConfigCli {
+    /// Runs the subcommand and returns the exit code for the process: 1 when
+    /// `check` found problems, 0 otherwise.
+    pub fn run(self) -> Result<i32> {
+        let ConfigCli {
+            config_overrides,
+            cmd,
+        } = self;
+
+        match cmd {
+            ConfigSubcommand::Check => return run_check(),
+            ConfigSubcommand::Show(args) => run_show(&config_overrides, args)?,
+            ConfigSubcommand::Schema => {
+                let output = serde_json::to_string_pretty(&config_toml_schema())?;
+                println!("{output}");
+            }
+        }
+
+        Ok(0)
+    }
+}
+
+fn run_check() -> Result<i32> {
+    let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+    let cwd = std::env::current_dir()?;
+
+    let mut files = Vec::new();
+    let user_config_path = codex_home.join("config.toml");
+    if user_config_path.is_file() {
+        files.push(read_config_file(user_config_path)?);
+    }
+    // A broken user config is reported by the check itself; it only means the
+    // project cannot be trusted.
+    let user_config =
+        load_config_as_toml(&codex_home).unwrap_or_else(|_| TomlValue::Table(Table::new()));
+    if let Some((project_config_path, trusted)) = find_project_config(&user_config, &cwd) {
+        if !trusted {
+            println!(
+                "note: {} is ignored because the project is not trusted",
+                project_config_path.display()
+            );
+        }
+        files.push(read_config_file(project_config_path)?);
+    }
+
+    if files.is_empty() {
+        println!("No config files found.");
+        return Ok(0);
+    }
+
+    let diagnostics = check_config_files(&files);
+    if diagnostics.is_empty() {
+        for file in &files {
+            println!("{}: ok", file.path.display());
+        }
+        return Ok(0);
+    }
+    for diagnostic in &diagnostics {
+        println!("{diagnostic}");
+    }
+    Ok(1)
+}
+
+fn read_config_file(path: PathBuf) -> Result<ConfigFile> {
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("failed to read {}", path.display()))?;
+    Ok(ConfigFile { path, contents })
+}
+
+fn run_show(config_overrides: &CliConfigOverrides, show_args: ShowArgs) -> Result<()> {
+    let overrides = config_overrides.parse_overrides().map_err(|e| anyhow!(e))?;
+
+    let table = if show_args.effective {
+        let config = Config::load_with_cli_overrides(
+            overrides,
+            ConfigOverrides {
+                config_profile: show_args.profile,
+                ..Default::default()
+            },
+        )
+        .context("failed to load configuration")?;
+        effective_config_toml(&config)?
+    } else {
+        let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
+        let cwd = std::env::current_dir()?;
+        let (merged, _) = load_layered_config_as_toml(&codex_home, &cwd, overrides)
+            .context("failed to load configuration")?;
+        match merged {
+            TomlValue::Table(table) => table,
+            _ => Table::new(),
+        }
+    };
+
+    print!("{}", toml::to_string_pretty(&table)?);
+    Ok(())
+}
+
+/// Renders the resolved `config` with the same keys as `config.toml`.
+fn effective_config_toml(config: &Config) -> Result<Table> {
+    let mut table = Table::new();
+    insert(&mut table, "model", &config.model)?;
+    insert(&mut table, "review_model", &config.review_model)?;
+    insert(&mut table, "model_provider", &config.model_provider_id)?;
+    if let Some(profile) = &config.active_profile {
+        insert(&mut table, "profile", profile)?;
+    }
+    if let Some(window) = config.model_context_window {
+        insert(&mut table, "model_context_window", window)?;
+    }
+    if let Some(max_output_tokens) = config.model_max_output_tokens {
+        insert(&mut table, "model_max_output_tokens", max_output_tokens)?;
+    }
+    if let Some(limit) = config.model_auto_compact_token_limit {
+        insert(&mut table, "model_auto_compact_token_limit", limit)?;
+    }
+    insert(&mut table, "compaction", &config.compaction)?;
+    insert(
+        &mut table,
+        "compaction_keep_recent_turns",
+        config.compaction_keep_recent_turns,
+    )?;
+    insert(&mut table, "fallback", &config.fallback)?;
+    insert(&mut table, "approval_policy", config.approval_policy)?;
+
+    let sandbox_mode = match &config.sandbox_policy {
+        SandboxPolicy::DangerFullAccess => "danger-full-access",
+        SandboxPolicy::ReadOnly => "read-only",
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots,
+            network_access,
+            exclude_tmpdir_env_var,
+            exclude_slash_tmp,
+            readable_roots,
+            allowed_domains,
+        } => {
+            let mut workspace_write = Table::new();
+            insert(&mut workspace_write, "writable_roots", writable_roots)?;
+            insert(&mut workspace_write, "network_access", network_access)?;
+            insert(
+                &mut workspace_write,
+                "exclude_tmpdir_env_var",
+                exclude_tmpdir_env_var,
+            )?;
+            insert(&mut workspace_write, "exclude_slash_tmp", exclude_slash_tmp)?;
+            insert(
+                &mut workspace_write,
+                "restrict_read_access",
+                readable_roots.is_some(),
+            )?;
+            if let Some(readable_roots) = readable_roots {
+                insert(&mut workspace_write, "readable_roots", readable_roots)?;
+            }
+            insert(&mut workspace_write, "allowed_domains", allowed_domains)?;
+            table.insert(
+                "sandbox_workspace_write".to_string(),
+                TomlValue::Table(workspace_write),
+            );
+            "workspace-write"
+        }
+    };
+    insert(&mut table, "sandbox_mode", sandbox_mode)?;
+
+    if let Some(effort) = config.model_reasoning_effort {
+        insert(&mut table, "model_reasoning_effort", effort)?;
+    }
+    insert(
+        &mut table,
+        "model_reasoning_summary",
+        config.model_reasoning_summary,
+    )?;
+    if let Some(verbosity) = config.model_verbosity {
+        insert(&mut table, "model_verbosity", verbosity)?;
+    }
+    insert(
+        &mut table,
+        "model_supports_reasoning_summaries",
+        config.model_family.supports_reasoning_summaries,
+    )?;
+    insert(
+        &mut table,
+        "model_reasoning_summary_format",
+        &config.model_family.reasoning_summary_format,
+    )?;
+    insert(
+        &mut table,
+        "hide_agent_reasoning",
+        config.hide_agent_reasoning,
+    )?;
+    insert(
+        &mut table,
+        "show_raw_agent_reasoning",
+        config.show_raw_agent_reasoning,
+    )?;
+    insert(&mut table, "chatgpt_base_url", &config.chatgpt_base_url)?;
+    insert(
+        &mut table,
+        "experimental_use_exec_command_tool",
+        config.use_experimental_streamable_shell_tool,
+    )?;
+    insert(
+        &mut table,
+        "experimental_use_unified_exec_tool",
+        config.use_experimental_unified_exec_tool,
+    )?;
+    insert(
+        &mut table,
+        "parallel_tool_calls",
+        config.parallel_tool_calls,
+    )?;
+    if let Some(max_tool_calls) = config.max_turn_tool_calls {
+        insert(&mut table, "max_turn_tool_calls", max_tool_calls)?;
+    }
+    if let Some(max_tokens) = config.max_task_tokens {
+        insert(&mut table, "max_task_tokens", max_tokens)?;
+    }
+    if let Some(max_duration) = config.max_task_duration {
+        insert(&mut table, "max_task_duration", max_duration.as_secs())?;
+    }
+    if let Some(max_cost) = config.max_cost_usd {
+        insert(&mut table, "max_cost_usd", max_cost)?;
+    }
+    insert(
+        &mut table,
+        "output_schema_repair_attempts",
+        config.output_schema_repair_attempts,
+    )?;
+    insert(
+        &mut table,
+        "project_doc_max_bytes",
+        config.project_doc_max_bytes,
+    )?;
+    if let Some(notify) = &config.notify {
+        insert(&mut table, "notify", notify)?;
+    }
+    insert(&mut table, "file_opener", config.file_opener)?;
+    insert(
+        &mut table,
+        "disable_paste_burst",
+        config.disable_paste_burst,
+    )?;
+    insert(&mut table, "history", &config.history)?;
+
+    let mut tools = Table::new();
+    insert(&mut tools, "web_search", config.tools_web_search_request)?;
+    insert(&mut tools, "view_image", config.include_view_image_tool)?;
+    insert(&mut tools, "delegate", config.include_delegate_tool)?;
+    table.insert("tools".to_string(), TomlValue::Table(tools));
+
+    let mut tui = Table::new();
+    insert(&mut tui, "notifications", &config.tui_notifications)?;
+    table.insert("tui".to_string(), TomlValue::Table(tui));
+
+    let policy = &config.shell_environment_policy;
+    let mut shell_environment_policy = Table::new();
+    insert(&mut shell_environment_policy, "inherit", &policy.inherit)?;
+    insert(
+        &mut shell_environment_policy,
+        "ignore_default_excludes",
+        policy.ignore_default_excludes,
+    )?;
+    insert(
+        &mut shell_environment_policy,
+        "exclude",
+        policy
+            .exclude
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+    )?;
+    insert(
+        &mut shell_environment_policy,
+        "set",
+        policy.r#set.iter().collect::<BTreeMap<_, _>>(),
+    )?;
+    insert(
+        &mut shell_environment_policy,
+        "include_only",
+        policy
+            .include_only
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>(),
+    )?;
+    insert(
+        &mut shell_environment_policy,
+        "experimental_use_profile",
+        policy.use_profile,
+    )?;
+    table.insert(
+        "shell_environment_policy".to_string(),
+        TomlValue::Table(shell_environment_policy),
+    );
+    insert(&mut table, "resource_limits", config.resource_limits)?;
+    insert(
+        &mut table,
+        "linux_sandbox_backend",
+        config.linux_sandbox_backend,
+    )?;
+
+    let mut mcp_servers = Table::new();
+    for (name, server) in &config.mcp_servers {
+        let mut entry = Table::new();
+        insert(&mut entry, "command", &server.command)?;
+        insert(&mut entry, "args", &server.args)?;
+        if let Some(env) = &server.env {
+            let prefix = format!("mcp_servers.{name}.env");
+            let env: BTreeMap<_, _> = redact_values(config, &prefix, env);
+            insert(&mut entry, "env", env)?;
+        }
+        if let Some(timeout) = server.startup_timeout_sec {
+            insert(&mut entry, "startup_timeout_sec", timeout.as_secs_f64())?;
+        }
+        if let Some(timeout) = server.tool_timeout_sec {
+            insert(&mut entry, "tool_timeout_sec", timeout.as_secs_f64())?;
+        }
+        mcp_servers.insert(name.clone(), TomlValue::Table(entry));
+    }
+    table.insert("mcp_servers".to_string(), TomlValue::Table(mcp_servers));
+    let mut model_providers = BTreeMap::new();
+    for (id, provider) in &config.model_providers {
+        let mut provider = provider.clone();
+        if let Some(headers) = &provider.http_headers {
+            let prefix = format!("model_providers.{id}.http_headers");
+            provider.http_headers = Some(redact_values(config, &prefix, headers));
+        }
+        if let Some(params) = &provider.query_params {
+            let prefix = format!("model_providers.{id}.query_params");
+            provider.query_params = Some(redact_values(config, &prefix, params));
+        }
+        model_providers.insert(id, provider);
+    }
+    insert(&mut table, "model_providers", model_providers)?;
+    insert(
+        &mut table,
+        "model_pricing",
+        config.model_pricing.iter().collect::<BTreeMap<_, _>>(),
+    )?;
+    insert(
+        &mut table,
+        "models",
+        config.models.iter().collect::<BTreeMap<_, _>>(),
+    )?;
+
+    Ok(table)
+}
+
+/// Copies `values`, the table at the dotted path `prefix`, with interpolated
+/// values redacted.
+fn redact_values<T>(config: &Config, prefix: &str, values: &HashMap<String, String>) -> T
+where
+    T: FromIterator<(String, String)>,
+{
+    values
+        .iter()
+        .map(|(key, value)| {
+            let value = config
+                .config_origins
+                .redact(&format!("{prefix}.{key}"), value);
+            (key.clone(), value.to_string())
+        })
+        .collect()
+}
+
+fn insert(table: &mut Table, key: &str, value: impl Serialize) -> Result<()> {
+    let value = TomlValue::try_from(value).with_context(|| format!("failed to render `{key}`"))?;
+    table.insert(key.to_string(), value);
+    Ok(())
+}
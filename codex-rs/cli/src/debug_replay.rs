@@ -0,0 +1,635 @@
+//! `codex debug replay`: re-runs a recorded session against a fake model that
+//! answers every request with the next response captured in the rollout, then
+//! reports where the re-executed tool calls diverge from the recording.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use codex_common::CliConfigOverrides;
+use codex_core::AuthManager;
+use codex_core::CodexConversation;
+use codex_core::ConversationManager;
+use codex_core::NewConversation;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::content_items_to_text;
+use codex_core::is_session_prefix_message;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::CompactedItem;
+use codex_core::protocol::ErrorEvent;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::RolloutItem;
+use codex_core::protocol::RolloutLine;
+use codex_core::protocol::SandboxPolicy;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ResponseItem;
+use serde_json::Value;
+
+use crate::ReplayCommand;
+use crate::fake_model::FakeModel;
+use crate::fake_model::ModelResponse;
+use crate::fake_model::render_sse;
+
+const REPLAY_PROVIDER_ID: &str = "replay";
+
+/// Replays `command.rollout` and returns the exit code for the process: 1 when
+/// the replay diverged from the recording, 0 otherwise.
+pub async fn run_replay(
+    command: ReplayCommand,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+) -> anyhow::Result<i32> {
+    let ReplayCommand {
+        rollout,
+        repo,
+        config_overrides,
+    } = command;
+
+    let recording = Recording::from_rollout_items(&read_rollout(&rollout)?);
+    let Some(recorded_cwd) = recording.cwd.clone() else {
+        anyhow::bail!("{} has no session metadata", rollout.display());
+    };
+    let repo = repo.unwrap_or_else(|| recorded_cwd.clone());
+
+    let scratch = tempfile::tempdir()?;
+    let scratch_cwd = scratch.path().canonicalize()?;
+    copy_dir_all(&repo, &scratch_cwd)
+        .with_context(|| format!("failed to copy {} to a scratch directory", repo.display()))?;
+
+    let responses = recording
+        .responses
+        .iter()
+        .enumerate()
+        .map(|(idx, response)| {
+            rewrite_paths(&render_sse(idx, response), &recorded_cwd, &scratch_cwd)
+        })
+        .collect();
+    let fake_model = FakeModel::start(responses)?;
+
+    let config = load_replay_config(
+        config_overrides,
+        &recording,
+        &fake_model,
+        scratch_cwd.clone(),
+        codex_linux_sandbox_exe,
+    )?;
+    let conversation_manager =
+        ConversationManager::new(AuthManager::shared(config.codex_home.clone()));
+    let NewConversation {
+        conversation,
+        session_configured,
+        ..
+    } = conversation_manager.new_conversation(config).await?;
+
+    for items in recording.user_inputs.iter().cloned() {
+        let sub_id = conversation.submit(Op::UserInput { items }).await?;
+        wait_for_task(&conversation, &sub_id).await?;
+    }
+    conversation.submit(Op::Shutdown).await?;
+    while !matches!(
+        conversation.next_event().await?.msg,
+        EventMsg::ShutdownComplete
+    ) {}
+
+    let (served, unexpected_requests) = fake_model.finish();
+    let replayed = Recording::from_rollout_items(&read_rollout(&session_configured.rollout_path)?);
+    let replayed_calls: Vec<ToolCall> = replayed
+        .tool_calls
+        .into_iter()
+        .map(|call| call.rewrite_paths(&scratch_cwd, &recorded_cwd))
+        .collect();
+
+    let mut divergences = Vec::new();
+    if unexpected_requests > 0 {
+        divergences.push(format!(
+            "the agent requested {unexpected_requests} more model response(s) than were recorded"
+        ));
+    }
+    if served < recording.responses.len() {
+        divergences.push(format!(
+            "{} recorded model response(s) were never requested",
+            recording.responses.len() - served
+        ));
+    }
+    divergences.extend(compare_tool_calls(&recording.tool_calls, &replayed_calls));
+
+    println!(
+        "replayed {served} model response(s) and {} tool call(s) from {}",
+        replayed_calls.len(),
+        rollout.display()
+    );
+    if divergences.is_empty() {
+        println!("no divergences");
+        return Ok(0);
+    }
+    for divergence in &divergences {
+        println!("- {divergence}");
+    }
+    println!("{} divergence(s)", divergences.len());
+    Ok(1)
+}
+
+fn load_replay_config(
+    config_overrides: CliConfigOverrides,
+    recording: &Recording,
+    fake_model: &FakeModel,
+    cwd: PathBuf,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+) -> anyhow::Result<Config> {
+    let mut config = Config::load_with_cli_overrides(
+        config_overrides
+            .parse_overrides()
+            .map_err(anyhow::Error::msg)?,
+        ConfigOverrides {
+            model: recording.model.clone(),
+            cwd: Some(cwd),
+            // Nobody is around to answer approval prompts.
+            approval_policy: Some(AskForApproval::Never),
+            codex_linux_sandbox_exe,
+            ..Default::default()
+        },
+    )?;
+    config.model_provider_id = REPLAY_PROVIDER_ID.to_string();
+    config.model_provider = fake_model.provider_info();
+    config.fallback.clear();
+    if let Some(sandbox_policy) = &recording.sandbox_policy {
+        config.sandbox_policy = sandbox_policy.clone();
+    }
+    Ok(config)
+}
+
+/// Drives the conversation until the task started by `sub_id` ends, denying
+/// any approval requests along the way.
+async fn wait_for_task(conversation: &CodexConversation, sub_id: &str) -> anyhow::Result<()> {
+    loop {
+        let event = conversation.next_event().await?;
+        match event.msg {
+            EventMsg::TaskComplete(_) | EventMsg::TurnAborted(_) if event.id == sub_id => {
+                return Ok(());
+            }
+            EventMsg::Error(ErrorEvent { message }) => {
+                eprintln!("replayed turn failed: {message}");
+            }
+            EventMsg::ExecApprovalRequest(_) => {
+                conversation
+                    .submit(Op::ExecApproval {
+                        id: event.id,
+                        decision: ReviewDecision::Denied,
+                    })
+                    .await?;
+            }
+            EventMsg::ApplyPatchApprovalRequest(_) => {
+                conversation
+                    .submit(Op::PatchApproval {
+                        id: event.id,
+                        decision: ReviewDecision::Denied,
+                    })
+                    .await?;
+            }
+            _ => {}
+        }
+    }
+}
+
+pub(crate) fn read_rollout(path: &Path) -> anyhow::Result<Vec<RolloutItem>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("failed to read rollout {}", path.display()))?;
+    let mut items = Vec::new();
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        match serde_json::from_str::<RolloutLine>(line) {
+            Ok(rollout_line) => items.push(rollout_line.item),
+            Err(err) => tracing::warn!("skipping unreadable rollout line: {err}"),
+        }
+    }
+    Ok(items)
+}
+
+/// What a rollout says about the session that produced it.
+#[derive(Debug, Default)]
+struct Recording {
+    cwd: Option<PathBuf>,
+    model: Option<String>,
+    sandbox_policy: Option<SandboxPolicy>,
+    /// Messages the user sent, one per task.
+    user_inputs: Vec<Vec<InputItem>>,
+    /// Model responses, in the order they were requested.
+    responses: Vec<ModelResponse>,
+    /// Tool calls made by the model, in order, with their outputs.
+    tool_calls: Vec<ToolCall>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ToolCall {
+    call_id: String,
+    /// Tool name and arguments as sent by the model.
+    invocation: String,
+    is_patch: bool,
+    output: Option<String>,
+}
+
+impl Recording {
+    fn from_rollout_items(items: &[RolloutItem]) -> Self {
+        let mut recording = Self::default();
+        // A `TurnContext` entry is written for every model request, so the
+        // model output between two of them is one response.
+        let mut current: Option<ModelResponse> = None;
+        for item in items {
+            match item {
+                RolloutItem::SessionMeta(meta_line) => {
+                    recording
+                        .cwd
+                        .get_or_insert_with(|| meta_line.meta.cwd.clone());
+                }
+                RolloutItem::TurnContext(turn_context) => {
+                    recording
+                        .model
+                        .get_or_insert_with(|| turn_context.model.clone());
+                    recording
+                        .sandbox_policy
+                        .get_or_insert_with(|| turn_context.sandbox_policy.clone());
+                    recording.push_response(current.take());
+                    current = Some(ModelResponse::default());
+                }
+                RolloutItem::Compacted(CompactedItem::Summary { message }) => {
+                    if let Some(response) = current.as_mut() {
+                        response.items.push(ResponseItem::Message {
+                            id: None,
+                            role: "assistant".to_string(),
+                            content: vec![ContentItem::OutputText {
+                                text: message.clone(),
+                            }],
+                        });
+                    }
+                }
+                RolloutItem::Compacted(_) => {}
+                RolloutItem::EventMsg(EventMsg::TokenCount(token_count)) => {
+                    if let (Some(response), Some(info)) = (current.as_mut(), &token_count.info) {
+                        response.usage = Some(info.last_token_usage.clone());
+                    }
+                }
+                RolloutItem::EventMsg(_) => {}
+                RolloutItem::ResponseItem(item) => {
+                    recording.record_tool_item(item);
+                    if let Some(input) = user_input(item) {
+                        recording.user_inputs.push(input);
+                    } else if is_model_output(item)
+                        && let Some(response) = current.as_mut()
+                    {
+                        response.items.push(item.clone());
+                    }
+                }
+            }
+        }
+        recording.push_response(current);
+        recording
+    }
+
+    /// Requests whose stream failed before producing anything leave an empty
+    /// response behind; the retry that follows is what the agent consumed.
+    fn push_response(&mut self, response: Option<ModelResponse>) {
+        if let Some(response) = response
+            && !response.items.is_empty()
+        {
+            self.responses.push(response);
+        }
+    }
+
+    fn record_tool_item(&mut self, item: &ResponseItem) {
+        match item {
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => self
+                .tool_calls
+                .push(ToolCall::new(call_id, name, arguments)),
+            ResponseItem::CustomToolCall {
+                name,
+                input,
+                call_id,
+                ..
+            } => self.tool_calls.push(ToolCall::new(call_id, name, input)),
+            ResponseItem::LocalShellCall {
+                id,
+                call_id,
+                action: LocalShellAction::Exec(exec),
+                ..
+            } => {
+                if let Some(call_id) = call_id.as_ref().or(id.as_ref()) {
+                    let command = exec.command.join(" ");
+                    self.tool_calls
+                        .push(ToolCall::new(call_id, "local_shell", &command));
+                }
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                self.record_output(call_id, &output.content);
+            }
+            ResponseItem::CustomToolCallOutput { call_id, output } => {
+                self.record_output(call_id, output);
+            }
+            _ => {}
+        }
+    }
+
+    fn record_output(&mut self, call_id: &str, output: &str) {
+        if let Some(call) = self
+            .tool_calls
+            .iter_mut()
+            .rev()
+            .find(|call| call.call_id == call_id)
+        {
+            call.output = Some(output.to_string());
+        }
+    }
+}
+
+impl ToolCall {
+    fn new(call_id: &str, name: &str, arguments: &str) -> Self {
+        Self {
+            call_id: call_id.to_string(),
+            invocation: format!("{name} {arguments}"),
+            is_patch: name == "apply_patch" || arguments.contains("apply_patch"),
+            output: None,
+        }
+    }
+
+    fn rewrite_paths(self, from: &Path, to: &Path) -> Self {
+        Self {
+            invocation: rewrite_paths(&self.invocation, from, to),
+            output: self.output.map(|output| rewrite_paths(&output, from, to)),
+            ..self
+        }
+    }
+}
+
+fn user_input(item: &ResponseItem) -> Option<Vec<InputItem>> {
+    let ResponseItem::Message { role, content, .. } = item else {
+        return None;
+    };
+    if role != "user"
+        || content_items_to_text(content).is_some_and(|text| is_session_prefix_message(&text))
+    {
+        return None;
+    }
+    Some(
+        content
+            .iter()
+            .filter_map(|content_item| match content_item {
+                ContentItem::InputText { text } => Some(InputItem::Text { text: text.clone() }),
+                ContentItem::InputImage { image_url } => Some(InputItem::Image {
+                    image_url: image_url.clone(),
+                }),
+                ContentItem::OutputText { .. } => None,
+            })
+            .collect(),
+    )
+}
+
+fn is_model_output(item: &ResponseItem) -> bool {
+    match item {
+        ResponseItem::Message { role, .. } => role == "assistant",
+        ResponseItem::Reasoning { .. }
+        | ResponseItem::LocalShellCall { .. }
+        | ResponseItem::FunctionCall { .. }
+        | ResponseItem::CustomToolCall { .. }
+        | ResponseItem::WebSearchCall { .. } => true,
+        ResponseItem::FunctionCallOutput { .. }
+        | ResponseItem::CustomToolCallOutput { .. }
+        | ResponseItem::Other => false,
+    }
+}
+
+/// Pairs up recorded and replayed tool calls in order and describes every
+/// pair that does not match.
+fn compare_tool_calls(recorded: &[ToolCall], replayed: &[ToolCall]) -> Vec<String> {
+    let mut divergences = Vec::new();
+    for idx in 0..recorded.len().max(replayed.len()) {
+        match (recorded.get(idx), replayed.get(idx)) {
+            (Some(recorded), Some(replayed)) => {
+                if recorded.invocation != replayed.invocation {
+                    divergences.push(format!(
+                        "call {idx}: different command\n  recorded: {}\n  replayed: {}",
+                        recorded.invocation, replayed.invocation
+                    ));
+                    continue;
+                }
+                let recorded_output = recorded.output.as_deref().map(comparable_output);
+                let replayed_output = replayed.output.as_deref().map(comparable_output);
+                if recorded_output != replayed_output {
+                    let what = if recorded.is_patch {
+                        "different patch result"
+                    } else {
+                        "different output"
+                    };
+                    divergences.push(format!(
+                        "call {idx} ({}): {what}\n  recorded: {}\n  replayed: {}",
+                        recorded.invocation,
+                        recorded_output.unwrap_or_default(),
+                        replayed_output.unwrap_or_default()
+                    ));
+                }
+            }
+            (Some(recorded), None) => divergences.push(format!(
+                "call {idx}: recorded but not replayed: {}",
+                recorded.invocation
+            )),
+            (None, Some(replayed)) => divergences.push(format!(
+                "call {idx}: replayed but not recorded: {}",
+                replayed.invocation
+            )),
+            (None, None) => {}
+        }
+    }
+    divergences
+}
+
+/// Exec outputs carry the wall-clock duration of the command; only what the
+/// command did is compared.
+fn comparable_output(output: &str) -> String {
+    match serde_json::from_str::<Value>(output) {
+        Ok(value) if value.get("output").is_some() => {
+            let exit_code = value
+                .pointer("/metadata/exit_code")
+                .cloned()
+                .unwrap_or(Value::Null);
+            format!(
+                "exit code {exit_code}: {}",
+                value["output"].as_str().unwrap_or_default()
+            )
+        }
+        _ => output.to_string(),
+    }
+}
+
+/// Replaces `from` with `to` in `text`, both as written and as escaped in a
+/// JSON string.
+fn rewrite_paths(text: &str, from: &Path, to: &Path) -> String {
+    let from = from.to_string_lossy();
+    let to = to.to_string_lossy();
+    let escaped = |path: &str| {
+        let quoted = Value::String(path.to_string()).to_string();
+        quoted[1..quoted.len() - 1].to_string()
+    };
+    text.replace(&escaped(&from), &escaped(&to))
+        .replace(&*from, &to)
+}
+
+pub(crate) fn copy_dir_all(from: &Path, to: &Path) -> io::Result<()> {
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        let target = to.join(entry.file_name());
+        if file_type.is_dir() {
+            copy_dir_all(&entry.path(), &target)?;
+        } else if file_type.is_symlink() {
+            copy_symlink(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(fs::read_link(from)?, to)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+    fs::copy(from, to).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::protocol::TurnContextItem;
+    use codex_protocol::config_types::ReasoningSummary;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn turn_context() -> RolloutItem {
+        RolloutItem::TurnContext(TurnContextItem {
+            cwd: PathBuf::from("/repo"),
+            approval_policy: AskForApproval::Never,
+            sandbox_policy: SandboxPolicy::ReadOnly,
+            model: "gpt-5".to_string(),
+            model_provider: None,
+            effort: None,
+            summary: ReasoningSummary::Auto,
+        })
+    }
+
+    fn message(role: &str, text: &str) -> RolloutItem {
+        RolloutItem::ResponseItem(ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        })
+    }
+
+    fn shell_call(call_id: &str, output: &str) -> [RolloutItem; 2] {
+        [
+            RolloutItem::ResponseItem(ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: r#"{"command":["ls"],"workdir":"/repo"}"#.to_string(),
+                call_id: call_id.to_string(),
+            }),
+            RolloutItem::ResponseItem(ResponseItem::FunctionCallOutput {
+                call_id: call_id.to_string(),
+                output: FunctionCallOutputPayload {
+                    content: output.to_string(),
+                    success: Some(true),
+                },
+            }),
+        ]
+    }
+
+    #[test]
+    fn splits_rollout_into_model_responses() {
+        let [call, output] = shell_call("call-1", "out");
+        let items = vec![
+            message("user", "<environment_context>cwd</environment_context>"),
+            message("user", "list files"),
+            turn_context(),
+            call,
+            output,
+            // A request whose stream failed and was retried.
+            turn_context(),
+            turn_context(),
+            message("assistant", "done"),
+        ];
+
+        let recording = Recording::from_rollout_items(&items);
+
+        assert_eq!(recording.user_inputs.len(), 1);
+        assert_eq!(recording.model.as_deref(), Some("gpt-5"));
+        let response_lengths: Vec<usize> = recording
+            .responses
+            .iter()
+            .map(|response| response.items.len())
+            .collect();
+        assert_eq!(response_lengths, vec![1, 1]);
+        assert_eq!(
+            recording.tool_calls,
+            vec![ToolCall {
+                call_id: "call-1".to_string(),
+                invocation: r#"shell {"command":["ls"],"workdir":"/repo"}"#.to_string(),
+                is_patch: false,
+                output: Some("out".to_string()),
+            }]
+        );
+    }
+
+    #[test]
+    fn reports_output_changes_but_not_durations() {
+        let exec_output = |output: &str, duration: f32| {
+            json!({
+                "output": output,
+                "metadata": { "exit_code": 0, "duration_seconds": duration },
+            })
+            .to_string()
+        };
+        let recorded = Recording::from_rollout_items(
+            &[
+                shell_call("call-1", &exec_output("a\n", 0.1)),
+                shell_call("call-2", &exec_output("b\n", 0.1)),
+            ]
+            .concat(),
+        );
+        let replayed = Recording::from_rollout_items(
+            &[
+                shell_call("call-1", &exec_output("a\n", 0.7)),
+                shell_call("call-2", &exec_output("c\n", 0.1)),
+            ]
+            .concat(),
+        );
+
+        let divergences = compare_tool_calls(&recorded.tool_calls, &replayed.tool_calls);
+
+        assert_eq!(divergences.len(), 1, "{divergences:?}");
+        assert!(divergences[0].starts_with("call 1 "), "{divergences:?}");
+        assert!(divergences[0].contains("different output"));
+    }
+
+    #[test]
+    fn rewrites_paths_inside_json_arguments() {
+        let arguments = r#"{"workdir":"/repo","command":["cat","/repo/a.txt"]}"#;
+        assert_eq!(
+            rewrite_paths(arguments, Path::new("/repo"), Path::new("/tmp/scratch")),
+            r#"{"workdir":"/tmp/scratch","command":["cat","/tmp/scratch/a.txt"]}"#
+        );
+    }
+}
@@ -5,6 +5,7 @@ use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::exec_env::create_env;
 use codex_core::landlock::spawn_command_under_linux_sandbox;
+use codex_core::resource_limits::ResourceLimiter;
 use codex_core::seatbelt::spawn_command_under_seatbelt;
 use codex_core::spawn::StdioPolicy;
 use codex_protocol::config_types::SandboxMode;
@@ -85,6 +86,7 @@ async fn run_command_under_sandbox(
 
     let stdio_policy = StdioPolicy::Inherit;
     let env = create_env(&config.shell_environment_policy);
+    let resource_limiter = ResourceLimiter::new(&config.resource_limits)?;
 
     let mut child = match sandbox_type {
         SandboxType::Seatbelt => {
@@ -95,6 +97,7 @@ async fn run_command_under_sandbox(
                 sandbox_policy_cwd.as_path(),
                 stdio_policy,
                 env,
+                &resource_limiter,
             )
             .await?
         }
@@ -111,6 +114,8 @@ async fn run_command_under_sandbox(
                 sandbox_policy_cwd.as_path(),
                 stdio_policy,
                 env,
+                &resource_limiter,
+                None,
             )
             .await?
         }
@@ -0,0 +1,381 @@
+//! `codex eval`: runs a suite of agent tasks, each in a scratch copy of its
+//! repository, and reports per task whether its success check passed along
+//! with the turns, tool calls, tokens and wall time it took.
+//!
+//! A suite is a JSONL file with one task per line:
+//!
+//! ```json
+//! {"id": "fix-typo", "repo": "fixtures/typo", "prompt": "Fix the typo in README.md", "check": "grep -q receive README.md"}
+//! ```
+//!
+//! `repo` and the optional `responses` file are resolved relative to the
+//! suite. When `responses` is set the task runs against a scripted model that
+//! answers the n-th request with the n-th line of that file (a JSON array of
+//! response items), which keeps the harness usable offline.
+
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Instant;
+
+use anyhow::Context;
+use codex_core::AuthManager;
+use codex_core::CodexConversation;
+use codex_core::ConversationManager;
+use codex_core::NewConversation;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::ErrorEvent;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::RolloutItem;
+use codex_core::protocol::TokenCountEvent;
+use codex_protocol::models::ResponseItem;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::EvalCommand;
+use crate::debug_replay::copy_dir_all;
+use crate::debug_replay::read_rollout;
+use crate::fake_model::FakeModel;
+use crate::fake_model::ModelResponse;
+use crate::fake_model::render_sse;
+
+const SCRIPTED_PROVIDER_ID: &str = "scripted";
+const DEFAULT_VARIANT: &str = "default";
+
+/// One line of a suite file.
+#[derive(Debug, Deserialize)]
+struct EvalTask {
+    id: String,
+    /// Repository fixture the task starts from.
+    repo: PathBuf,
+    prompt: String,
+    /// Shell command run in the task's workspace once the agent is done; the
+    /// task passes when it exits successfully.
+    check: String,
+    /// Scripted model responses, one JSON array of response items per line.
+    #[serde(default)]
+    responses: Option<PathBuf>,
+}
+
+/// Outcome of running one task with one variant.
+#[derive(Debug, Serialize)]
+struct EvalResult {
+    id: String,
+    variant: String,
+    passed: bool,
+    /// Number of user turns, not counting the extra requests made for
+    /// retries, fallbacks or output-schema repairs.
+    turns: usize,
+    tool_calls: usize,
+    tokens: u64,
+    wall_time_secs: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Runs the suite and returns the exit code: non-zero when a task failed.
+pub async fn run_eval(
+    command: EvalCommand,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+) -> anyhow::Result<i32> {
+    let tasks = read_suite(&command.suite)?;
+    let suite_dir = command
+        .suite
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let variants: Vec<Option<&str>> = if command.profiles.is_empty() {
+        vec![None]
+    } else {
+        command.profiles.iter().map(|p| Some(p.as_str())).collect()
+    };
+
+    let mut results = Vec::new();
+    for task in &tasks {
+        for &profile in &variants {
+            let result = run_task(
+                &command,
+                task,
+                &suite_dir,
+                profile,
+                codex_linux_sandbox_exe.clone(),
+            )
+            .await
+            .unwrap_or_else(|err| EvalResult {
+                id: task.id.clone(),
+                variant: profile.unwrap_or(DEFAULT_VARIANT).to_string(),
+                passed: false,
+                turns: 0,
+                tool_calls: 0,
+                tokens: 0,
+                wall_time_secs: 0.0,
+                error: Some(format!("{err:#}")),
+            });
+            results.push(result);
+        }
+    }
+
+    print_report(&results, &variants);
+    if let Some(output) = &command.output {
+        let mut lines = String::new();
+        for result in &results {
+            lines.push_str(&serde_json::to_string(result)?);
+            lines.push('\n');
+        }
+        fs::write(output, lines)
+            .with_context(|| format!("failed to write results to {}", output.display()))?;
+    }
+    let all_passed = results.iter().all(|result| result.passed);
+    Ok(if all_passed { 0 } else { 1 })
+}
+
+fn read_suite(path: &Path) -> anyhow::Result<Vec<EvalTask>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("failed to read suite {}", path.display()))?;
+    let mut tasks = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let task = serde_json::from_str(line)
+            .with_context(|| format!("{}:{}: invalid task", path.display(), idx + 1))?;
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+fn read_scripted_responses(path: &Path) -> anyhow::Result<Vec<String>> {
+    let text = fs::read_to_string(path)
+        .with_context(|| format!("failed to read responses {}", path.display()))?;
+    let mut responses = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let items: Vec<ResponseItem> = serde_json::from_str(line)
+            .with_context(|| format!("{}:{}: invalid response", path.display(), idx + 1))?;
+        let response = ModelResponse { items, usage: None };
+        responses.push(render_sse(responses.len(), &response));
+    }
+    Ok(responses)
+}
+
+async fn run_task(
+    command: &EvalCommand,
+    task: &EvalTask,
+    suite_dir: &Path,
+    profile: Option<&str>,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+) -> anyhow::Result<EvalResult> {
+    let repo = suite_dir.join(&task.repo);
+    let scratch = tempfile::tempdir()?;
+    let cwd = scratch.path().canonicalize()?;
+    copy_dir_all(&repo, &cwd)
+        .with_context(|| format!("failed to copy {} to a scratch directory", repo.display()))?;
+
+    let fake_model = match &task.responses {
+        Some(responses) => {
+            let responses = read_scripted_responses(&suite_dir.join(responses))?;
+            Some(FakeModel::start(responses)?)
+        }
+        None => None,
+    };
+    let mut config = Config::load_with_cli_overrides(
+        command
+            .config_overrides
+            .parse_overrides()
+            .map_err(anyhow::Error::msg)?,
+        ConfigOverrides {
+            cwd: Some(cwd.clone()),
+            // Nobody is around to answer approval prompts.
+            approval_policy: Some(AskForApproval::Never),
+            sandbox_mode: Some(command.sandbox_mode.into()),
+            config_profile: profile.map(str::to_string),
+            codex_linux_sandbox_exe,
+            ..Default::default()
+        },
+    )?;
+    if let Some(fake_model) = &fake_model {
+        config.model_provider_id = SCRIPTED_PROVIDER_ID.to_string();
+        config.model_provider = fake_model.provider_info();
+        config.fallback.clear();
+    }
+
+    let conversation_manager =
+        ConversationManager::new(AuthManager::shared(config.codex_home.clone()));
+    let NewConversation {
+        conversation,
+        session_configured,
+        ..
+    } = conversation_manager.new_conversation(config).await?;
+
+    let started = Instant::now();
+    let items = vec![InputItem::Text {
+        text: task.prompt.clone(),
+    }];
+    let sub_id = conversation.submit(Op::UserInput { items }).await?;
+    let outcome = wait_for_task(&conversation, &sub_id).await?;
+    let wall_time_secs = started.elapsed().as_secs_f64();
+    conversation.submit(Op::Shutdown).await?;
+    while !matches!(
+        conversation.next_event().await?.msg,
+        EventMsg::ShutdownComplete
+    ) {}
+    if let Some(fake_model) = fake_model {
+        fake_model.finish();
+    }
+
+    let rollout = read_rollout(&session_configured.rollout_path)?;
+    let turns = rollout
+        .iter()
+        .filter(|item| matches!(item, RolloutItem::EventMsg(EventMsg::UserMessage(_))))
+        .count();
+    let tool_calls = rollout
+        .iter()
+        .filter(|item| matches!(item, RolloutItem::ResponseItem(item) if is_tool_call(item)))
+        .count();
+    let passed = run_check(&task.check, &cwd).await?;
+
+    Ok(EvalResult {
+        id: task.id.clone(),
+        variant: profile.unwrap_or(DEFAULT_VARIANT).to_string(),
+        passed,
+        turns,
+        tool_calls,
+        tokens: outcome.tokens,
+        wall_time_secs,
+        error: outcome.error,
+    })
+}
+
+#[derive(Debug, Default)]
+struct TaskOutcome {
+    tokens: u64,
+    error: Option<String>,
+}
+
+/// Drives the conversation until the task started by `sub_id` ends, keeping
+/// track of the reported token usage and the last error.
+async fn wait_for_task(
+    conversation: &CodexConversation,
+    sub_id: &str,
+) -> anyhow::Result<TaskOutcome> {
+    let mut outcome = TaskOutcome::default();
+    loop {
+        let event = conversation.next_event().await?;
+        match event.msg {
+            EventMsg::TaskComplete(_) if event.id == sub_id => return Ok(outcome),
+            EventMsg::TurnAborted(aborted) if event.id == sub_id => {
+                outcome.error = Some(format!("turn aborted: {:?}", aborted.reason));
+                return Ok(outcome);
+            }
+            EventMsg::Error(ErrorEvent { message }) => outcome.error = Some(message),
+            EventMsg::TokenCount(TokenCountEvent {
+                info: Some(info), ..
+            }) => {
+                outcome.tokens = info.total_token_usage.total_tokens;
+            }
+            EventMsg::ExecApprovalRequest(_) => {
+                conversation
+                    .submit(Op::ExecApproval {
+                        id: event.id,
+                        decision: ReviewDecision::Denied,
+                    })
+                    .await?;
+            }
+            EventMsg::ApplyPatchApprovalRequest(_) => {
+                conversation
+                    .submit(Op::PatchApproval {
+                        id: event.id,
+                        decision: ReviewDecision::Denied,
+                    })
+                    .await?;
+            }
+            _ => {}
+        }
+    }
+}
+
+fn is_tool_call(item: &ResponseItem) -> bool {
+    matches!(
+        item,
+        ResponseItem::FunctionCall { .. }
+            | ResponseItem::LocalShellCall { .. }
+            | ResponseItem::CustomToolCall { .. }
+    )
+}
+
+async fn run_check(check: &str, cwd: &Path) -> anyhow::Result<bool> {
+    let mut command = if cfg!(windows) {
+        let mut command = tokio::process::Command::new("cmd");
+        command.arg("/C");
+        command
+    } else {
+        let mut command = tokio::process::Command::new("sh");
+        command.arg("-c");
+        command
+    };
+    let status = command
+        .arg(check)
+        .current_dir(cwd)
+        .stdin(std::process::Stdio::null())
+        .status()
+        .await
+        .with_context(|| format!("failed to run check `{check}`"))?;
+    Ok(status.success())
+}
+
+fn print_report(results: &[EvalResult], variants: &[Option<&str>]) {
+    let id_width = results
+        .iter()
+        .map(|result| result.id.len())
+        .chain(std::iter::once("task".len()))
+        .max()
+        .unwrap_or_default();
+    let variant_width = results
+        .iter()
+        .map(|result| result.variant.len())
+        .chain(std::iter::once("variant".len()))
+        .max()
+        .unwrap_or_default();
+    println!(
+        "{:id_width$}  {:variant_width$}  {:6}  {:>5}  {:>10}  {:>8}  {:>8}",
+        "task", "variant", "result", "turns", "tool calls", "tokens", "time"
+    );
+    for result in results {
+        let status = if result.passed { "pass" } else { "fail" };
+        println!(
+            "{:id_width$}  {:variant_width$}  {status:6}  {:>5}  {:>10}  {:>8}  {:>7.1}s",
+            result.id,
+            result.variant,
+            result.turns,
+            result.tool_calls,
+            result.tokens,
+            result.wall_time_secs
+        );
+        if let Some(error) = &result.error {
+            println!("    error: {error}");
+        }
+    }
+
+    println!();
+    for profile in variants {
+        let variant = profile.unwrap_or(DEFAULT_VARIANT);
+        let runs: Vec<&EvalResult> = results
+            .iter()
+            .filter(|result| result.variant == variant)
+            .collect();
+        let passed = runs.iter().filter(|result| result.passed).count();
+        let tokens: u64 = runs.iter().map(|result| result.tokens).sum();
+        let wall_time_secs: f64 = runs.iter().map(|result| result.wall_time_secs).sum();
+        println!(
+            "{variant}: {passed}/{} passed, {tokens} tokens, {wall_time_secs:.1}s",
+            runs.len()
+        );
+    }
+}
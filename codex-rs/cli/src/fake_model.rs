@@ -0,0 +1,166 @@
+//! In-process stand-in for a Responses API model provider that answers each
+//! request with the next response from a script.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+
+use codex_core::ModelProviderInfo;
+use codex_core::WireApi;
+use codex_core::protocol::TokenUsage;
+use codex_protocol::models::ResponseItem;
+use serde_json::Value;
+use serde_json::json;
+use tiny_http::Header;
+use tiny_http::Response;
+use tiny_http::Server;
+
+/// One scripted model response: the output items and, optionally, the token
+/// usage to report for it.
+#[derive(Debug, Default)]
+pub(crate) struct ModelResponse {
+    pub(crate) items: Vec<ResponseItem>,
+    pub(crate) usage: Option<TokenUsage>,
+}
+
+/// Renders `response` as the Responses API server-sent events for it.
+pub(crate) fn render_sse(idx: usize, response: &ModelResponse) -> String {
+    let mut events: Vec<Value> = response
+        .items
+        .iter()
+        .map(|item| json!({ "type": "response.output_item.done", "item": item }))
+        .collect();
+    let usage = response.usage.as_ref().map(|usage| {
+        json!({
+            "input_tokens": usage.input_tokens,
+            "input_tokens_details": { "cached_tokens": usage.cached_input_tokens },
+            "output_tokens": usage.output_tokens,
+            "output_tokens_details": { "reasoning_tokens": usage.reasoning_output_tokens },
+            "total_tokens": usage.total_tokens,
+        })
+    });
+    events.push(json!({
+        "type": "response.completed",
+        "response": { "id": format!("fake-{idx}"), "usage": usage },
+    }));
+    events
+        .iter()
+        .map(|event| {
+            let kind = event["type"].as_str().unwrap_or_default();
+            format!("event: {kind}\ndata: {event}\n\n")
+        })
+        .collect()
+}
+
+/// Answers each request with the next scripted response, and with an empty
+/// response once they run out.
+pub(crate) struct FakeModel {
+    server: Arc<Server>,
+    base_url: String,
+    state: Arc<Mutex<FakeModelState>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct FakeModelState {
+    responses: VecDeque<String>,
+    served: usize,
+    unexpected_requests: usize,
+}
+
+impl FakeModel {
+    pub(crate) fn start(responses: Vec<String>) -> anyhow::Result<Self> {
+        let server = Arc::new(
+            Server::http("127.0.0.1:0")
+                .map_err(|err| anyhow::anyhow!("failed to start the fake model: {err}"))?,
+        );
+        let Some(addr) = server.server_addr().to_ip() else {
+            anyhow::bail!("the fake model is not listening on a TCP port");
+        };
+        let content_type = Header::from_bytes(&b"Content-Type"[..], &b"text/event-stream"[..])
+            .map_err(|()| anyhow::anyhow!("invalid content type header"))?;
+        let state = Arc::new(Mutex::new(FakeModelState {
+            responses: responses.into(),
+            ..Default::default()
+        }));
+
+        let thread = std::thread::spawn({
+            let server = server.clone();
+            let state = state.clone();
+            move || {
+                for request in server.incoming_requests() {
+                    let body = match state.lock() {
+                        Ok(mut state) => state.next_response(),
+                        Err(_) => break,
+                    };
+                    let response = Response::from_string(body).with_header(content_type.clone());
+                    if let Err(err) = request.respond(response) {
+                        tracing::warn!("failed to answer fake model request: {err}");
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            server,
+            base_url: format!("http://{addr}/v1"),
+            state,
+            thread: Some(thread),
+        })
+    }
+
+    pub(crate) fn provider_info(&self) -> ModelProviderInfo {
+        ModelProviderInfo {
+            name: "Scripted responses".into(),
+            base_url: Some(self.base_url.clone()),
+            env_key: None,
+            env_key_instructions: None,
+            wire_api: WireApi::Responses,
+            query_params: None,
+            http_headers: None,
+            env_http_headers: None,
+            // A retry would consume the next scripted response.
+            request_max_retries: Some(0),
+            stream_max_retries: Some(0),
+            stream_idle_timeout_ms: None,
+            requires_openai_auth: false,
+            supports_reasoning: None,
+            supports_images: None,
+            interpolate: false,
+        }
+    }
+
+    /// Stops the server and returns how many scripted responses were served
+    /// and how many requests arrived after they ran out.
+    pub(crate) fn finish(mut self) -> (usize, usize) {
+        self.server.unblock();
+        if let Some(thread) = self.thread.take()
+            && thread.join().is_err()
+        {
+            tracing::warn!("fake model thread panicked");
+        }
+        match self.state.lock() {
+            Ok(state) => (state.served, state.unexpected_requests),
+            Err(_) => (0, 0),
+        }
+    }
+}
+
+impl FakeModelState {
+    fn next_response(&mut self) -> String {
+        match self.responses.pop_front() {
+            Some(response) => {
+                self.served += 1;
+                response
+            }
+            None => {
+                self.unexpected_requests += 1;
+                render_sse(
+                    self.served + self.unexpected_requests,
+                    &ModelResponse::default(),
+                )
+            }
+        }
+    }
+}
@@ -1,10 +1,15 @@
+pub mod debug_replay;
 pub mod debug_sandbox;
+pub mod eval;
 mod exit_status;
+mod fake_model;
 pub mod login;
 pub mod proto;
 
 use clap::Parser;
 use codex_common::CliConfigOverrides;
+use codex_common::SandboxModeCliArg;
+use std::path::PathBuf;
 
 #[derive(Debug, Parser)]
 pub struct SeatbeltCommand {
@@ -33,3 +38,46 @@ pub struct LandlockCommand {
     #[arg(trailing_var_arg = true)]
     pub command: Vec<String>,
 }
+
+#[derive(Debug, Parser)]
+pub struct ReplayCommand {
+    /// Directory to copy into the scratch workspace. Defaults to the working
+    /// directory recorded in the rollout.
+    #[arg(long = "repo", value_name = "DIR")]
+    pub repo: Option<PathBuf>,
+
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Rollout file of the session to replay.
+    #[arg(value_name = "ROLLOUT")]
+    pub rollout: PathBuf,
+}
+
+#[derive(Debug, Parser)]
+pub struct EvalCommand {
+    /// Config profile to evaluate. Repeat to run the suite once per profile
+    /// and compare them side by side.
+    #[arg(long = "profile", short = 'p', value_name = "NAME")]
+    pub profiles: Vec<String>,
+
+    /// Sandbox policy for the commands the agent runs.
+    #[arg(
+        long = "sandbox",
+        short = 's',
+        value_enum,
+        default_value = "workspace-write"
+    )]
+    pub sandbox_mode: SandboxModeCliArg,
+
+    /// Write one JSON line per task and profile with the results.
+    #[arg(long = "output", short = 'o', value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// JSONL file with one task per line.
+    #[arg(value_name = "SUITE")]
+    pub suite: PathBuf,
+}
@@ -5,7 +5,9 @@ use clap_complete::generate;
 use codex_arg0::arg0_dispatch_or_else;
 use codex_chatgpt::apply_command::ApplyCommand;
 use codex_chatgpt::apply_command::run_apply_command;
+use codex_cli::EvalCommand;
 use codex_cli::LandlockCommand;
+use codex_cli::ReplayCommand;
 use codex_cli::SeatbeltCommand;
 use codex_cli::login::run_login_status;
 use codex_cli::login::run_login_with_api_key;
@@ -20,8 +22,10 @@ use owo_colors::OwoColorize;
 use std::path::PathBuf;
 use supports_color::Stream;
 
+mod config_cmd;
 mod mcp_cmd;
 
+use crate::config_cmd::ConfigCli;
 use crate::mcp_cmd::McpCli;
 use crate::proto::ProtoCli;
 
@@ -65,6 +69,9 @@ enum Subcommand {
     /// [experimental] Run Codex as an MCP server and manage MCP servers.
     Mcp(McpCli),
 
+    /// Validate, explain and print the configuration.
+    Config(ConfigCli),
+
     /// Run the Protocol stream via stdin/stdout
     #[clap(visible_alias = "p")]
     Proto(ProtoCli),
@@ -75,6 +82,10 @@ enum Subcommand {
     /// Internal debugging commands.
     Debug(DebugArgs),
 
+    /// Run a suite of tasks, each in a scratch copy of its repository, and report the results.
+    /// Exits with 1 when a task fails.
+    Eval(EvalCommand),
+
     /// Apply the latest diff produced by Codex agent as a `git apply` to your local working tree.
     #[clap(visible_alias = "a")]
     Apply(ApplyCommand),
@@ -126,6 +137,10 @@ enum DebugCommand {
 
     /// Run a command under Landlock+seccomp (Linux only).
     Landlock(LandlockCommand),
+
+    /// Replay a recorded session against its recorded model responses and
+    /// report where the tool calls diverge.
+    Replay(ReplayCommand),
 }
 
 #[derive(Debug, Parser)]
@@ -226,13 +241,26 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
                 &mut exec_cli.config_overrides,
                 root_config_overrides.clone(),
             );
-            codex_exec::run_main(exec_cli, codex_linux_sandbox_exe).await?;
+            let exit_code = codex_exec::run_main(exec_cli, codex_linux_sandbox_exe).await?;
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
         }
         Some(Subcommand::Mcp(mut mcp_cli)) => {
             // Propagate any root-level config overrides (e.g. `-c key=value`).
             prepend_config_flags(&mut mcp_cli.config_overrides, root_config_overrides.clone());
             mcp_cli.run(codex_linux_sandbox_exe).await?;
         }
+        Some(Subcommand::Config(mut config_cli)) => {
+            prepend_config_flags(
+                &mut config_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            let exit_code = config_cli.run()?;
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
+        }
         Some(Subcommand::Resume(ResumeCommand {
             session_id,
             last,
@@ -319,7 +347,29 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
                 )
                 .await?;
             }
+            DebugCommand::Replay(mut replay_cli) => {
+                prepend_config_flags(
+                    &mut replay_cli.config_overrides,
+                    root_config_overrides.clone(),
+                );
+                let exit_code =
+                    codex_cli::debug_replay::run_replay(replay_cli, codex_linux_sandbox_exe)
+                        .await?;
+                if exit_code != 0 {
+                    std::process::exit(exit_code);
+                }
+            }
         },
+        Some(Subcommand::Eval(mut eval_cli)) => {
+            prepend_config_flags(
+                &mut eval_cli.config_overrides,
+                root_config_overrides.clone(),
+            );
+            let exit_code = codex_cli::eval::run_eval(eval_cli, codex_linux_sandbox_exe).await?;
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
+        }
         Some(Subcommand::Apply(mut apply_cli)) => {
             prepend_config_flags(
                 &mut apply_cli.config_overrides,
@@ -9,9 +9,9 @@ use anyhow::bail;
 use codex_common::CliConfigOverrides;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
+use codex_core::config::add_global_mcp_server;
 use codex_core::config::find_codex_home;
-use codex_core::config::load_global_mcp_servers;
-use codex_core::config::write_global_mcp_servers;
+use codex_core::config::remove_global_mcp_server;
 use codex_core::config_types::McpServerConfig;
 
 /// [experimental] Launch Codex as an MCP server or manage configured MCP servers.
@@ -141,20 +141,17 @@ fn run_add(config_overrides: &CliConfigOverrides, add_args: AddArgs) -> Result<(
     };
 
     let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
-    let mut servers = load_global_mcp_servers(&codex_home)
-        .with_context(|| format!("failed to load MCP servers from {}", codex_home.display()))?;
 
     let new_entry = McpServerConfig {
         command: command_bin,
         args: command_args,
         env: env_map,
+        interpolate: false,
         startup_timeout_sec: None,
         tool_timeout_sec: None,
     };
 
-    servers.insert(name.clone(), new_entry);
-
-    write_global_mcp_servers(&codex_home, &servers)
+    add_global_mcp_server(&codex_home, &name, &new_entry)
         .with_context(|| format!("failed to write MCP servers to {}", codex_home.display()))?;
 
     println!("Added global MCP server '{name}'.");
@@ -170,15 +167,8 @@ fn run_remove(config_overrides: &CliConfigOverrides, remove_args: RemoveArgs) ->
     validate_server_name(&name)?;
 
     let codex_home = find_codex_home().context("failed to resolve CODEX_HOME")?;
-    let mut servers = load_global_mcp_servers(&codex_home)
-        .with_context(|| format!("failed to load MCP servers from {}", codex_home.display()))?;
-
-    let removed = servers.remove(&name).is_some();
-
-    if removed {
-        write_global_mcp_servers(&codex_home, &servers)
-            .with_context(|| format!("failed to write MCP servers to {}", codex_home.display()))?;
-    }
+    let removed = remove_global_mcp_server(&codex_home, &name)
+        .with_context(|| format!("failed to write MCP servers to {}", codex_home.display()))?;
 
     if removed {
         println!("Removed global MCP server '{name}'.");
@@ -201,11 +191,7 @@ fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Resul
         let json_entries: Vec<_> = entries
             .into_iter()
             .map(|(name, cfg)| {
-                let env = cfg.env.as_ref().map(|env| {
-                    env.iter()
-                        .map(|(k, v)| (k.clone(), v.clone()))
-                        .collect::<BTreeMap<_, _>>()
-                });
+                let env = display_env(&config, name, cfg);
                 serde_json::json!({
                     "name": name,
                     "command": cfg.command,
@@ -238,18 +224,14 @@ fn run_list(config_overrides: &CliConfigOverrides, list_args: ListArgs) -> Resul
             cfg.args.join(" ")
         };
 
-        let env = match cfg.env.as_ref() {
+        let env = match display_env(&config, name, cfg) {
             None => "-".to_string(),
             Some(map) if map.is_empty() => "-".to_string(),
-            Some(map) => {
-                let mut pairs: Vec<_> = map.iter().collect();
-                pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
-                pairs
-                    .into_iter()
-                    .map(|(k, v)| format!("{k}={v}"))
-                    .collect::<Vec<_>>()
-                    .join(", ")
-            }
+            Some(map) => map
+                .into_iter()
+                .map(|(k, v)| format!("{k}={v}"))
+                .collect::<Vec<_>>()
+                .join(", "),
         };
 
         rows.push([name.clone(), cfg.command.clone(), args, env]);
@@ -301,11 +283,7 @@ fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Result<(
     };
 
     if get_args.json {
-        let env = server.env.as_ref().map(|env| {
-            env.iter()
-                .map(|(k, v)| (k.clone(), v.clone()))
-                .collect::<BTreeMap<_, _>>()
-        });
+        let env = display_env(&config, &get_args.name, server);
         let output = serde_json::to_string_pretty(&serde_json::json!({
             "name": get_args.name,
             "command": server.command,
@@ -330,18 +308,14 @@ fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Result<(
         server.args.join(" ")
     };
     println!("  args: {args}");
-    let env_display = match server.env.as_ref() {
+    let env_display = match display_env(&config, &get_args.name, server) {
         None => "-".to_string(),
         Some(map) if map.is_empty() => "-".to_string(),
-        Some(map) => {
-            let mut pairs: Vec<_> = map.iter().collect();
-            pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
-            pairs
-                .into_iter()
-                .map(|(k, v)| format!("{k}={v}"))
-                .collect::<Vec<_>>()
-                .join(", ")
-        }
+        Some(map) => map
+            .into_iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join(", "),
     };
     println!("  env: {env_display}");
     if let Some(timeout) = server.startup_timeout_sec {
@@ -355,6 +329,24 @@ fn run_get(config_overrides: &CliConfigOverrides, get_args: GetArgs) -> Result<(
     Ok(())
 }
 
+/// Returns the environment of the server `name`, sorted by variable, with
+/// interpolated values redacted.
+fn display_env(
+    config: &Config,
+    name: &str,
+    server: &McpServerConfig,
+) -> Option<BTreeMap<String, String>> {
+    server.env.as_ref().map(|env| {
+        env.iter()
+            .map(|(key, value)| {
+                let path = format!("mcp_servers.{name}.env.{key}");
+                let value = config.config_origins.redact(&path, value);
+                (key.clone(), value.to_string())
+            })
+            .collect()
+    })
+}
+
 fn parse_env_pair(raw: &str) -> Result<(String, String), String> {
     let mut parts = raw.splitn(2, '=');
     let key = parts
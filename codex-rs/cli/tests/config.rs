@@ -0,0 +1,85 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use predicates::str::contains;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use tempfile::TempDir;
+
+fn codex_command(codex_home: &Path) -> Result<assert_cmd::Command> {
+    let mut cmd = assert_cmd::Command::cargo_bin("codex")?;
+    cmd.env("CODEX_HOME", codex_home);
+    Ok(cmd)
+}
+
+#[test]
+fn check_reports_unknown_keys_and_undefined_profiles() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    let cwd = TempDir::new()?;
+    fs::write(
+        codex_home.path().join("config.toml"),
+        "modle = \"o3\"\nprofile = \"fast\"\n",
+    )?;
+
+    codex_command(codex_home.path())?
+        .current_dir(cwd.path())
+        .args(["config", "check"])
+        .assert()
+        .failure()
+        .stdout(contains("config.toml:1:1: unknown key `modle`"))
+        .stdout(contains(
+            "config.toml:2:11: profile `fast` is not defined in any `[profiles]` table",
+        ));
+
+    fs::write(
+        codex_home.path().join("config.toml"),
+        "model = \"o3\"\nprofile = \"fast\"\n\n[profiles.fast]\nmodel = \"gpt-5\"\n",
+    )?;
+    codex_command(codex_home.path())?
+        .current_dir(cwd.path())
+        .args(["config", "check"])
+        .assert()
+        .success()
+        .stdout(contains("config.toml: ok"));
+
+    Ok(())
+}
+
+#[test]
+fn show_effective_resolves_profile_and_defaults() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    let cwd = TempDir::new()?;
+    fs::write(
+        codex_home.path().join("config.toml"),
+        "model = \"o3\"\n\n[profiles.fast]\nmodel = \"gpt-5\"\napproval_policy = \"never\"\n",
+    )?;
+
+    let output = codex_command(codex_home.path())?
+        .current_dir(cwd.path())
+        .args(["config", "show", "--effective", "--profile", "fast"])
+        .output()?;
+    assert!(output.status.success());
+    let effective: toml::Table = toml::from_str(&String::from_utf8(output.stdout)?)?;
+    assert_eq!(effective["model"].as_str(), Some("gpt-5"));
+    assert_eq!(effective["approval_policy"].as_str(), Some("never"));
+    assert_eq!(effective["profile"].as_str(), Some("fast"));
+    assert_eq!(effective["model_provider"].as_str(), Some("openai"));
+
+    Ok(())
+}
+
+#[test]
+fn schema_describes_config_toml() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    let output = codex_command(codex_home.path())?
+        .args(["config", "schema"])
+        .output()?;
+    assert!(output.status.success());
+    let schema: Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(schema["title"], "ConfigToml");
+    assert!(schema["properties"]["model"].is_object());
+    assert!(schema["definitions"]["ConfigProfile"].is_object());
+
+    Ok(())
+}
@@ -0,0 +1,97 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use predicates::str::contains;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use serde_json::json;
+use tempfile::TempDir;
+
+fn codex_command(codex_home: &Path) -> Result<assert_cmd::Command> {
+    let mut cmd = assert_cmd::Command::cargo_bin("codex")?;
+    cmd.env("CODEX_HOME", codex_home);
+    Ok(cmd)
+}
+
+#[cfg(unix)]
+#[test]
+fn eval_runs_suite_against_scripted_model() -> Result<()> {
+    let codex_home = TempDir::new()?;
+    let suite_dir = TempDir::new()?;
+    fs::create_dir(suite_dir.path().join("repo"))?;
+    fs::write(suite_dir.path().join("repo/README.md"), "fixture\n")?;
+
+    let responses = [
+        json!([{
+            "type": "function_call",
+            "call_id": "call-1",
+            "name": "shell",
+            "arguments": json!({ "command": ["touch", "done.txt"] }).to_string(),
+        }]),
+        json!([{
+            "type": "message",
+            "role": "assistant",
+            "content": [{ "type": "output_text", "text": "Created done.txt." }],
+        }]),
+    ];
+    fs::write(
+        suite_dir.path().join("responses.jsonl"),
+        responses.each_ref().map(ToString::to_string).join("\n"),
+    )?;
+    let tasks = [
+        json!({
+            "id": "creates-file",
+            "repo": "repo",
+            "prompt": "Create done.txt",
+            "check": "test -f done.txt",
+            "responses": "responses.jsonl",
+        }),
+        json!({
+            "id": "checks-other-file",
+            "repo": "repo",
+            "prompt": "Create done.txt",
+            "check": "test -f missing.txt",
+            "responses": "responses.jsonl",
+        }),
+    ];
+    let suite = suite_dir.path().join("suite.jsonl");
+    fs::write(&suite, tasks.each_ref().map(ToString::to_string).join("\n"))?;
+    let output = suite_dir.path().join("results.jsonl");
+
+    codex_command(codex_home.path())?
+        .arg("eval")
+        .args(["--sandbox", "danger-full-access", "--output"])
+        .arg(&output)
+        .arg(&suite)
+        .assert()
+        .code(1)
+        .stdout(contains("default: 1/2 passed"));
+
+    let results: Vec<Value> = fs::read_to_string(&output)?
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<_, _>>()?;
+    let summary: Vec<(&str, bool, u64, u64)> = results
+        .iter()
+        .map(|result| {
+            (
+                result["id"].as_str().unwrap_or_default(),
+                result["passed"].as_bool().unwrap_or_default(),
+                result["turns"].as_u64().unwrap_or_default(),
+                result["tool_calls"].as_u64().unwrap_or_default(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            ("creates-file", true, 1, 1),
+            ("checks-other-file", false, 1, 1),
+        ]
+    );
+    // The fixture itself is never modified.
+    assert!(!suite_dir.path().join("repo/done.txt").exists());
+
+    Ok(())
+}
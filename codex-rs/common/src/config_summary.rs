@@ -1,30 +1,65 @@
 use codex_core::WireApi;
 use codex_core::config::Config;
+use codex_core::config_layer::ConfigLayer;
 
 use crate::sandbox_summary::summarize_sandbox_policy;
 
 /// Build a list of key/value pairs summarizing the effective configuration.
+/// Values that come from the project config or the command line rather than
+/// the user's own config are annotated with their origin.
 pub fn create_config_summary_entries(config: &Config) -> Vec<(&'static str, String)> {
+    let annotate = |value: String, keys: &[&str]| {
+        let layer = keys
+            .iter()
+            .map(|key| config.value_origin(key))
+            .max()
+            .unwrap_or(ConfigLayer::Default);
+        match layer {
+            ConfigLayer::Project | ConfigLayer::CommandLine => format!("{value} ({layer})"),
+            ConfigLayer::Default | ConfigLayer::User => value,
+        }
+    };
     let mut entries = vec![
         ("workdir", config.cwd.display().to_string()),
-        ("model", config.model.clone()),
-        ("provider", config.model_provider_id.clone()),
-        ("approval", config.approval_policy.to_string()),
-        ("sandbox", summarize_sandbox_policy(&config.sandbox_policy)),
+        ("model", annotate(config.model.clone(), &["model"])),
+        (
+            "provider",
+            annotate(config.model_provider_id.clone(), &["model_provider"]),
+        ),
+        (
+            "approval",
+            annotate(config.approval_policy.to_string(), &["approval_policy"]),
+        ),
+        (
+            "sandbox",
+            annotate(
+                summarize_sandbox_policy(&config.sandbox_policy),
+                &["sandbox_mode", "sandbox_workspace_write"],
+            ),
+        ),
     ];
+    if let Some(project_config) = &config.config_origins.project_config {
+        entries.push(("project config", project_config.display().to_string()));
+    }
     if config.model_provider.wire_api == WireApi::Responses
         && config.model_family.supports_reasoning_summaries
     {
         entries.push((
             "reasoning effort",
-            config
-                .model_reasoning_effort
-                .map(|effort| effort.to_string())
-                .unwrap_or_else(|| "none".to_string()),
+            annotate(
+                config
+                    .model_reasoning_effort
+                    .map(|effort| effort.to_string())
+                    .unwrap_or_else(|| "none".to_string()),
+                &["model_reasoning_effort"],
+            ),
         ));
         entries.push((
             "reasoning summaries",
-            config.model_reasoning_summary.to_string(),
+            annotate(
+                config.model_reasoning_summary.to_string(),
+                &["model_reasoning_summary"],
+            ),
         ));
     }
 
@@ -1,3 +1,4 @@
+use codex_core::protocol::NetworkAccess;
 use codex_core::protocol::SandboxPolicy;
 
 pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
@@ -9,6 +10,8 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
             network_access,
             exclude_tmpdir_env_var,
             exclude_slash_tmp,
+            readable_roots,
+            allowed_domains,
         } => {
             let mut summary = "workspace-write".to_string();
 
@@ -27,8 +30,28 @@ pub fn summarize_sandbox_policy(sandbox_policy: &SandboxPolicy) -> String {
             );
 
             summary.push_str(&format!(" [{}]", writable_entries.join(", ")));
-            if *network_access {
-                summary.push_str(" (network access enabled)");
+            if let Some(readable_roots) = readable_roots {
+                let mut readable_entries = vec!["system".to_string(), "toolchains".to_string()];
+                readable_entries.extend(
+                    readable_roots
+                        .iter()
+                        .map(|p| p.to_string_lossy().to_string()),
+                );
+                summary.push_str(&format!(
+                    " (reads limited to workspace, {})",
+                    readable_entries.join(", ")
+                ));
+            }
+            match network_access {
+                NetworkAccess::Enabled => summary.push_str(" (network access enabled)"),
+                NetworkAccess::Loopback => summary.push_str(" (loopback network only)"),
+                NetworkAccess::Disabled => {}
+            }
+            if *network_access != NetworkAccess::Enabled && !allowed_domains.is_empty() {
+                summary.push_str(&format!(
+                    " (network access limited to {})",
+                    allowed_domains.join(", ")
+                ));
             }
             summary
         }
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use bytes::Bytes;
@@ -5,7 +6,6 @@ use eventsource_stream::Eventsource;
 use futures::Stream;
 use futures::StreamExt;
 use futures::TryStreamExt;
-use reqwest::StatusCode;
 use serde_json::json;
 use std::pin::Pin;
 use std::task::Context;
@@ -23,7 +23,8 @@ use crate::error::CodexErr;
 use crate::error::Result;
 use crate::model_family::ModelFamily;
 use crate::openai_tools::create_tools_json_for_chat_completions_api;
-use crate::util::backoff;
+use crate::provider_health::ProviderHealth;
+use crate::provider_health::send_with_retries;
 use codex_protocol::models::ContentItem;
 use codex_protocol::models::ReasoningItemContent;
 use codex_protocol::models::ResponseItem;
@@ -32,8 +33,10 @@ use codex_protocol::models::ResponseItem;
 pub(crate) async fn stream_chat_completions(
     prompt: &Prompt,
     model_family: &ModelFamily,
+    parallel_tool_calls: bool,
     client: &reqwest::Client,
     provider: &ModelProviderInfo,
+    health: &ProviderHealth,
 ) -> Result<ResponseStream> {
     if prompt.output_schema.is_some() {
         return Err(CodexErr::UnsupportedOperation(
@@ -172,7 +175,14 @@ pub(crate) async fn stream_chat_completions(
                     last_assistant_text = Some(text.clone());
                 }
 
-                let mut msg = json!({"role": role, "content": text});
+                let has_images = content
+                    .iter()
+                    .any(|c| matches!(c, ContentItem::InputImage { .. }));
+                let mut msg = if has_images && provider.supports_images() {
+                    json!({"role": role, "content": content_parts(content)})
+                } else {
+                    json!({"role": role, "content": text})
+                };
                 if role == "assistant"
                     && let Some(reasoning) = reasoning_by_anchor_index.get(&idx)
                     && let Some(obj) = msg.as_object_mut()
@@ -274,12 +284,19 @@ pub(crate) async fn stream_chat_completions(
     }
 
     let tools_json = create_tools_json_for_chat_completions_api(&prompt.tools)?;
-    let payload = json!({
+    let mut payload = json!({
         "model": model_family.slug,
         "messages": messages,
         "stream": true,
         "tools": tools_json,
     });
+    // The API rejects `parallel_tool_calls` when no tools are supplied.
+    if parallel_tool_calls
+        && !tools_json.is_empty()
+        && let Some(obj) = payload.as_object_mut()
+    {
+        obj.insert("parallel_tool_calls".to_string(), json!(true));
+    }
 
     debug!(
         "POST to {}: {}",
@@ -287,89 +304,85 @@ pub(crate) async fn stream_chat_completions(
         serde_json::to_string_pretty(&payload).unwrap_or_default()
     );
 
-    let mut attempt = 0;
-    let max_retries = provider.request_max_retries();
-    loop {
-        attempt += 1;
-
-        let req_builder = provider.create_request_builder(client, &None).await?;
-
-        let res = req_builder
+    let resp = send_with_retries(client, provider, health, |req_builder| {
+        req_builder
             .header(reqwest::header::ACCEPT, "text/event-stream")
             .json(&payload)
-            .send()
-            .await;
-
-        match res {
-            Ok(resp) if resp.status().is_success() => {
-                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
-                let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
-                tokio::spawn(process_chat_sse(
-                    stream,
-                    tx_event,
-                    provider.stream_idle_timeout(),
-                ));
-                return Ok(ResponseStream { rx_event });
-            }
-            Ok(res) => {
-                let status = res.status();
-                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
-                    let body = (res.text().await).unwrap_or_default();
-                    return Err(CodexErr::UnexpectedStatus(status, body));
-                }
-
-                if attempt > max_retries {
-                    return Err(CodexErr::RetryLimit(status));
-                }
-
-                let retry_after_secs = res
-                    .headers()
-                    .get(reqwest::header::RETRY_AFTER)
-                    .and_then(|v| v.to_str().ok())
-                    .and_then(|s| s.parse::<u64>().ok());
+    })
+    .await?;
+
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+    let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+    tokio::spawn(process_chat_sse(
+        stream,
+        tx_event,
+        provider.stream_idle_timeout(),
+        provider.supports_reasoning(),
+    ));
+    Ok(ResponseStream { rx_event })
+}
 
-                let delay = retry_after_secs
-                    .map(|s| Duration::from_millis(s * 1_000))
-                    .unwrap_or_else(|| backoff(attempt));
-                tokio::time::sleep(delay).await;
+/// Converts message content into Chat Completions content parts, sending
+/// images as `image_url` parts.
+fn content_parts(content: &[ContentItem]) -> Vec<serde_json::Value> {
+    content
+        .iter()
+        .map(|c| match c {
+            ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                json!({"type": "text", "text": text})
             }
-            Err(e) => {
-                if attempt > max_retries {
-                    return Err(e.into());
-                }
-                let delay = backoff(attempt);
-                tokio::time::sleep(delay).await;
+            ContentItem::InputImage { image_url } => {
+                json!({"type": "image_url", "image_url": {"url": image_url}})
             }
-        }
-    }
+        })
+        .collect()
+}
+
+/// Extracts the text of a `reasoning` / `reasoning_content` value. Some
+/// providers stream it as a plain string while others nest the text under an
+/// object (e.g. `{ "reasoning": { "text": "…" } }`).
+fn extract_reasoning_text(value: &serde_json::Value) -> Option<&str> {
+    let text = match value {
+        serde_json::Value::String(s) => Some(s.as_str()),
+        serde_json::Value::Object(obj) => obj
+            .get("text")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.is_empty())
+            .or_else(|| obj.get("content").and_then(|v| v.as_str())),
+        _ => None,
+    };
+    text.filter(|s| !s.is_empty())
 }
 
 /// Lightweight SSE processor for the Chat Completions streaming format. The
 /// output is mapped onto Codex's internal [`ResponseEvent`] so that the rest
-/// of the pipeline can stay agnostic of the underlying wire format.
+/// of the pipeline can stay agnostic of the underlying wire format. Reasoning
+/// deltas are ignored unless `include_reasoning` is set.
 async fn process_chat_sse<S>(
     stream: S,
     tx_event: mpsc::Sender<Result<ResponseEvent>>,
     idle_timeout: Duration,
+    include_reasoning: bool,
 ) where
     S: Stream<Item = Result<Bytes>> + Unpin,
 {
     let mut stream = stream.eventsource();
 
-    // State to accumulate a function call across streaming chunks.
+    // State to accumulate function calls across streaming chunks.
     // OpenAI may split the `arguments` string over multiple `delta` events
     // until the chunk whose `finish_reason` is `tool_calls` is emitted. We
-    // keep collecting the pieces here and forward a single
-    // `ResponseItem::FunctionCall` once the call is complete.
+    // keep collecting the pieces here and forward one
+    // `ResponseItem::FunctionCall` per call once they are complete. When
+    // parallel tool calls are enabled, the calls are distinguished by their
+    // `index` field.
     #[derive(Default)]
     struct FunctionCallState {
         name: Option<String>,
         arguments: String,
         call_id: Option<String>,
-        active: bool,
     }
 
-    let mut fn_call_state = FunctionCallState::default();
+    let mut fn_call_states: BTreeMap<u64, FunctionCallState> = BTreeMap::new();
     let mut assistant_text = String::new();
     let mut reasoning_text = String::new();
 
@@ -462,63 +475,23 @@ async fn process_chat_sse<S>(
                     .await;
             }
 
-            // Forward any reasoning/thinking deltas if present.
-            // Some providers stream `reasoning` as a plain string while others
-            // nest the text under an object (e.g. `{ "reasoning": { "text": "…" } }`).
-            if let Some(reasoning_val) = choice.get("delta").and_then(|d| d.get("reasoning")) {
-                let mut maybe_text = reasoning_val
-                    .as_str()
-                    .map(str::to_string)
-                    .filter(|s| !s.is_empty());
-
-                if maybe_text.is_none() && reasoning_val.is_object() {
-                    if let Some(s) = reasoning_val
-                        .get("text")
-                        .and_then(|t| t.as_str())
-                        .filter(|s| !s.is_empty())
-                    {
-                        maybe_text = Some(s.to_string());
-                    } else if let Some(s) = reasoning_val
-                        .get("content")
-                        .and_then(|t| t.as_str())
-                        .filter(|s| !s.is_empty())
-                    {
-                        maybe_text = Some(s.to_string());
-                    }
-                }
-
-                if let Some(reasoning) = maybe_text {
-                    // Accumulate so we can emit a terminal Reasoning item at the end.
-                    reasoning_text.push_str(&reasoning);
-                    let _ = tx_event
-                        .send(Ok(ResponseEvent::ReasoningContentDelta(reasoning)))
-                        .await;
-                }
-            }
-
-            // Some providers only include reasoning on the final message object.
-            if let Some(message_reasoning) = choice.get("message").and_then(|m| m.get("reasoning"))
-            {
-                // Accept either a plain string or an object with { text | content }
-                if let Some(s) = message_reasoning.as_str() {
-                    if !s.is_empty() {
-                        reasoning_text.push_str(s);
-                        let _ = tx_event
-                            .send(Ok(ResponseEvent::ReasoningContentDelta(s.to_string())))
-                            .await;
-                    }
-                } else if let Some(obj) = message_reasoning.as_object()
-                    && let Some(s) = obj
-                        .get("text")
-                        .and_then(|v| v.as_str())
-                        .or_else(|| obj.get("content").and_then(|v| v.as_str()))
-                    && !s.is_empty()
-                {
-                    reasoning_text.push_str(s);
-                    let _ = tx_event
-                        .send(Ok(ResponseEvent::ReasoningContentDelta(s.to_string())))
-                        .await;
-                }
+            // Forward any reasoning/thinking deltas if present. Servers differ
+            // in the field name (`reasoning_content` vs. `reasoning`), and some
+            // only include reasoning on the final message object.
+            let reasoning = ["delta", "message"]
+                .iter()
+                .filter_map(|key| choice.get(*key))
+                .flat_map(|d| [d.get("reasoning_content"), d.get("reasoning")])
+                .flatten()
+                .find_map(extract_reasoning_text);
+            if include_reasoning && let Some(reasoning) = reasoning {
+                // Accumulate so we can emit a terminal Reasoning item at the end.
+                reasoning_text.push_str(reasoning);
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::ReasoningContentDelta(
+                        reasoning.to_string(),
+                    )))
+                    .await;
             }
 
             // Handle streaming function / tool calls.
@@ -526,25 +499,27 @@ async fn process_chat_sse<S>(
                 .get("delta")
                 .and_then(|d| d.get("tool_calls"))
                 .and_then(|tc| tc.as_array())
-                && let Some(tool_call) = tool_calls.first()
             {
-                // Mark that we have an active function call in progress.
-                fn_call_state.active = true;
-
-                // Extract call_id if present.
-                if let Some(id) = tool_call.get("id").and_then(|v| v.as_str()) {
-                    fn_call_state.call_id.get_or_insert_with(|| id.to_string());
-                }
+                for tool_call in tool_calls {
+                    let index = tool_call.get("index").and_then(|v| v.as_u64()).unwrap_or(0);
+                    let fn_call_state = fn_call_states.entry(index).or_default();
 
-                // Extract function details if present.
-                if let Some(function) = tool_call.get("function") {
-                    if let Some(name) = function.get("name").and_then(|n| n.as_str()) {
-                        fn_call_state.name.get_or_insert_with(|| name.to_string());
+                    // Extract call_id if present.
+                    if let Some(id) = tool_call.get("id").and_then(|v| v.as_str()) {
+                        fn_call_state.call_id.get_or_insert_with(|| id.to_string());
                     }
 
-                    if let Some(args_fragment) = function.get("arguments").and_then(|a| a.as_str())
-                    {
-                        fn_call_state.arguments.push_str(args_fragment);
+                    // Extract function details if present.
+                    if let Some(function) = tool_call.get("function") {
+                        if let Some(name) = function.get("name").and_then(|n| n.as_str()) {
+                            fn_call_state.name.get_or_insert_with(|| name.to_string());
+                        }
+
+                        if let Some(args_fragment) =
+                            function.get("arguments").and_then(|a| a.as_str())
+                        {
+                            fn_call_state.arguments.push_str(args_fragment);
+                        }
                     }
                 }
             }
@@ -552,7 +527,7 @@ async fn process_chat_sse<S>(
             // Emit end-of-turn when finish_reason signals completion.
             if let Some(finish_reason) = choice.get("finish_reason").and_then(|v| v.as_str()) {
                 match finish_reason {
-                    "tool_calls" if fn_call_state.active => {
+                    "tool_calls" if !fn_call_states.is_empty() => {
                         // First, flush the terminal raw reasoning so UIs can finalize
                         // the reasoning stream before any exec/tool events begin.
                         if !reasoning_text.is_empty() {
@@ -567,15 +542,17 @@ async fn process_chat_sse<S>(
                             let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
                         }
 
-                        // Then emit the FunctionCall response item.
-                        let item = ResponseItem::FunctionCall {
-                            id: None,
-                            name: fn_call_state.name.clone().unwrap_or_else(|| "".to_string()),
-                            arguments: fn_call_state.arguments.clone(),
-                            call_id: fn_call_state.call_id.clone().unwrap_or_else(String::new),
-                        };
+                        // Then emit the FunctionCall response items in call order.
+                        for fn_call_state in std::mem::take(&mut fn_call_states).into_values() {
+                            let item = ResponseItem::FunctionCall {
+                                id: None,
+                                name: fn_call_state.name.unwrap_or_default(),
+                                arguments: fn_call_state.arguments,
+                                call_id: fn_call_state.call_id.unwrap_or_default(),
+                            };
 
-                        let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                            let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                        }
                     }
                     "stop" => {
                         // Regular turn without tool-call. Emit the final assistant message
@@ -615,7 +592,7 @@ async fn process_chat_sse<S>(
                     .await;
 
                 // Prepare for potential next turn (should not happen in same stream).
-                // fn_call_state = FunctionCallState::default();
+                // fn_call_states.clear();
 
                 return; // End processing for this SSE stream.
             }
@@ -2,6 +2,7 @@ use std::io::BufRead;
 use std::path::Path;
 use std::sync::OnceLock;
 use std::time::Duration;
+use std::time::Instant;
 
 use crate::AuthManager;
 use crate::auth::CodexAuth;
@@ -32,18 +33,24 @@ use crate::client_common::ResponsesApiRequest;
 use crate::client_common::create_reasoning_param_for_request;
 use crate::client_common::create_text_param_for_request;
 use crate::config::Config;
+use crate::config_types::ModelPricing;
 use crate::default_client::create_client;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::UsageLimitReachedError;
 use crate::flags::CODEX_RS_SSE_FIXTURE;
+use crate::messages_api::MessagesRequestParams;
+use crate::messages_api::stream_messages;
 use crate::model_family::ModelFamily;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::WireApi;
-use crate::openai_model_info::get_model_info;
+use crate::openai_model_info::ModelInfo;
+use crate::openai_model_info::get_configured_model_info;
 use crate::openai_tools::create_tools_json_for_responses_api;
 use crate::protocol::RateLimitSnapshot;
 use crate::protocol::TokenUsage;
+use crate::provider_health::ProviderHealth;
+use crate::provider_health::ProviderHealthRegistry;
 use crate::token_data::PlanType;
 use crate::util::backoff;
 use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
@@ -77,6 +84,7 @@ pub struct ModelClient {
     conversation_id: ConversationId,
     effort: Option<ReasoningEffortConfig>,
     summary: ReasoningSummaryConfig,
+    provider_health: Arc<ProviderHealthRegistry>,
 }
 
 impl ModelClient {
@@ -87,6 +95,7 @@ impl ModelClient {
         effort: Option<ReasoningEffortConfig>,
         summary: ReasoningSummaryConfig,
         conversation_id: ConversationId,
+        provider_health: Arc<ProviderHealthRegistry>,
     ) -> Self {
         let client = create_client();
 
@@ -98,23 +107,73 @@ impl ModelClient {
             conversation_id,
             effort,
             summary,
+            provider_health,
+        }
+    }
+
+    /// Rebuilds this client to talk to `model` on another provider, keeping
+    /// the conversation, auth and reasoning settings. Used to fall back to
+    /// another model mid-session.
+    pub fn with_model(
+        &self,
+        model_provider_id: String,
+        provider: ModelProviderInfo,
+        model: String,
+        model_family: ModelFamily,
+    ) -> Self {
+        let model_info = get_configured_model_info(&model_family, &self.config.models);
+        let mut config = (*self.config).clone();
+        config.model = model;
+        config.model_family = model_family;
+        config.model_context_window = model_info.as_ref().map(|info| info.context_window);
+        config.model_max_output_tokens =
+            model_info.as_ref().and_then(|info| info.max_output_tokens);
+        config.model_auto_compact_token_limit =
+            model_info.and_then(|info| info.auto_compact_token_limit);
+        config.model_provider_id = model_provider_id;
+        config.model_provider = provider.clone();
+
+        Self {
+            config: Arc::new(config),
+            auth_manager: self.auth_manager.clone(),
+            client: self.client.clone(),
+            provider,
+            conversation_id: self.conversation_id,
+            effort: self.effort,
+            summary: self.summary,
+            provider_health: self.provider_health.clone(),
         }
     }
 
     pub fn get_model_context_window(&self) -> Option<u64> {
         self.config
             .model_context_window
-            .or_else(|| get_model_info(&self.config.model_family).map(|info| info.context_window))
+            .or_else(|| self.get_model_info().map(|info| info.context_window))
+    }
+
+    /// Token prices for the current model: the `[model_pricing]` entry for its
+    /// slug if configured, otherwise the built-in price, if known.
+    pub fn get_model_pricing(&self) -> Option<ModelPricing> {
+        self.config
+            .model_pricing
+            .get(&self.config.model)
+            .copied()
+            .or_else(|| self.get_model_info().and_then(|info| info.pricing))
     }
 
     pub fn get_auto_compact_token_limit(&self) -> Option<i64> {
         self.config.model_auto_compact_token_limit.or_else(|| {
-            get_model_info(&self.config.model_family).and_then(|info| info.auto_compact_token_limit)
+            self.get_model_info()
+                .and_then(|info| info.auto_compact_token_limit)
         })
     }
 
-    /// Dispatches to either the Responses or Chat implementation depending on
-    /// the provider config.  Public callers always invoke `stream()` – the
+    fn get_model_info(&self) -> Option<ModelInfo> {
+        get_configured_model_info(&self.config.model_family, &self.config.models)
+    }
+
+    /// Dispatches to the Responses, Chat or Messages implementation depending
+    /// on the provider config.  Public callers always invoke `stream()` – the
     /// specialised helpers are private to avoid accidental misuse.
     pub async fn stream(&self, prompt: &Prompt) -> Result<ResponseStream> {
         match self.provider.wire_api {
@@ -124,8 +183,10 @@ impl ModelClient {
                 let response_stream = stream_chat_completions(
                     prompt,
                     &self.config.model_family,
+                    self.config.parallel_tool_calls,
                     &self.client,
                     &self.provider,
+                    &self.provider_health(),
                 )
                 .await?;
 
@@ -154,6 +215,16 @@ impl ModelClient {
 
                 Ok(ResponseStream { rx_event: rx })
             }
+            WireApi::Messages => {
+                let params = MessagesRequestParams {
+                    model_family: &self.config.model_family,
+                    max_output_tokens: self.config.model_max_output_tokens,
+                    effort: self.effort,
+                    parallel_tool_calls: self.config.parallel_tool_calls,
+                };
+                let health = self.provider_health();
+                stream_messages(prompt, params, &self.client, &self.provider, &health).await
+            }
         }
     }
 
@@ -211,7 +282,7 @@ impl ModelClient {
             input: &input_with_instructions,
             tools: &tools_json,
             tool_choice: "auto",
-            parallel_tool_calls: false,
+            parallel_tool_calls: self.config.parallel_tool_calls,
             reasoning,
             store: azure_workaround,
             stream: true,
@@ -228,9 +299,11 @@ impl ModelClient {
 
         let mut attempt = 0;
         let max_retries = self.provider.request_max_retries();
+        let health = self.provider_health();
 
         loop {
             attempt += 1;
+            health.before_request().await?;
 
             // Always fetch the latest auth in case a prior attempt refreshed the token.
             let auth = auth_manager.as_ref().and_then(|m| m.auth());
@@ -261,6 +334,7 @@ impl ModelClient {
                 req_builder = req_builder.header("chatgpt-account-id", account_id);
             }
 
+            let request_start = Instant::now();
             let res = req_builder.send().await;
             if let Ok(resp) = &res {
                 trace!(
@@ -275,15 +349,18 @@ impl ModelClient {
 
             match res {
                 Ok(resp) if resp.status().is_success() => {
+                    health.record_success(request_start.elapsed());
                     let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
 
-                    if let Some(snapshot) = parse_rate_limit_snapshot(resp.headers())
-                        && tx_event
+                    if let Some(snapshot) = parse_rate_limit_snapshot(resp.headers()) {
+                        health.record_rate_limits(&snapshot);
+                        if tx_event
                             .send(Ok(ResponseEvent::RateLimits(snapshot)))
                             .await
                             .is_err()
-                    {
-                        debug!("receiver dropped rate limit snapshot event");
+                        {
+                            debug!("receiver dropped rate limit snapshot event");
+                        }
                     }
 
                     // spawn task to process SSE
@@ -300,11 +377,12 @@ impl ModelClient {
                     let status = res.status();
 
                     // Pull out Retry‑After header if present.
-                    let retry_after_secs = res
+                    let retry_after = res
                         .headers()
                         .get(reqwest::header::RETRY_AFTER)
                         .and_then(|v| v.to_str().ok())
-                        .and_then(|s| s.parse::<u64>().ok());
+                        .and_then(|s| s.parse::<u64>().ok())
+                        .map(Duration::from_secs);
 
                     if status == StatusCode::UNAUTHORIZED
                         && let Some(manager) = auth_manager.as_ref()
@@ -324,11 +402,18 @@ impl ModelClient {
                         || status == StatusCode::UNAUTHORIZED
                         || status.is_server_error())
                     {
+                        health.record_rejected(request_start.elapsed());
                         // Surface the error body to callers. Use `unwrap_or_default` per Clippy.
                         let body = res.text().await.unwrap_or_default();
                         return Err(CodexErr::UnexpectedStatus(status, body));
                     }
 
+                    if status == StatusCode::UNAUTHORIZED {
+                        health.record_rejected(request_start.elapsed());
+                    } else {
+                        health.record_failure(retry_after);
+                    }
+
                     if status == StatusCode::TOO_MANY_REQUESTS {
                         let rate_limit_snapshot = parse_rate_limit_snapshot(res.headers());
                         let body = res.json::<ErrorResponse>().await.ok();
@@ -360,12 +445,11 @@ impl ModelClient {
                         return Err(CodexErr::RetryLimit(status));
                     }
 
-                    let delay = retry_after_secs
-                        .map(|s| Duration::from_millis(s * 1_000))
-                        .unwrap_or_else(|| backoff(attempt));
+                    let delay = retry_after.unwrap_or_else(|| backoff(attempt));
                     tokio::time::sleep(delay).await;
                 }
                 Err(e) => {
+                    health.record_failure(None);
                     if attempt > max_retries {
                         return Err(e.into());
                     }
@@ -376,10 +460,28 @@ impl ModelClient {
         }
     }
 
+    /// Health of this client's provider, shared with every other client of
+    /// the same registry that talks to it.
+    fn provider_health(&self) -> Arc<ProviderHealth> {
+        self.provider_health
+            .for_provider(&self.config.model_provider_id, &self.provider)
+    }
+
+    /// Returns the changes of the provider's circuit state since the last
+    /// call, as messages for the user.
+    pub(crate) fn take_provider_health_notices(&self) -> Vec<String> {
+        self.provider_health().take_notices()
+    }
+
     pub fn get_provider(&self) -> ModelProviderInfo {
         self.provider.clone()
     }
 
+    /// Returns the key of the provider in the `model_providers` map.
+    pub fn get_model_provider_id(&self) -> String {
+        self.config.model_provider_id.clone()
+    }
+
     /// Returns the currently configured model slug.
     pub fn get_model(&self) -> String {
         self.config.model.clone()
@@ -403,6 +505,20 @@ impl ModelClient {
     pub fn get_auth_manager(&self) -> Option<Arc<AuthManager>> {
         self.auth_manager.clone()
     }
+
+    pub fn get_provider_health(&self) -> Arc<ProviderHealthRegistry> {
+        self.provider_health.clone()
+    }
+
+    /// Whether the model may emit several tool calls in one response.
+    pub fn get_parallel_tool_calls(&self) -> bool {
+        self.config.parallel_tool_calls
+    }
+
+    /// Returns the configuration this client was created with.
+    pub fn get_config(&self) -> Arc<Config> {
+        Arc::clone(&self.config)
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -895,6 +1011,9 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            supports_reasoning: None,
+            supports_images: None,
+            interpolate: false,
         };
 
         let events = collect_events(
@@ -955,6 +1074,9 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            supports_reasoning: None,
+            supports_images: None,
+            interpolate: false,
         };
 
         let events = collect_events(&[sse1.as_bytes()], provider).await;
@@ -989,6 +1111,9 @@ mod tests {
             stream_max_retries: Some(0),
             stream_idle_timeout_ms: Some(1000),
             requires_openai_auth: false,
+            supports_reasoning: None,
+            supports_images: None,
+            interpolate: false,
         };
 
         let events = collect_events(&[sse1.as_bytes()], provider).await;
@@ -1094,6 +1219,9 @@ mod tests {
                 stream_max_retries: Some(0),
                 stream_idle_timeout_ms: Some(1000),
                 requires_openai_auth: false,
+                supports_reasoning: None,
+                supports_images: None,
+                interpolate: false,
             };
 
             let out = run_sse(evs, provider).await;
@@ -4,8 +4,11 @@ use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::OnceLock;
+use std::sync::RwLock;
 use std::sync::atomic::AtomicU64;
 use std::time::Duration;
+use std::time::Instant;
 
 use crate::AuthManager;
 use crate::client_common::REVIEW_PROMPT;
@@ -26,8 +29,12 @@ use codex_protocol::protocol::TaskStartedEvent;
 use codex_protocol::protocol::TurnAbortReason;
 use codex_protocol::protocol::TurnAbortedEvent;
 use codex_protocol::protocol::TurnContextItem;
+use futures::future::BoxFuture;
 use futures::prelude::*;
+use futures::stream::FuturesUnordered;
 use mcp_types::CallToolResult;
+use mcp_types::ContentBlock;
+use mcp_types::TextContent;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json;
@@ -42,6 +49,7 @@ use tracing::trace;
 use tracing::warn;
 
 use crate::ModelProviderInfo;
+use crate::WireApi;
 use crate::apply_patch;
 use crate::apply_patch::ApplyPatchExec;
 use crate::apply_patch::CODEX_APPLY_PATCH_ARG1;
@@ -51,8 +59,18 @@ use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::config::Config;
+use crate::config_reload::ConfigChanges;
+use crate::config_reload::watch_config_files;
+use crate::config_reload::watched_config_files;
+use crate::config_reload::with_applied_changes;
+use crate::config_types::LinuxSandboxBackend;
+use crate::config_types::McpServerConfig;
+use crate::config_types::ModelFallback;
+use crate::config_types::ResourceLimits;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::conversation_history::ConversationHistory;
+use crate::delegate_tool::DELEGATE_TOOL_NAME;
+use crate::delegate_tool::handle_delegate_task;
 use crate::environment_context::EnvironmentContext;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
@@ -70,14 +88,25 @@ use crate::exec_command::ExecSessionManager;
 use crate::exec_command::WRITE_STDIN_TOOL_NAME;
 use crate::exec_command::WriteStdinParams;
 use crate::exec_env::create_env;
+use crate::is_safe_command::is_known_safe_command;
 use crate::mcp_connection_manager::McpConnectionManager;
 use crate::mcp_tool_call::handle_mcp_tool_call;
-use crate::model_family::find_family_for_model;
-use crate::openai_model_info::get_model_info;
+use crate::model_family::derive_default_model_family;
+use crate::model_family::find_configured_family_for_model;
+use crate::network_proxy::NetworkProxy;
+use crate::network_proxy::NetworkRequest;
+use crate::openai_model_info::estimate_cost_usd;
+use crate::openai_model_info::get_configured_model_info;
 use crate::openai_tools::ApplyPatchToolArgs;
+use crate::openai_tools::ConfigShellToolType;
 use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::ToolsConfigParams;
 use crate::openai_tools::get_openai_tools;
+use crate::output_schema;
+use crate::output_store::OutputStore;
+use crate::output_store::READ_OUTPUT_TOOL_NAME;
+use crate::output_store::handle_read_output;
+use crate::overlay_sandbox::OverlaySandbox;
 use crate::parse_command::parse_command;
 use crate::plan_tool::handle_update_plan;
 use crate::project_doc::get_user_instructions;
@@ -88,6 +117,7 @@ use crate::protocol::AgentReasoningSectionBreakEvent;
 use crate::protocol::ApplyPatchApprovalRequestEvent;
 use crate::protocol::AskForApproval;
 use crate::protocol::BackgroundEventEvent;
+use crate::protocol::CompactedItem;
 use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
@@ -97,7 +127,9 @@ use crate::protocol::ExecCommandEndEvent;
 use crate::protocol::FileChange;
 use crate::protocol::InputItem;
 use crate::protocol::ListCustomPromptsResponseEvent;
+use crate::protocol::NetworkAccessEvent;
 use crate::protocol::Op;
+use crate::protocol::OutputSchemaValidationFailedEvent;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
 use crate::protocol::RateLimitSnapshot;
@@ -107,18 +139,22 @@ use crate::protocol::SandboxPolicy;
 use crate::protocol::SessionConfiguredEvent;
 use crate::protocol::StreamErrorEvent;
 use crate::protocol::Submission;
+use crate::protocol::TaskBudget;
 use crate::protocol::TaskCompleteEvent;
 use crate::protocol::TokenCountEvent;
 use crate::protocol::TokenUsage;
 use crate::protocol::TokenUsageInfo;
 use crate::protocol::TurnDiffEvent;
 use crate::protocol::WebSearchBeginEvent;
+use crate::provider_health::ProviderHealthRegistry;
 use crate::rollout::RolloutRecorder;
 use crate::rollout::RolloutRecorderParams;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
 use crate::safety::assess_safety_for_untrusted_command;
+use crate::sandbox_denials::explain_denials;
 use crate::shell;
+use crate::tokenizer::TokenEstimator;
 use crate::turn_diff_tracker::TurnDiffTracker;
 use crate::unified_exec::UnifiedExecSessionManager;
 use crate::user_instructions::UserInstructions;
@@ -136,6 +172,7 @@ use codex_protocol::models::ShellToolCallParams;
 use codex_protocol::protocol::InitialHistory;
 
 pub mod compact;
+use self::compact::apply_compaction_stage;
 use self::compact::build_compacted_history;
 use self::compact::collect_user_messages;
 
@@ -164,12 +201,16 @@ pub(crate) const MODEL_FORMAT_MAX_LINES: usize = 256; // lines
 pub(crate) const MODEL_FORMAT_HEAD_LINES: usize = MODEL_FORMAT_MAX_LINES / 2;
 pub(crate) const MODEL_FORMAT_TAIL_LINES: usize = MODEL_FORMAT_MAX_LINES - MODEL_FORMAT_HEAD_LINES; // 128
 pub(crate) const MODEL_FORMAT_HEAD_BYTES: usize = MODEL_FORMAT_MAX_BYTES / 2;
+// Upper bound on any single tool output recorded in history, as counted by the
+// model's tokenizer.
+pub(crate) const MODEL_FORMAT_MAX_TOKENS: u64 = 8 * 1024;
 
 impl Codex {
     /// Spawn a new [`Codex`] and initialize the session.
     pub async fn spawn(
         config: Config,
         auth_manager: Arc<AuthManager>,
+        provider_health: Arc<ProviderHealthRegistry>,
         conversation_history: InitialHistory,
     ) -> CodexResult<CodexSpawnOk> {
         let (tx_sub, rx_sub) = async_channel::bounded(SUBMISSION_CHANNEL_CAPACITY);
@@ -197,6 +238,7 @@ impl Codex {
             configure_session,
             config.clone(),
             auth_manager.clone(),
+            provider_health,
             tx_event.clone(),
             conversation_history,
         )
@@ -207,6 +249,13 @@ impl Codex {
         })?;
         let conversation_id = session.conversation_id;
 
+        // Apply edits to config.toml while the session is running. The watcher
+        // stops once the submission channel is closed.
+        if config.load_args.is_some() {
+            let paths = watched_config_files(&config);
+            tokio::spawn(watch_config_files(paths, tx_sub.downgrade()));
+        }
+
         // This task will run until Op::Shutdown is received.
         tokio::spawn(submission_loop(session, turn_context, config, rx_sub));
         let codex = Codex {
@@ -262,6 +311,8 @@ struct State {
     history: ConversationHistory,
     token_info: Option<TokenUsageInfo>,
     latest_rate_limits: Option<RateLimitSnapshot>,
+    /// Whether the user was told that the provider does not receive images.
+    images_left_out_reported: bool,
 }
 
 /// Context for an initialized model agent
@@ -271,8 +322,9 @@ pub(crate) struct Session {
     conversation_id: ConversationId,
     tx_event: Sender<Event>,
 
-    /// Manager for external MCP servers/tools.
-    mcp_connection_manager: McpConnectionManager,
+    /// Manager for external MCP servers/tools. Replaced when the configured
+    /// servers change.
+    mcp_connection_manager: RwLock<Arc<McpConnectionManager>>,
     session_manager: ExecSessionManager,
     unified_exec_manager: UnifiedExecSessionManager,
 
@@ -283,6 +335,10 @@ pub(crate) struct Session {
     rollout: Mutex<Option<RolloutRecorder>>,
     state: Mutex<State>,
     codex_linux_sandbox_exe: Option<PathBuf>,
+    resource_limits: ResourceLimits,
+    linux_sandbox_backend: LinuxSandboxBackend,
+    /// Full output of the commands run in this session, for `read_output`.
+    output_store: OutputStore,
     user_shell: shell::Shell,
     show_raw_agent_reasoning: bool,
     next_internal_sub_id: AtomicU64,
@@ -312,6 +368,51 @@ impl TurnContext {
             .map(PathBuf::from)
             .map_or_else(|| self.cwd.clone(), |p| self.cwd.join(p))
     }
+
+    /// Returns a copy of this context whose client talks to the `fallback`
+    /// model instead. The tools are kept as-is so the same prompt can be
+    /// retried unchanged.
+    fn with_fallback_model(
+        &self,
+        fallback: &ModelFallback,
+        provider: ModelProviderInfo,
+    ) -> TurnContext {
+        let config = self.client.get_config();
+        let model_family = find_configured_family_for_model(&fallback.model, &config.models)
+            .unwrap_or_else(|| derive_default_model_family(&fallback.model));
+        // Keep the tools this turn offers, but let the fallback model's family
+        // pick the shell and apply_patch variants it understands.
+        let tools_config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: self.tools_config.plan_tool,
+            include_apply_patch_tool: config.include_apply_patch_tool,
+            include_web_search_request: self.tools_config.web_search_request,
+            use_streamable_shell_tool: matches!(
+                self.tools_config.shell_type,
+                ConfigShellToolType::Streamable
+            ),
+            include_view_image_tool: self.tools_config.include_view_image_tool,
+            experimental_unified_exec_tool: self.tools_config.experimental_unified_exec_tool,
+            include_delegate_tool: self.tools_config.delegate_tool,
+        });
+        TurnContext {
+            client: self.client.with_model(
+                fallback.model_provider.clone(),
+                provider,
+                fallback.model.clone(),
+                model_family,
+            ),
+            cwd: self.cwd.clone(),
+            base_instructions: self.base_instructions.clone(),
+            user_instructions: self.user_instructions.clone(),
+            approval_policy: self.approval_policy,
+            sandbox_policy: self.sandbox_policy.clone(),
+            shell_environment_policy: self.shell_environment_policy.clone(),
+            tools_config,
+            is_review_mode: self.is_review_mode,
+            final_output_json_schema: self.final_output_json_schema.clone(),
+        }
+    }
 }
 
 /// Configure the model session.
@@ -353,6 +454,7 @@ impl Session {
         configure_session: ConfigureSession,
         config: Arc<Config>,
         auth_manager: Arc<AuthManager>,
+        provider_health: Arc<ProviderHealthRegistry>,
         tx_event: Sender<Event>,
         initial_history: InitialHistory,
     ) -> anyhow::Result<(Arc<Self>, TurnContext)> {
@@ -452,6 +554,7 @@ impl Session {
             model_reasoning_effort,
             model_reasoning_summary,
             conversation_id,
+            provider_health,
         );
         let turn_context = TurnContext {
             client,
@@ -463,6 +566,7 @@ impl Session {
                 use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                 include_view_image_tool: config.include_view_image_tool,
                 experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+                include_delegate_tool: config.include_delegate_tool,
             }),
             user_instructions,
             base_instructions,
@@ -476,13 +580,16 @@ impl Session {
         let sess = Arc::new(Session {
             conversation_id,
             tx_event: tx_event.clone(),
-            mcp_connection_manager,
+            mcp_connection_manager: RwLock::new(Arc::new(mcp_connection_manager)),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
             notifier: notify,
             state: Mutex::new(state),
             rollout: Mutex::new(Some(rollout_recorder)),
             codex_linux_sandbox_exe: config.codex_linux_sandbox_exe.clone(),
+            resource_limits: config.resource_limits,
+            linux_sandbox_backend: config.linux_sandbox_backend,
+            output_store: OutputStore::default(),
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
             next_internal_sub_id: AtomicU64::new(0),
@@ -617,6 +724,27 @@ impl Session {
         action: &ApplyPatchAction,
         reason: Option<String>,
         grant_root: Option<PathBuf>,
+    ) -> oneshot::Receiver<ReviewDecision> {
+        self.request_file_changes_approval(
+            sub_id,
+            call_id,
+            convert_apply_patch_to_protocol(action),
+            reason,
+            grant_root,
+        )
+        .await
+    }
+
+    /// Asks the user to approve file changes that were not made by
+    /// `apply_patch`, such as the writes of a command run under the overlay
+    /// sandbox. They are shown like a patch.
+    async fn request_file_changes_approval(
+        &self,
+        sub_id: String,
+        call_id: String,
+        changes: HashMap<PathBuf, FileChange>,
+        reason: Option<String>,
+        grant_root: Option<PathBuf>,
     ) -> oneshot::Receiver<ReviewDecision> {
         // Add the tx_approve callback to the map before sending the request.
         let (tx_approve, rx_approve) = oneshot::channel();
@@ -633,7 +761,7 @@ impl Session {
             id: event_id,
             msg: EventMsg::ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent {
                 call_id,
-                changes: convert_apply_patch_to_protocol(action),
+                changes,
                 reason,
                 grant_root,
             }),
@@ -680,16 +808,21 @@ impl Session {
                 RolloutItem::ResponseItem(response_item) => {
                     history.record_items(std::iter::once(response_item));
                 }
-                RolloutItem::Compacted(compacted) => {
+                RolloutItem::Compacted(CompactedItem::Summary { message }) => {
                     let snapshot = history.contents();
                     let user_messages = collect_user_messages(&snapshot);
                     let rebuilt = build_compacted_history(
                         self.build_initial_context(turn_context),
                         &user_messages,
-                        &compacted.message,
+                        message,
                     );
                     history.replace(rebuilt);
                 }
+                RolloutItem::Compacted(stage) => {
+                    let mut items = history.contents();
+                    apply_compaction_stage(&mut items, stage);
+                    history.replace(items);
+                }
                 _ => {}
             }
         }
@@ -746,17 +879,42 @@ impl Session {
         {
             let mut state = self.state.lock().await;
             if let Some(token_usage) = token_usage {
-                let info = TokenUsageInfo::new_or_append(
+                let mut info = TokenUsageInfo::new_or_append(
                     &state.token_info,
                     &Some(token_usage.clone()),
                     turn_context.client.get_model_context_window(),
                 );
+                if let Some(info) = info.as_mut()
+                    && let Some(pricing) = turn_context.client.get_model_pricing()
+                {
+                    info.append_cost_usd(estimate_cost_usd(&pricing, token_usage));
+                }
                 state.token_info = info;
             }
         }
         self.send_token_count_event(sub_id).await;
     }
 
+    /// Output tokens generated so far in this session, as tracked by the
+    /// session's [`TokenUsageInfo`] totals.
+    async fn total_output_tokens(&self) -> u64 {
+        let state = self.state.lock().await;
+        state
+            .token_info
+            .as_ref()
+            .map(|info| info.total_token_usage.output_tokens)
+            .unwrap_or(0)
+    }
+
+    /// Estimated cost of this session so far, if any priced usage was seen.
+    async fn total_cost_usd(&self) -> Option<f64> {
+        let state = self.state.lock().await;
+        state
+            .token_info
+            .as_ref()
+            .and_then(|info| info.total_cost_usd)
+    }
+
     async fn update_rate_limits(&self, sub_id: &str, new_rate_limits: RateLimitSnapshot) {
         {
             let mut state = self.state.lock().await;
@@ -861,7 +1019,12 @@ impl Session {
         // Send full stdout/stderr to clients; do not truncate.
         let stdout = stdout.text.clone();
         let stderr = stderr.text.clone();
-        let formatted_output = format_exec_output_str(output);
+        let output_handle = if is_apply_patch {
+            None
+        } else {
+            self.output_store.save(call_id, &aggregated_output.text)
+        };
+        let formatted_output = format_exec_output_str(output, output_handle.as_deref());
         let aggregated_output: String = aggregated_output.text.clone();
 
         let msg = if is_apply_patch {
@@ -892,16 +1055,106 @@ impl Session {
         // If this is an apply_patch, after we emit the end patch, emit a second event
         // with the full turn diff if there is one.
         if is_apply_patch {
-            let unified_diff = turn_diff_tracker.get_unified_diff();
-            if let Ok(Some(unified_diff)) = unified_diff {
-                let msg = EventMsg::TurnDiff(TurnDiffEvent { unified_diff });
-                let event = Event {
-                    id: sub_id.into(),
-                    msg,
-                };
-                self.send_event(event).await;
+            self.send_turn_diff(turn_diff_tracker, sub_id).await;
+        }
+    }
+
+    async fn send_turn_diff(&self, turn_diff_tracker: &mut TurnDiffTracker, sub_id: &str) {
+        let unified_diff = turn_diff_tracker.get_unified_diff();
+        if let Ok(Some(unified_diff)) = unified_diff {
+            let msg = EventMsg::TurnDiff(TurnDiffEvent { unified_diff });
+            let event = Event {
+                id: sub_id.into(),
+                msg,
+            };
+            self.send_event(event).await;
+        }
+    }
+
+    /// Shows the writes of a command that ran under the overlay sandbox as a
+    /// patch and copies them into the workspace once the user approves them.
+    /// Returns a note for the model on what became of them, if there were any.
+    async fn apply_overlay_changes(
+        &self,
+        turn_diff_tracker: &mut TurnDiffTracker,
+        approval_policy: AskForApproval,
+        sub_id: &str,
+        call_id: &str,
+        overlay_sandbox: &OverlaySandbox,
+    ) -> Option<String> {
+        let collected = overlay_sandbox.collect_changes().and_then(|changes| {
+            let file_changes = changes.file_changes()?;
+            Ok((changes, file_changes))
+        });
+        let (changes, file_changes) = match collected {
+            Ok((changes, _)) if changes.is_empty() => return None,
+            Ok(collected) => collected,
+            Err(e) => {
+                return Some(format!(
+                    "failed to read the file changes made by the command, they were discarded: {e}"
+                ));
             }
+        };
+
+        if approval_policy == AskForApproval::Never {
+            return Some(
+                "the command ran against a copy of the workspace and its file changes were discarded because they cannot be approved".to_string(),
+            );
         }
+        let rx_approve = self
+            .request_file_changes_approval(
+                sub_id.to_string(),
+                call_id.to_string(),
+                file_changes.clone(),
+                Some(
+                    "The command ran against a copy of the workspace. Apply its changes?"
+                        .to_string(),
+                ),
+                None,
+            )
+            .await;
+        match rx_approve.await.unwrap_or_default() {
+            ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {}
+            ReviewDecision::Denied | ReviewDecision::Abort => {
+                return Some(
+                    "the user rejected the file changes made by the command, the workspace is unchanged".to_string(),
+                );
+            }
+        }
+
+        turn_diff_tracker.on_patch_begin(&file_changes);
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
+                call_id: call_id.to_string(),
+                auto_approved: false,
+                changes: file_changes,
+            }),
+        })
+        .await;
+        let result = changes.apply();
+        self.send_event(Event {
+            id: sub_id.to_string(),
+            msg: EventMsg::PatchApplyEnd(PatchApplyEndEvent {
+                call_id: call_id.to_string(),
+                stdout: String::new(),
+                stderr: result
+                    .as_ref()
+                    .err()
+                    .map(ToString::to_string)
+                    .unwrap_or_default(),
+                success: result.is_ok(),
+            }),
+        })
+        .await;
+        self.send_turn_diff(turn_diff_tracker, sub_id).await;
+
+        Some(match result {
+            Ok(()) => {
+                "the file changes made by the command were applied to the workspace".to_string()
+            }
+            Err(e) => format!("failed to apply the file changes made by the command: {e}"),
+        })
     }
     /// Runs the exec tool call and emits events for the begin and end of the
     /// command even on error.
@@ -920,20 +1173,36 @@ impl Session {
         self.on_exec_command_begin(turn_diff_tracker, begin_ctx.clone())
             .await;
 
+        let mut params = exec_args.params;
+        let network_proxy = self
+            .start_network_proxy(
+                &sub_id,
+                &call_id,
+                exec_args.sandbox_type,
+                exec_args.sandbox_policy,
+            )
+            .await;
+        if let Some(network_proxy) = &network_proxy {
+            network_proxy.apply_to_env(&mut params.env);
+        }
+
         let result = process_exec_tool_call(
-            exec_args.params,
+            params,
             exec_args.sandbox_type,
             exec_args.sandbox_policy,
             exec_args.sandbox_cwd,
             exec_args.codex_linux_sandbox_exe,
+            exec_args.resource_limits,
             exec_args.stdout_stream,
         )
         .await;
+        drop(network_proxy);
 
         let output_stderr;
         let borrowed: &ExecToolCallOutput = match &result {
             Ok(output) => output,
-            Err(CodexErr::Sandbox(SandboxErr::Timeout { output })) => output,
+            Err(CodexErr::Sandbox(SandboxErr::Timeout { output }))
+            | Err(CodexErr::Sandbox(SandboxErr::ResourceLimitExceeded { output, .. })) => output,
             Err(e) => {
                 output_stderr = ExecToolCallOutput {
                     exit_code: -1,
@@ -958,6 +1227,79 @@ impl Session {
         result
     }
 
+    /// Starts a network proxy for a sandboxed command when the sandbox policy
+    /// limits network access to `allowed_domains`. Each request that goes
+    /// through the proxy is reported as a `NetworkAccess` event.
+    async fn start_network_proxy(
+        &self,
+        sub_id: &str,
+        call_id: &str,
+        sandbox_type: SandboxType,
+        sandbox_policy: &SandboxPolicy,
+    ) -> Option<NetworkProxy> {
+        let allowed_domains = sandbox_policy.allowed_domains();
+        if sandbox_type == SandboxType::None || allowed_domains.is_empty() {
+            return None;
+        }
+
+        let tx_event = self.tx_event.clone();
+        let event_sub_id = sub_id.to_string();
+        let event_call_id = call_id.to_string();
+        let on_request = move |request: NetworkRequest| {
+            let NetworkRequest {
+                host,
+                port,
+                allowed,
+            } = request;
+            let event = Event {
+                id: event_sub_id.clone(),
+                msg: EventMsg::NetworkAccess(NetworkAccessEvent {
+                    call_id: event_call_id.clone(),
+                    host,
+                    port,
+                    allowed,
+                }),
+            };
+            if let Err(e) = tx_event.try_send(event) {
+                warn!("failed to send network access event: {e}");
+            }
+        };
+        match NetworkProxy::start(allowed_domains.to_vec(), on_request).await {
+            Ok(network_proxy) => Some(network_proxy),
+            Err(e) => {
+                self.notify_background_event(
+                    sub_id,
+                    format!(
+                        "failed to start the network proxy, network access stays disabled: {e}"
+                    ),
+                )
+                .await;
+                None
+            }
+        }
+    }
+
+    /// Tells the user, once per session, that images are not sent to
+    /// `provider_name`.
+    async fn notify_images_left_out(&self, sub_id: &str, provider_name: &str) {
+        {
+            let mut state = self.state.lock().await;
+            if state.images_left_out_reported {
+                return;
+            }
+            state.images_left_out_reported = true;
+        }
+        self.notify_background_event(
+            sub_id,
+            format!(
+                "Images are not sent to {provider_name}, so the model only sees the text of \
+                 messages with images. Set `supports_images = true` for the provider if its \
+                 Chat Completions endpoint accepts them."
+            ),
+        )
+        .await;
+    }
+
     /// Helper that emits a BackgroundEvent with the given message. This keeps
     /// the call‑sites terse so adding more diagnostics does not clutter the
     /// core agent logic.
@@ -982,13 +1324,33 @@ impl Session {
     }
 
     /// Build the full turn input by concatenating the current conversation
-    /// history with additional items for this turn.
-    pub async fn turn_input_with_history(&self, extra: Vec<ResponseItem>) -> Vec<ResponseItem> {
-        let history = {
+    /// history with additional items for this turn. Also returns the estimated
+    /// size in tokens of that input, so that the caller can compact before
+    /// sending a request that would overflow the context window.
+    pub async fn turn_input_with_history(
+        &self,
+        turn_context: &TurnContext,
+        extra: Vec<ResponseItem>,
+    ) -> (Vec<ResponseItem>, u64) {
+        let estimator = TokenEstimator::for_model_family(&turn_context.client.get_model_family());
+        let (history, reported_estimate) = {
             let state = self.state.lock().await;
-            state.history.contents()
+            (
+                state.history.contents(),
+                state.history.estimate_tokens_from_report(estimator),
+            )
+        };
+        let extra_tokens = estimator.count_items(&extra);
+        let input = [history, extra].concat();
+        // Without a size reported for the current history, e.g. on the first
+        // turn of a resumed session or after compaction, count all of it. The
+        // instructions are left out: they are sent with every request, so a
+        // limit below their size would compact before every turn.
+        let estimated_tokens = match reported_estimate {
+            Some(history_tokens) => history_tokens + extra_tokens,
+            None => estimator.count_items(&input),
         };
-        [history, extra].concat()
+        (input, estimated_tokens)
     }
 
     /// Returns the input if there was no task running to inject into
@@ -1019,11 +1381,57 @@ impl Session {
         tool: &str,
         arguments: Option<serde_json::Value>,
     ) -> anyhow::Result<CallToolResult> {
-        self.mcp_connection_manager
+        self.mcp_connection_manager()
             .call_tool(server, tool, arguments)
             .await
     }
 
+    fn mcp_connection_manager(&self) -> Arc<McpConnectionManager> {
+        self.mcp_connection_manager
+            .read()
+            .map(|manager| Arc::clone(&manager))
+            .unwrap_or_default()
+    }
+
+    /// Start the MCP servers in `mcp_servers` whose configuration changed,
+    /// stop the ones that are no longer configured and report the servers
+    /// that failed to start.
+    async fn reconfigure_mcp_servers(
+        &self,
+        sub_id: &str,
+        mcp_servers: HashMap<String, McpServerConfig>,
+    ) {
+        let current = self.mcp_connection_manager();
+        let failed_clients = match current.reconfigure(mcp_servers).await {
+            Ok((manager, failed_clients)) => {
+                if let Ok(mut guard) = self.mcp_connection_manager.write() {
+                    *guard = Arc::new(manager);
+                }
+                failed_clients
+            }
+            Err(e) => {
+                let message = format!("Failed to reconfigure MCP servers: {e:#}");
+                error!("{message}");
+                self.send_event(Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::Error(ErrorEvent { message }),
+                })
+                .await;
+                return;
+            }
+        };
+
+        for (server_name, err) in failed_clients {
+            let message = format!("MCP client for `{server_name}` failed to start: {err:#}");
+            error!("{message}");
+            self.send_event(Event {
+                id: sub_id.to_string(),
+                msg: EventMsg::Error(ErrorEvent { message }),
+            })
+            .await;
+        }
+    }
+
     pub async fn interrupt_task(&self) {
         info!("interrupt received: abort current task, if any");
         let mut state = self.state.lock().await;
@@ -1166,10 +1574,120 @@ impl AgentTask {
     }
 }
 
+/// Settings that replace the corresponding values of the persistent turn
+/// context. `None` keeps the current value.
+struct TurnContextOverrides {
+    cwd: Option<PathBuf>,
+    approval_policy: Option<AskForApproval>,
+    sandbox_policy: Option<SandboxPolicy>,
+    model: Option<String>,
+    effort: Option<Option<ReasoningEffortConfig>>,
+    summary: Option<ReasoningSummaryConfig>,
+    shell_environment_policy: Option<ShellEnvironmentPolicy>,
+}
+
+/// Recalculate the persistent turn context with the provided overrides.
+async fn override_turn_context(
+    sess: &Session,
+    config: &Config,
+    prev: &TurnContext,
+    overrides: TurnContextOverrides,
+) -> Arc<TurnContext> {
+    let TurnContextOverrides {
+        cwd,
+        approval_policy,
+        sandbox_policy,
+        model,
+        effort,
+        summary,
+        shell_environment_policy,
+    } = overrides;
+    let provider = prev.client.get_provider();
+
+    // Effective model + family
+    let (effective_model, effective_family) = if let Some(ref m) = model {
+        let fam = find_configured_family_for_model(m, &config.models)
+            .unwrap_or_else(|| config.model_family.clone());
+        (m.clone(), fam)
+    } else {
+        (prev.client.get_model(), prev.client.get_model_family())
+    };
+
+    // Effective reasoning settings
+    let effective_effort = effort.unwrap_or(prev.client.get_reasoning_effort());
+    let effective_summary = summary.unwrap_or(prev.client.get_reasoning_summary());
+
+    let auth_manager = prev.client.get_auth_manager();
+    let provider_health = prev.client.get_provider_health();
+
+    // Build updated config for the client
+    let mut updated_config = config.clone();
+    updated_config.model = effective_model.clone();
+    updated_config.model_family = effective_family.clone();
+    if let Some(model_info) = get_configured_model_info(&effective_family, &config.models) {
+        updated_config.model_context_window = Some(model_info.context_window);
+    }
+
+    let client = ModelClient::new(
+        Arc::new(updated_config),
+        auth_manager,
+        provider,
+        effective_effort,
+        effective_summary,
+        sess.conversation_id,
+        provider_health,
+    );
+
+    let new_approval_policy = approval_policy.unwrap_or(prev.approval_policy);
+    let new_sandbox_policy = sandbox_policy
+        .clone()
+        .unwrap_or(prev.sandbox_policy.clone());
+    let new_cwd = cwd.clone().unwrap_or_else(|| prev.cwd.clone());
+
+    let tools_config = ToolsConfig::new(&ToolsConfigParams {
+        model_family: &effective_family,
+        include_plan_tool: config.include_plan_tool,
+        include_apply_patch_tool: config.include_apply_patch_tool,
+        include_web_search_request: config.tools_web_search_request,
+        use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
+        include_view_image_tool: config.include_view_image_tool,
+        experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+        include_delegate_tool: config.include_delegate_tool,
+    });
+
+    let new_turn_context = TurnContext {
+        client,
+        tools_config,
+        user_instructions: prev.user_instructions.clone(),
+        base_instructions: prev.base_instructions.clone(),
+        approval_policy: new_approval_policy,
+        sandbox_policy: new_sandbox_policy.clone(),
+        shell_environment_policy: shell_environment_policy
+            .unwrap_or_else(|| prev.shell_environment_policy.clone()),
+        cwd: new_cwd.clone(),
+        is_review_mode: false,
+        final_output_json_schema: None,
+    };
+
+    // Optionally persist changes to model / effort
+    if cwd.is_some() || approval_policy.is_some() || sandbox_policy.is_some() {
+        sess.record_conversation_items(&[ResponseItem::from(EnvironmentContext::new(
+            cwd,
+            approval_policy,
+            sandbox_policy,
+            // Shell is not configurable from turn to turn
+            None,
+        ))])
+        .await;
+    }
+
+    Arc::new(new_turn_context)
+}
+
 async fn submission_loop(
     sess: Arc<Session>,
     turn_context: TurnContext,
-    config: Arc<Config>,
+    mut config: Arc<Config>,
     rx_sub: Receiver<Submission>,
 ) {
     // Wrap once to avoid cloning TurnContext for each task.
@@ -1189,85 +1707,68 @@ async fn submission_loop(
                 effort,
                 summary,
             } => {
-                // Recalculate the persistent turn context with provided overrides.
-                let prev = Arc::clone(&turn_context);
-                let provider = prev.client.get_provider();
-
-                // Effective model + family
-                let (effective_model, effective_family) = if let Some(ref m) = model {
-                    let fam =
-                        find_family_for_model(m).unwrap_or_else(|| config.model_family.clone());
-                    (m.clone(), fam)
-                } else {
-                    (prev.client.get_model(), prev.client.get_model_family())
+                let overrides = TurnContextOverrides {
+                    cwd,
+                    approval_policy,
+                    sandbox_policy,
+                    model,
+                    effort,
+                    summary,
+                    shell_environment_policy: None,
                 };
-
-                // Effective reasoning settings
-                let effective_effort = effort.unwrap_or(prev.client.get_reasoning_effort());
-                let effective_summary = summary.unwrap_or(prev.client.get_reasoning_summary());
-
-                let auth_manager = prev.client.get_auth_manager();
-
-                // Build updated config for the client
-                let mut updated_config = (*config).clone();
-                updated_config.model = effective_model.clone();
-                updated_config.model_family = effective_family.clone();
-                if let Some(model_info) = get_model_info(&effective_family) {
-                    updated_config.model_context_window = Some(model_info.context_window);
-                }
-
-                let client = ModelClient::new(
-                    Arc::new(updated_config),
-                    auth_manager,
-                    provider,
-                    effective_effort,
-                    effective_summary,
-                    sess.conversation_id,
-                );
-
-                let new_approval_policy = approval_policy.unwrap_or(prev.approval_policy);
-                let new_sandbox_policy = sandbox_policy
-                    .clone()
-                    .unwrap_or(prev.sandbox_policy.clone());
-                let new_cwd = cwd.clone().unwrap_or_else(|| prev.cwd.clone());
-
-                let tools_config = ToolsConfig::new(&ToolsConfigParams {
-                    model_family: &effective_family,
-                    include_plan_tool: config.include_plan_tool,
-                    include_apply_patch_tool: config.include_apply_patch_tool,
-                    include_web_search_request: config.tools_web_search_request,
-                    use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
-                    include_view_image_tool: config.include_view_image_tool,
-                    experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
-                });
-
-                let new_turn_context = TurnContext {
-                    client,
-                    tools_config,
-                    user_instructions: prev.user_instructions.clone(),
-                    base_instructions: prev.base_instructions.clone(),
-                    approval_policy: new_approval_policy,
-                    sandbox_policy: new_sandbox_policy.clone(),
-                    shell_environment_policy: prev.shell_environment_policy.clone(),
-                    cwd: new_cwd.clone(),
-                    is_review_mode: false,
-                    final_output_json_schema: None,
+                turn_context =
+                    override_turn_context(&sess, &config, &turn_context, overrides).await;
+            }
+            Op::ReloadConfig => {
+                // Reloading reads the config files and may run the commands
+                // of `$(...)` references, so keep it off the runtime threads.
+                let running = Arc::clone(&config);
+                let reloaded = match tokio::task::spawn_blocking(move || running.reload())
+                    .await
+                    .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+                {
+                    Ok(reloaded) => reloaded,
+                    Err(e) => {
+                        warn!("failed to reload config: {e:#}");
+                        sess.notify_background_event(
+                            &sub.id,
+                            format!("Failed to reload config.toml: {e}"),
+                        )
+                        .await;
+                        continue;
+                    }
                 };
+                let changes = ConfigChanges::between(&config, &reloaded);
+                if changes.is_empty() {
+                    continue;
+                }
 
-                // Install the new persistent context for subsequent tasks/turns.
-                turn_context = Arc::new(new_turn_context);
-
-                // Optionally persist changes to model / effort
-                if cwd.is_some() || approval_policy.is_some() || sandbox_policy.is_some() {
-                    sess.record_conversation_items(&[ResponseItem::from(EnvironmentContext::new(
-                        cwd,
-                        approval_policy,
-                        sandbox_policy,
-                        // Shell is not configurable from turn to turn
-                        None,
-                    ))])
-                    .await;
+                config = Arc::new(with_applied_changes(&config, &reloaded, &changes));
+                if changes.affects_turn_context() {
+                    let changed = |key| changes.applied.contains(&key);
+                    let overrides = TurnContextOverrides {
+                        cwd: None,
+                        approval_policy: changed("approval_policy")
+                            .then_some(reloaded.approval_policy),
+                        sandbox_policy: changed("sandbox_mode")
+                            .then(|| reloaded.sandbox_policy.clone()),
+                        model: changed("model").then(|| reloaded.model.clone()),
+                        effort: changed("model_reasoning_effort")
+                            .then_some(reloaded.model_reasoning_effort),
+                        summary: changed("model_reasoning_summary")
+                            .then_some(reloaded.model_reasoning_summary),
+                        shell_environment_policy: changed("shell_environment_policy")
+                            .then(|| reloaded.shell_environment_policy.clone()),
+                    };
+                    turn_context =
+                        override_turn_context(&sess, &config, &turn_context, overrides).await;
+                }
+                if changes.applied.contains(&"mcp_servers") {
+                    sess.reconfigure_mcp_servers(&sub.id, reloaded.mcp_servers.clone())
+                        .await;
                 }
+                sess.notify_background_event(&sub.id, changes.message())
+                    .await;
             }
             Op::UserInput { items } => {
                 // attempt to inject input into current task
@@ -1293,16 +1794,19 @@ async fn submission_loop(
                     // Derive a fresh TurnContext for this turn using the provided overrides.
                     let provider = turn_context.client.get_provider();
                     let auth_manager = turn_context.client.get_auth_manager();
+                    let provider_health = turn_context.client.get_provider_health();
 
                     // Derive a model family for the requested model; fall back to the session's.
-                    let model_family = find_family_for_model(&model)
+                    let model_family = find_configured_family_for_model(&model, &config.models)
                         .unwrap_or_else(|| config.model_family.clone());
 
                     // Create a per‑turn Config clone with the requested model/family.
                     let mut per_turn_config = (*config).clone();
                     per_turn_config.model = model.clone();
                     per_turn_config.model_family = model_family.clone();
-                    if let Some(model_info) = get_model_info(&model_family) {
+                    if let Some(model_info) =
+                        get_configured_model_info(&model_family, &config.models)
+                    {
                         per_turn_config.model_context_window = Some(model_info.context_window);
                     }
 
@@ -1315,6 +1819,7 @@ async fn submission_loop(
                         effort,
                         summary,
                         sess.conversation_id,
+                        provider_health,
                     );
 
                     let fresh_turn_context = TurnContext {
@@ -1329,6 +1834,7 @@ async fn submission_loop(
                             include_view_image_tool: config.include_view_image_tool,
                             experimental_unified_exec_tool: config
                                 .use_experimental_unified_exec_tool,
+                            include_delegate_tool: config.include_delegate_tool,
                         }),
                         user_instructions: turn_context.user_instructions.clone(),
                         base_instructions: turn_context.base_instructions.clone(),
@@ -1417,7 +1923,7 @@ async fn submission_loop(
                 let sub_id = sub.id.clone();
 
                 // This is a cheap lookup from the connection manager's cache.
-                let tools = sess.mcp_connection_manager.list_all_tools();
+                let tools = sess.mcp_connection_manager().list_all_tools();
                 let event = Event {
                     id: sub_id,
                     msg: EventMsg::McpListToolsResponse(
@@ -1544,7 +2050,7 @@ async fn spawn_review_thread(
     review_request: ReviewRequest,
 ) {
     let model = config.review_model.clone();
-    let review_model_family = find_family_for_model(&model)
+    let review_model_family = find_configured_family_for_model(&model, &config.models)
         .unwrap_or_else(|| parent_turn_context.client.get_model_family());
     let tools_config = ToolsConfig::new(&ToolsConfigParams {
         model_family: &review_model_family,
@@ -1554,12 +2060,14 @@ async fn spawn_review_thread(
         use_streamable_shell_tool: false,
         include_view_image_tool: false,
         experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+        include_delegate_tool: false,
     });
 
     let base_instructions = REVIEW_PROMPT.to_string();
     let review_prompt = review_request.prompt.clone();
     let provider = parent_turn_context.client.get_provider();
     let auth_manager = parent_turn_context.client.get_auth_manager();
+    let provider_health = parent_turn_context.client.get_provider_health();
     let model_family = review_model_family.clone();
 
     // Build per‑turn client with the requested model/family.
@@ -1568,7 +2076,7 @@ async fn spawn_review_thread(
     per_turn_config.model_family = model_family.clone();
     per_turn_config.model_reasoning_effort = Some(ReasoningEffortConfig::Low);
     per_turn_config.model_reasoning_summary = ReasoningSummaryConfig::Detailed;
-    if let Some(model_info) = get_model_info(&model_family) {
+    if let Some(model_info) = get_configured_model_info(&model_family, &config.models) {
         per_turn_config.model_context_window = Some(model_info.context_window);
     }
 
@@ -1580,6 +2088,7 @@ async fn spawn_review_thread(
         per_turn_config.model_reasoning_effort,
         per_turn_config.model_reasoning_summary,
         sess.conversation_id,
+        provider_health,
     );
 
     let review_turn_context = TurnContext {
@@ -1669,7 +2178,37 @@ async fn run_task(
     let mut turn_diff_tracker = TurnDiffTracker::new();
     let mut auto_compact_recently_attempted = false;
 
+    let estimator = TokenEstimator::for_model_family(&turn_context.client.get_model_family());
+    let auto_compact_limit = turn_context
+        .client
+        .get_auto_compact_token_limit()
+        .unwrap_or(i64::MAX);
+
+    let config = turn_context.client.get_config();
+    let budget = TaskBudgetTracker::new(&config);
+    let output_tokens_at_start = sess.total_output_tokens().await;
+    let mut budget_exceeded: Option<TaskBudget> = None;
+    // The output schema is compiled once for the whole task. An invalid
+    // schema fails the task without asking the model for repairs.
+    let final_output_schema = turn_context
+        .final_output_json_schema
+        .as_ref()
+        .filter(|_| !is_review_mode)
+        .map(|schema| {
+            output_schema::check_output_schema(schema)
+                .map_err(|err| vec![format!("the output schema is invalid: {err}")])
+        });
+    let mut output_schema_repairs: u32 = 0;
+    let mut output_schema_errors: Option<Vec<String>> = None;
+
     loop {
+        // A command that is already running is never cut off, so the
+        // deadline is checked between turns and before each tool call.
+        if let Some(exceeded) = budget.check_deadline() {
+            budget_exceeded = Some(exceeded);
+            break;
+        }
+
         // Note that pending_input would be something like a message the user
         // submitted through the UI while the model was running. Though the UI
         // may support this, the model might not.
@@ -1697,7 +2236,19 @@ async fn run_task(
             review_thread_history.clone()
         } else {
             sess.record_conversation_items(&pending_input).await;
-            sess.turn_input_with_history(pending_input).await
+            let (turn_input, estimated_tokens) = sess
+                .turn_input_with_history(turn_context.as_ref(), pending_input)
+                .await;
+            // Compact before the request that would overflow the context
+            // window, rather than after the server reports the usage.
+            if !auto_compact_recently_attempted && estimated_tokens as i64 >= auto_compact_limit {
+                info!("estimated prompt of {estimated_tokens} tokens exceeds auto-compact limit");
+                auto_compact_recently_attempted = true;
+                compact::run_inline_auto_compact_task(sess.clone(), turn_context.clone(), &sub_id)
+                    .await;
+                continue;
+            }
+            turn_input
         };
 
         let turn_input_messages: Vec<String> = turn_input
@@ -1713,12 +2264,17 @@ async fn run_task(
                 })
             })
             .collect();
+        let provider = turn_context.client.get_provider();
+        if images_left_out(&provider, &turn_input) {
+            sess.notify_images_left_out(&sub_id, &provider.name).await;
+        }
         match run_turn(
             &sess,
             turn_context.as_ref(),
             &mut turn_diff_tracker,
             sub_id.clone(),
             turn_input,
+            &budget,
         )
         .await
         {
@@ -1737,6 +2293,11 @@ async fn run_task(
                 let token_limit_reached = total_usage_tokens
                     .map(|tokens| (tokens as i64) >= limit)
                     .unwrap_or(false);
+                // The items of this turn are recorded below, so the reported
+                // size covers exactly the history as it is now.
+                if !is_review_mode && let Some(tokens) = total_usage_tokens {
+                    sess.state.lock().await.history.set_reported_tokens(tokens);
+                }
                 let mut items_to_record_in_conversation_history = Vec::<ResponseItem>::new();
                 let mut responses = Vec::<ResponseInputItem>::new();
                 for processed_response_item in processed_items {
@@ -1750,6 +2311,8 @@ async fn run_task(
                             ResponseItem::LocalShellCall { .. },
                             Some(ResponseInputItem::FunctionCallOutput { call_id, output }),
                         ) => {
+                            // Command output was already shortened by
+                            // `format_exec_output_str`.
                             items_to_record_in_conversation_history.push(item);
                             items_to_record_in_conversation_history.push(
                                 ResponseItem::FunctionCallOutput {
@@ -1759,14 +2322,15 @@ async fn run_task(
                             );
                         }
                         (
-                            ResponseItem::FunctionCall { .. },
+                            ResponseItem::FunctionCall { name, .. },
                             Some(ResponseInputItem::FunctionCallOutput { call_id, output }),
                         ) => {
+                            let output = truncate_function_call_output(estimator, name, output);
                             items_to_record_in_conversation_history.push(item);
                             items_to_record_in_conversation_history.push(
                                 ResponseItem::FunctionCallOutput {
                                     call_id: call_id.clone(),
-                                    output: output.clone(),
+                                    output,
                                 },
                             );
                         }
@@ -1774,6 +2338,8 @@ async fn run_task(
                             ResponseItem::CustomToolCall { .. },
                             Some(ResponseInputItem::CustomToolCallOutput { call_id, output }),
                         ) => {
+                            // The only custom tool is apply_patch, whose output
+                            // is formatted like a command's.
                             items_to_record_in_conversation_history.push(item);
                             items_to_record_in_conversation_history.push(
                                 ResponseItem::CustomToolCallOutput {
@@ -1789,12 +2355,12 @@ async fn run_task(
                             items_to_record_in_conversation_history.push(item);
                             let output = match result {
                                 Ok(call_tool_result) => {
-                                    convert_call_tool_result_to_function_call_output_payload(
-                                        call_tool_result,
-                                    )
+                                    truncate_mcp_tool_result(estimator, call_tool_result)
                                 }
                                 Err(err) => FunctionCallOutputPayload {
-                                    content: err.clone(),
+                                    content: estimator
+                                        .truncate_middle(err, MODEL_FORMAT_MAX_TOKENS)
+                                        .0,
                                     success: Some(false),
                                 },
                             };
@@ -1841,6 +2407,16 @@ async fn run_task(
                     }
                 }
 
+                // Only check the budgets when the model wants to keep going;
+                // a turn that produced its final answer completes normally.
+                if !responses.is_empty() {
+                    budget_exceeded =
+                        check_task_budgets(&sess, &budget, &config, output_tokens_at_start).await;
+                    if budget_exceeded.is_some() {
+                        break;
+                    }
+                }
+
                 if token_limit_reached {
                     if auto_compact_recently_attempted {
                         let limit_str = limit.to_string();
@@ -1859,7 +2435,12 @@ async fn run_task(
                         break;
                     }
                     auto_compact_recently_attempted = true;
-                    compact::run_inline_auto_compact_task(sess.clone(), turn_context.clone()).await;
+                    compact::run_inline_auto_compact_task(
+                        sess.clone(),
+                        turn_context.clone(),
+                        &sub_id,
+                    )
+                    .await;
                     continue;
                 }
 
@@ -1869,6 +2450,45 @@ async fn run_task(
                     last_agent_message = get_last_assistant_message_from_turn(
                         &items_to_record_in_conversation_history,
                     );
+                    // Check the final message against the output schema and
+                    // give the model a chance to fix it before giving up.
+                    if let Some(schema) = &final_output_schema
+                        && let Err(errors) =
+                            schema.as_ref().map_err(Clone::clone).and_then(|schema| {
+                                schema.validate_final_output(last_agent_message.as_deref())
+                            })
+                    {
+                        let max_repairs = config.output_schema_repair_attempts;
+                        if schema.is_ok() && output_schema_repairs < max_repairs {
+                            // A repair is another request to the model, so
+                            // it is subject to the task budgets like any
+                            // other turn.
+                            budget_exceeded =
+                                check_task_budgets(&sess, &budget, &config, output_tokens_at_start)
+                                    .await;
+                            if budget_exceeded.is_some() {
+                                break;
+                            }
+                            output_schema_repairs += 1;
+                            sess.notify_background_event(
+                                &sub_id,
+                                format!(
+                                    "final message does not match the output schema; asking the model to fix it ({output_schema_repairs}/{max_repairs})"
+                                ),
+                            )
+                            .await;
+                            let repair_request = ResponseItem::Message {
+                                id: None,
+                                role: "user".to_string(),
+                                content: vec![ContentItem::InputText {
+                                    text: output_schema::repair_prompt(&errors),
+                                }],
+                            };
+                            sess.record_conversation_items(&[repair_request]).await;
+                            continue;
+                        }
+                        output_schema_errors = Some(errors);
+                    }
                     sess.notifier()
                         .notify(&UserNotification::AgentTurnComplete {
                             turn_id: sub_id.clone(),
@@ -1909,13 +2529,261 @@ async fn run_task(
         )
         .await;
     }
-
-    sess.remove_task(&sub_id).await;
-    let event = Event {
-        id: sub_id,
-        msg: EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }),
-    };
-    sess.send_event(event).await;
+
+    sess.remove_task(&sub_id).await;
+    if budget_exceeded.is_none()
+        && let Some(errors) = output_schema_errors
+    {
+        let event = Event {
+            id: sub_id.clone(),
+            msg: EventMsg::OutputSchemaValidationFailed(OutputSchemaValidationFailedEvent {
+                errors,
+            }),
+        };
+        sess.send_event(event).await;
+    }
+    let msg = match budget_exceeded {
+        Some(budget) => EventMsg::TurnAborted(TurnAbortedEvent {
+            reason: TurnAbortReason::BudgetExceeded(budget),
+        }),
+        None => EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }),
+    };
+    let event = Event { id: sub_id, msg };
+    sess.send_event(event).await;
+}
+
+/// Caps the output of the function `name` at [`MODEL_FORMAT_MAX_TOKENS`] so
+/// that a single oversized result cannot blow the context window. Tools that
+/// already cap their own output are left alone: cutting them again would
+/// split their JSON or the marker that says where the rest can be read.
+fn truncate_function_call_output(
+    estimator: TokenEstimator,
+    name: &str,
+    output: &FunctionCallOutputPayload,
+) -> FunctionCallOutputPayload {
+    let caps_own_output = matches!(
+        name,
+        "container.exec"
+            | "shell"
+            | "apply_patch"
+            | "unified_exec"
+            | READ_OUTPUT_TOOL_NAME
+            | EXEC_COMMAND_TOOL_NAME
+            | WRITE_STDIN_TOOL_NAME
+    );
+    if caps_own_output {
+        return output.clone();
+    }
+    FunctionCallOutputPayload {
+        content: estimator
+            .truncate_middle(&output.content, MODEL_FORMAT_MAX_TOKENS)
+            .0,
+        success: output.success,
+    }
+}
+
+/// Converts an MCP tool result for the history, capped at
+/// [`MODEL_FORMAT_MAX_TOKENS`]. A result that is too large keeps as many of
+/// its content blocks as fit, so the model still gets well-formed JSON, plus
+/// a note on how many were left out. A leading text block that does not fit
+/// on its own has its middle elided instead.
+fn truncate_mcp_tool_result(
+    estimator: TokenEstimator,
+    call_tool_result: &CallToolResult,
+) -> FunctionCallOutputPayload {
+    let output = convert_call_tool_result_to_function_call_output_payload(call_tool_result);
+    if estimator.count(&output.content) <= MODEL_FORMAT_MAX_TOKENS {
+        return output;
+    }
+
+    // Leave room for the note on the omitted blocks.
+    let budget = MODEL_FORMAT_MAX_TOKENS - 64;
+    let total = call_tool_result.content.len();
+    let mut content = Vec::new();
+    let mut used = 0;
+    for block in &call_tool_result.content {
+        let tokens = serde_json::to_string(block)
+            .map(|serialized| estimator.count(&serialized))
+            .unwrap_or(u64::MAX);
+        if used + tokens <= budget {
+            used += tokens;
+            content.push(block.clone());
+            continue;
+        }
+        if content.is_empty()
+            && let ContentBlock::TextContent(text) = block
+        {
+            content.push(ContentBlock::TextContent(TextContent {
+                text: estimator.truncate_middle(&text.text, budget).0,
+                ..text.clone()
+            }));
+        }
+        break;
+    }
+    let omitted = total - content.len();
+    if omitted > 0 {
+        content.push(ContentBlock::TextContent(TextContent {
+            annotations: None,
+            text: format!(
+                "[... omitted {omitted} of {total} content blocks; the result was over {MODEL_FORMAT_MAX_TOKENS} tokens ...]"
+            ),
+            r#type: "text".to_string(),
+        }));
+    }
+    // The structured content is not cut apart; the model gets the content
+    // blocks that fit instead.
+    convert_call_tool_result_to_function_call_output_payload(&CallToolResult {
+        content,
+        is_error: call_tool_result.is_error,
+        structured_content: None,
+    })
+}
+
+/// Returns the first token or cost budget in `config` that the task has gone
+/// over. These are only known once a turn completes; the tool call and
+/// wall-clock budgets are checked by [`TaskBudgetTracker`] before each call.
+fn exceeded_task_budget(
+    config: &Config,
+    output_tokens: u64,
+    session_cost_usd: Option<f64>,
+) -> Option<TaskBudget> {
+    if config
+        .max_task_tokens
+        .is_some_and(|max| output_tokens > max)
+    {
+        return Some(TaskBudget::Tokens);
+    }
+    if let (Some(max), Some(cost)) = (config.max_cost_usd, session_cost_usd)
+        && cost > max
+    {
+        return Some(TaskBudget::Cost);
+    }
+    None
+}
+
+/// The budget a task has exceeded before it sends another request to the
+/// model, if any.
+async fn check_task_budgets(
+    sess: &Session,
+    budget: &TaskBudgetTracker,
+    config: &Config,
+    output_tokens_at_start: u64,
+) -> Option<TaskBudget> {
+    let task_output_tokens = sess
+        .total_output_tokens()
+        .await
+        .saturating_sub(output_tokens_at_start);
+    let session_cost_usd = sess.total_cost_usd().await;
+    budget
+        .check_deadline()
+        .or_else(|| exceeded_task_budget(config, task_output_tokens, session_cost_usd))
+}
+
+/// The tool call and wall-clock budgets of a task. Both are checked before a
+/// tool call is dispatched, so a response with many parallel calls cannot run
+/// more calls than allowed, and a command is never aborted halfway because
+/// the deadline passed while it ran.
+struct TaskBudgetTracker {
+    max_tool_calls: Option<u64>,
+    deadline: Option<Instant>,
+    tool_calls: AtomicU64,
+    exceeded: OnceLock<TaskBudget>,
+}
+
+impl TaskBudgetTracker {
+    fn new(config: &Config) -> Self {
+        Self {
+            max_tool_calls: config.max_turn_tool_calls,
+            deadline: config
+                .max_task_duration
+                .map(|duration| Instant::now() + duration),
+            tool_calls: AtomicU64::new(0),
+            exceeded: OnceLock::new(),
+        }
+    }
+
+    /// Counts a tool call the model asked for, or returns the budget that
+    /// forbids running it. Once a budget is exceeded no further call runs.
+    fn start_tool_call(&self) -> Result<(), TaskBudget> {
+        if let Some(exceeded) = self.check_deadline() {
+            return Err(exceeded);
+        }
+        let tool_calls = self
+            .tool_calls
+            .fetch_add(1, std::sync::atomic::Ordering::SeqCst)
+            + 1;
+        if self.max_tool_calls.is_some_and(|max| tool_calls > max) {
+            return Err(self.trip(TaskBudget::ToolCalls));
+        }
+        Ok(())
+    }
+
+    /// Returns the budget the task has exceeded, if any, counting the
+    /// deadline as exceeded once it has passed.
+    fn check_deadline(&self) -> Option<TaskBudget> {
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Some(self.trip(TaskBudget::Duration));
+        }
+        self.exceeded()
+    }
+
+    fn exceeded(&self) -> Option<TaskBudget> {
+        self.exceeded.get().copied()
+    }
+
+    /// Records `budget` as exceeded unless another budget already was, and
+    /// returns the one that was recorded.
+    fn trip(&self, budget: TaskBudget) -> TaskBudget {
+        *self.exceeded.get_or_init(|| budget)
+    }
+}
+
+/// Whether the images in `input` are left out of the request because
+/// `provider` speaks Chat Completions and does not accept `image_url` parts.
+fn images_left_out(provider: &ModelProviderInfo, input: &[ResponseItem]) -> bool {
+    provider.wire_api == WireApi::Chat
+        && !provider.supports_images()
+        && input.iter().any(|item| match item {
+            ResponseItem::Message { content, .. } => content
+                .iter()
+                .any(|c| matches!(c, ContentItem::InputImage { .. })),
+            _ => false,
+        })
+}
+
+/// The output sent back for a tool call that was not run because `budget`
+/// is used up, so that every call in the history has an output.
+fn budget_exceeded_response(item: &ResponseItem, budget: TaskBudget) -> Option<ResponseInputItem> {
+    let content = format!("not run: the task exceeded its `{budget}` budget");
+    match item {
+        ResponseItem::FunctionCall { call_id, .. } => Some(ResponseInputItem::FunctionCallOutput {
+            call_id: call_id.clone(),
+            output: FunctionCallOutputPayload {
+                content,
+                success: Some(false),
+            },
+        }),
+        ResponseItem::LocalShellCall { id, call_id, .. } => {
+            let call_id = call_id.clone().or_else(|| id.clone())?;
+            Some(ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content,
+                    success: Some(false),
+                },
+            })
+        }
+        ResponseItem::CustomToolCall { call_id, .. } => {
+            Some(ResponseInputItem::CustomToolCallOutput {
+                call_id: call_id.clone(),
+                output: content,
+            })
+        }
+        _ => None,
+    }
 }
 
 /// Parse the review output; when not valid JSON, build a structured
@@ -1949,10 +2817,11 @@ async fn run_turn(
     turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: String,
     input: Vec<ResponseItem>,
+    budget: &TaskBudgetTracker,
 ) -> CodexResult<TurnRunResult> {
     let tools = get_openai_tools(
         &turn_context.tools_config,
-        Some(sess.mcp_connection_manager.list_all_tools()),
+        Some(sess.mcp_connection_manager().list_all_tools()),
     );
 
     let prompt = Prompt {
@@ -1962,23 +2831,69 @@ async fn run_turn(
         output_schema: turn_context.final_output_json_schema.clone(),
     };
 
+    // When the model is rate limited or unavailable, the same prompt is
+    // retried against each entry of the configured `fallback` chain in turn.
+    let mut fallbacks = turn_context
+        .client
+        .get_config()
+        .fallback
+        .clone()
+        .into_iter();
+    let mut fallback_turn_context: Option<TurnContext> = None;
+
     let mut retries = 0;
     loop {
-        match try_run_turn(sess, turn_context, turn_diff_tracker, &sub_id, &prompt).await {
+        let active_turn_context = fallback_turn_context.as_ref().unwrap_or(turn_context);
+        let result = try_run_turn(
+            sess,
+            active_turn_context,
+            turn_diff_tracker,
+            &sub_id,
+            &prompt,
+            budget,
+        )
+        .await;
+        for notice in active_turn_context.client.take_provider_health_notices() {
+            sess.notify_background_event(&sub_id, notice).await;
+        }
+        match result {
             Ok(output) => return Ok(output),
             Err(CodexErr::Interrupted) => return Err(CodexErr::Interrupted),
             Err(CodexErr::EnvVar(var)) => return Err(CodexErr::EnvVar(var)),
-            Err(CodexErr::UsageLimitReached(e)) => {
-                let rate_limits = e.rate_limits.clone();
-                if let Some(rate_limits) = rate_limits {
+            Err(
+                e @ (CodexErr::UsageLimitReached(_)
+                | CodexErr::RetryLimit(_)
+                | CodexErr::InternalServerError
+                | CodexErr::ProviderUnavailable(_)),
+            ) => {
+                if let CodexErr::UsageLimitReached(limit) = &e
+                    && let Some(rate_limits) = limit.rate_limits.clone()
+                {
                     sess.update_rate_limits(&sub_id, rate_limits).await;
                 }
-                return Err(CodexErr::UsageLimitReached(e));
+                let next = next_fallback_turn_context(
+                    sess,
+                    &sub_id,
+                    active_turn_context,
+                    &mut fallbacks,
+                    &e,
+                )
+                .await;
+                match next {
+                    Some(next) => {
+                        fallback_turn_context = Some(next);
+                        retries = 0;
+                    }
+                    None => return Err(e),
+                }
             }
             Err(CodexErr::UsageNotIncluded) => return Err(CodexErr::UsageNotIncluded),
             Err(e) => {
                 // Use the configured provider-specific stream retry budget.
-                let max_retries = turn_context.client.get_provider().stream_max_retries();
+                let max_retries = active_turn_context
+                    .client
+                    .get_provider()
+                    .stream_max_retries();
                 if retries < max_retries {
                     retries += 1;
                     let delay = match e {
@@ -2009,6 +2924,39 @@ async fn run_turn(
     }
 }
 
+/// Advances to the next usable entry of the `fallback` chain and tells the
+/// user which model took over. Returns `None` once the chain is exhausted.
+async fn next_fallback_turn_context(
+    sess: &Session,
+    sub_id: &str,
+    turn_context: &TurnContext,
+    fallbacks: &mut std::vec::IntoIter<ModelFallback>,
+    err: &CodexErr,
+) -> Option<TurnContext> {
+    let config = turn_context.client.get_config();
+    for fallback in fallbacks.by_ref() {
+        let Some(provider) = config.model_providers.get(&fallback.model_provider) else {
+            warn!(
+                "skipping fallback model `{}`: provider `{}` not found",
+                fallback.model, fallback.model_provider
+            );
+            continue;
+        };
+        sess.notify_background_event(
+            sub_id,
+            format!(
+                "{} failed ({err}); falling back to {} via {}",
+                turn_context.client.get_model(),
+                fallback.model,
+                fallback.model_provider
+            ),
+        )
+        .await;
+        return Some(turn_context.with_fallback_model(&fallback, provider.clone()));
+    }
+    None
+}
+
 /// When the model is prompted, it returns a stream of events. Some of these
 /// events map to a `ResponseItem`. A `ResponseItem` may need to be
 /// "handled" such that it produces a `ResponseInputItem` that needs to be
@@ -2031,6 +2979,7 @@ async fn try_run_turn(
     turn_diff_tracker: &mut TurnDiffTracker,
     sub_id: &str,
     prompt: &Prompt,
+    budget: &TaskBudgetTracker,
 ) -> CodexResult<TurnRunResult> {
     // call_ids that are part of this response.
     let completed_call_ids = prompt
@@ -2087,24 +3036,40 @@ async fn try_run_turn(
         })
     };
 
+    let mut stream = turn_context.client.clone().stream(&prompt).await?;
+
+    // Record the context only once the stream is open, so that the rollout
+    // names the model that actually answered (which may be a fallback).
     let rollout_item = RolloutItem::TurnContext(TurnContextItem {
         cwd: turn_context.cwd.clone(),
         approval_policy: turn_context.approval_policy,
         sandbox_policy: turn_context.sandbox_policy.clone(),
         model: turn_context.client.get_model(),
+        model_provider: Some(turn_context.client.get_model_provider_id()),
         effort: turn_context.client.get_reasoning_effort(),
         summary: turn_context.client.get_reasoning_summary(),
     });
     sess.persist_rollout_items(&[rollout_item]).await;
-    let mut stream = turn_context.client.clone().stream(&prompt).await?;
 
-    let mut output = Vec::new();
+    let parallel_tool_calls = turn_context.client.get_parallel_tool_calls();
+    let mut output: Vec<ProcessedResponseItem> = Vec::new();
+    // Tool calls running concurrently with the stream, tagged with the index
+    // of their placeholder in `output` so the history keeps the model's order.
+    let mut in_flight: FuturesUnordered<
+        BoxFuture<'_, (usize, CodexResult<Option<ResponseInputItem>>)>,
+    > = FuturesUnordered::new();
 
     loop {
         // Poll the next item from the model stream. We must inspect *both* Ok and Err
         // cases so that transient stream failures (e.g., dropped SSE connection before
         // `response.completed`) bubble up and trigger the caller's retry logic.
-        let event = stream.next().await;
+        let event = tokio::select! {
+            Some((idx, response)) = in_flight.next(), if !in_flight.is_empty() => {
+                output[idx].response = response?;
+                continue;
+            }
+            event = stream.next() => event,
+        };
         let Some(event) = event else {
             // Channel closed without yielding a final Completed event or explicit error.
             // Treat as a disconnected stream so the caller can retry.
@@ -2126,6 +3091,45 @@ async fn try_run_turn(
         match event {
             ResponseEvent::Created => {}
             ResponseEvent::OutputItemDone(item) => {
+                if is_tool_call(&item)
+                    && let Err(exceeded) = budget.start_tool_call()
+                {
+                    let response = budget_exceeded_response(&item, exceeded);
+                    output.push(ProcessedResponseItem { item, response });
+                    continue;
+                }
+
+                if parallel_tool_calls && is_parallel_safe_call(sess, &item) {
+                    output.push(ProcessedResponseItem {
+                        item: item.clone(),
+                        response: None,
+                    });
+                    let idx = output.len() - 1;
+                    let call = async move {
+                        // Parallel-safe calls never write files, so they get
+                        // their own diff tracker rather than sharing the turn's.
+                        let mut turn_diff_tracker = TurnDiffTracker::new();
+                        let response = handle_response_item(
+                            sess,
+                            turn_context,
+                            &mut turn_diff_tracker,
+                            sub_id,
+                            item,
+                        )
+                        .await;
+                        (idx, response)
+                    };
+                    in_flight.push(call.boxed());
+                    continue;
+                }
+
+                // Anything that may need approval or write files waits for the
+                // concurrent calls to finish and then runs on its own.
+                if is_tool_call(&item) {
+                    while let Some((idx, response)) = in_flight.next().await {
+                        output[idx].response = response?;
+                    }
+                }
                 let response = handle_response_item(
                     sess,
                     turn_context,
@@ -2154,6 +3158,10 @@ async fn try_run_turn(
                 response_id: _,
                 token_usage,
             } => {
+                while let Some((idx, response)) = in_flight.next().await {
+                    output[idx].response = response?;
+                }
+
                 sess.update_token_usage_info(sub_id, turn_context, token_usage.as_ref())
                     .await;
 
@@ -2216,6 +3224,38 @@ async fn try_run_turn(
     }
 }
 
+fn is_tool_call(item: &ResponseItem) -> bool {
+    matches!(
+        item,
+        ResponseItem::FunctionCall { .. }
+            | ResponseItem::LocalShellCall { .. }
+            | ResponseItem::CustomToolCall { .. }
+    )
+}
+
+/// Whether `item` is a tool call that can run concurrently with the other
+/// calls of the same response: it must neither need approval nor write files.
+fn is_parallel_safe_call(sess: &Session, item: &ResponseItem) -> bool {
+    match item {
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => match name.as_str() {
+            "container.exec" | "shell" => serde_json::from_str::<ShellToolCallParams>(arguments)
+                .is_ok_and(|params| {
+                    params.with_escalated_permissions != Some(true)
+                        && is_known_safe_command(&params.command)
+                }),
+            "view_image" | READ_OUTPUT_TOOL_NAME => true,
+            _ => sess.mcp_connection_manager().is_read_only_tool(name),
+        },
+        ResponseItem::LocalShellCall {
+            action: LocalShellAction::Exec(action),
+            ..
+        } => is_known_safe_command(&action.command),
+        _ => false,
+    }
+}
+
 async fn handle_response_item(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2342,6 +3382,7 @@ async fn handle_response_item(
 
 async fn handle_unified_exec_tool_call(
     sess: &Session,
+    turn_context: &TurnContext,
     call_id: String,
     session_id: Option<String>,
     arguments: Vec<String>,
@@ -2380,9 +3421,13 @@ async fn handle_unified_exec_tool_call(
                 output: &'a str,
             }
 
+            // Cap the output before it is serialized so the JSON stays whole.
+            let estimator =
+                TokenEstimator::for_model_family(&turn_context.client.get_model_family());
+            let (output, _) = estimator.truncate_middle(&value.output, MODEL_FORMAT_MAX_TOKENS);
             match serde_json::to_string(&SerializedUnifiedExecResult {
                 session_id: value.session_id.map(|id| id.to_string()),
-                output: &value.output,
+                output: &output,
             }) {
                 Ok(serialized) => FunctionCallOutputPayload {
                     content: serialized,
@@ -2458,6 +3503,7 @@ async fn handle_function_call(
 
             handle_unified_exec_tool_call(
                 sess,
+                turn_context,
                 call_id,
                 args.session_id,
                 args.input,
@@ -2530,6 +3576,10 @@ async fn handle_function_call(
             .await
         }
         "update_plan" => handle_update_plan(sess, arguments, sub_id, call_id).await,
+        READ_OUTPUT_TOOL_NAME => handle_read_output(&sess.output_store, arguments, call_id),
+        DELEGATE_TOOL_NAME => {
+            handle_delegate_task(sess, turn_context, arguments, sub_id, call_id).await
+        }
         EXEC_COMMAND_TOOL_NAME => {
             // TODO(mbolin): Sandbox check.
             let exec_params = match serde_json::from_str::<ExecCommandParams>(&arguments) {
@@ -2579,7 +3629,7 @@ async fn handle_function_call(
             }
         }
         _ => {
-            match sess.mcp_connection_manager.parse_tool_name(&name) {
+            match sess.mcp_connection_manager().parse_tool_name(&name) {
                 Some((server, tool_name)) => {
                     handle_mcp_tool_call(sess, &sub_id, call_id, server, tool_name, arguments).await
                 }
@@ -2689,6 +3739,7 @@ pub struct ExecInvokeArgs<'a> {
     pub sandbox_policy: &'a SandboxPolicy,
     pub sandbox_cwd: &'a Path,
     pub codex_linux_sandbox_exe: &'a Option<PathBuf>,
+    pub resource_limits: &'a ResourceLimits,
     pub stdout_stream: Option<StdoutStream>,
 }
 
@@ -2869,6 +3920,33 @@ async fn handle_container_exec_with_params(
         }
     };
 
+    // Patches are reviewed before they run, so they keep writing to the
+    // workspace directly.
+    let sandbox_type = if sandbox_type == SandboxType::LinuxSeccomp
+        && apply_patch_exec.is_none()
+        && sess.linux_sandbox_backend == LinuxSandboxBackend::Overlay
+    {
+        SandboxType::LinuxOverlay
+    } else {
+        sandbox_type
+    };
+    let overlay_sandbox = if sandbox_type == SandboxType::LinuxOverlay {
+        match OverlaySandbox::new() {
+            Ok(overlay_sandbox) => Some(overlay_sandbox),
+            Err(e) => {
+                return ResponseInputItem::FunctionCallOutput {
+                    call_id,
+                    output: FunctionCallOutputPayload {
+                        content: format!("failed to set up the overlay sandbox: {e}"),
+                        success: None,
+                    },
+                };
+            }
+        }
+    } else {
+        None
+    };
+
     let exec_command_context = ExecCommandContext {
         sub_id: sub_id.clone(),
         call_id: call_id.clone(),
@@ -2886,16 +3964,21 @@ async fn handle_container_exec_with_params(
     };
 
     let params = maybe_translate_shell_command(params, sess, turn_context);
+    let mut exec_params = params.clone();
+    if let Some(overlay_sandbox) = &overlay_sandbox {
+        overlay_sandbox.apply_to_env(&mut exec_params.env);
+    }
     let output_result = sess
         .run_exec_with_events(
             turn_diff_tracker,
             exec_command_context.clone(),
             ExecInvokeArgs {
-                params: params.clone(),
+                params: exec_params,
                 sandbox_type,
                 sandbox_policy: &turn_context.sandbox_policy,
                 sandbox_cwd: &turn_context.cwd,
                 codex_linux_sandbox_exe: &sess.codex_linux_sandbox_exe,
+                resource_limits: &sess.resource_limits,
                 stdout_stream: if exec_command_context.apply_patch.is_some() {
                     None
                 } else {
@@ -2914,7 +3997,25 @@ async fn handle_container_exec_with_params(
             let ExecToolCallOutput { exit_code, .. } = &output;
 
             let is_success = *exit_code == 0;
-            let content = format_exec_output(&output);
+            let notice = match &overlay_sandbox {
+                Some(overlay_sandbox) => {
+                    sess.apply_overlay_changes(
+                        turn_diff_tracker,
+                        turn_context.approval_policy,
+                        &sub_id,
+                        &call_id,
+                        overlay_sandbox,
+                    )
+                    .await
+                }
+                None => None,
+            };
+            let output_handle = sess.output_store.handle_for_call(&call_id);
+            let content = format_exec_output_with_notice(
+                &output,
+                output_handle.as_deref(),
+                notice.as_deref(),
+            );
             ResponseInputItem::FunctionCallOutput {
                 call_id: call_id.clone(),
                 output: FunctionCallOutputPayload {
@@ -2924,24 +4025,72 @@ async fn handle_container_exec_with_params(
             }
         }
         Err(CodexErr::Sandbox(error)) => {
+            // A command that timed out or was stopped may have written to
+            // the overlay before it ended. A denied command may be retried
+            // without the sandbox, so its writes are dropped instead.
+            let notice = match &overlay_sandbox {
+                Some(_) if matches!(error, SandboxErr::Denied { .. }) => Some(
+                    "the file changes made by the command were discarded because the sandbox blocked it".to_string(),
+                ),
+                Some(overlay_sandbox) => {
+                    sess.apply_overlay_changes(
+                        turn_diff_tracker,
+                        turn_context.approval_policy,
+                        &sub_id,
+                        &call_id,
+                        overlay_sandbox,
+                    )
+                    .await
+                }
+                None => None,
+            };
             handle_sandbox_error(
                 turn_diff_tracker,
                 params,
                 exec_command_context,
                 error,
                 sandbox_type,
+                notice.as_deref(),
                 sess,
                 turn_context,
             )
             .await
         }
-        Err(e) => ResponseInputItem::FunctionCallOutput {
-            call_id: call_id.clone(),
-            output: FunctionCallOutputPayload {
-                content: format!("execution error: {e}"),
-                success: None,
-            },
-        },
+        Err(e) => {
+            let notice = match &overlay_sandbox {
+                Some(overlay_sandbox) => {
+                    sess.apply_overlay_changes(
+                        turn_diff_tracker,
+                        turn_context.approval_policy,
+                        &sub_id,
+                        &call_id,
+                        overlay_sandbox,
+                    )
+                    .await
+                }
+                None => None,
+            };
+            let content = match notice {
+                Some(notice) => format!("{notice}\nexecution error: {e}"),
+                None => format!("execution error: {e}"),
+            };
+            ResponseInputItem::FunctionCallOutput {
+                call_id: call_id.clone(),
+                output: FunctionCallOutputPayload {
+                    content,
+                    success: None,
+                },
+            }
+        }
+    }
+}
+
+/// Puts `notice`, such as what became of the writes of an overlay sandbox,
+/// in front of `other`.
+fn join_notices(notice: Option<&str>, other: Option<&str>) -> Option<String> {
+    match (notice, other) {
+        (Some(notice), Some(other)) => Some(format!("{notice}\n{other}")),
+        (notice, other) => notice.or(other).map(str::to_string),
     }
 }
 
@@ -2951,15 +4100,34 @@ async fn handle_sandbox_error(
     exec_command_context: ExecCommandContext,
     error: SandboxErr,
     sandbox_type: SandboxType,
+    notice: Option<&str>,
     sess: &Session,
     turn_context: &TurnContext,
 ) -> ResponseInputItem {
     let call_id = exec_command_context.call_id.clone();
     let sub_id = exec_command_context.sub_id.clone();
     let cwd = exec_command_context.cwd.clone();
+    let output_handle = sess.output_store.handle_for_call(&call_id);
 
     if let SandboxErr::Timeout { output } = &error {
-        let content = format_exec_output(output);
+        let content = format_exec_output_with_notice(output, output_handle.as_deref(), notice);
+        return ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content,
+                success: Some(false),
+            },
+        };
+    }
+
+    // The limits apply with or without the sandbox, so retrying without it
+    // would not help.
+    if let SandboxErr::ResourceLimitExceeded { limit, output } = &error {
+        let content = format_exec_output_with_notice(
+            output,
+            output_handle.as_deref(),
+            join_notices(notice, Some(&limit.explanation())).as_deref(),
+        );
         return ResponseInputItem::FunctionCallOutput {
             call_id,
             output: FunctionCallOutputPayload {
@@ -2969,16 +4137,48 @@ async fn handle_sandbox_error(
         };
     }
 
+    if let SandboxErr::ProcessLimit(_) = &error {
+        return ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content: join_notices(notice, Some(&error.to_string())).unwrap_or_default(),
+                success: Some(false),
+            },
+        };
+    }
+
+    let denials = match &error {
+        SandboxErr::Denied { output, denials } if !denials.is_empty() => Some((output, denials)),
+        _ => None,
+    };
+
     // Early out if either the user never wants to be asked for approval, or
     // we're letting the model manage escalation requests. Otherwise, continue
     match turn_context.approval_policy {
         AskForApproval::Never | AskForApproval::OnRequest => {
+            let content = match denials {
+                Some((output, denials)) => {
+                    let can_escalate =
+                        matches!(turn_context.approval_policy, AskForApproval::OnRequest);
+                    format_exec_output_with_notice(
+                        output,
+                        output_handle.as_deref(),
+                        join_notices(notice, Some(&explain_denials(denials, can_escalate)))
+                            .as_deref(),
+                    )
+                }
+                None => join_notices(
+                    notice,
+                    Some(&format!(
+                        "failed in sandbox {sandbox_type:?} with execution error: {error}"
+                    )),
+                )
+                .unwrap_or_default(),
+            };
             return ResponseInputItem::FunctionCallOutput {
                 call_id,
                 output: FunctionCallOutputPayload {
-                    content: format!(
-                        "failed in sandbox {sandbox_type:?} with execution error: {error}"
-                    ),
+                    content,
                     success: Some(false),
                 },
             };
@@ -2994,17 +4194,22 @@ async fn handle_sandbox_error(
     // exit codes merit a retry.
 
     // For now, we categorically ask the user to retry without sandbox and
-    // emit the raw error as a background event.
+    // emit the raw error as a background event. When the sandbox reported
+    // what it blocked, the prompt says so.
     sess.notify_background_event(&sub_id, format!("Execution failed: {error}"))
         .await;
 
+    let reason = match denials.and_then(|(_, denials)| denials.first()) {
+        Some(denial) => format!("{denial}; retry without sandbox?"),
+        None => "command failed; retry without sandbox?".to_string(),
+    };
     let decision = sess
         .request_command_approval(
             sub_id.clone(),
             call_id.clone(),
             params.command.clone(),
             cwd.clone(),
-            Some("command failed; retry without sandbox?".to_string()),
+            Some(reason),
         )
         .await;
 
@@ -3031,6 +4236,7 @@ async fn handle_sandbox_error(
                         sandbox_policy: &turn_context.sandbox_policy,
                         sandbox_cwd: &turn_context.cwd,
                         codex_linux_sandbox_exe: &sess.codex_linux_sandbox_exe,
+                        resource_limits: &sess.resource_limits,
                         stdout_stream: if exec_command_context.apply_patch.is_some() {
                             None
                         } else {
@@ -3049,7 +4255,12 @@ async fn handle_sandbox_error(
                     let ExecToolCallOutput { exit_code, .. } = &retry_output;
 
                     let is_success = *exit_code == 0;
-                    let content = format_exec_output(&retry_output);
+                    let retry_output_handle = sess.output_store.handle_for_call(&call_id);
+                    let content = format_exec_output_with_notice(
+                        &retry_output,
+                        retry_output_handle.as_deref(),
+                        notice,
+                    );
 
                     ResponseInputItem::FunctionCallOutput {
                         call_id: call_id.clone(),
@@ -3073,7 +4284,8 @@ async fn handle_sandbox_error(
             ResponseInputItem::FunctionCallOutput {
                 call_id,
                 output: FunctionCallOutputPayload {
-                    content: "exec command rejected by user".to_string(),
+                    content: join_notices(notice, Some("exec command rejected by user"))
+                        .unwrap_or_default(),
                     success: None,
                 },
             }
@@ -3081,7 +4293,10 @@ async fn handle_sandbox_error(
     }
 }
 
-fn format_exec_output_str(exec_output: &ExecToolCallOutput) -> String {
+/// Formats the output of a command for the model. When the output is too long,
+/// the middle is left out and the marker names `output_handle`, under which the
+/// model can read it with the `read_output` tool.
+fn format_exec_output_str(exec_output: &ExecToolCallOutput, output_handle: Option<&str>) -> String {
     let ExecToolCallOutput {
         aggregated_output, ..
     } = exec_output;
@@ -3122,7 +4337,12 @@ fn format_exec_output_str(exec_output: &ExecToolCallOutput) -> String {
     } else {
         String::new()
     };
-    let marker = format!("\n[... omitted {omitted} of {total_lines} lines ...]\n\n");
+    let marker = match output_handle {
+        Some(handle) => format!(
+            "\n[... omitted {omitted} of {total_lines} lines; read them with `read_output` and handle `{handle}` ...]\n\n"
+        ),
+        None => format!("\n[... omitted {omitted} of {total_lines} lines ...]\n\n"),
+    };
 
     // Byte budgets for head/tail around the marker
     let mut head_budget = MODEL_FORMAT_HEAD_BYTES.min(MODEL_FORMAT_MAX_BYTES);
@@ -3193,8 +4413,13 @@ fn take_last_bytes_at_char_boundary(s: &str, maxb: usize) -> &str {
     &s[start..]
 }
 
-/// Exec output is a pre-serialized JSON payload
-fn format_exec_output(exec_output: &ExecToolCallOutput) -> String {
+/// Exec output is a pre-serialized JSON payload, with `notice` on the first
+/// line of the output.
+fn format_exec_output_with_notice(
+    exec_output: &ExecToolCallOutput,
+    output_handle: Option<&str>,
+    notice: Option<&str>,
+) -> String {
     let ExecToolCallOutput {
         exit_code,
         duration,
@@ -3216,7 +4441,13 @@ fn format_exec_output(exec_output: &ExecToolCallOutput) -> String {
     // round to 1 decimal place
     let duration_seconds = ((duration.as_secs_f32()) * 10.0).round() / 10.0;
 
-    let formatted_output = format_exec_output_str(exec_output);
+    let formatted_output = match notice {
+        Some(notice) => format!(
+            "{notice}\n{}",
+            format_exec_output_str(exec_output, output_handle)
+        ),
+        None => format_exec_output_str(exec_output, output_handle),
+    };
 
     let payload = ExecOutput {
         output: &formatted_output,
@@ -3347,12 +4578,9 @@ mod tests {
     use super::*;
     use crate::config::ConfigOverrides;
     use crate::config::ConfigToml;
-    use crate::protocol::CompactedItem;
     use crate::protocol::InitialHistory;
     use crate::protocol::ResumedHistory;
     use codex_protocol::models::ContentItem;
-    use mcp_types::ContentBlock;
-    use mcp_types::TextContent;
     use pretty_assertions::assert_eq;
     use serde::Deserialize;
     use serde_json::json;
@@ -3441,7 +4669,7 @@ mod tests {
             timed_out: false,
         };
 
-        let out = format_exec_output_str(&exec);
+        let out = format_exec_output_str(&exec, None);
 
         // Expect elision marker with correct counts
         let omitted = 400 - MODEL_FORMAT_MAX_LINES; // 144
@@ -3467,6 +4695,32 @@ mod tests {
         assert!(tail.ends_with(&expected_tail), "tail mismatch");
     }
 
+    #[test]
+    fn model_truncation_names_output_handle() {
+        let full = (1..=400)
+            .map(|i| format!("line{i}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let exec = ExecToolCallOutput {
+            exit_code: 0,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(String::new()),
+            aggregated_output: StreamOutput::new(full),
+            duration: StdDuration::from_secs(1),
+            timed_out: false,
+        };
+
+        let out = format_exec_output_str(&exec, Some("output-7"));
+
+        let omitted = 400 - MODEL_FORMAT_MAX_LINES;
+        assert!(
+            out.contains(&format!(
+                "\n[... omitted {omitted} of 400 lines; read them with `read_output` and handle `output-7` ...]\n\n"
+            )),
+            "missing handle: {out}"
+        );
+    }
+
     #[test]
     fn model_truncation_respects_byte_budget() {
         // Construct a large output (about 100kB) so byte budget dominates
@@ -3484,7 +4738,7 @@ mod tests {
             timed_out: false,
         };
 
-        let out = format_exec_output_str(&exec);
+        let out = format_exec_output_str(&exec, None);
         assert!(out.len() <= MODEL_FORMAT_MAX_BYTES, "exceeds byte budget");
         assert!(out.contains("omitted"), "should contain elision marker");
 
@@ -3515,7 +4769,7 @@ mod tests {
             timed_out: true,
         };
 
-        let out = format_exec_output_str(&exec);
+        let out = format_exec_output_str(&exec, None);
 
         assert_eq!(
             out,
@@ -3575,6 +4829,185 @@ mod tests {
         assert_eq!(expected, got);
     }
 
+    #[test]
+    fn oversized_mcp_results_are_cut_at_content_blocks() {
+        let estimator = TokenEstimator::O200kBase;
+        let block = text_block(&"word ".repeat(1_000));
+        let ctr = CallToolResult {
+            content: vec![block.clone(); 20],
+            is_error: None,
+            structured_content: Some(json!({ "blocks": 20 })),
+        };
+
+        let got = truncate_mcp_tool_result(estimator, &ctr);
+        assert!(estimator.count(&got.content) <= MODEL_FORMAT_MAX_TOKENS);
+        assert_eq!(Some(true), got.success);
+        let blocks: Vec<ContentBlock> =
+            serde_json::from_str(&got.content).expect("output stays valid JSON");
+        let kept = blocks.len() - 1;
+        assert!(kept > 0);
+        assert!(blocks[..kept].iter().all(|b| b == &block));
+        assert_eq!(
+            Some(&text_block(&format!(
+                "[... omitted {} of 20 content blocks; the result was over {MODEL_FORMAT_MAX_TOKENS} tokens ...]",
+                20 - kept
+            ))),
+            blocks.last()
+        );
+
+        let small = CallToolResult {
+            content: vec![text_block("alpha")],
+            is_error: None,
+            structured_content: None,
+        };
+        assert_eq!(
+            convert_call_tool_result_to_function_call_output_payload(&small),
+            truncate_mcp_tool_result(estimator, &small)
+        );
+    }
+
+    #[test]
+    fn command_output_is_not_truncated_twice() {
+        let estimator = TokenEstimator::O200kBase;
+        let output = FunctionCallOutputPayload {
+            content: "x ".repeat(2 * MODEL_FORMAT_MAX_TOKENS as usize),
+            success: Some(true),
+        };
+        assert_eq!(
+            output,
+            truncate_function_call_output(estimator, "shell", &output)
+        );
+
+        let truncated = truncate_function_call_output(estimator, "update_plan", &output);
+        assert!(estimator.count(&truncated.content) <= MODEL_FORMAT_MAX_TOKENS);
+    }
+
+    #[test]
+    fn task_budgets_trip_only_once_exceeded() {
+        let codex_home = tempfile::tempdir().expect("create temp dir");
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .expect("load default test config");
+        assert_eq!(
+            None,
+            exceeded_task_budget(&config, u64::MAX, Some(f64::MAX))
+        );
+
+        config.max_turn_tool_calls = Some(3);
+        config.max_task_tokens = Some(1_000);
+        config.max_cost_usd = Some(2.0);
+        assert_eq!(None, exceeded_task_budget(&config, 1_000, Some(2.0)));
+        assert_eq!(None, exceeded_task_budget(&config, 1_000, None));
+        assert_eq!(
+            Some(TaskBudget::Tokens),
+            exceeded_task_budget(&config, 1_001, None)
+        );
+        assert_eq!(
+            Some(TaskBudget::Cost),
+            exceeded_task_budget(&config, 1_000, Some(2.5))
+        );
+
+        let budget = TaskBudgetTracker::new(&config);
+        for _ in 0..3 {
+            assert_eq!(Ok(()), budget.start_tool_call());
+        }
+        assert_eq!(None, budget.exceeded());
+        assert_eq!(Err(TaskBudget::ToolCalls), budget.start_tool_call());
+        assert_eq!(Some(TaskBudget::ToolCalls), budget.exceeded());
+    }
+
+    #[test]
+    fn images_are_left_out_only_for_chat_providers_without_image_support() {
+        let input = vec![ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![
+                ContentItem::InputText {
+                    text: "what is this?".to_string(),
+                },
+                ContentItem::InputImage {
+                    image_url: "data:image/png;base64,AAAA".to_string(),
+                },
+            ],
+        }];
+        let mut provider = crate::built_in_model_providers()["openai"].clone();
+        assert!(!images_left_out(&provider, &input));
+
+        provider.wire_api = WireApi::Chat;
+        assert!(images_left_out(&provider, &input));
+        assert!(!images_left_out(&provider, &input[..0]));
+
+        provider.supports_images = Some(true);
+        assert!(!images_left_out(&provider, &input));
+    }
+
+    #[test]
+    fn tool_calls_are_refused_once_the_deadline_passes() {
+        let codex_home = tempfile::tempdir().expect("create temp dir");
+        let mut config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .expect("load default test config");
+        config.max_task_duration = Some(StdDuration::ZERO);
+
+        let budget = TaskBudgetTracker::new(&config);
+        assert_eq!(Err(TaskBudget::Duration), budget.start_tool_call());
+        assert_eq!(Some(TaskBudget::Duration), budget.check_deadline());
+
+        let response = budget_exceeded_response(
+            &ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: "{}".to_string(),
+                call_id: "call-1".to_string(),
+            },
+            TaskBudget::Duration,
+        );
+        assert_eq!(
+            Some(ResponseInputItem::FunctionCallOutput {
+                call_id: "call-1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "not run: the task exceeded its `max_task_duration` budget"
+                        .to_string(),
+                    success: Some(false),
+                },
+            }),
+            response
+        );
+    }
+
+    #[test]
+    fn fallback_model_gets_tools_for_its_own_family() {
+        let (_session, turn_context) = make_session_and_context();
+        assert!(matches!(
+            turn_context.tools_config.shell_type,
+            ConfigShellToolType::Default
+        ));
+
+        let provider = turn_context.client.get_provider();
+        let fallback = turn_context.with_fallback_model(
+            &ModelFallback {
+                model_provider: turn_context.client.get_model_provider_id(),
+                model: "codex-mini-latest".to_string(),
+            },
+            provider,
+        );
+        assert_eq!("codex-mini-latest", fallback.client.get_model());
+        assert!(matches!(
+            fallback.tools_config.shell_type,
+            ConfigShellToolType::Local
+        ));
+        assert_eq!(
+            turn_context.tools_config.plan_tool,
+            fallback.tools_config.plan_tool
+        );
+    }
+
     fn text_block(s: &str) -> ContentBlock {
         ContentBlock::TextContent(TextContent {
             annotations: None,
@@ -3601,6 +5034,7 @@ mod tests {
             config.model_reasoning_effort,
             config.model_reasoning_summary,
             conversation_id,
+            Arc::default(),
         );
         let tools_config = ToolsConfig::new(&ToolsConfigParams {
             model_family: &config.model_family,
@@ -3610,6 +5044,7 @@ mod tests {
             use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
             include_view_image_tool: config.include_view_image_tool,
             experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+            include_delegate_tool: config.include_delegate_tool,
         });
         let turn_context = TurnContext {
             client,
@@ -3626,7 +5061,7 @@ mod tests {
         let session = Session {
             conversation_id,
             tx_event,
-            mcp_connection_manager: McpConnectionManager::default(),
+            mcp_connection_manager: RwLock::new(Arc::new(McpConnectionManager::default())),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
             notifier: UserNotifier::default(),
@@ -3636,6 +5071,9 @@ mod tests {
                 ..Default::default()
             }),
             codex_linux_sandbox_exe: None,
+            resource_limits: ResourceLimits::default(),
+            linux_sandbox_backend: LinuxSandboxBackend::default(),
+            output_store: OutputStore::default(),
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
             next_internal_sub_id: AtomicU64::new(0),
@@ -3685,7 +5123,7 @@ mod tests {
             summary1,
         );
         live_history.replace(rebuilt1);
-        rollout_items.push(RolloutItem::Compacted(CompactedItem {
+        rollout_items.push(RolloutItem::Compacted(CompactedItem::Summary {
             message: summary1.to_string(),
         }));
 
@@ -3699,6 +5137,23 @@ mod tests {
         live_history.record_items(std::iter::once(&user2));
         rollout_items.push(RolloutItem::ResponseItem(user2.clone()));
 
+        let tool_call = ResponseItem::FunctionCall {
+            id: None,
+            name: "shell".to_string(),
+            arguments: "{\"command\":[\"ls\"]}".to_string(),
+            call_id: "call-1".to_string(),
+        };
+        let tool_output = ResponseItem::FunctionCallOutput {
+            call_id: "call-1".to_string(),
+            output: FunctionCallOutputPayload {
+                content: "file1\nfile2".to_string(),
+                success: Some(true),
+            },
+        };
+        live_history.record_items([&tool_call, &tool_output]);
+        rollout_items.push(RolloutItem::ResponseItem(tool_call));
+        rollout_items.push(RolloutItem::ResponseItem(tool_output));
+
         let assistant2 = ResponseItem::Message {
             id: None,
             role: "assistant".to_string(),
@@ -3718,7 +5173,7 @@ mod tests {
             summary2,
         );
         live_history.replace(rebuilt2);
-        rollout_items.push(RolloutItem::Compacted(CompactedItem {
+        rollout_items.push(RolloutItem::Compacted(CompactedItem::Summary {
             message: summary2.to_string(),
         }));
 
@@ -3742,6 +5197,14 @@ mod tests {
         live_history.record_items(std::iter::once(&assistant3));
         rollout_items.push(RolloutItem::ResponseItem(assistant3.clone()));
 
+        let elide = CompactedItem::ElideToolOutputs {
+            keep_recent_turns: 1,
+        };
+        let mut elided = live_history.contents();
+        assert!(apply_compaction_stage(&mut elided, &elide));
+        live_history.replace(elided);
+        rollout_items.push(RolloutItem::Compacted(elide));
+
         (rollout_items, live_history.contents())
     }
 
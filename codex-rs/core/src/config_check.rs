@@ -0,0 +1,406 @@
+//! Validation of `config.toml` files for `codex config check`.
+//!
+//! Deserializing [`ConfigToml`] silently skips keys it does not know, so typos
+//! go unnoticed. This module walks the parsed document against the JSON Schema
+//! of [`ConfigToml`] to find them, and reports type errors and references to
+//! undefined profiles or model providers along with their line and column.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use serde_json::Value as JsonValue;
+use toml_edit::Document;
+use toml_edit::Item;
+use toml_edit::TableLike;
+use toml_edit::Value as TomlEditValue;
+
+use crate::config::ConfigToml;
+use crate::model_provider_info::built_in_model_providers;
+
+/// Keys the loader accepts through `#[serde(alias)]`, which the schema does not
+/// list.
+const KEY_ALIASES: &[&str] = &["tools.web_search_request"];
+
+/// A config file to check.
+#[derive(Debug, Clone)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    pub contents: String,
+}
+
+/// A problem found in a config file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiagnostic {
+    pub path: PathBuf,
+    /// 1-based line and column (in characters) of the offending key or value,
+    /// when known.
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(
+                f,
+                "{}:{line}:{column}: {}",
+                self.path.display(),
+                self.message
+            ),
+            None => write!(f, "{}: {}", self.path.display(), self.message),
+        }
+    }
+}
+
+/// Returns the JSON Schema of `config.toml`.
+pub fn config_toml_schema() -> JsonValue {
+    serde_json::to_value(schemars::schema_for!(ConfigToml)).unwrap_or_default()
+}
+
+/// Checks `files` for syntax errors, unknown keys, values of the wrong type and
+/// references to profiles or model providers that none of the files define.
+pub fn check_config_files(files: &[ConfigFile]) -> Vec<ConfigDiagnostic> {
+    let schema = config_toml_schema();
+    let mut diagnostics = Vec::new();
+    let mut documents = Vec::new();
+    for file in files {
+        match Document::parse(file.contents.as_str()) {
+            Ok(document) => documents.push((file, document)),
+            Err(err) => {
+                diagnostics.push(file.diagnostic(err.span(), err.message().to_string()));
+            }
+        }
+    }
+
+    let mut profiles = BTreeSet::new();
+    let mut providers: BTreeSet<String> = built_in_model_providers().into_keys().collect();
+    for (_, document) in &documents {
+        profiles.extend(table_keys(document.as_table().get("profiles")));
+        providers.extend(table_keys(document.as_table().get("model_providers")));
+    }
+
+    for (file, document) in &documents {
+        let mut checker = Checker {
+            file,
+            schema: &schema,
+            profiles: &profiles,
+            providers: &providers,
+            diagnostics: &mut diagnostics,
+        };
+        checker.check_table(document.as_table(), &schema, "");
+        checker.check_references(document.as_table());
+        if let Err(err) = toml::from_str::<ConfigToml>(&file.contents) {
+            diagnostics.push(file.diagnostic(err.span(), err.message().to_string()));
+        }
+    }
+    diagnostics
+}
+
+impl ConfigFile {
+    fn diagnostic(&self, span: Option<Range<usize>>, message: String) -> ConfigDiagnostic {
+        ConfigDiagnostic {
+            path: self.path.clone(),
+            position: span.map(|span| line_column(&self.contents, span.start)),
+            message,
+        }
+    }
+}
+
+struct Checker<'a> {
+    file: &'a ConfigFile,
+    schema: &'a JsonValue,
+    profiles: &'a BTreeSet<String>,
+    providers: &'a BTreeSet<String>,
+    diagnostics: &'a mut Vec<ConfigDiagnostic>,
+}
+
+impl Checker<'_> {
+    fn check_table(&mut self, table: &dyn TableLike, schema: &JsonValue, prefix: &str) {
+        for (key, item) in table.iter() {
+            let path = if prefix.is_empty() {
+                key.to_string()
+            } else {
+                format!("{prefix}.{key}")
+            };
+            match child_schema(self.schema, schema, key) {
+                KeySchema::Known(child) => self.check_item(item, child, &path),
+                KeySchema::Unknown if !KEY_ALIASES.contains(&path.as_str()) => {
+                    let span = table.get_key_value(key).and_then(|(key, _)| key.span());
+                    self.report(span, format!("unknown key `{path}`"));
+                }
+                KeySchema::Unknown | KeySchema::Unconstrained | KeySchema::NotATable => {}
+            }
+        }
+    }
+
+    fn check_item(&mut self, item: &Item, schema: &JsonValue, path: &str) {
+        match item {
+            Item::Table(table) => self.check_table(table, schema, path),
+            Item::Value(TomlEditValue::InlineTable(table)) => {
+                self.check_table(table, schema, path);
+            }
+            Item::ArrayOfTables(tables) => {
+                if let Some(items) = items_schema(self.schema, schema) {
+                    for table in tables.iter() {
+                        self.check_table(table, items, path);
+                    }
+                }
+            }
+            Item::Value(TomlEditValue::Array(values)) => {
+                if let Some(items) = items_schema(self.schema, schema) {
+                    for value in values.iter() {
+                        if let TomlEditValue::InlineTable(table) = value {
+                            self.check_table(table, items, path);
+                        }
+                    }
+                }
+            }
+            Item::None | Item::Value(_) => {}
+        }
+    }
+
+    fn check_references(&mut self, root: &dyn TableLike) {
+        if let Some(value) = root.get("profile").and_then(Item::as_value)
+            && let Some(profile) = value.as_str()
+            && !self.profiles.contains(profile)
+        {
+            self.report(
+                value.span(),
+                format!("profile `{profile}` is not defined in any `[profiles]` table"),
+            );
+        }
+
+        let mut provider_refs: Vec<&TomlEditValue> = Vec::new();
+        provider_refs.extend(root.get("model_provider").and_then(Item::as_value));
+        if let Some(profiles) = root.get("profiles").and_then(Item::as_table_like) {
+            for (_, profile) in profiles.iter() {
+                if let Some(profile) = profile.as_table_like() {
+                    provider_refs.extend(profile.get("model_provider").and_then(Item::as_value));
+                }
+            }
+        }
+        match root.get("fallback") {
+            Some(Item::ArrayOfTables(entries)) => {
+                for entry in entries.iter() {
+                    provider_refs.extend(entry.get("model_provider").and_then(Item::as_value));
+                }
+            }
+            Some(Item::Value(TomlEditValue::Array(entries))) => {
+                for entry in entries.iter().filter_map(TomlEditValue::as_inline_table) {
+                    provider_refs.extend(entry.get("model_provider"));
+                }
+            }
+            _ => {}
+        }
+        for value in provider_refs {
+            if let Some(provider) = value.as_str()
+                && !self.providers.contains(provider)
+            {
+                self.report(
+                    value.span(),
+                    format!("model provider `{provider}` is not defined in `model_providers`"),
+                );
+            }
+        }
+    }
+
+    fn report(&mut self, span: Option<Range<usize>>, message: String) {
+        self.diagnostics.push(self.file.diagnostic(span, message));
+    }
+}
+
+/// Follows `$ref`s in `schema` to the definitions of `root` they point at.
+fn resolve<'a>(root: &'a JsonValue, mut schema: &'a JsonValue) -> &'a JsonValue {
+    while let Some(reference) = schema.get("$ref").and_then(JsonValue::as_str) {
+        let Some(definition) = reference
+            .strip_prefix("#/definitions/")
+            .and_then(|name| root.get("definitions")?.get(name))
+        else {
+            break;
+        };
+        schema = definition;
+    }
+    schema
+}
+
+/// What `schema`, describing a table, says about the value under `key`.
+fn child_schema<'a>(root: &'a JsonValue, schema: &'a JsonValue, key: &str) -> KeySchema<'a> {
+    let schema = resolve(root, schema);
+    if let Some(child) = schema.get("properties").and_then(|p| p.get(key)) {
+        return KeySchema::Known(child);
+    }
+    let own = match schema.get("additionalProperties") {
+        Some(JsonValue::Bool(true)) => KeySchema::Unconstrained,
+        Some(JsonValue::Bool(false)) => KeySchema::Unknown,
+        Some(child) => KeySchema::Known(child),
+        None if schema.get("properties").is_some() => KeySchema::Unknown,
+        None if is_object_type(schema) => KeySchema::Unconstrained,
+        None => KeySchema::NotATable,
+    };
+    subschemas(schema)
+        .map(|subschema| child_schema(root, subschema, key))
+        .fold(own, KeySchema::most_permissive)
+}
+
+/// The schema of the elements of the array described by `schema`.
+fn items_schema<'a>(root: &'a JsonValue, schema: &'a JsonValue) -> Option<&'a JsonValue> {
+    let schema = resolve(root, schema);
+    if let Some(items) = schema.get("items").filter(|items| items.is_object()) {
+        return Some(items);
+    }
+    subschemas(schema).find_map(|alt| items_schema(root, alt))
+}
+
+enum KeySchema<'a> {
+    /// The key is declared with this schema.
+    Known(&'a JsonValue),
+    /// The table accepts any key.
+    Unconstrained,
+    /// The table only accepts the keys it declares.
+    Unknown,
+    /// The schema does not describe a table.
+    NotATable,
+}
+
+impl<'a> KeySchema<'a> {
+    fn rank(&self) -> u8 {
+        match self {
+            KeySchema::Known(_) => 3,
+            KeySchema::Unconstrained => 2,
+            KeySchema::Unknown => 1,
+            KeySchema::NotATable => 0,
+        }
+    }
+
+    /// Combines what two alternatives of a schema say about a key.
+    fn most_permissive(self, other: KeySchema<'a>) -> KeySchema<'a> {
+        if other.rank() > self.rank() {
+            other
+        } else {
+            self
+        }
+    }
+}
+
+fn subschemas(schema: &JsonValue) -> impl Iterator<Item = &JsonValue> {
+    ["anyOf", "oneOf", "allOf"]
+        .into_iter()
+        .filter_map(|combinator| schema.get(combinator).and_then(JsonValue::as_array))
+        .flatten()
+}
+
+fn is_object_type(schema: &JsonValue) -> bool {
+    match schema.get("type") {
+        Some(JsonValue::String(ty)) => ty == "object",
+        Some(JsonValue::Array(types)) => types.iter().any(|ty| ty == "object"),
+        _ => false,
+    }
+}
+
+fn table_keys(item: Option<&Item>) -> Vec<String> {
+    item.and_then(Item::as_table_like)
+        .map(|table| table.iter().map(|(key, _)| key.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Converts a byte offset into a 1-based line and column.
+fn line_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |idx| idx + 1);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn check(contents: &str) -> Vec<String> {
+        let file = ConfigFile {
+            path: PathBuf::from("config.toml"),
+            contents: contents.to_string(),
+        };
+        check_config_files(&[file])
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn valid_config_has_no_diagnostics() {
+        let contents = r#"
+model = "o3"
+profile = "fast"
+
+[tools]
+web_search_request = true
+
+[profiles.fast]
+model = "gpt-5"
+model_provider = "openai"
+
+[mcp_servers.docs]
+command = "npx"
+startup_timeout_ms = 2000
+
+[shell_environment_policy]
+set = { CI = "1" }
+"#;
+        assert_eq!(check(contents), Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_unknown_keys_with_their_position() {
+        let contents = r#"
+modle = "o3"
+
+[profiles.fast]
+model = "gpt-5"
+aproval_policy = "never"
+
+[mcp_servers.docs]
+command = "npx"
+timeout = 5
+
+[sandbox_workspace_write]
+writable_roots = ["/tmp"]
+network = true
+"#;
+        assert_eq!(
+            check(contents),
+            vec![
+                "config.toml:2:1: unknown key `modle`",
+                "config.toml:6:1: unknown key `profiles.fast.aproval_policy`",
+                "config.toml:10:1: unknown key `mcp_servers.docs.timeout`",
+                "config.toml:14:1: unknown key `sandbox_workspace_write.network`",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_type_errors_and_undefined_references() {
+        let contents = r#"model = "o3"
+profile = "slow"
+model_provider = "acme"
+model_context_window = "large"
+"#;
+        let diagnostics = check(contents);
+        assert_eq!(diagnostics.len(), 3, "{diagnostics:?}");
+        assert_eq!(
+            diagnostics[0],
+            "config.toml:2:11: profile `slow` is not defined in any `[profiles]` table"
+        );
+        assert_eq!(
+            diagnostics[1],
+            "config.toml:3:18: model provider `acme` is not defined in `model_providers`"
+        );
+        assert!(
+            diagnostics[2].starts_with("config.toml:4:24: "),
+            "{diagnostics:?}"
+        );
+    }
+}
@@ -0,0 +1,282 @@
+//! Interpolation of `${VAR}`, `${VAR:-default}` and `$(command)` references
+//! in config values.
+//!
+//! Secrets such as API tokens should not have to be written into
+//! `config.toml`. An MCP server or model provider that sets
+//! `interpolate = true` may reference environment variables and, when the
+//! user opts in with `interpolate_commands = true`, the output of a shell
+//! command in the string values of the tables listed for it in
+//! [`INTERPOLATED_TABLES`]. Other values, including those of entries without
+//! `interpolate = true`, are used as written. References are resolved once,
+//! when the configuration is loaded. `$$` stands for a literal `$`.
+
+use std::io::Error;
+use std::io::ErrorKind;
+use std::process::Command;
+
+use toml::Value as TomlValue;
+
+/// Tables of entries that can opt into interpolation, with the tables of
+/// each entry whose string values are then interpolated.
+const INTERPOLATED_TABLES: &[(&str, &[&str])] = &[
+    ("mcp_servers", &["env"]),
+    ("model_providers", &["http_headers", "query_params"]),
+];
+
+/// Resolves the references in the interpolated values of `root` in place and
+/// returns the dotted key paths of the values that contained any.
+pub(crate) fn interpolate_config_values(
+    root: &mut TomlValue,
+    allow_commands: bool,
+) -> std::io::Result<Vec<String>> {
+    let mut interpolated = Vec::new();
+    for (entries_key, tables) in INTERPOLATED_TABLES {
+        let Some(TomlValue::Table(entries)) = root.get_mut(*entries_key) else {
+            continue;
+        };
+        for (name, entry) in entries.iter_mut() {
+            let TomlValue::Table(entry) = entry else {
+                continue;
+            };
+            let opted_in = entry
+                .get("interpolate")
+                .and_then(TomlValue::as_bool)
+                .unwrap_or(false);
+            if !opted_in {
+                continue;
+            }
+            for table_key in *tables {
+                let Some(TomlValue::Table(table)) = entry.get_mut(*table_key) else {
+                    continue;
+                };
+                for (key, value) in table.iter_mut() {
+                    let TomlValue::String(raw) = value else {
+                        continue;
+                    };
+                    let path = format!("{entries_key}.{name}.{table_key}.{key}");
+                    let resolved = interpolate(raw, &path, allow_commands)?;
+                    if resolved != *raw {
+                        *raw = resolved;
+                        interpolated.push(path);
+                    }
+                }
+            }
+        }
+    }
+    Ok(interpolated)
+}
+
+/// Expands the references in `raw`, the value of the config key `key`.
+fn interpolate(raw: &str, key: &str, allow_commands: bool) -> std::io::Result<String> {
+    let mut resolved = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(idx) = rest.find('$') {
+        resolved.push_str(&rest[..idx]);
+        let after = &rest[idx + 1..];
+        if let Some(after) = after.strip_prefix('$') {
+            resolved.push('$');
+            rest = after;
+        } else if let Some(body) = after.strip_prefix('{') {
+            let end = body
+                .find('}')
+                .ok_or_else(|| invalid_data(format!("unterminated `${{` in `{key}`")))?;
+            resolved.push_str(&resolve_variable(&body[..end], key)?);
+            rest = &body[end + 1..];
+        } else if let Some(body) = after.strip_prefix('(') {
+            let end = closing_paren(body)
+                .ok_or_else(|| invalid_data(format!("unterminated `$(` in `{key}`")))?;
+            resolved.push_str(&run_command(&body[..end], key, allow_commands)?);
+            rest = &body[end + 1..];
+        } else {
+            resolved.push('$');
+            rest = after;
+        }
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
+
+fn resolve_variable(expression: &str, key: &str) -> std::io::Result<String> {
+    let (name, default) = match expression.split_once(":-") {
+        Some((name, default)) => (name, Some(default)),
+        None => (expression, None),
+    };
+    let is_valid_name =
+        !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !is_valid_name {
+        return Err(invalid_data(format!(
+            "invalid environment variable name `{name}` in `{key}`"
+        )));
+    }
+
+    match (std::env::var(name).ok(), default) {
+        (Some(value), Some(default)) if value.is_empty() => Ok(default.to_string()),
+        (Some(value), _) => Ok(value),
+        (None, Some(default)) => Ok(default.to_string()),
+        (None, None) => Err(invalid_data(format!(
+            "environment variable `{name}` referenced by `{key}` is not set"
+        ))),
+    }
+}
+
+/// Returns the index of the `)` that closes the `$(` preceding `body`.
+fn closing_paren(body: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (idx, c) in body.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(idx),
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+fn run_command(command: &str, key: &str, allow_commands: bool) -> std::io::Result<String> {
+    if !allow_commands {
+        return Err(invalid_data(format!(
+            "`$({command})` in `{key}` is not allowed; set `interpolate_commands = true` in \
+             $CODEX_HOME/config.toml to run commands from config values"
+        )));
+    }
+
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    let output = cmd.arg(command).output().map_err(|e| {
+        Error::new(
+            e.kind(),
+            format!("failed to run `{command}` referenced by `{key}`: {e}"),
+        )
+    })?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(invalid_data(format!(
+            "command `{command}` referenced by `{key}` failed with {}: {}",
+            output.status,
+            stderr.trim()
+        )));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn interpolate_toml(text: &str, allow_commands: bool) -> std::io::Result<TomlValue> {
+        let mut value: TomlValue = toml::from_str(text).expect("valid toml");
+        interpolate_config_values(&mut value, allow_commands)?;
+        Ok(value)
+    }
+
+    #[test]
+    fn expands_variables_defaults_and_escapes() {
+        let path = std::env::var("PATH").expect("PATH is set");
+        let mut value: TomlValue = toml::from_str(
+            r#"
+[mcp_servers.docs]
+command = "${PATH}"
+interpolate = true
+env = { SEARCH = "${PATH}", LEVEL = "${CODEX_TEST_UNSET_VARIABLE:-info}", PRICE = "$$5 $x" }
+
+[mcp_servers.search]
+command = "search"
+env = { PRICE = "$$5 ${NOT_A_REFERENCE} $(not a command)" }
+
+[model_providers.acme]
+interpolate = true
+http_headers = { Authorization = "Bearer ${CODEX_TEST_UNSET_VARIABLE:-}" }
+"#,
+        )
+        .expect("valid toml");
+
+        let mut interpolated =
+            interpolate_config_values(&mut value, false).expect("interpolation succeeds");
+
+        let env = &value["mcp_servers"]["docs"]["env"];
+        assert_eq!(env["SEARCH"].as_str(), Some(path.as_str()));
+        assert_eq!(env["LEVEL"].as_str(), Some("info"));
+        assert_eq!(env["PRICE"].as_str(), Some("$5 $x"));
+        assert_eq!(
+            value["model_providers"]["acme"]["http_headers"]["Authorization"].as_str(),
+            Some("Bearer ")
+        );
+        // Only the listed tables are interpolated.
+        assert_eq!(
+            value["mcp_servers"]["docs"]["command"].as_str(),
+            Some("${PATH}")
+        );
+        // Entries that did not opt in are used as written.
+        assert_eq!(
+            value["mcp_servers"]["search"]["env"]["PRICE"].as_str(),
+            Some("$$5 ${NOT_A_REFERENCE} $(not a command)")
+        );
+        interpolated.sort();
+        assert_eq!(
+            interpolated,
+            vec![
+                "mcp_servers.docs.env.LEVEL",
+                "mcp_servers.docs.env.PRICE",
+                "mcp_servers.docs.env.SEARCH",
+                "model_providers.acme.http_headers.Authorization",
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_variable_names_the_variable_and_the_key() {
+        let err = interpolate_toml(
+            "[model_providers.acme]\ninterpolate = true\nquery_params = { api-key = \"${CODEX_TEST_UNSET_VARIABLE}\" }\n",
+            false,
+        )
+        .expect_err("unset variable");
+        assert_eq!(
+            err.to_string(),
+            "environment variable `CODEX_TEST_UNSET_VARIABLE` referenced by \
+             `model_providers.acme.query_params.api-key` is not set"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn commands_only_run_when_allowed() {
+        let text = "[mcp_servers.docs]\ninterpolate = true\nenv = { TOKEN = \"$(printf 'se(c)ret\\n')\" }\n";
+
+        let err = interpolate_toml(text, false).expect_err("commands are disabled");
+        assert!(
+            err.to_string().contains("interpolate_commands = true"),
+            "{err}"
+        );
+
+        let value = interpolate_toml(text, true).expect("command succeeds");
+        assert_eq!(
+            value["mcp_servers"]["docs"]["env"]["TOKEN"].as_str(),
+            Some("se(c)ret")
+        );
+
+        let err = interpolate_toml(
+            "[mcp_servers.docs]\ninterpolate = true\nenv = { TOKEN = \"$(exit 3)\" }\n",
+            true,
+        )
+        .expect_err("command fails");
+        assert!(
+            err.to_string()
+                .starts_with("command `exit 3` referenced by `mcp_servers.docs.env.TOKEN` failed"),
+            "{err}"
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Bookkeeping for where each effective configuration value came from.
+//!
+//! [`Config::load_with_cli_overrides`](crate::config::Config::load_with_cli_overrides)
+//! merges several layers of configuration; [`ConfigOrigins`] remembers which
+//! layer last set each key so that the UI can explain the effective values.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::path::PathBuf;
+
+use toml::Value as TomlValue;
+
+/// Shown in place of interpolated values, which may hold secrets.
+pub const REDACTED: &str = "[redacted]";
+
+/// A source of configuration values, ordered from lowest to highest
+/// precedence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConfigLayer {
+    /// No layer set the value, so the built-in default applies.
+    Default,
+    /// `$CODEX_HOME/config.toml`.
+    User,
+    /// `.codex/config.toml` at the root of a trusted project.
+    Project,
+    /// `-c key=value` overrides and dedicated command-line flags.
+    CommandLine,
+}
+
+impl fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ConfigLayer::Default => "default",
+            ConfigLayer::User => "user config",
+            ConfigLayer::Project => "project config",
+            ConfigLayer::CommandLine => "command line",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Records which [`ConfigLayer`] set each configuration key.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigOrigins {
+    /// Project config file that was merged into the configuration, if any.
+    pub project_config: Option<PathBuf>,
+    /// Dotted key path of every leaf value, mapped to the layer that set it.
+    keys: BTreeMap<String, ConfigLayer>,
+    /// Keys set through strongly-typed overrides such as `--model`, which win
+    /// over profiles as well as over the top-level keys.
+    flags: BTreeSet<String>,
+    /// Keys whose values were expanded from `${VAR}` or `$(command)`
+    /// references and may therefore hold secrets.
+    interpolated: BTreeSet<String>,
+}
+
+impl ConfigOrigins {
+    /// Records every leaf of `value`, found under the dotted `prefix`, as set
+    /// by `layer`.
+    pub(crate) fn record(&mut self, layer: ConfigLayer, prefix: &str, value: &TomlValue) {
+        match value {
+            TomlValue::Table(table) => {
+                self.keys.remove(prefix);
+                for (key, value) in table {
+                    let path = if prefix.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{prefix}.{key}")
+                    };
+                    self.record(layer, &path, value);
+                }
+            }
+            _ => {
+                // A scalar replaces anything previously set beneath it.
+                let nested = format!("{prefix}.");
+                self.keys.retain(|key, _| !key.starts_with(&nested));
+                self.keys.insert(prefix.to_string(), layer);
+            }
+        }
+    }
+
+    /// Records `key` as set by a dedicated command-line flag.
+    pub(crate) fn record_flag(&mut self, key: &str) {
+        self.flags.insert(key.to_string());
+    }
+
+    /// Records `key` as holding an interpolated value.
+    pub(crate) fn record_interpolated(&mut self, key: String) {
+        self.interpolated.insert(key);
+    }
+
+    /// Whether the value of `key`, a dotted path such as
+    /// `mcp_servers.docs.env.TOKEN`, was interpolated. Such values should be
+    /// redacted when the configuration is displayed.
+    pub fn is_interpolated(&self, key: &str) -> bool {
+        self.interpolated.contains(key)
+    }
+
+    /// Returns `value`, the value of `key`, or [`REDACTED`] if it was
+    /// interpolated.
+    pub fn redact<'a>(&self, key: &str, value: &'a str) -> &'a str {
+        if self.is_interpolated(key) {
+            REDACTED
+        } else {
+            value
+        }
+    }
+
+    /// Returns the layer that decided the value of `key`, a dotted path such as
+    /// `model` or `sandbox_workspace_write`. For a table this is the
+    /// highest-precedence layer that set anything inside it. A value from the
+    /// active `profile` takes precedence over the top-level key.
+    pub fn layer_of(&self, key: &str, profile: Option<&str>) -> ConfigLayer {
+        if self.flags.contains(key) {
+            return ConfigLayer::CommandLine;
+        }
+        if let Some(profile) = profile {
+            let layer = self.layer_of_path(&format!("profiles.{profile}.{key}"));
+            if layer != ConfigLayer::Default {
+                return layer;
+            }
+        }
+        self.layer_of_path(key)
+    }
+
+    /// Whether `layer` decided the value of `key` or of anything inside it.
+    pub fn is_set_by(&self, key: &str, layer: ConfigLayer) -> bool {
+        let nested = format!("{key}.");
+        self.keys.iter().any(|(path, path_layer)| {
+            *path_layer == layer && (path.as_str() == key || path.starts_with(&nested))
+        })
+    }
+
+    fn layer_of_path(&self, path: &str) -> ConfigLayer {
+        let nested = format!("{path}.");
+        self.keys
+            .iter()
+            .filter(|(key, _)| key.as_str() == path || key.starts_with(&nested))
+            .map(|(_, layer)| *layer)
+            .max()
+            .unwrap_or(ConfigLayer::Default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn toml(text: &str) -> TomlValue {
+        toml::from_str(text).expect("valid toml")
+    }
+
+    #[test]
+    fn later_layers_win_and_tables_report_their_newest_value() {
+        let mut origins = ConfigOrigins::default();
+        origins.record(
+            ConfigLayer::User,
+            "",
+            &toml(
+                r#"
+model = "o3"
+approval_policy = "on-request"
+
+[sandbox_workspace_write]
+network_access = false
+
+[profiles.fast]
+model = "gpt-5"
+"#,
+            ),
+        );
+        origins.record(
+            ConfigLayer::Project,
+            "",
+            &toml(
+                r#"
+model = "gpt-5-codex"
+
+[sandbox_workspace_write]
+writable_roots = []
+"#,
+            ),
+        );
+        origins.record(
+            ConfigLayer::CommandLine,
+            "approval_policy",
+            &TomlValue::String("never".to_string()),
+        );
+
+        assert_eq!(origins.layer_of("model", None), ConfigLayer::Project);
+        assert_eq!(
+            origins.layer_of("approval_policy", None),
+            ConfigLayer::CommandLine
+        );
+        assert_eq!(
+            origins.layer_of("sandbox_workspace_write", None),
+            ConfigLayer::Project
+        );
+        assert_eq!(origins.layer_of("model", Some("fast")), ConfigLayer::User);
+        assert_eq!(origins.layer_of("sandbox_mode", None), ConfigLayer::Default);
+
+        origins.record_flag("model");
+        assert_eq!(
+            origins.layer_of("model", Some("fast")),
+            ConfigLayer::CommandLine
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::path::PathBuf;
 
@@ -8,7 +9,7 @@ use codex_protocol::config_types::Verbosity;
 
 /// Collection of common configuration options that a user can define as a unit
 /// in `config.toml`.
-#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, JsonSchema)]
 pub struct ConfigProfile {
     pub model: Option<String>,
     /// The key in the `model_providers` map identifying the
@@ -20,6 +21,11 @@ pub struct ConfigProfile {
     pub model_verbosity: Option<Verbosity>,
     pub chatgpt_base_url: Option<String>,
     pub experimental_instructions_file: Option<PathBuf>,
+    pub max_turn_tool_calls: Option<u64>,
+    pub max_task_tokens: Option<u64>,
+    /// Maximum task duration in seconds.
+    pub max_task_duration: Option<u64>,
+    pub max_cost_usd: Option<f64>,
 }
 
 impl From<ConfigProfile> for codex_protocol::mcp_protocol::Profile {
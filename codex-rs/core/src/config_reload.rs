@@ -0,0 +1,433 @@
+//! Reloading `config.toml` into a running session.
+//!
+//! [`watch_config_files`] polls the user and project config files and submits
+//! [`Op::ReloadConfig`] when they change. The session then reloads its
+//! [`Config`], applies the settings that can change mid-session and reports
+//! the ones that only take effect after a restart (see [`ConfigChanges`]).
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use async_channel::WeakSender;
+use codex_protocol::protocol::Op;
+use codex_protocol::protocol::Submission;
+
+use crate::config::CONFIG_TOML_FILE;
+use crate::config::Config;
+use crate::config::PROJECT_CONFIG_DIR;
+use crate::config_layer::ConfigLayer;
+use crate::git_info::get_git_repo_root;
+
+/// How often the config files are checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Keys that are not applied to a running session when the project config
+/// sets them, with the `config.toml` keys their value is derived from. A
+/// change to a file in the repository must not loosen approvals or the
+/// sandbox, or start MCP servers, without the user restarting Codex.
+const PROJECT_SENSITIVE_KEYS: &[(&str, &[&str])] = &[
+    ("approval_policy", &["approval_policy"]),
+    ("sandbox_mode", &["sandbox_mode", "sandbox_workspace_write"]),
+    ("shell_environment_policy", &["shell_environment_policy"]),
+    ("mcp_servers", &["mcp_servers"]),
+];
+
+/// The config files whose changes are applied to a session using `config`.
+pub(crate) fn watched_config_files(config: &Config) -> Vec<PathBuf> {
+    let mut paths = vec![config.codex_home.join(CONFIG_TOML_FILE)];
+    if let Some(project_root) = get_git_repo_root(&config.cwd) {
+        paths.push(project_root.join(PROJECT_CONFIG_DIR).join(CONFIG_TOML_FILE));
+    }
+    paths
+}
+
+/// Submits [`Op::ReloadConfig`] whenever the contents of one of `paths`
+/// change. Returns once the submission channel is closed.
+pub(crate) async fn watch_config_files(paths: Vec<PathBuf>, tx_sub: WeakSender<Submission>) {
+    let mut contents = read_files(&paths);
+    let mut reloads = 0u64;
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let Some(tx_sub) = tx_sub.upgrade() else {
+            return;
+        };
+
+        let current = read_files(&paths);
+        if current == contents {
+            continue;
+        }
+        contents = current;
+        reloads += 1;
+        let submission = Submission {
+            id: format!("config-reload-{reloads}"),
+            op: Op::ReloadConfig,
+        };
+        if tx_sub.send(submission).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn read_files(paths: &[PathBuf]) -> Vec<Option<String>> {
+    paths
+        .iter()
+        .map(|path| std::fs::read_to_string(path).ok())
+        .collect()
+}
+
+/// The `config.toml` keys whose effective value differs between the running
+/// [`Config`] and a freshly reloaded one.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ConfigChanges {
+    /// Keys that are applied to the running session.
+    pub applied: Vec<&'static str>,
+    /// Keys that only take effect after Codex is restarted.
+    pub restart_required: Vec<&'static str>,
+    /// Keys that could otherwise be applied but that the project config sets
+    /// (see [`PROJECT_SENSITIVE_KEYS`]); they also wait for a restart.
+    pub project_restart_required: Vec<&'static str>,
+}
+
+impl ConfigChanges {
+    pub fn between(running: &Config, reloaded: &Config) -> Self {
+        let applied = [
+            ("model", running.model != reloaded.model),
+            (
+                "model_reasoning_effort",
+                running.model_reasoning_effort != reloaded.model_reasoning_effort,
+            ),
+            (
+                "model_reasoning_summary",
+                running.model_reasoning_summary != reloaded.model_reasoning_summary,
+            ),
+            (
+                "approval_policy",
+                running.approval_policy != reloaded.approval_policy,
+            ),
+            (
+                "sandbox_mode",
+                running.sandbox_policy != reloaded.sandbox_policy,
+            ),
+            (
+                "shell_environment_policy",
+                running.shell_environment_policy != reloaded.shell_environment_policy,
+            ),
+            ("mcp_servers", running.mcp_servers != reloaded.mcp_servers),
+        ];
+        let restart_required = [
+            (
+                "review_model",
+                running.review_model != reloaded.review_model,
+            ),
+            (
+                "model_context_window",
+                running.model_context_window != reloaded.model_context_window,
+            ),
+            (
+                "model_max_output_tokens",
+                running.model_max_output_tokens != reloaded.model_max_output_tokens,
+            ),
+            (
+                "model_auto_compact_token_limit",
+                running.model_auto_compact_token_limit != reloaded.model_auto_compact_token_limit,
+            ),
+            ("compaction", running.compaction != reloaded.compaction),
+            (
+                "compaction_keep_recent_turns",
+                running.compaction_keep_recent_turns != reloaded.compaction_keep_recent_turns,
+            ),
+            (
+                "model_provider",
+                running.model_provider_id != reloaded.model_provider_id
+                    || running.model_provider != reloaded.model_provider,
+            ),
+            (
+                "model_providers",
+                running.model_providers != reloaded.model_providers,
+            ),
+            (
+                "model_pricing",
+                running.model_pricing != reloaded.model_pricing,
+            ),
+            ("models", running.models != reloaded.models),
+            ("fallback", running.fallback != reloaded.fallback),
+            (
+                "model_verbosity",
+                running.model_verbosity != reloaded.model_verbosity,
+            ),
+            (
+                "hide_agent_reasoning",
+                running.hide_agent_reasoning != reloaded.hide_agent_reasoning,
+            ),
+            (
+                "show_raw_agent_reasoning",
+                running.show_raw_agent_reasoning != reloaded.show_raw_agent_reasoning,
+            ),
+            (
+                "instructions",
+                running.user_instructions != reloaded.user_instructions
+                    || running.base_instructions != reloaded.base_instructions,
+            ),
+            ("notify", running.notify != reloaded.notify),
+            (
+                "tui.notifications",
+                running.tui_notifications != reloaded.tui_notifications,
+            ),
+            (
+                "project_doc_max_bytes",
+                running.project_doc_max_bytes != reloaded.project_doc_max_bytes,
+            ),
+            ("history", running.history != reloaded.history),
+            (
+                "resource_limits",
+                running.resource_limits != reloaded.resource_limits,
+            ),
+            (
+                "linux_sandbox_backend",
+                running.linux_sandbox_backend != reloaded.linux_sandbox_backend,
+            ),
+            ("file_opener", running.file_opener != reloaded.file_opener),
+            (
+                "chatgpt_base_url",
+                running.chatgpt_base_url != reloaded.chatgpt_base_url,
+            ),
+            (
+                "tools",
+                running.include_plan_tool != reloaded.include_plan_tool
+                    || running.include_apply_patch_tool != reloaded.include_apply_patch_tool
+                    || running.tools_web_search_request != reloaded.tools_web_search_request
+                    || running.include_view_image_tool != reloaded.include_view_image_tool
+                    || running.include_delegate_tool != reloaded.include_delegate_tool
+                    || running.use_experimental_streamable_shell_tool
+                        != reloaded.use_experimental_streamable_shell_tool
+                    || running.use_experimental_unified_exec_tool
+                        != reloaded.use_experimental_unified_exec_tool,
+            ),
+            (
+                "parallel_tool_calls",
+                running.parallel_tool_calls != reloaded.parallel_tool_calls,
+            ),
+            (
+                "max_turn_tool_calls",
+                running.max_turn_tool_calls != reloaded.max_turn_tool_calls,
+            ),
+            (
+                "max_task_tokens",
+                running.max_task_tokens != reloaded.max_task_tokens,
+            ),
+            (
+                "max_task_duration",
+                running.max_task_duration != reloaded.max_task_duration,
+            ),
+            (
+                "max_cost_usd",
+                running.max_cost_usd != reloaded.max_cost_usd,
+            ),
+            (
+                "output_schema_repair_attempts",
+                running.output_schema_repair_attempts != reloaded.output_schema_repair_attempts,
+            ),
+            (
+                "disable_paste_burst",
+                running.disable_paste_burst != reloaded.disable_paste_burst,
+            ),
+        ];
+        let (project_restart_required, applied) = changed_keys(&applied)
+            .into_iter()
+            .partition(|key| is_set_by_project(running, reloaded, key));
+        Self {
+            applied,
+            restart_required: changed_keys(&restart_required),
+            project_restart_required,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.applied.is_empty()
+            && self.restart_required.is_empty()
+            && self.project_restart_required.is_empty()
+    }
+
+    /// Whether any setting other than `mcp_servers` must be applied to the
+    /// turn context.
+    pub fn affects_turn_context(&self) -> bool {
+        self.applied.iter().any(|key| *key != "mcp_servers")
+    }
+
+    /// A message for the user that lists the changed keys.
+    pub fn message(&self) -> String {
+        let mut parts = vec!["config.toml changed.".to_string()];
+        if !self.applied.is_empty() {
+            parts.push(format!("Applied: {}.", self.applied.join(", ")));
+        }
+        if !self.restart_required.is_empty() {
+            parts.push(format!(
+                "Restart Codex to apply: {}.",
+                self.restart_required.join(", ")
+            ));
+        }
+        if !self.project_restart_required.is_empty() {
+            parts.push(format!(
+                "Set by the project config, so only applied after a restart: {}.",
+                self.project_restart_required.join(", ")
+            ));
+        }
+        parts.join(" ")
+    }
+}
+
+/// Whether the project config set `key`, one of [`PROJECT_SENSITIVE_KEYS`],
+/// before or after the reload.
+fn is_set_by_project(running: &Config, reloaded: &Config, key: &str) -> bool {
+    let Some((_, sources)) = PROJECT_SENSITIVE_KEYS
+        .iter()
+        .find(|(sensitive, _)| *sensitive == key)
+    else {
+        return false;
+    };
+    sources.iter().any(|source| {
+        running
+            .config_origins
+            .is_set_by(source, ConfigLayer::Project)
+            || reloaded
+                .config_origins
+                .is_set_by(source, ConfigLayer::Project)
+    })
+}
+
+fn changed_keys(keys: &[(&'static str, bool)]) -> Vec<&'static str> {
+    keys.iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(key, _)| *key)
+        .collect()
+}
+
+/// Returns `running` with the settings listed in [`ConfigChanges::applied`]
+/// taken from `reloaded`.
+pub(crate) fn with_applied_changes(
+    running: &Config,
+    reloaded: &Config,
+    changes: &ConfigChanges,
+) -> Config {
+    let applied = |key| changes.applied.contains(&key);
+    let mut config = running.clone();
+    config.model = reloaded.model.clone();
+    config.model_family = reloaded.model_family.clone();
+    config.model_reasoning_effort = reloaded.model_reasoning_effort;
+    config.model_reasoning_summary = reloaded.model_reasoning_summary;
+    if applied("approval_policy") {
+        config.approval_policy = reloaded.approval_policy;
+    }
+    if applied("sandbox_mode") {
+        config.sandbox_policy = reloaded.sandbox_policy.clone();
+    }
+    if applied("shell_environment_policy") {
+        config.shell_environment_policy = reloaded.shell_environment_policy.clone();
+    }
+    if applied("mcp_servers") {
+        config.mcp_servers = reloaded.mcp_servers.clone();
+    }
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ConfigOverrides;
+    use crate::config::ConfigToml;
+    use crate::protocol::AskForApproval;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn load(codex_home: &TempDir, toml: &str) -> Config {
+        let cfg: ConfigToml = toml::from_str(toml).expect("valid config.toml");
+        Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            codex_home.path().to_path_buf(),
+        )
+        .expect("load config")
+    }
+
+    #[test]
+    fn lists_applied_and_restart_required_keys() {
+        let codex_home = TempDir::new().expect("tempdir");
+        let running = load(
+            &codex_home,
+            "model = \"o3\"\napproval_policy = \"on-request\"\n",
+        );
+        let reloaded = load(
+            &codex_home,
+            r#"
+model = "o3"
+approval_policy = "never"
+notify = ["notify-send"]
+
+[shell_environment_policy]
+inherit = "core"
+"#,
+        );
+
+        let changes = ConfigChanges::between(&running, &reloaded);
+        assert_eq!(
+            changes,
+            ConfigChanges {
+                applied: vec!["approval_policy", "shell_environment_policy"],
+                restart_required: vec!["notify"],
+                project_restart_required: Vec::new(),
+            }
+        );
+        assert!(changes.affects_turn_context());
+        assert_eq!(
+            changes.message(),
+            "config.toml changed. Applied: approval_policy, shell_environment_policy. \
+             Restart Codex to apply: notify."
+        );
+
+        let updated = with_applied_changes(&running, &reloaded, &changes);
+        assert_eq!(updated.approval_policy, AskForApproval::Never);
+        assert_eq!(updated.notify, None);
+        assert_eq!(
+            ConfigChanges::between(&updated, &reloaded).restart_required,
+            vec!["notify"]
+        );
+        assert!(ConfigChanges::between(&running, &running).is_empty());
+    }
+
+    #[test]
+    fn project_mcp_servers_are_not_applied_until_restart() {
+        let codex_home = TempDir::new().expect("tempdir");
+        let running = load(&codex_home, "model = \"o3\"\n");
+        let project_toml = r#"
+model = "gpt-5-codex"
+
+[mcp_servers.docs]
+command = "docs-server"
+"#;
+        let mut reloaded = load(&codex_home, project_toml);
+        reloaded.config_origins.record(
+            ConfigLayer::Project,
+            "",
+            &toml::from_str(project_toml).expect("valid toml"),
+        );
+
+        let changes = ConfigChanges::between(&running, &reloaded);
+        assert_eq!(
+            changes,
+            ConfigChanges {
+                applied: vec!["model"],
+                restart_required: Vec::new(),
+                project_restart_required: vec!["mcp_servers"],
+            }
+        );
+        assert_eq!(
+            changes.message(),
+            "config.toml changed. Applied: model. \
+             Set by the project config, so only applied after a restart: mcp_servers."
+        );
+
+        let updated = with_applied_changes(&running, &reloaded, &changes);
+        assert_eq!(updated.model, "gpt-5-codex");
+        assert!(updated.mcp_servers.is_empty());
+    }
+}
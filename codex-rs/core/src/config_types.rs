@@ -8,11 +8,17 @@ use std::path::PathBuf;
 use std::time::Duration;
 use wildmatch::WildMatchPattern;
 
+use schemars::JsonSchema;
+use schemars::r#gen::SchemaGenerator;
+use schemars::schema::Schema;
 use serde::Deserialize;
 use serde::Deserializer;
 use serde::Serialize;
 use serde::de::Error as SerdeError;
 
+use crate::protocol::NetworkAccess;
+use crate::tool_apply_patch::ApplyPatchToolType;
+
 #[derive(Serialize, Debug, Clone, PartialEq)]
 pub struct McpServerConfig {
     pub command: String,
@@ -23,6 +29,10 @@ pub struct McpServerConfig {
     #[serde(default)]
     pub env: Option<HashMap<String, String>>,
 
+    /// Whether `${VAR}` and `$(command)` references in `env` are resolved.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub interpolate: bool,
+
     /// Startup timeout in seconds for initializing MCP server & initially listing tools.
     #[serde(
         default,
@@ -36,26 +46,36 @@ pub struct McpServerConfig {
     pub tool_timeout_sec: Option<Duration>,
 }
 
+/// `config.toml` representation of [`McpServerConfig`], which also accepts the
+/// legacy `startup_timeout_ms` field.
+#[derive(Deserialize, JsonSchema)]
+#[schemars(rename = "McpServerConfig")]
+struct RawMcpServerConfig {
+    command: String,
+    #[serde(default)]
+    args: Vec<String>,
+    #[serde(default)]
+    env: Option<HashMap<String, String>>,
+    /// Resolve `${VAR}` and `$(command)` references in `env`.
+    #[serde(default)]
+    interpolate: bool,
+    /// Startup timeout in seconds for initializing MCP server & initially listing tools.
+    #[serde(default)]
+    startup_timeout_sec: Option<f64>,
+    /// Deprecated: use `startup_timeout_sec`.
+    #[serde(default)]
+    startup_timeout_ms: Option<u64>,
+    /// Default timeout in seconds for MCP tool calls initiated via this server.
+    #[serde(default, with = "option_duration_secs")]
+    #[schemars(with = "Option<f64>")]
+    tool_timeout_sec: Option<Duration>,
+}
+
 impl<'de> Deserialize<'de> for McpServerConfig {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        #[derive(Deserialize)]
-        struct RawMcpServerConfig {
-            command: String,
-            #[serde(default)]
-            args: Vec<String>,
-            #[serde(default)]
-            env: Option<HashMap<String, String>>,
-            #[serde(default)]
-            startup_timeout_sec: Option<f64>,
-            #[serde(default)]
-            startup_timeout_ms: Option<u64>,
-            #[serde(default, with = "option_duration_secs")]
-            tool_timeout_sec: Option<Duration>,
-        }
-
         let raw = RawMcpServerConfig::deserialize(deserializer)?;
 
         let startup_timeout_sec = match (raw.startup_timeout_sec, raw.startup_timeout_ms) {
@@ -71,12 +91,23 @@ impl<'de> Deserialize<'de> for McpServerConfig {
             command: raw.command,
             args: raw.args,
             env: raw.env,
+            interpolate: raw.interpolate,
             startup_timeout_sec,
             tool_timeout_sec: raw.tool_timeout_sec,
         })
     }
 }
 
+impl JsonSchema for McpServerConfig {
+    fn schema_name() -> String {
+        RawMcpServerConfig::schema_name()
+    }
+
+    fn json_schema(generator: &mut SchemaGenerator) -> Schema {
+        RawMcpServerConfig::json_schema(generator)
+    }
+}
+
 mod option_duration_secs {
     use serde::Deserialize;
     use serde::Deserializer;
@@ -103,7 +134,7 @@ mod option_duration_secs {
     }
 }
 
-#[derive(Deserialize, Debug, Copy, Clone, PartialEq)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Copy, Clone, PartialEq)]
 pub enum UriBasedFileOpener {
     #[serde(rename = "vscode")]
     VsCode,
@@ -134,8 +165,87 @@ impl UriBasedFileOpener {
     }
 }
 
+/// One entry of the `fallback` chain: a model to retry a turn with when the
+/// current model is rate limited or unavailable.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ModelFallback {
+    /// Key into the `model_providers` map.
+    pub model_provider: String,
+    pub model: String,
+}
+
+/// One stage of the compaction pipeline that runs when the conversation
+/// reaches the auto-compact token limit. Stages run in the configured order
+/// until the history fits again.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum CompactionStrategy {
+    /// Replace the outputs of older tool calls with a short stub.
+    ElideToolOutputs,
+    /// Remove older reasoning items.
+    CollapseReasoning,
+    /// Replace older image inputs with a text placeholder.
+    DropImages,
+    /// Ask the model to summarize the conversation so far.
+    Summarize,
+}
+
+impl CompactionStrategy {
+    pub fn default_pipeline() -> Vec<Self> {
+        vec![
+            Self::ElideToolOutputs,
+            Self::CollapseReasoning,
+            Self::DropImages,
+            Self::Summarize,
+        ]
+    }
+}
+
+/// Token prices for a model, in US dollars per million tokens.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    pub input: f64,
+    /// Price of input tokens served from the prompt cache. Defaults to `input`.
+    pub cached_input: Option<f64>,
+    pub output: f64,
+    /// Price of reasoning output tokens. Defaults to `output`.
+    pub reasoning_output: Option<f64>,
+}
+
+/// Capabilities and limits of a model, declared under `[models.<slug>]`.
+/// Values set here take precedence over the ones built into Codex, which lets
+/// self-hosted and newly released models be used with the right settings.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq, Default)]
+pub struct ModelConfig {
+    /// Size of the context window in tokens.
+    pub context_window: Option<u64>,
+
+    /// Maximum number of output tokens the model can generate.
+    pub max_output_tokens: Option<u64>,
+
+    /// Token threshold at which the conversation history is compacted.
+    pub auto_compact_token_limit: Option<i64>,
+
+    /// Whether the model accepts the `reasoning` request parameter.
+    pub supports_reasoning_summaries: Option<bool>,
+
+    /// Whether the model expects the native `local_shell` tool.
+    pub uses_local_shell_tool: Option<bool>,
+
+    /// How the `apply_patch` tool is provided to the model.
+    pub apply_patch_tool_type: Option<ApplyPatchToolType>,
+
+    /// File with the base instructions to use for the model. Relative paths
+    /// are resolved against the working directory.
+    pub base_instructions_file: Option<PathBuf>,
+
+    /// Contents of `base_instructions_file`, read when the config is loaded.
+    #[serde(skip)]
+    pub base_instructions: Option<String>,
+}
+
 /// Settings that govern if and what will be written to `~/.codex/history.jsonl`.
-#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq, Default)]
 pub struct History {
     /// If true, history entries will not be written to disk.
     pub persistence: HistoryPersistence,
@@ -145,7 +255,7 @@ pub struct History {
     pub max_bytes: Option<usize>,
 }
 
-#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Default)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Copy, Clone, PartialEq, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum HistoryPersistence {
     /// Save all history entries to disk.
@@ -155,7 +265,7 @@ pub enum HistoryPersistence {
     None,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, JsonSchema)]
 #[serde(untagged)]
 pub enum Notifications {
     Enabled(bool),
@@ -169,7 +279,7 @@ impl Default for Notifications {
 }
 
 /// Collection of settings that are specific to the TUI.
-#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
 pub struct Tui {
     /// Enable desktop notifications from the TUI when the terminal is unfocused.
     /// Defaults to `false`.
@@ -177,16 +287,70 @@ pub struct Tui {
     pub notifications: Notifications,
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
 pub struct SandboxWorkspaceWrite {
     #[serde(default)]
     pub writable_roots: Vec<PathBuf>,
+    /// `true`, `false` or `"loopback"`.
     #[serde(default)]
-    pub network_access: bool,
+    pub network_access: NetworkAccess,
     #[serde(default)]
     pub exclude_tmpdir_env_var: bool,
     #[serde(default)]
     pub exclude_slash_tmp: bool,
+    /// Limit reads to the workspace, the writable roots, toolchains, system
+    /// directories and `readable_roots`.
+    #[serde(default)]
+    pub restrict_read_access: bool,
+    /// Additional folders that may be read when `restrict_read_access` is
+    /// set.
+    #[serde(default)]
+    pub readable_roots: Vec<PathBuf>,
+    /// Hosts that commands may reach through the Codex network proxy while
+    /// `network_access` is not `true`, e.g. `"crates.io"` or `"registry.npmjs.org"`.
+    /// Each entry also matches its subdomains.
+    #[serde(default)]
+    pub allowed_domains: Vec<String>,
+}
+
+/// Limits applied to every command the agent runs. Limits that are not set
+/// are inherited from the Codex process.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ResourceLimits {
+    /// CPU time per process, in seconds (`RLIMIT_CPU`).
+    pub cpu_time_secs: Option<u64>,
+    /// Address space per process, in megabytes (`RLIMIT_AS`).
+    pub memory_mb: Option<u64>,
+    /// Maximum number of processes of the command as a whole, enforced
+    /// with the `pids.max` of its own cgroup v2 sub-tree. Linux only.
+    pub max_processes: Option<u64>,
+    /// Maximum number of open files per process (`RLIMIT_NOFILE`).
+    pub max_open_files: Option<u64>,
+    /// Largest file a process may write, in megabytes (`RLIMIT_FSIZE`).
+    pub max_file_size_mb: Option<u64>,
+    /// Stop the command once it has printed this many megabytes to stdout
+    /// and stderr combined.
+    pub max_output_mb: Option<u64>,
+    /// On Linux, also run each command in its own cgroup v2 sub-tree so that
+    /// `memory_mb` applies to the command as a whole. Falls back to the
+    /// per-process limits when cgroups are not delegated to the user. Always
+    /// on when `max_processes` is set.
+    #[serde(default)]
+    pub cgroup: bool,
+}
+
+/// How sandboxed commands are isolated on Linux.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinuxSandboxBackend {
+    /// Landlock restricts file-system access and seccomp blocks the network.
+    #[default]
+    Landlock,
+
+    /// The command runs in unprivileged user, mount and pid namespaces and
+    /// sees the workspace through an overlay file-system. Its writes are
+    /// shown as a diff and only copied into the workspace once approved.
+    Overlay,
 }
 
 impl From<SandboxWorkspaceWrite> for codex_protocol::mcp_protocol::SandboxSettings {
@@ -200,7 +364,7 @@ impl From<SandboxWorkspaceWrite> for codex_protocol::mcp_protocol::SandboxSettin
     }
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq, Default)]
 #[serde(rename_all = "kebab-case")]
 pub enum ShellEnvironmentPolicyInherit {
     /// "Core" environment variables for the platform. On UNIX, this would
@@ -217,7 +381,7 @@ pub enum ShellEnvironmentPolicyInherit {
 
 /// Policy for building the `env` when spawning a process via either the
 /// `shell` or `local_shell` tool.
-#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+#[derive(Deserialize, JsonSchema, Debug, Clone, PartialEq, Default)]
 pub struct ShellEnvironmentPolicyToml {
     pub inherit: Option<ShellEnvironmentPolicyInherit>,
 
@@ -296,7 +460,7 @@ impl From<ShellEnvironmentPolicyToml> for ShellEnvironmentPolicy {
     }
 }
 
-#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default, Hash)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Clone, PartialEq, Eq, Default, Hash)]
 #[serde(rename_all = "kebab-case")]
 pub enum ReasoningSummaryFormat {
     #[default]
@@ -1,15 +1,46 @@
 use codex_protocol::models::ResponseItem;
 
+use crate::tokenizer::TokenEstimator;
+
 /// Transcript of conversation history
 #[derive(Debug, Clone, Default)]
 pub(crate) struct ConversationHistory {
     /// The oldest items are at the beginning of the vector.
     items: Vec<ResponseItem>,
+
+    /// Size of the context window the server reported for the last request,
+    /// and how many of `items` that request was sent with. Cleared when the
+    /// items are replaced.
+    reported_tokens: Option<(u64, usize)>,
 }
 
 impl ConversationHistory {
     pub(crate) fn new() -> Self {
-        Self { items: Vec::new() }
+        Self {
+            items: Vec::new(),
+            reported_tokens: None,
+        }
+    }
+
+    /// Notes that a request sent with all current items took up `tokens` of
+    /// the context window, as reported by the server.
+    pub(crate) fn set_reported_tokens(&mut self, tokens: u64) {
+        self.reported_tokens = Some((tokens, self.items.len()));
+    }
+
+    /// Estimated size in tokens of the items, based on the size the server
+    /// last reported. Only the items recorded since then are counted locally,
+    /// leaving out the model's own output, which the report already includes.
+    /// `None` if no size was reported for the current items.
+    pub(crate) fn estimate_tokens_from_report(&self, estimator: TokenEstimator) -> Option<u64> {
+        let (tokens, reported_items) = self.reported_tokens?;
+        let added = self
+            .items
+            .get(reported_items..)
+            .unwrap_or_default()
+            .iter()
+            .filter(|item| !is_model_output(item));
+        Some(tokens + estimator.count_items(added))
     }
 
     /// Returns a clone of the contents in the transcript.
@@ -34,6 +65,23 @@ impl ConversationHistory {
 
     pub(crate) fn replace(&mut self, items: Vec<ResponseItem>) {
         self.items = items;
+        self.reported_tokens = None;
+    }
+}
+
+/// Whether `item` was generated by the model, as opposed to being input that
+/// the client sends, such as user messages and tool outputs.
+fn is_model_output(item: &ResponseItem) -> bool {
+    match item {
+        ResponseItem::Message { role, .. } => role == "assistant",
+        ResponseItem::FunctionCall { .. }
+        | ResponseItem::CustomToolCall { .. }
+        | ResponseItem::LocalShellCall { .. }
+        | ResponseItem::Reasoning { .. }
+        | ResponseItem::WebSearchCall { .. } => true,
+        ResponseItem::FunctionCallOutput { .. }
+        | ResponseItem::CustomToolCallOutput { .. }
+        | ResponseItem::Other => false,
     }
 }
 
@@ -57,6 +105,7 @@ fn is_api_message(message: &ResponseItem) -> bool {
 mod tests {
     use super::*;
     use codex_protocol::models::ContentItem;
+    use codex_protocol::models::FunctionCallOutputPayload;
 
     fn assistant_msg(text: &str) -> ResponseItem {
         ResponseItem::Message {
@@ -117,4 +166,30 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn estimates_tokens_added_since_the_last_report() {
+        let estimator = TokenEstimator::O200kBase;
+        let mut h = ConversationHistory::default();
+        h.record_items([&user_msg("hi")]);
+        assert_eq!(h.estimate_tokens_from_report(estimator), None);
+
+        h.set_reported_tokens(1_000);
+        let output = ResponseItem::FunctionCallOutput {
+            call_id: "call-1".to_string(),
+            output: FunctionCallOutputPayload {
+                content: "hello world".to_string(),
+                success: Some(true),
+            },
+        };
+        // The assistant message is part of the reported usage already.
+        h.record_items([&assistant_msg("hello"), &output]);
+        assert_eq!(
+            h.estimate_tokens_from_report(estimator),
+            Some(1_000 + estimator.count_items([&output]))
+        );
+
+        h.replace(vec![user_msg("summary")]);
+        assert_eq!(h.estimate_tokens_from_report(estimator), None);
+    }
 }
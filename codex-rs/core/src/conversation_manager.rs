@@ -12,6 +12,7 @@ use crate::error::Result as CodexResult;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
 use crate::protocol::SessionConfiguredEvent;
+use crate::provider_health::ProviderHealthRegistry;
 use crate::rollout::RolloutRecorder;
 use codex_protocol::mcp_protocol::ConversationId;
 use codex_protocol::models::ResponseItem;
@@ -35,13 +36,26 @@ pub struct NewConversation {
 pub struct ConversationManager {
     conversations: Arc<RwLock<HashMap<ConversationId, Arc<CodexConversation>>>>,
     auth_manager: Arc<AuthManager>,
+    /// Health of the model providers, shared by the conversations of this
+    /// manager.
+    provider_health: Arc<ProviderHealthRegistry>,
 }
 
 impl ConversationManager {
     pub fn new(auth_manager: Arc<AuthManager>) -> Self {
+        Self::with_provider_health(auth_manager, Arc::default())
+    }
+
+    /// Construct a manager whose conversations share `provider_health` with
+    /// another manager's, e.g. for the sub-agents of a conversation.
+    pub(crate) fn with_provider_health(
+        auth_manager: Arc<AuthManager>,
+        provider_health: Arc<ProviderHealthRegistry>,
+    ) -> Self {
         Self {
             conversations: Arc::new(RwLock::new(HashMap::new())),
             auth_manager,
+            provider_health,
         }
     }
 
@@ -51,6 +65,11 @@ impl ConversationManager {
         Self::new(crate::AuthManager::from_auth_for_testing(auth))
     }
 
+    /// Health of the model providers used by this manager's conversations.
+    pub fn provider_health(&self) -> Arc<ProviderHealthRegistry> {
+        self.provider_health.clone()
+    }
+
     pub async fn new_conversation(&self, config: Config) -> CodexResult<NewConversation> {
         self.spawn_conversation(config, self.auth_manager.clone())
             .await
@@ -64,7 +83,13 @@ impl ConversationManager {
         let CodexSpawnOk {
             codex,
             conversation_id,
-        } = Codex::spawn(config, auth_manager, InitialHistory::New).await?;
+        } = Codex::spawn(
+            config,
+            auth_manager,
+            self.provider_health.clone(),
+            InitialHistory::New,
+        )
+        .await?;
         self.finalize_spawn(codex, conversation_id).await
     }
 
@@ -121,7 +146,13 @@ impl ConversationManager {
         let CodexSpawnOk {
             codex,
             conversation_id,
-        } = Codex::spawn(config, auth_manager, initial_history).await?;
+        } = Codex::spawn(
+            config,
+            auth_manager,
+            self.provider_health.clone(),
+            initial_history,
+        )
+        .await?;
         self.finalize_spawn(codex, conversation_id).await
     }
 
@@ -155,7 +186,7 @@ impl ConversationManager {
         let CodexSpawnOk {
             codex,
             conversation_id,
-        } = Codex::spawn(config, auth_manager, history).await?;
+        } = Codex::spawn(config, auth_manager, self.provider_health.clone(), history).await?;
 
         self.finalize_spawn(codex, conversation_id).await
     }
@@ -0,0 +1,439 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use codex_protocol::config_types::SandboxMode;
+use codex_protocol::models::FunctionCallOutputPayload;
+use codex_protocol::models::ResponseInputItem;
+use futures::FutureExt;
+use futures::future::BoxFuture;
+use serde::Deserialize;
+
+use crate::codex::Session;
+use crate::codex::TurnContext;
+use crate::config::Config;
+use crate::conversation_manager::ConversationManager;
+use crate::conversation_manager::NewConversation;
+use crate::error::Result as CodexResult;
+use crate::model_family::derive_default_model_family;
+use crate::model_family::find_configured_family_for_model;
+use crate::openai_model_info::get_configured_model_info;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use crate::protocol::AskForApproval;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::InputItem;
+use crate::protocol::Op;
+use crate::protocol::SandboxPolicy;
+use crate::protocol::SubAgentEventEvent;
+
+pub(crate) const DELEGATE_TOOL_NAME: &str = "delegate_task";
+
+pub(crate) static DELEGATE_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "prompt".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Self-contained description of the sub-task for the sub-agent.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "cwd".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Working directory for the sub-agent. Must be inside the current working directory."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "model".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Model to use for the sub-agent. Defaults to the current model.".to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "sandbox_mode".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "One of: read-only, workspace-write, danger-full-access. May not be more permissive than the current sandbox."
+                    .to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: DELEGATE_TOOL_NAME.to_string(),
+        description:
+            r#"Delegates a self-contained sub-task to a sub-agent that runs in its own conversation.
+The sub-agent cannot see this conversation, so include all necessary context in the prompt.
+Returns the sub-agent's final message once it has finished.
+"#
+            .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["prompt".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+#[derive(Debug, Deserialize)]
+struct DelegateTaskArgs {
+    prompt: String,
+    #[serde(default)]
+    cwd: Option<String>,
+    #[serde(default)]
+    model: Option<String>,
+    #[serde(default)]
+    sandbox_mode: Option<SandboxMode>,
+}
+
+/// Runs a sub-agent to completion in a child conversation, forwarding each of
+/// its events to the parent as [`EventMsg::SubAgentEvent`], and returns its
+/// final message as the tool output.
+pub(crate) async fn handle_delegate_task(
+    sess: &Session,
+    turn_context: &TurnContext,
+    arguments: String,
+    sub_id: String,
+    call_id: String,
+) -> ResponseInputItem {
+    let args = match serde_json::from_str::<DelegateTaskArgs>(&arguments) {
+        Ok(args) => args,
+        Err(e) => {
+            return failure(call_id, format!("failed to parse function arguments: {e}"));
+        }
+    };
+
+    let Some(auth_manager) = turn_context.client.get_auth_manager() else {
+        return failure(call_id, "sub-agents require an auth manager".to_string());
+    };
+
+    let config = match build_child_config(turn_context, &args) {
+        Ok(config) => config,
+        Err(message) => return failure(call_id, message),
+    };
+
+    let manager = ConversationManager::with_provider_health(
+        auth_manager,
+        turn_context.client.get_provider_health(),
+    );
+    let NewConversation {
+        conversation_id,
+        conversation,
+        ..
+    } = match spawn_child(&manager, config).await {
+        Ok(new_conversation) => new_conversation,
+        Err(e) => return failure(call_id, format!("failed to start sub-agent: {e}")),
+    };
+
+    if let Err(e) = conversation
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text { text: args.prompt }],
+        })
+        .await
+    {
+        return failure(call_id, format!("failed to submit task to sub-agent: {e}"));
+    }
+
+    let mut result: Result<Option<String>, String> = Ok(None);
+    loop {
+        let event = match conversation.next_event().await {
+            Ok(event) => event,
+            Err(e) => {
+                result = Err(format!("sub-agent stopped unexpectedly: {e}"));
+                break;
+            }
+        };
+
+        let done = match &event.msg {
+            EventMsg::TaskComplete(ev) => {
+                result = Ok(ev.last_agent_message.clone());
+                true
+            }
+            EventMsg::TurnAborted(ev) => {
+                result = Err(format!("sub-agent aborted: {:?}", ev.reason));
+                true
+            }
+            EventMsg::Error(ev) => {
+                result = Err(format!("sub-agent failed: {}", ev.message));
+                false
+            }
+            EventMsg::ShutdownComplete => true,
+            _ => false,
+        };
+
+        sess.send_event(Event {
+            id: sub_id.clone(),
+            msg: EventMsg::SubAgentEvent(SubAgentEventEvent {
+                call_id: call_id.clone(),
+                conversation_id,
+                msg: Box::new(event.msg),
+            }),
+        })
+        .await;
+
+        if done {
+            break;
+        }
+    }
+
+    // Let the child flush its rollout before it is dropped.
+    if conversation.submit(Op::Shutdown).await.is_ok() {
+        while let Ok(event) = conversation.next_event().await {
+            if matches!(event.msg, EventMsg::ShutdownComplete) {
+                break;
+            }
+        }
+    }
+    manager.remove_conversation(&conversation_id).await;
+
+    match result {
+        Ok(last_agent_message) => ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content: last_agent_message
+                    .unwrap_or_else(|| "sub-agent finished without a final message".to_string()),
+                success: Some(true),
+            },
+        },
+        Err(message) => failure(call_id, message),
+    }
+}
+
+/// The child's submission loop eventually calls back into
+/// [`handle_delegate_task`], so the spawn future is boxed behind an explicit
+/// signature to keep the async types from becoming self-referential.
+fn spawn_child(
+    manager: &ConversationManager,
+    config: Config,
+) -> BoxFuture<'_, CodexResult<NewConversation>> {
+    manager.new_conversation(config).boxed()
+}
+
+/// Derives the child's `Config` from the parent turn. The child inherits the
+/// parent's effective settings, including overrides made since the session
+/// started, and may only narrow them: its cwd must stay inside the parent's
+/// cwd and its sandbox may not be more permissive.
+fn build_child_config(
+    turn_context: &TurnContext,
+    args: &DelegateTaskArgs,
+) -> Result<Config, String> {
+    // The client's config supplies everything a turn cannot change; the turn
+    // context decides the rest.
+    let client = &turn_context.client;
+    let mut config = (*client.get_config()).clone();
+    config.model = client.get_model();
+    config.model_family = client.get_model_family();
+    config.model_provider_id = client.get_model_provider_id();
+    config.model_provider = client.get_provider();
+    config.model_reasoning_effort = client.get_reasoning_effort();
+    config.model_reasoning_summary = client.get_reasoning_summary();
+    config.base_instructions = turn_context.base_instructions.clone();
+    config.user_instructions = turn_context.user_instructions.clone();
+    config.shell_environment_policy = turn_context.shell_environment_policy.clone();
+
+    config.cwd = resolve_child_cwd(&turn_context.cwd, args.cwd.as_deref())?;
+    config.sandbox_policy = narrow_sandbox_policy(&turn_context.sandbox_policy, args.sandbox_mode)?;
+    config.approval_policy = child_approval_policy(turn_context.approval_policy)?;
+    // Keep delegation one level deep.
+    config.include_delegate_tool = false;
+
+    if let Some(model) = &args.model {
+        config.model = model.clone();
+        config.model_family = find_configured_family_for_model(model, &config.models)
+            .unwrap_or_else(|| derive_default_model_family(model));
+    }
+    if let Some(model_info) = get_configured_model_info(&config.model_family, &config.models) {
+        config.model_context_window = Some(model_info.context_window);
+        config.model_max_output_tokens = model_info.max_output_tokens;
+        config.model_auto_compact_token_limit = model_info.auto_compact_token_limit;
+    }
+
+    Ok(config)
+}
+
+/// Nobody is watching the child to answer approval prompts, so it never asks.
+/// Under `on-request` and `on-failure` the parent only asks to leave the
+/// sandbox, which the child keeps, so this is no looser. Under `untrusted`
+/// the parent asks before most commands even inside the sandbox, so nothing
+/// is delegated.
+fn child_approval_policy(parent: AskForApproval) -> Result<AskForApproval, String> {
+    match parent {
+        AskForApproval::UnlessTrusted => Err(
+            "sub-agents cannot run under the `untrusted` approval policy because nobody can approve their commands"
+                .to_string(),
+        ),
+        AskForApproval::OnFailure | AskForApproval::OnRequest | AskForApproval::Never => {
+            Ok(AskForApproval::Never)
+        }
+    }
+}
+
+fn resolve_child_cwd(parent_cwd: &Path, requested: Option<&str>) -> Result<PathBuf, String> {
+    let Some(requested) = requested else {
+        return Ok(parent_cwd.to_path_buf());
+    };
+    let parent = parent_cwd
+        .canonicalize()
+        .map_err(|e| format!("failed to resolve cwd {}: {e}", parent_cwd.display()))?;
+    let child = parent_cwd
+        .join(requested)
+        .canonicalize()
+        .map_err(|e| format!("failed to resolve sub-agent cwd {requested}: {e}"))?;
+    if !child.starts_with(&parent) {
+        return Err(format!(
+            "sub-agent cwd {} must be inside {}",
+            child.display(),
+            parent.display()
+        ));
+    }
+    Ok(child)
+}
+
+fn narrow_sandbox_policy(
+    parent: &SandboxPolicy,
+    requested: Option<SandboxMode>,
+) -> Result<SandboxPolicy, String> {
+    let parent_mode = match parent {
+        SandboxPolicy::ReadOnly => SandboxMode::ReadOnly,
+        SandboxPolicy::WorkspaceWrite { .. } => SandboxMode::WorkspaceWrite,
+        SandboxPolicy::DangerFullAccess => SandboxMode::DangerFullAccess,
+    };
+    let Some(requested) = requested else {
+        return Ok(parent.clone());
+    };
+    if requested == parent_mode {
+        return Ok(parent.clone());
+    }
+    match (parent_mode, requested) {
+        // The read-only policy reads the whole disk, so it would lift the
+        // parent's `readable_roots`.
+        (_, SandboxMode::ReadOnly) if !parent.has_full_disk_read_access() => Err(format!(
+            "sub-agent sandbox `{requested}` would allow reading outside the readable roots of the current `{parent_mode}` sandbox"
+        )),
+        (_, SandboxMode::ReadOnly) => Ok(SandboxPolicy::new_read_only_policy()),
+        (SandboxMode::DangerFullAccess, SandboxMode::WorkspaceWrite) => {
+            Ok(SandboxPolicy::new_workspace_write_policy())
+        }
+        _ => Err(format!(
+            "sub-agent sandbox `{requested}` is more permissive than the current `{parent_mode}` sandbox"
+        )),
+    }
+}
+
+fn failure(call_id: String, content: String) -> ResponseInputItem {
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(false),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::NetworkAccess;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn sandbox_defaults_to_parent_policy() {
+        let parent = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![PathBuf::from("/extra")],
+            network_access: NetworkAccess::Enabled,
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+            readable_roots: None,
+            allowed_domains: vec![],
+        };
+        assert_eq!(narrow_sandbox_policy(&parent, None), Ok(parent.clone()));
+        assert_eq!(
+            narrow_sandbox_policy(&parent, Some(SandboxMode::WorkspaceWrite)),
+            Ok(parent)
+        );
+    }
+
+    #[test]
+    fn sandbox_can_be_narrowed() {
+        assert_eq!(
+            narrow_sandbox_policy(
+                &SandboxPolicy::DangerFullAccess,
+                Some(SandboxMode::WorkspaceWrite)
+            ),
+            Ok(SandboxPolicy::new_workspace_write_policy())
+        );
+        assert_eq!(
+            narrow_sandbox_policy(
+                &SandboxPolicy::new_workspace_write_policy(),
+                Some(SandboxMode::ReadOnly)
+            ),
+            Ok(SandboxPolicy::new_read_only_policy())
+        );
+    }
+
+    #[test]
+    fn sandbox_cannot_be_widened() {
+        assert!(
+            narrow_sandbox_policy(
+                &SandboxPolicy::new_read_only_policy(),
+                Some(SandboxMode::WorkspaceWrite)
+            )
+            .is_err()
+        );
+        assert!(
+            narrow_sandbox_policy(
+                &SandboxPolicy::new_workspace_write_policy(),
+                Some(SandboxMode::DangerFullAccess)
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn sandbox_keeps_parent_read_restrictions() {
+        let parent = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: NetworkAccess::Disabled,
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+            readable_roots: Some(vec![PathBuf::from("/usr")]),
+            allowed_domains: vec![],
+        };
+        assert!(narrow_sandbox_policy(&parent, Some(SandboxMode::ReadOnly)).is_err());
+        let child = narrow_sandbox_policy(&parent, None).expect("parent policy");
+        assert!(!child.has_full_disk_read_access());
+        assert_eq!(child, parent);
+    }
+
+    #[test]
+    fn untrusted_parent_cannot_delegate() {
+        assert!(child_approval_policy(AskForApproval::UnlessTrusted).is_err());
+        assert_eq!(
+            child_approval_policy(AskForApproval::OnRequest),
+            Ok(AskForApproval::Never)
+        );
+    }
+
+    #[test]
+    fn cwd_must_stay_inside_parent() {
+        let parent = tempfile::tempdir().expect("tempdir");
+        std::fs::create_dir(parent.path().join("sub")).expect("mkdir");
+        let parent_cwd = parent.path().to_path_buf();
+
+        let child = resolve_child_cwd(&parent_cwd, Some("sub")).expect("sub is inside parent");
+        assert!(child.ends_with("sub"));
+        assert!(resolve_child_cwd(&parent_cwd, Some("..")).is_err());
+        assert_eq!(resolve_child_cwd(&parent_cwd, None), Ok(parent_cwd));
+    }
+}
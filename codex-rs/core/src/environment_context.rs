@@ -4,6 +4,7 @@ use strum_macros::Display as DeriveDisplay;
 
 use crate::codex::TurnContext;
 use crate::protocol::AskForApproval;
+use crate::protocol::NetworkAccess as SandboxNetworkAccess;
 use crate::protocol::SandboxPolicy;
 use crate::shell::Shell;
 use codex_protocol::config_types::SandboxMode;
@@ -18,6 +19,7 @@ use std::path::PathBuf;
 #[strum(serialize_all = "kebab-case")]
 pub enum NetworkAccess {
     Restricted,
+    Loopback,
     Enabled,
 }
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -28,6 +30,8 @@ pub(crate) struct EnvironmentContext {
     pub sandbox_mode: Option<SandboxMode>,
     pub network_access: Option<NetworkAccess>,
     pub writable_roots: Option<Vec<PathBuf>>,
+    /// Set only when the sandbox limits which folders may be read.
+    pub readable_roots: Option<Vec<PathBuf>>,
     pub shell: Option<Shell>,
 }
 
@@ -38,6 +42,10 @@ impl EnvironmentContext {
         sandbox_policy: Option<SandboxPolicy>,
         shell: Option<Shell>,
     ) -> Self {
+        let readable_roots = match (&sandbox_policy, &cwd) {
+            (Some(sandbox_policy), Some(cwd)) => sandbox_policy.get_readable_roots_with_cwd(cwd),
+            _ => None,
+        };
         Self {
             cwd,
             approval_policy,
@@ -47,18 +55,13 @@ impl EnvironmentContext {
                 Some(SandboxPolicy::WorkspaceWrite { .. }) => Some(SandboxMode::WorkspaceWrite),
                 None => None,
             },
-            network_access: match sandbox_policy {
-                Some(SandboxPolicy::DangerFullAccess) => Some(NetworkAccess::Enabled),
-                Some(SandboxPolicy::ReadOnly) => Some(NetworkAccess::Restricted),
-                Some(SandboxPolicy::WorkspaceWrite { network_access, .. }) => {
-                    if network_access {
-                        Some(NetworkAccess::Enabled)
-                    } else {
-                        Some(NetworkAccess::Restricted)
-                    }
+            network_access: sandbox_policy.as_ref().map(|sandbox_policy| {
+                match sandbox_policy.network_access() {
+                    SandboxNetworkAccess::Disabled => NetworkAccess::Restricted,
+                    SandboxNetworkAccess::Loopback => NetworkAccess::Loopback,
+                    SandboxNetworkAccess::Enabled => NetworkAccess::Enabled,
                 }
-                None => None,
-            },
+            }),
             writable_roots: match sandbox_policy {
                 Some(SandboxPolicy::WorkspaceWrite { writable_roots, .. }) => {
                     if writable_roots.is_empty() {
@@ -69,6 +72,7 @@ impl EnvironmentContext {
                 }
                 _ => None,
             },
+            readable_roots,
             shell,
         }
     }
@@ -83,6 +87,7 @@ impl EnvironmentContext {
             sandbox_mode,
             network_access,
             writable_roots,
+            readable_roots,
             // should compare all fields except shell
             shell: _,
         } = other;
@@ -92,6 +97,7 @@ impl EnvironmentContext {
             && self.sandbox_mode == *sandbox_mode
             && self.network_access == *network_access
             && self.writable_roots == *writable_roots
+            && self.readable_roots == *readable_roots
     }
 }
 
@@ -118,6 +124,7 @@ impl EnvironmentContext {
     ///   <approval_policy>...</approval_policy>
     ///   <sandbox_mode>...</sandbox_mode>
     ///   <writable_roots>...</writable_roots>
+    ///   <readable_roots>...</readable_roots>
     ///   <network_access>...</network_access>
     ///   <shell>...</shell>
     /// </environment_context>
@@ -150,6 +157,16 @@ impl EnvironmentContext {
             }
             lines.push("  </writable_roots>".to_string());
         }
+        if let Some(readable_roots) = self.readable_roots {
+            lines.push("  <readable_roots>".to_string());
+            for readable_root in readable_roots {
+                lines.push(format!(
+                    "    <root>{}</root>",
+                    readable_root.to_string_lossy()
+                ));
+            }
+            lines.push("  </readable_roots>".to_string());
+        }
         if let Some(shell) = self.shell
             && let Some(shell_name) = shell.name()
         {
@@ -183,9 +200,15 @@ mod tests {
     fn workspace_write_policy(writable_roots: Vec<&str>, network_access: bool) -> SandboxPolicy {
         SandboxPolicy::WorkspaceWrite {
             writable_roots: writable_roots.into_iter().map(PathBuf::from).collect(),
-            network_access,
+            network_access: if network_access {
+                SandboxNetworkAccess::Enabled
+            } else {
+                SandboxNetworkAccess::Disabled
+            },
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
+            readable_roots: None,
+            allowed_domains: vec![],
         }
     }
 
@@ -212,6 +235,55 @@ mod tests {
         assert_eq!(context.serialize_to_xml(), expected);
     }
 
+    #[test]
+    fn serialize_loopback_environment_context() {
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: SandboxNetworkAccess::Loopback,
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+            readable_roots: None,
+            allowed_domains: vec![],
+        };
+        let context = EnvironmentContext::new(None, None, Some(policy), None);
+
+        let expected = r#"<environment_context>
+  <sandbox_mode>workspace-write</sandbox_mode>
+  <network_access>loopback</network_access>
+</environment_context>"#;
+
+        assert_eq!(context.serialize_to_xml(), expected);
+    }
+
+    #[test]
+    fn serialize_restricted_read_environment_context() {
+        let repo = tempfile::TempDir::new().expect("tempdir");
+        let data = tempfile::TempDir::new().expect("tempdir");
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: SandboxNetworkAccess::Disabled,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            readable_roots: Some(vec![data.path().to_path_buf()]),
+            allowed_domains: vec![],
+        };
+        let context = EnvironmentContext::new(
+            Some(repo.path().to_path_buf()),
+            Some(AskForApproval::OnRequest),
+            Some(policy),
+            None,
+        );
+
+        let readable_roots = context.readable_roots.clone().unwrap_or_default();
+        assert_eq!(readable_roots[..2], [repo.path(), data.path()]);
+        assert!(readable_roots.contains(&PathBuf::from("/usr")));
+
+        let xml = context.serialize_to_xml();
+        let data_root = format!("    <root>{}</root>", data.path().display());
+        assert!(xml.contains("  <readable_roots>"), "{xml}");
+        assert!(xml.contains(&data_root), "{xml}");
+    }
+
     #[test]
     fn serialize_read_only_environment_context() {
         let context = EnvironmentContext::new(
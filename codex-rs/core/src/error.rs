@@ -1,4 +1,6 @@
 use crate::exec::ExecToolCallOutput;
+use crate::resource_limits::ResourceLimit;
+use crate::sandbox_denials::SandboxDenial;
 use crate::token_data::KnownPlan;
 use crate::token_data::PlanType;
 use codex_protocol::mcp_protocol::ConversationId;
@@ -14,12 +16,16 @@ pub type Result<T> = std::result::Result<T, CodexErr>;
 
 #[derive(Error, Debug)]
 pub enum SandboxErr {
-    /// Error from sandbox execution
+    /// Error from sandbox execution. `denials` lists what the sandbox blocked,
+    /// when the sandbox could tell.
     #[error(
         "sandbox denied exec error, exit code: {}, stdout: {}, stderr: {}",
         .output.exit_code, .output.stdout.text, .output.stderr.text
     )]
-    Denied { output: Box<ExecToolCallOutput> },
+    Denied {
+        output: Box<ExecToolCallOutput>,
+        denials: Vec<SandboxDenial>,
+    },
 
     /// Error from linux seccomp filter setup
     #[cfg(target_os = "linux")]
@@ -39,9 +45,26 @@ pub enum SandboxErr {
     #[error("command was killed by a signal")]
     Signal(i32),
 
+    /// Command ran into one of the configured `resource_limits`
+    #[error("command exceeded the {limit}")]
+    ResourceLimitExceeded {
+        limit: ResourceLimit,
+        output: Box<ExecToolCallOutput>,
+    },
+
+    /// `resource_limits.max_processes` is set but the command's cgroup could
+    /// not be created, so the limit could not be enforced.
+    #[error("cannot limit the processes of the command: {0}")]
+    ProcessLimit(String),
+
     /// Error from linux landlock
     #[error("Landlock was not able to fully enforce all sandbox rules")]
     LandlockRestrict,
+
+    /// The network namespace for a loopback-only or proxied network could
+    /// not be set up, e.g. because unprivileged user namespaces are disabled.
+    #[error("cannot create a network namespace: {0}")]
+    NetworkNamespace(String),
 }
 
 #[derive(Error, Debug)]
@@ -94,6 +117,11 @@ pub enum CodexErr {
     #[error("exceeded retry limit, last status: {0}")]
     RetryLimit(StatusCode),
 
+    /// The provider's circuit is open after repeated failures, so the request
+    /// was not sent.
+    #[error("{0}")]
+    ProviderUnavailable(ProviderUnavailableError),
+
     /// Agent loop died unexpectedly
     #[error("internal error; agent loop died unexpectedly")]
     InternalAgentDied,
@@ -175,6 +203,25 @@ impl std::fmt::Display for UsageLimitReachedError {
     }
 }
 
+#[derive(Debug)]
+pub struct ProviderUnavailableError {
+    pub(crate) provider: String,
+    pub(crate) consecutive_failures: u32,
+    pub(crate) retry_in: Duration,
+}
+
+impl std::fmt::Display for ProviderUnavailableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} failed {} requests in a row; not sending requests for {}s",
+            self.provider,
+            self.consecutive_failures,
+            self.retry_in.as_secs_f64().ceil()
+        )
+    }
+}
+
 fn retry_suffix(resets_in_seconds: Option<u64>) -> String {
     if let Some(secs) = resets_in_seconds {
         let reset_duration = format_reset_duration(secs);
@@ -254,12 +301,24 @@ impl CodexErr {
 
 pub fn get_error_message_ui(e: &CodexErr) -> String {
     match e {
-        CodexErr::Sandbox(SandboxErr::Denied { output }) => output.stderr.text.clone(),
+        CodexErr::Sandbox(SandboxErr::Denied { output, denials }) => {
+            let mut message = output.stderr.text.clone();
+            for denial in denials {
+                if !message.is_empty() && !message.ends_with('\n') {
+                    message.push('\n');
+                }
+                message.push_str(&format!("sandbox: {denial}"));
+            }
+            message
+        }
         // Timeouts are not sandbox errors from a UX perspective; present them plainly
         CodexErr::Sandbox(SandboxErr::Timeout { output }) => format!(
             "error: command timed out after {} ms",
             output.duration.as_millis()
         ),
+        CodexErr::Sandbox(SandboxErr::ResourceLimitExceeded { limit, .. }) => {
+            format!("error: command exceeded the {limit}")
+        }
         _ => e.to_string(),
     }
 }
@@ -6,6 +6,9 @@ use std::io;
 use std::path::Path;
 use std::path::PathBuf;
 use std::process::ExitStatus;
+use std::sync::Arc;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 use std::time::Instant;
 
@@ -14,7 +17,9 @@ use tokio::io::AsyncRead;
 use tokio::io::AsyncReadExt;
 use tokio::io::BufReader;
 use tokio::process::Child;
+use tokio::sync::Notify;
 
+use crate::config_types::ResourceLimits;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::error::SandboxErr;
@@ -24,7 +29,11 @@ use crate::protocol::EventMsg;
 use crate::protocol::ExecCommandOutputDeltaEvent;
 use crate::protocol::ExecOutputStream;
 use crate::protocol::SandboxPolicy;
+use crate::resource_limits::ResourceLimiter;
+use crate::sandbox_denials::SandboxDenialLog;
 use crate::seatbelt::spawn_command_under_seatbelt;
+use crate::spawn::CODEX_SANDBOX_DENIALS_FD_ENV_VAR;
+use crate::spawn::CODEX_SANDBOX_OVERLAY_DIR_ENV_VAR;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
 
@@ -70,6 +79,11 @@ pub enum SandboxType {
 
     /// Only available on Linux.
     LinuxSeccomp,
+
+    /// Only available on Linux. The command's writes land in an overlay
+    /// directory instead of the real file-system and are only copied over
+    /// once approved.
+    LinuxOverlay,
 }
 
 #[derive(Clone)]
@@ -85,15 +99,28 @@ pub async fn process_exec_tool_call(
     sandbox_policy: &SandboxPolicy,
     sandbox_cwd: &Path,
     codex_linux_sandbox_exe: &Option<PathBuf>,
+    resource_limits: &ResourceLimits,
     stdout_stream: Option<StdoutStream>,
 ) -> Result<ExecToolCallOutput> {
     let start = Instant::now();
 
     let timeout_duration = params.timeout_duration();
+    let resource_limiter = ResourceLimiter::new(resource_limits)?;
+    let max_output_bytes = resource_limiter.max_output_bytes();
 
+    // Only the Linux sandbox helper reports what it blocked.
+    let mut denial_log = None;
     let raw_output_result: std::result::Result<RawExecToolCallOutput, CodexErr> = match sandbox_type
     {
-        SandboxType::None => exec(params, sandbox_policy, stdout_stream.clone()).await,
+        SandboxType::None => {
+            exec(
+                params,
+                sandbox_policy,
+                &resource_limiter,
+                stdout_stream.clone(),
+            )
+            .await
+        }
         SandboxType::MacosSeatbelt => {
             let ExecParams {
                 command,
@@ -108,18 +135,40 @@ pub async fn process_exec_tool_call(
                 sandbox_cwd,
                 StdioPolicy::RedirectForShellTool,
                 env,
+                &resource_limiter,
             )
             .await?;
-            consume_truncated_output(child, timeout_duration, stdout_stream.clone()).await
+            consume_truncated_output(
+                child,
+                timeout_duration,
+                max_output_bytes,
+                stdout_stream.clone(),
+            )
+            .await
         }
-        SandboxType::LinuxSeccomp => {
+        SandboxType::LinuxSeccomp | SandboxType::LinuxOverlay => {
             let ExecParams {
                 command,
                 cwd: command_cwd,
-                env,
+                mut env,
                 ..
             } = params;
 
+            // The helper only sets up an overlay when Codex has prepared a
+            // directory for it.
+            if sandbox_type == SandboxType::LinuxSeccomp {
+                env.remove(CODEX_SANDBOX_OVERLAY_DIR_ENV_VAR);
+            } else if !env.contains_key(CODEX_SANDBOX_OVERLAY_DIR_ENV_VAR) {
+                return Err(CodexErr::Io(io::Error::other(
+                    "the overlay sandbox requires an overlay directory",
+                )));
+            }
+
+            // Without a log the command still runs, Codex just cannot say
+            // what the sandbox blocked.
+            env.remove(CODEX_SANDBOX_DENIALS_FD_ENV_VAR);
+            denial_log = SandboxDenialLog::new().ok();
+
             let codex_linux_sandbox_exe = codex_linux_sandbox_exe
                 .as_ref()
                 .ok_or(CodexErr::LandlockSandboxExecutableNotProvided)?;
@@ -131,10 +180,12 @@ pub async fn process_exec_tool_call(
                 sandbox_cwd,
                 StdioPolicy::RedirectForShellTool,
                 env,
+                &resource_limiter,
+                denial_log.as_ref(),
             )
             .await?;
 
-            consume_truncated_output(child, timeout_duration, stdout_stream).await
+            consume_truncated_output(child, timeout_duration, max_output_bytes, stdout_stream).await
         }
     };
     let duration = start.elapsed();
@@ -142,19 +193,25 @@ pub async fn process_exec_tool_call(
         Ok(raw_output) => {
             #[allow(unused_mut)]
             let mut timed_out = raw_output.timed_out;
+            #[allow(unused_mut)]
+            let mut signal = None;
 
             #[cfg(target_family = "unix")]
             {
-                if let Some(signal) = raw_output.exit_status.signal() {
-                    if signal == TIMEOUT_CODE {
+                if let Some(s) = raw_output.exit_status.signal() {
+                    if s == TIMEOUT_CODE {
                         timed_out = true;
                     } else {
-                        return Err(CodexErr::Sandbox(SandboxErr::Signal(signal)));
+                        signal = Some(s);
                     }
                 }
             }
 
-            let mut exit_code = raw_output.exit_status.code().unwrap_or(-1);
+            let mut exit_code = raw_output
+                .exit_status
+                .code()
+                .or(signal.map(|s| EXIT_CODE_SIGNAL_BASE + s))
+                .unwrap_or(-1);
             if timed_out {
                 exit_code = EXEC_TIMEOUT_EXIT_CODE;
             }
@@ -177,9 +234,30 @@ pub async fn process_exec_tool_call(
                 }));
             }
 
-            if exit_code != 0 && is_likely_sandbox_denied(sandbox_type, exit_code) {
+            if let Some(limit) = resource_limiter.exceeded_limit(
+                signal,
+                raw_output.output_limit_exceeded,
+                &exec_output,
+            ) {
+                return Err(CodexErr::Sandbox(SandboxErr::ResourceLimitExceeded {
+                    limit,
+                    output: Box::new(exec_output),
+                }));
+            }
+
+            if let Some(signal) = signal {
+                return Err(CodexErr::Sandbox(SandboxErr::Signal(signal)));
+            }
+
+            let denials = denial_log
+                .map(|denial_log| denial_log.read())
+                .unwrap_or_default();
+            if exit_code != 0
+                && (!denials.is_empty() || is_likely_sandbox_denied(sandbox_type, exit_code))
+            {
                 return Err(CodexErr::Sandbox(SandboxErr::Denied {
                     output: Box::new(exec_output),
+                    denials,
                 }));
             }
 
@@ -196,9 +274,11 @@ pub async fn process_exec_tool_call(
 /// because of the sandbox - a command in the user's zshrc file might hit an
 /// error, but the command itself might fail or succeed for other reasons.
 /// For now, we conservatively check for 'command not found' (exit code 127),
-/// and can add additional cases as necessary.
+/// and can add additional cases as necessary. Under the overlay sandbox the
+/// command can write anywhere in its copy of the workspace, so a failure is
+/// its own unless the sandbox reported a denial.
 fn is_likely_sandbox_denied(sandbox_type: SandboxType, exit_code: i32) -> bool {
-    if sandbox_type == SandboxType::None {
+    if matches!(sandbox_type, SandboxType::None | SandboxType::LinuxOverlay) {
         return false;
     }
 
@@ -224,6 +304,9 @@ struct RawExecToolCallOutput {
     pub stderr: StreamOutput<Vec<u8>>,
     pub aggregated_output: StreamOutput<Vec<u8>>,
     pub timed_out: bool,
+    /// The command was stopped because its output went over
+    /// `resource_limits.max_output_mb`.
+    pub output_limit_exceeded: bool,
 }
 
 impl StreamOutput<String> {
@@ -262,6 +345,7 @@ pub struct ExecToolCallOutput {
 async fn exec(
     params: ExecParams,
     sandbox_policy: &SandboxPolicy,
+    resource_limiter: &ResourceLimiter,
     stdout_stream: Option<StdoutStream>,
 ) -> Result<RawExecToolCallOutput> {
     let timeout = params.timeout_duration();
@@ -284,16 +368,59 @@ async fn exec(
         sandbox_policy,
         StdioPolicy::RedirectForShellTool,
         env,
+        resource_limiter,
+        None,
     )
     .await?;
-    consume_truncated_output(child, timeout, stdout_stream).await
+    consume_truncated_output(
+        child,
+        timeout,
+        resource_limiter.max_output_bytes(),
+        stdout_stream,
+    )
+    .await
+}
+
+/// Bytes a command may still print before it is stopped, shared by the
+/// readers of its stdout and stderr.
+struct OutputBudget {
+    max_bytes: usize,
+    used: AtomicUsize,
+    exceeded: Notify,
+}
+
+impl OutputBudget {
+    fn new(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            used: AtomicUsize::new(0),
+            exceeded: Notify::new(),
+        }
+    }
+
+    /// Records `n` more bytes of output and returns how many of them still
+    /// fit in the budget. Notifies `exceeded` once the budget is used up.
+    fn take(&self, n: usize) -> usize {
+        let used_before = self.used.fetch_add(n, Ordering::Relaxed);
+        let allowed = self.max_bytes.saturating_sub(used_before).min(n);
+        if allowed < n {
+            self.exceeded.notify_one();
+        }
+        allowed
+    }
+
+    fn is_exceeded(&self) -> bool {
+        self.used.load(Ordering::Relaxed) > self.max_bytes
+    }
 }
 
 /// Consumes the output of a child process, truncating it so it is suitable for
-/// use as the output of a `shell` tool call. Also enforces specified timeout.
+/// use as the output of a `shell` tool call. Also enforces specified timeout,
+/// and stops the child once it has printed more than `max_output_bytes`.
 async fn consume_truncated_output(
     mut child: Child,
     timeout: Duration,
+    max_output_bytes: Option<usize>,
     stdout_stream: Option<StdoutStream>,
 ) -> Result<RawExecToolCallOutput> {
     // Both stdout and stderr were configured with `Stdio::piped()`
@@ -312,19 +439,28 @@ async fn consume_truncated_output(
     })?;
 
     let (agg_tx, agg_rx) = async_channel::unbounded::<Vec<u8>>();
+    let output_budget = max_output_bytes.map(|max_bytes| Arc::new(OutputBudget::new(max_bytes)));
 
     let stdout_handle = tokio::spawn(read_capped(
         BufReader::new(stdout_reader),
         stdout_stream.clone(),
         false,
         Some(agg_tx.clone()),
+        output_budget.clone(),
     ));
     let stderr_handle = tokio::spawn(read_capped(
         BufReader::new(stderr_reader),
         stdout_stream.clone(),
         true,
         Some(agg_tx.clone()),
+        output_budget.clone(),
     ));
+    let output_budget_exceeded = async {
+        match &output_budget {
+            Some(output_budget) => output_budget.exceeded.notified().await,
+            None => std::future::pending().await,
+        }
+    };
 
     let (exit_status, timed_out) = tokio::select! {
         result = tokio::time::timeout(timeout, child.wait()) => {
@@ -341,6 +477,10 @@ async fn consume_truncated_output(
                 }
             }
         }
+        _ = output_budget_exceeded => {
+            child.start_kill()?;
+            (child.wait().await?, false)
+        }
         _ = tokio::signal::ctrl_c() => {
             child.start_kill()?;
             (synthetic_exit_status(EXIT_CODE_SIGNAL_BASE + SIGKILL_CODE), false)
@@ -349,6 +489,9 @@ async fn consume_truncated_output(
 
     let stdout = stdout_handle.await??;
     let stderr = stderr_handle.await??;
+    let output_limit_exceeded = output_budget
+        .as_ref()
+        .is_some_and(|output_budget| output_budget.is_exceeded());
 
     drop(agg_tx);
 
@@ -367,6 +510,7 @@ async fn consume_truncated_output(
         stderr,
         aggregated_output,
         timed_out,
+        output_limit_exceeded,
     })
 }
 
@@ -375,6 +519,7 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
     stream: Option<StdoutStream>,
     is_stderr: bool,
     aggregate_tx: Option<Sender<Vec<u8>>>,
+    output_budget: Option<Arc<OutputBudget>>,
 ) -> io::Result<StreamOutput<Vec<u8>>> {
     let mut buf = Vec::with_capacity(AGGREGATE_BUFFER_INITIAL_CAPACITY);
     let mut tmp = [0u8; READ_CHUNK_SIZE];
@@ -387,11 +532,16 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
         if n == 0 {
             break;
         }
+        let allowed = output_budget
+            .as_ref()
+            .map_or(n, |output_budget| output_budget.take(n));
+        let chunk = &tmp[..allowed];
 
         if let Some(stream) = &stream
             && emitted_deltas < MAX_EXEC_OUTPUT_DELTAS_PER_CALL
+            && !chunk.is_empty()
         {
-            let chunk = tmp[..n].to_vec();
+            let chunk = chunk.to_vec();
             let msg = EventMsg::ExecCommandOutputDelta(ExecCommandOutputDeltaEvent {
                 call_id: stream.call_id.clone(),
                 stream: if is_stderr {
@@ -411,10 +561,14 @@ async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
         }
 
         if let Some(tx) = &aggregate_tx {
-            let _ = tx.send(tmp[..n].to_vec()).await;
+            let _ = tx.send(chunk.to_vec()).await;
         }
 
-        append_all(&mut buf, &tmp[..n]);
+        append_all(&mut buf, chunk);
+        if allowed < n {
+            // The command is being stopped for printing too much.
+            break;
+        }
         // Continue reading to EOF to avoid back-pressure
     }
 
@@ -1,8 +1,12 @@
 use crate::config_types::EnvironmentVariablePattern;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyInherit;
+use crate::spawn::CODEX_NETWORK_PROXY_ENV_VAR;
+use crate::spawn::CODEX_NETWORK_PROXY_SOCKET_ENV_VAR;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::path::Path;
 
 /// Construct an environment map based on the rules in the specified policy. The
 /// resulting map can be passed directly to `Command::envs()` after calling
@@ -68,6 +72,41 @@ where
     env_map
 }
 
+/// Routes the HTTP(S) traffic of a command built from [`create_env`] through
+/// the Codex network proxy listening on `proxy_addr`. Any `NO_PROXY` from the
+/// user's environment is dropped because direct connections are blocked by the
+/// sandbox anyway.
+pub fn set_network_proxy_env(
+    env: &mut HashMap<String, String>,
+    proxy_addr: SocketAddr,
+    proxy_socket: Option<&Path>,
+) {
+    let proxy_url = format!("http://{proxy_addr}");
+    for name in [
+        "HTTP_PROXY",
+        "HTTPS_PROXY",
+        "ALL_PROXY",
+        "http_proxy",
+        "https_proxy",
+        "all_proxy",
+    ] {
+        env.insert(name.to_string(), proxy_url.clone());
+    }
+    env.remove("NO_PROXY");
+    env.remove("no_proxy");
+
+    env.insert(
+        CODEX_NETWORK_PROXY_ENV_VAR.to_string(),
+        proxy_addr.to_string(),
+    );
+    if let Some(proxy_socket) = proxy_socket {
+        env.insert(
+            CODEX_NETWORK_PROXY_SOCKET_ENV_VAR.to_string(),
+            proxy_socket.to_string_lossy().to_string(),
+        );
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -191,4 +230,22 @@ mod tests {
         };
         assert_eq!(result, expected);
     }
+
+    #[test]
+    fn test_set_network_proxy_env() {
+        let mut env: HashMap<String, String> = hashmap! {
+            "PATH".to_string() => "/usr/bin".to_string(),
+            "NO_PROXY".to_string() => "*".to_string(),
+        };
+
+        let proxy_addr: SocketAddr = "127.0.0.1:4128".parse().expect("socket addr");
+        set_network_proxy_env(&mut env, proxy_addr, Some(Path::new("/tmp/proxy.sock")));
+
+        assert_eq!(env["HTTPS_PROXY"], "http://127.0.0.1:4128");
+        assert_eq!(env["http_proxy"], "http://127.0.0.1:4128");
+        assert_eq!(env[CODEX_NETWORK_PROXY_ENV_VAR], "127.0.0.1:4128");
+        assert_eq!(env[CODEX_NETWORK_PROXY_SOCKET_ENV_VAR], "/tmp/proxy.sock");
+        assert!(!env.contains_key("NO_PROXY"));
+        assert_eq!(env["PATH"], "/usr/bin");
+    }
 }
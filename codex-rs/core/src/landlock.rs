@@ -1,4 +1,6 @@
 use crate::protocol::SandboxPolicy;
+use crate::resource_limits::ResourceLimiter;
+use crate::sandbox_denials::SandboxDenialLog;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
 use std::collections::HashMap;
@@ -13,6 +15,11 @@ use tokio::process::Child;
 /// helper accepts a list of `--sandbox-permission`/`-s` flags mirroring the
 /// public CLI. We convert the internal [`SandboxPolicy`] representation into
 /// the equivalent CLI options.
+///
+/// The limits of `resource_limiter` are applied to the helper, which passes
+/// them on to the command when it execs it. With a `denial_log`, the helper
+/// reports what the sandbox blocked in it.
+#[allow(clippy::too_many_arguments)]
 pub async fn spawn_command_under_linux_sandbox<P>(
     codex_linux_sandbox_exe: P,
     command: Vec<String>,
@@ -21,6 +28,8 @@ pub async fn spawn_command_under_linux_sandbox<P>(
     sandbox_policy_cwd: &Path,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
+    resource_limiter: &ResourceLimiter,
+    denial_log: Option<&SandboxDenialLog>,
 ) -> std::io::Result<Child>
 where
     P: AsRef<Path>,
@@ -35,6 +44,8 @@ where
         sandbox_policy,
         stdio_policy,
         env,
+        resource_limiter,
+        denial_log,
     )
     .await
 }
@@ -16,11 +16,16 @@ mod codex_conversation;
 pub mod token_data;
 pub use codex_conversation::CodexConversation;
 pub mod config;
+pub mod config_check;
 pub mod config_edit;
+mod config_interpolation;
+pub mod config_layer;
 pub mod config_profile;
+mod config_reload;
 pub mod config_types;
 mod conversation_history;
 pub mod custom_prompts;
+mod delegate_tool;
 mod environment_context;
 pub mod error;
 pub mod exec;
@@ -34,7 +39,10 @@ pub mod landlock;
 mod mcp_connection_manager;
 mod mcp_tool_call;
 mod message_history;
+mod messages_api;
 mod model_provider_info;
+mod network_proxy;
+mod overlay_sandbox;
 pub mod parse_command;
 mod truncate;
 mod unified_exec;
@@ -57,14 +65,20 @@ pub mod default_client;
 pub mod model_family;
 mod openai_model_info;
 mod openai_tools;
+pub mod output_schema;
+mod output_store;
 pub mod plan_tool;
 pub mod project_doc;
+pub mod provider_health;
+pub mod resource_limits;
 mod rollout;
 pub(crate) mod safety;
+pub mod sandbox_denials;
 pub mod seatbelt;
 pub mod shell;
 pub mod spawn;
 pub mod terminal;
+mod tokenizer;
 mod tool_apply_patch;
 pub mod turn_diff_tracker;
 pub use rollout::ARCHIVED_SESSIONS_SUBDIR;
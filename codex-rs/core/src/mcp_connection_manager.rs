@@ -85,10 +85,13 @@ struct ToolInfo {
     tool: Tool,
 }
 
+#[derive(Clone)]
 struct ManagedClient {
     client: Arc<McpClient>,
     startup_timeout: Duration,
     tool_timeout: Option<Duration>,
+    /// The configuration the server was started with.
+    config: McpServerConfig,
 }
 
 /// A thin wrapper around a set of running [`McpClient`] instances.
@@ -121,104 +124,37 @@ impl McpConnectionManager {
             return Ok((Self::default(), ClientStartErrors::default()));
         }
 
-        // Launch all configured servers concurrently.
-        let mut join_set = JoinSet::new();
-        let mut errors = ClientStartErrors::new();
+        let (clients, errors) = start_clients(mcp_servers).await;
+        Ok((Self::with_clients(clients).await, errors))
+    }
 
+    /// Build a manager for `mcp_servers`, reusing the clients of `self` whose
+    /// configuration did not change and starting the others. Clients of
+    /// servers that are no longer configured are shut down once the last
+    /// reference to `self` is dropped.
+    pub async fn reconfigure(
+        &self,
+        mcp_servers: HashMap<String, McpServerConfig>,
+    ) -> Result<(Self, ClientStartErrors)> {
+        let mut clients = HashMap::with_capacity(mcp_servers.len());
+        let mut changed = HashMap::new();
         for (server_name, cfg) in mcp_servers {
-            // Validate server name before spawning
-            if !is_valid_mcp_server_name(&server_name) {
-                let error = anyhow::anyhow!(
-                    "invalid server name '{}': must match pattern ^[a-zA-Z0-9_-]+$",
-                    server_name
-                );
-                errors.insert(server_name, error);
-                continue;
-            }
-
-            let startup_timeout = cfg.startup_timeout_sec.unwrap_or(DEFAULT_STARTUP_TIMEOUT);
-
-            let tool_timeout = cfg.tool_timeout_sec.unwrap_or(DEFAULT_TOOL_TIMEOUT);
-
-            join_set.spawn(async move {
-                let McpServerConfig {
-                    command, args, env, ..
-                } = cfg;
-                let client_res = McpClient::new_stdio_client(
-                    command.into(),
-                    args.into_iter().map(OsString::from).collect(),
-                    env,
-                )
-                .await;
-                match client_res {
-                    Ok(client) => {
-                        // Initialize the client.
-                        let params = mcp_types::InitializeRequestParams {
-                            capabilities: ClientCapabilities {
-                                experimental: None,
-                                roots: None,
-                                sampling: None,
-                                // https://modelcontextprotocol.io/specification/2025-06-18/client/elicitation#capabilities
-                                // indicates this should be an empty object.
-                                elicitation: Some(json!({})),
-                            },
-                            client_info: Implementation {
-                                name: "codex-mcp-client".to_owned(),
-                                version: env!("CARGO_PKG_VERSION").to_owned(),
-                                title: Some("Codex".into()),
-                                // This field is used by Codex when it is an MCP
-                                // server: it should not be used when Codex is
-                                // an MCP client.
-                                user_agent: None,
-                            },
-                            protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
-                        };
-                        let initialize_notification_params = None;
-                        let init_result = client
-                            .initialize(
-                                params,
-                                initialize_notification_params,
-                                Some(startup_timeout),
-                            )
-                            .await;
-                        (
-                            (server_name, tool_timeout),
-                            init_result.map(|_| (client, startup_timeout)),
-                        )
-                    }
-                    Err(e) => ((server_name, tool_timeout), Err(e.into())),
-                }
-            });
-        }
-
-        let mut clients: HashMap<String, ManagedClient> = HashMap::with_capacity(join_set.len());
-
-        while let Some(res) = join_set.join_next().await {
-            let ((server_name, tool_timeout), client_res) = match res {
-                Ok(result) => result,
-                Err(e) => {
-                    warn!("Task panic when starting MCP server: {e:#}");
-                    continue;
-                }
-            };
-
-            match client_res {
-                Ok((client, startup_timeout)) => {
-                    clients.insert(
-                        server_name,
-                        ManagedClient {
-                            client: Arc::new(client),
-                            startup_timeout,
-                            tool_timeout: Some(tool_timeout),
-                        },
-                    );
+            match self.clients.get(&server_name) {
+                Some(managed) if managed.config == cfg => {
+                    clients.insert(server_name, managed.clone());
                 }
-                Err(e) => {
-                    errors.insert(server_name, e);
+                _ => {
+                    changed.insert(server_name, cfg);
                 }
             }
         }
 
+        let (started, errors) = start_clients(changed).await;
+        clients.extend(started);
+        Ok((Self::with_clients(clients).await, errors))
+    }
+
+    async fn with_clients(clients: HashMap<String, ManagedClient>) -> Self {
         let all_tools = match list_all_tools(&clients).await {
             Ok(tools) => tools,
             Err(e) => {
@@ -229,7 +165,7 @@ impl McpConnectionManager {
 
         let tools = qualify_tools(all_tools);
 
-        Ok((Self { clients, tools }, errors))
+        Self { clients, tools }
     }
 
     /// Returns a single map that contains **all** tools. Each key is the
@@ -266,6 +202,122 @@ impl McpConnectionManager {
             .get(tool_name)
             .map(|tool| (tool.server_name.clone(), tool.tool_name.clone()))
     }
+
+    /// Returns `true` if the fully-qualified tool is advertised by its server
+    /// as read-only via the `readOnlyHint` annotation.
+    pub fn is_read_only_tool(&self, tool_name: &str) -> bool {
+        self.tools
+            .get(tool_name)
+            .and_then(|tool| tool.tool.annotations.as_ref())
+            .and_then(|annotations| annotations.read_only_hint)
+            .unwrap_or(false)
+    }
+}
+
+/// Spawn and initialize a [`McpClient`] for each server in `mcp_servers`.
+async fn start_clients(
+    mcp_servers: HashMap<String, McpServerConfig>,
+) -> (HashMap<String, ManagedClient>, ClientStartErrors) {
+    // Launch all configured servers concurrently.
+    let mut join_set = JoinSet::new();
+    let mut errors = ClientStartErrors::new();
+
+    for (server_name, cfg) in mcp_servers {
+        // Validate server name before spawning
+        if !is_valid_mcp_server_name(&server_name) {
+            let error = anyhow::anyhow!(
+                "invalid server name '{}': must match pattern ^[a-zA-Z0-9_-]+$",
+                server_name
+            );
+            errors.insert(server_name, error);
+            continue;
+        }
+
+        let startup_timeout = cfg.startup_timeout_sec.unwrap_or(DEFAULT_STARTUP_TIMEOUT);
+
+        let tool_timeout = cfg.tool_timeout_sec.unwrap_or(DEFAULT_TOOL_TIMEOUT);
+
+        join_set.spawn(async move {
+            let McpServerConfig {
+                command, args, env, ..
+            } = cfg.clone();
+            let client_res = McpClient::new_stdio_client(
+                command.into(),
+                args.into_iter().map(OsString::from).collect(),
+                env,
+            )
+            .await;
+            match client_res {
+                Ok(client) => {
+                    // Initialize the client.
+                    let params = mcp_types::InitializeRequestParams {
+                        capabilities: ClientCapabilities {
+                            experimental: None,
+                            roots: None,
+                            sampling: None,
+                            // https://modelcontextprotocol.io/specification/2025-06-18/client/elicitation#capabilities
+                            // indicates this should be an empty object.
+                            elicitation: Some(json!({})),
+                        },
+                        client_info: Implementation {
+                            name: "codex-mcp-client".to_owned(),
+                            version: env!("CARGO_PKG_VERSION").to_owned(),
+                            title: Some("Codex".into()),
+                            // This field is used by Codex when it is an MCP
+                            // server: it should not be used when Codex is
+                            // an MCP client.
+                            user_agent: None,
+                        },
+                        protocol_version: mcp_types::MCP_SCHEMA_VERSION.to_owned(),
+                    };
+                    let initialize_notification_params = None;
+                    let init_result = client
+                        .initialize(
+                            params,
+                            initialize_notification_params,
+                            Some(startup_timeout),
+                        )
+                        .await;
+                    (
+                        (server_name, tool_timeout, cfg),
+                        init_result.map(|_| (client, startup_timeout)),
+                    )
+                }
+                Err(e) => ((server_name, tool_timeout, cfg), Err(e.into())),
+            }
+        });
+    }
+
+    let mut clients: HashMap<String, ManagedClient> = HashMap::with_capacity(join_set.len());
+
+    while let Some(res) = join_set.join_next().await {
+        let ((server_name, tool_timeout, config), client_res) = match res {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("Task panic when starting MCP server: {e:#}");
+                continue;
+            }
+        };
+
+        match client_res {
+            Ok((client, startup_timeout)) => {
+                clients.insert(
+                    server_name,
+                    ManagedClient {
+                        client: Arc::new(client),
+                        startup_timeout,
+                        tool_timeout: Some(tool_timeout),
+                        config,
+                    },
+                );
+            }
+            Err(e) => {
+                errors.insert(server_name, e);
+            }
+        }
+    }
+
+    (clients, errors)
 }
 
 /// Query every server for its available tools and return a single map that
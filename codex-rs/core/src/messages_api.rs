@@ -0,0 +1,667 @@
+//! Implementation of the Anthropic Messages API (`/v1/messages`).
+//!
+//! The request is built from the same [`Prompt`] as the other wire APIs and
+//! the streamed content blocks are mapped onto [`ResponseEvent`]s so that the
+//! rest of the pipeline stays agnostic of the underlying wire format. Thinking
+//! blocks are kept as [`ResponseItem::Reasoning`] items whose
+//! `encrypted_content` holds the block's signature, so they can be sent back
+//! unchanged on the next request as the API requires during tool use.
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::time::Duration;
+
+use bytes::Bytes;
+use eventsource_stream::Eventsource;
+use futures::Stream;
+use futures::StreamExt;
+use futures::TryStreamExt;
+use serde_json::Value;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+use tracing::debug;
+use tracing::trace;
+
+use crate::ModelProviderInfo;
+use crate::client_common::Prompt;
+use crate::client_common::ResponseEvent;
+use crate::client_common::ResponseStream;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::create_tools_json_for_chat_completions_api;
+use crate::protocol::TokenUsage;
+use crate::provider_health::ProviderHealth;
+use crate::provider_health::send_with_retries;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ReasoningItemContent;
+use codex_protocol::models::ResponseItem;
+
+/// Value of the `anthropic-version` header sent with every request.
+const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// `max_tokens` is mandatory in the Messages API; used when the model's
+/// maximum output is unknown.
+const DEFAULT_MAX_TOKENS: u64 = 8_192;
+
+/// The smallest thinking budget the API accepts.
+const MIN_THINKING_BUDGET_TOKENS: u64 = 1_024;
+
+/// Settings of the Messages API request that do not come from the [`Prompt`].
+pub(crate) struct MessagesRequestParams<'a> {
+    pub model_family: &'a ModelFamily,
+    pub max_output_tokens: Option<u64>,
+    pub effort: Option<ReasoningEffortConfig>,
+    pub parallel_tool_calls: bool,
+}
+
+/// Implementation for the Anthropic Messages API.
+pub(crate) async fn stream_messages(
+    prompt: &Prompt,
+    params: MessagesRequestParams<'_>,
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    health: &ProviderHealth,
+) -> Result<ResponseStream> {
+    if prompt.output_schema.is_some() {
+        return Err(CodexErr::UnsupportedOperation(
+            "output_schema is not supported for the Messages API".to_string(),
+        ));
+    }
+
+    let payload = build_messages_payload(prompt, &params)?;
+
+    debug!(
+        "POST to {}: {}",
+        provider.get_full_url(&None),
+        serde_json::to_string_pretty(&payload).unwrap_or_default()
+    );
+
+    let resp = send_with_retries(client, provider, health, |req_builder| {
+        req_builder
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .json(&payload)
+    })
+    .await?;
+
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
+    let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
+    tokio::spawn(process_messages_sse(
+        stream,
+        tx_event,
+        provider.stream_idle_timeout(),
+    ));
+    Ok(ResponseStream { rx_event })
+}
+
+fn build_messages_payload(prompt: &Prompt, params: &MessagesRequestParams<'_>) -> Result<Value> {
+    let messages = build_messages(&prompt.get_formatted_input());
+
+    // The Messages API describes tools with the same JSON Schema as Chat
+    // Completions, only without the `{"type": "function"}` wrapper.
+    let tools_json: Vec<Value> = create_tools_json_for_chat_completions_api(&prompt.tools)?
+        .into_iter()
+        .filter_map(|tool| {
+            let function = tool.get("function")?;
+            Some(json!({
+                "name": function.get("name")?,
+                "description": function.get("description").cloned().unwrap_or_default(),
+                "input_schema": function.get("parameters")?,
+            }))
+        })
+        .collect();
+
+    let max_tokens = params.max_output_tokens.unwrap_or(DEFAULT_MAX_TOKENS);
+    let mut payload = json!({
+        "model": params.model_family.slug,
+        "system": prompt.get_full_instructions(params.model_family),
+        "messages": messages,
+        "max_tokens": max_tokens,
+        "stream": true,
+    });
+    if !tools_json.is_empty()
+        && let Some(obj) = payload.as_object_mut()
+    {
+        obj.insert(
+            "tool_choice".to_string(),
+            json!({
+                "type": "auto",
+                "disable_parallel_tool_use": !params.parallel_tool_calls,
+            }),
+        );
+        obj.insert("tools".to_string(), json!(tools_json));
+    }
+    if let Some(budget_tokens) = thinking_budget(params.effort, max_tokens)
+        && let Some(obj) = payload.as_object_mut()
+    {
+        obj.insert(
+            "thinking".to_string(),
+            json!({"type": "enabled", "budget_tokens": budget_tokens}),
+        );
+    }
+    Ok(payload)
+}
+
+/// Extended thinking is enabled when a reasoning effort is configured. The
+/// budget has to leave room for the answer within `max_tokens`.
+fn thinking_budget(effort: Option<ReasoningEffortConfig>, max_tokens: u64) -> Option<u64> {
+    let budget = match effort? {
+        ReasoningEffortConfig::Minimal => return None,
+        ReasoningEffortConfig::Low => 2_048,
+        ReasoningEffortConfig::Medium => 8_192,
+        ReasoningEffortConfig::High => 24_576,
+    };
+    let budget = budget.min(max_tokens / 2);
+    (budget >= MIN_THINKING_BUDGET_TOKENS).then_some(budget)
+}
+
+/// Converts the conversation history into Messages API messages. Consecutive
+/// items of the same role are merged into one message because the API expects
+/// the roles to alternate.
+fn build_messages(input: &[ResponseItem]) -> Vec<Value> {
+    let mut messages: Vec<Value> = Vec::new();
+    // A `tool_result` block is rejected unless it answers a `tool_use` block
+    // of the previous assistant message.
+    let mut tool_use_ids: HashSet<&str> = HashSet::new();
+
+    for item in input {
+        match item {
+            ResponseItem::Message { role, content, .. } => {
+                let role = if role == "assistant" {
+                    "assistant"
+                } else {
+                    "user"
+                };
+                for c in content {
+                    let block = match c {
+                        ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                            if text.is_empty() {
+                                continue;
+                            }
+                            json!({"type": "text", "text": text})
+                        }
+                        ContentItem::InputImage { image_url } => image_block(image_url),
+                    };
+                    push_block(&mut messages, role, block);
+                }
+            }
+            ResponseItem::Reasoning {
+                content,
+                encrypted_content: Some(signature),
+                ..
+            } => {
+                let block = match content {
+                    Some(content) => {
+                        let mut thinking = String::new();
+                        for c in content {
+                            match c {
+                                ReasoningItemContent::ReasoningText { text }
+                                | ReasoningItemContent::Text { text } => thinking.push_str(text),
+                            }
+                        }
+                        json!({"type": "thinking", "thinking": thinking, "signature": signature})
+                    }
+                    None => json!({"type": "redacted_thinking", "data": signature}),
+                };
+                push_block(&mut messages, "assistant", block);
+            }
+            ResponseItem::FunctionCall {
+                name,
+                arguments,
+                call_id,
+                ..
+            } => {
+                let input = serde_json::from_str::<Value>(arguments)
+                    .ok()
+                    .filter(Value::is_object)
+                    .unwrap_or_else(|| json!({}));
+                tool_use_ids.insert(call_id.as_str());
+                push_block(
+                    &mut messages,
+                    "assistant",
+                    json!({"type": "tool_use", "id": call_id, "name": name, "input": input}),
+                );
+            }
+            ResponseItem::FunctionCallOutput { call_id, output } => {
+                if !tool_use_ids.contains(call_id.as_str()) {
+                    continue;
+                }
+                push_block(
+                    &mut messages,
+                    "user",
+                    json!({
+                        "type": "tool_result",
+                        "tool_use_id": call_id,
+                        "content": output.content,
+                        "is_error": output.success == Some(false),
+                    }),
+                );
+            }
+            // Reasoning without a signature comes from another wire API and
+            // cannot be replayed; freeform and built-in tools are not offered
+            // over the Messages API.
+            ResponseItem::Reasoning { .. }
+            | ResponseItem::LocalShellCall { .. }
+            | ResponseItem::CustomToolCall { .. }
+            | ResponseItem::CustomToolCallOutput { .. }
+            | ResponseItem::WebSearchCall { .. }
+            | ResponseItem::Other => {}
+        }
+    }
+
+    messages
+}
+
+fn push_block(messages: &mut Vec<Value>, role: &str, block: Value) {
+    if let Some(last) = messages.last_mut()
+        && last.get("role").and_then(Value::as_str) == Some(role)
+        && let Some(content) = last.get_mut("content").and_then(Value::as_array_mut)
+    {
+        content.push(block);
+        return;
+    }
+    messages.push(json!({"role": role, "content": [block]}));
+}
+
+/// Images are sent inline when they are `data:` URLs (local images) and by
+/// reference otherwise.
+fn image_block(image_url: &str) -> Value {
+    if let Some(rest) = image_url.strip_prefix("data:")
+        && let Some((media_type, data)) = rest.split_once(";base64,")
+    {
+        return json!({
+            "type": "image",
+            "source": {"type": "base64", "media_type": media_type, "data": data},
+        });
+    }
+    json!({"type": "image", "source": {"type": "url", "url": image_url}})
+}
+
+/// A content block of the streamed response that has not been completed yet.
+enum ContentBlock {
+    Text(String),
+    ToolUse {
+        id: String,
+        name: String,
+        partial_json: String,
+    },
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    RedactedThinking(String),
+}
+
+impl ContentBlock {
+    fn from_start(block: &Value) -> Option<Self> {
+        let str_field = |key: &str| {
+            block
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string()
+        };
+        match block.get("type").and_then(Value::as_str)? {
+            "text" => Some(ContentBlock::Text(str_field("text"))),
+            "tool_use" => Some(ContentBlock::ToolUse {
+                id: str_field("id"),
+                name: str_field("name"),
+                partial_json: String::new(),
+            }),
+            "thinking" => Some(ContentBlock::Thinking {
+                thinking: str_field("thinking"),
+                signature: str_field("signature"),
+            }),
+            "redacted_thinking" => Some(ContentBlock::RedactedThinking(str_field("data"))),
+            _ => None,
+        }
+    }
+
+    fn into_response_item(self) -> Option<ResponseItem> {
+        match self {
+            ContentBlock::Text(text) if text.is_empty() => None,
+            ContentBlock::Text(text) => Some(ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText { text }],
+            }),
+            ContentBlock::ToolUse {
+                id,
+                name,
+                partial_json,
+            } => Some(ResponseItem::FunctionCall {
+                id: None,
+                name,
+                arguments: if partial_json.is_empty() {
+                    "{}".to_string()
+                } else {
+                    partial_json
+                },
+                call_id: id,
+            }),
+            ContentBlock::Thinking {
+                thinking,
+                signature,
+            } => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: Some(vec![ReasoningItemContent::ReasoningText { text: thinking }]),
+                encrypted_content: Some(signature).filter(|s| !s.is_empty()),
+            }),
+            ContentBlock::RedactedThinking(data) => Some(ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: None,
+                encrypted_content: Some(data),
+            }),
+        }
+    }
+}
+
+/// Token usage reported across the `message_start` and `message_delta` events.
+#[derive(Default)]
+struct MessagesUsage {
+    input_tokens: u64,
+    cache_read_input_tokens: u64,
+    cache_creation_input_tokens: u64,
+    output_tokens: u64,
+}
+
+impl MessagesUsage {
+    fn update(&mut self, usage: &Value) {
+        let field = |key: &str| usage.get(key).and_then(Value::as_u64);
+        if let Some(tokens) = field("input_tokens") {
+            self.input_tokens = tokens;
+        }
+        if let Some(tokens) = field("cache_read_input_tokens") {
+            self.cache_read_input_tokens = tokens;
+        }
+        if let Some(tokens) = field("cache_creation_input_tokens") {
+            self.cache_creation_input_tokens = tokens;
+        }
+        if let Some(tokens) = field("output_tokens") {
+            self.output_tokens = tokens;
+        }
+    }
+
+    /// Unlike the OpenAI APIs, `input_tokens` excludes the tokens read from or
+    /// written to the prompt cache.
+    fn to_token_usage(&self) -> TokenUsage {
+        let input_tokens =
+            self.input_tokens + self.cache_read_input_tokens + self.cache_creation_input_tokens;
+        TokenUsage {
+            input_tokens,
+            cached_input_tokens: self.cache_read_input_tokens,
+            output_tokens: self.output_tokens,
+            reasoning_output_tokens: 0,
+            total_tokens: input_tokens + self.output_tokens,
+        }
+    }
+}
+
+/// SSE processor for the Messages API streaming format.
+async fn process_messages_sse<S>(
+    stream: S,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+    idle_timeout: Duration,
+) where
+    S: Stream<Item = Result<Bytes>> + Unpin,
+{
+    let mut stream = stream.eventsource();
+
+    let mut response_id = String::new();
+    let mut usage = MessagesUsage::default();
+    // Content blocks that have started but not stopped, by index.
+    let mut blocks: BTreeMap<u64, ContentBlock> = BTreeMap::new();
+
+    loop {
+        let sse = match timeout(idle_timeout, stream.next()).await {
+            Ok(Some(Ok(ev))) => ev,
+            Ok(Some(Err(e))) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(e.to_string(), None)))
+                    .await;
+                return;
+            }
+            Ok(None) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "stream closed before message_stop".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+            Err(_) => {
+                let _ = tx_event
+                    .send(Err(CodexErr::Stream(
+                        "idle timeout waiting for SSE".into(),
+                        None,
+                    )))
+                    .await;
+                return;
+            }
+        };
+
+        let event: Value = match serde_json::from_str(&sse.data) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        trace!("messages received SSE event: {event:?}");
+
+        let index = event.get("index").and_then(Value::as_u64).unwrap_or(0);
+        match event
+            .get("type")
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+        {
+            "message_start" => {
+                if let Some(message) = event.get("message") {
+                    if let Some(id) = message.get("id").and_then(Value::as_str) {
+                        response_id = id.to_string();
+                    }
+                    if let Some(message_usage) = message.get("usage") {
+                        usage.update(message_usage);
+                    }
+                }
+                let _ = tx_event.send(Ok(ResponseEvent::Created)).await;
+            }
+            "content_block_start" => {
+                if let Some(block) = event
+                    .get("content_block")
+                    .and_then(ContentBlock::from_start)
+                {
+                    blocks.insert(index, block);
+                }
+            }
+            "content_block_delta" => {
+                let (Some(delta), Some(block)) = (event.get("delta"), blocks.get_mut(&index))
+                else {
+                    continue;
+                };
+                let delta_type = delta.get("type").and_then(Value::as_str);
+                let Some(fragment) = ["text", "partial_json", "thinking", "signature"]
+                    .into_iter()
+                    .find_map(|key| delta.get(key).and_then(Value::as_str))
+                else {
+                    continue;
+                };
+                match (block, delta_type) {
+                    (ContentBlock::Text(text), Some("text_delta")) => {
+                        text.push_str(fragment);
+                        let _ = tx_event
+                            .send(Ok(ResponseEvent::OutputTextDelta(fragment.to_string())))
+                            .await;
+                    }
+                    (ContentBlock::ToolUse { partial_json, .. }, Some("input_json_delta")) => {
+                        partial_json.push_str(fragment);
+                    }
+                    (ContentBlock::Thinking { thinking, .. }, Some("thinking_delta")) => {
+                        thinking.push_str(fragment);
+                        let _ = tx_event
+                            .send(Ok(ResponseEvent::ReasoningContentDelta(
+                                fragment.to_string(),
+                            )))
+                            .await;
+                    }
+                    (ContentBlock::Thinking { signature, .. }, Some("signature_delta")) => {
+                        signature.push_str(fragment);
+                    }
+                    _ => {}
+                }
+            }
+            "content_block_stop" => {
+                if let Some(item) = blocks
+                    .remove(&index)
+                    .and_then(ContentBlock::into_response_item)
+                {
+                    let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+                }
+            }
+            "message_delta" => {
+                if let Some(delta_usage) = event.get("usage") {
+                    usage.update(delta_usage);
+                }
+            }
+            "message_stop" => {
+                let _ = tx_event
+                    .send(Ok(ResponseEvent::Completed {
+                        response_id,
+                        token_usage: Some(usage.to_token_usage()),
+                    }))
+                    .await;
+                return;
+            }
+            "error" => {
+                let message = event
+                    .get("error")
+                    .and_then(|error| error.get("message"))
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown error")
+                    .to_string();
+                let _ = tx_event.send(Err(CodexErr::Stream(message, None))).await;
+                return;
+            }
+            // `ping` and events added to the API later.
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    fn text_message(role: &str, text: &str) -> ResponseItem {
+        ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn merges_tool_turns_into_alternating_messages() {
+        let input = vec![
+            text_message("user", "list files"),
+            ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: Some(vec![ReasoningItemContent::ReasoningText {
+                    text: "run ls".to_string(),
+                }]),
+                encrypted_content: Some("sig".to_string()),
+            },
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: "{\"command\":[\"ls\"]}".to_string(),
+                call_id: "toolu_1".to_string(),
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "toolu_1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "README.md".to_string(),
+                    success: Some(true),
+                },
+            },
+            // Answers a call that was not made over the Messages API.
+            ResponseItem::FunctionCallOutput {
+                call_id: "call_2".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "ignored".to_string(),
+                    success: None,
+                },
+            },
+            text_message("user", "thanks"),
+        ];
+
+        assert_eq!(
+            build_messages(&input),
+            vec![
+                json!({"role": "user", "content": [{"type": "text", "text": "list files"}]}),
+                json!({"role": "assistant", "content": [
+                    {"type": "thinking", "thinking": "run ls", "signature": "sig"},
+                    {
+                        "type": "tool_use",
+                        "id": "toolu_1",
+                        "name": "shell",
+                        "input": {"command": ["ls"]},
+                    },
+                ]}),
+                json!({"role": "user", "content": [
+                    {
+                        "type": "tool_result",
+                        "tool_use_id": "toolu_1",
+                        "content": "README.md",
+                        "is_error": false,
+                    },
+                    {"type": "text", "text": "thanks"},
+                ]}),
+            ]
+        );
+    }
+
+    #[test]
+    fn sends_data_urls_inline() {
+        assert_eq!(
+            image_block("data:image/png;base64,AAAA"),
+            json!({
+                "type": "image",
+                "source": {"type": "base64", "media_type": "image/png", "data": "AAAA"},
+            })
+        );
+        assert_eq!(
+            image_block("https://example.com/cat.png"),
+            json!({"type": "image", "source": {"type": "url", "url": "https://example.com/cat.png"}})
+        );
+    }
+
+    #[test]
+    fn thinking_budget_fits_within_max_tokens() {
+        assert_eq!(thinking_budget(None, 8_192), None);
+        assert_eq!(
+            thinking_budget(Some(ReasoningEffortConfig::Minimal), 8_192),
+            None
+        );
+        assert_eq!(
+            thinking_budget(Some(ReasoningEffortConfig::Low), 8_192),
+            Some(2_048)
+        );
+        assert_eq!(
+            thinking_budget(Some(ReasoningEffortConfig::High), 8_192),
+            Some(4_096)
+        );
+        assert_eq!(
+            thinking_budget(Some(ReasoningEffortConfig::High), 1_024),
+            None
+        );
+    }
+}
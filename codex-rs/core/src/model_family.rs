@@ -1,3 +1,6 @@
+use std::collections::HashMap;
+
+use crate::config_types::ModelConfig;
 use crate::config_types::ReasoningSummaryFormat;
 use crate::tool_apply_patch::ApplyPatchToolType;
 
@@ -129,3 +132,30 @@ pub fn derive_default_model_family(model: &str) -> ModelFamily {
         base_instructions: BASE_INSTRUCTIONS.to_string(),
     }
 }
+
+/// Like [`find_family_for_model`], but with the `[models.<slug>]` entry from
+/// config.toml applied. Models that are only declared in config.toml start
+/// from the defaults of [`derive_default_model_family`].
+pub fn find_configured_family_for_model(
+    slug: &str,
+    models: &HashMap<String, ModelConfig>,
+) -> Option<ModelFamily> {
+    let Some(model) = models.get(slug) else {
+        return find_family_for_model(slug);
+    };
+    let mut family =
+        find_family_for_model(slug).unwrap_or_else(|| derive_default_model_family(slug));
+    if let Some(supports_reasoning_summaries) = model.supports_reasoning_summaries {
+        family.supports_reasoning_summaries = supports_reasoning_summaries;
+    }
+    if let Some(uses_local_shell_tool) = model.uses_local_shell_tool {
+        family.uses_local_shell_tool = uses_local_shell_tool;
+    }
+    if let Some(apply_patch_tool_type) = &model.apply_patch_tool_type {
+        family.apply_patch_tool_type = Some(apply_patch_tool_type.clone());
+    }
+    if let Some(base_instructions) = &model.base_instructions {
+        family.base_instructions = base_instructions.clone();
+    }
+    Some(family)
+}
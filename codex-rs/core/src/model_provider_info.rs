@@ -7,6 +7,7 @@
 
 use crate::CodexAuth;
 use codex_protocol::mcp_protocol::AuthMode;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashMap;
@@ -25,10 +26,11 @@ const MAX_REQUEST_MAX_RETRIES: u64 = 100;
 /// Wire protocol that the provider speaks. Most third-party services only
 /// implement the classic OpenAI Chat Completions JSON schema, whereas OpenAI
 /// itself (and a handful of others) additionally expose the more modern
-/// *Responses* API. The two protocols use different request/response shapes
-/// and *cannot* be auto-detected at runtime, therefore each provider entry
-/// must declare which one it expects.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// *Responses* API, and Anthropic exposes its own *Messages* API. The
+/// protocols use different request/response shapes and *cannot* be
+/// auto-detected at runtime, therefore each provider entry must declare which
+/// one it expects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum WireApi {
     /// The Responses API exposed by OpenAI at `/v1/responses`.
@@ -37,10 +39,13 @@ pub enum WireApi {
     /// Regular Chat Completions compatible with `/v1/chat/completions`.
     #[default]
     Chat,
+
+    /// The Messages API exposed by Anthropic at `/v1/messages`.
+    Messages,
 }
 
 /// Serializable representation of a provider definition.
-#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, JsonSchema)]
 pub struct ModelProviderInfo {
     /// Friendly display name.
     pub name: String,
@@ -86,13 +91,30 @@ pub struct ModelProviderInfo {
     /// and API key (if needed) comes from the "env_key" environment variable.
     #[serde(default)]
     pub requires_openai_auth: bool,
+
+    /// Whether reasoning streamed by a Chat Completions provider (as
+    /// `reasoning` or `reasoning_content` deltas) is shown as agent reasoning.
+    /// Defaults to true; set to false for servers whose reasoning output
+    /// should be ignored.
+    pub supports_reasoning: Option<bool>,
+
+    /// Whether the provider's Chat Completions endpoint accepts `image_url`
+    /// content parts. Defaults to false, in which case images attached to
+    /// user messages are not sent.
+    pub supports_images: Option<bool>,
+
+    /// Whether `${VAR}` and `$(command)` references in `http_headers` and
+    /// `query_params` are resolved when the config is loaded.
+    #[serde(default)]
+    pub interpolate: bool,
 }
 
 impl ModelProviderInfo {
     /// Construct a `POST` RequestBuilder for the given URL using the provided
     /// reqwest Client applying:
     ///   • provider-specific headers (static + env based)
-    ///   • Bearer auth header when an API key is available.
+    ///   • Bearer auth header when an API key is available (`x-api-key` for
+    ///     the Messages API).
     ///   • Auth token for OAuth.
     ///
     /// If the provider declares an `env_key` but the variable is missing/empty, returns an [`Err`] identical to the
@@ -119,7 +141,11 @@ impl ModelProviderInfo {
         let mut builder = client.post(url);
 
         if let Some(auth) = effective_auth.as_ref() {
-            builder = builder.bearer_auth(auth.get_token().await?);
+            let token = auth.get_token().await?;
+            builder = match self.wire_api {
+                WireApi::Messages => builder.header("x-api-key", token),
+                WireApi::Responses | WireApi::Chat => builder.bearer_auth(token),
+            };
         }
 
         Ok(self.apply_http_headers(builder))
@@ -139,7 +165,9 @@ impl ModelProviderInfo {
     }
 
     pub(crate) fn get_full_url(&self, auth: &Option<CodexAuth>) -> String {
-        let default_base_url = if matches!(
+        let default_base_url = if self.wire_api == WireApi::Messages {
+            "https://api.anthropic.com/v1"
+        } else if matches!(
             auth,
             Some(CodexAuth {
                 mode: AuthMode::ChatGPT,
@@ -159,6 +187,7 @@ impl ModelProviderInfo {
         match self.wire_api {
             WireApi::Responses => format!("{base_url}/responses{query_string}"),
             WireApi::Chat => format!("{base_url}/chat/completions{query_string}"),
+            WireApi::Messages => format!("{base_url}/messages{query_string}"),
         }
     }
 
@@ -239,6 +268,16 @@ impl ModelProviderInfo {
             .min(MAX_STREAM_MAX_RETRIES)
     }
 
+    /// Whether streamed Chat Completions reasoning is surfaced for this provider.
+    pub fn supports_reasoning(&self) -> bool {
+        self.supports_reasoning.unwrap_or(true)
+    }
+
+    /// Whether images are sent to this provider's Chat Completions endpoint.
+    pub fn supports_images(&self) -> bool {
+        self.supports_images.unwrap_or(false)
+    }
+
     /// Effective idle timeout for streaming responses.
     pub fn stream_idle_timeout(&self) -> Duration {
         self.stream_idle_timeout_ms
@@ -297,6 +336,9 @@ pub fn built_in_model_providers() -> HashMap<String, ModelProviderInfo> {
                 stream_max_retries: None,
                 stream_idle_timeout_ms: None,
                 requires_openai_auth: true,
+                supports_reasoning: None,
+                supports_images: None,
+                interpolate: false,
             },
         ),
         (BUILT_IN_OSS_MODEL_PROVIDER_ID, create_oss_provider()),
@@ -341,6 +383,9 @@ pub fn create_oss_provider_with_base_url(base_url: &str) -> ModelProviderInfo {
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
+        supports_reasoning: None,
+        supports_images: None,
+        interpolate: false,
     }
 }
 
@@ -380,6 +425,9 @@ base_url = "http://localhost:11434/v1"
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            supports_reasoning: None,
+            supports_images: None,
+            interpolate: false,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -409,6 +457,9 @@ query_params = { api-version = "2025-04-01-preview" }
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            supports_reasoning: None,
+            supports_images: None,
+            interpolate: false,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -441,6 +492,9 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            supports_reasoning: None,
+            supports_images: None,
+            interpolate: false,
         };
 
         let provider: ModelProviderInfo = toml::from_str(azure_provider_toml).unwrap();
@@ -463,6 +517,9 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
                 stream_max_retries: None,
                 stream_idle_timeout_ms: None,
                 requires_openai_auth: false,
+                supports_reasoning: None,
+                supports_images: None,
+                interpolate: false,
             }
         }
 
@@ -495,6 +552,9 @@ env_http_headers = { "X-Example-Env-Header" = "EXAMPLE_ENV_VAR" }
             stream_max_retries: None,
             stream_idle_timeout_ms: None,
             requires_openai_auth: false,
+            supports_reasoning: None,
+            supports_images: None,
+            interpolate: false,
         };
         assert!(named_provider.is_azure_responses_endpoint());
 
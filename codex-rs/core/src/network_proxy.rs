@@ -0,0 +1,460 @@
+//! Local HTTP proxy that lets sandboxed commands reach an allowlist of hosts.
+//!
+//! When the sandbox policy disables network access but lists
+//! `allowed_domains`, every sandboxed command gets its own proxy and finds it
+//! through the usual `HTTP_PROXY`/`HTTPS_PROXY` variables. `CONNECT` tunnels
+//! (used for HTTPS) and absolute-form `http://` requests are forwarded when the
+//! host is on the allowlist and answered with `403 Forbidden` otherwise.
+//!
+//! The proxy listens on a loopback port and, on Unix, on a Unix socket. The
+//! Linux sandbox runs the command in its own network namespace where the
+//! loopback port is not reachable, so the sandbox helper bridges it to the
+//! socket instead.
+
+use std::collections::HashMap;
+use std::io;
+use std::net::Ipv4Addr;
+use std::net::SocketAddr;
+#[cfg(unix)]
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use tracing::info;
+
+use crate::exec_env::set_network_proxy_env;
+
+/// Requests whose header is larger than this are rejected.
+const MAX_REQUEST_HEAD_BYTES: usize = 64 * 1024;
+
+/// A request that went through the proxy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct NetworkRequest {
+    pub host: String,
+    pub port: u16,
+    pub allowed: bool,
+}
+
+struct ProxyState {
+    allowed_domains: Vec<String>,
+    on_request: Box<dyn Fn(NetworkRequest) + Send + Sync>,
+}
+
+/// A running proxy. It stops accepting connections when dropped.
+pub(crate) struct NetworkProxy {
+    addr: SocketAddr,
+    #[cfg(unix)]
+    socket_path: PathBuf,
+    #[cfg(unix)]
+    _socket_dir: tempfile::TempDir,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl NetworkProxy {
+    /// Starts a proxy that forwards requests to `allowed_domains` and calls
+    /// `on_request` for every request it sees, allowed or not.
+    pub(crate) async fn start<F>(allowed_domains: Vec<String>, on_request: F) -> io::Result<Self>
+    where
+        F: Fn(NetworkRequest) + Send + Sync + 'static,
+    {
+        let state = Arc::new(ProxyState {
+            allowed_domains,
+            on_request: Box::new(on_request),
+        });
+
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await?;
+        let addr = listener.local_addr()?;
+        let mut tasks = vec![tokio::spawn(accept_tcp(listener, state.clone()))];
+
+        #[cfg(unix)]
+        {
+            let socket_dir = tempfile::Builder::new().prefix("codex-proxy-").tempdir()?;
+            let socket_path = socket_dir.path().join("proxy.sock");
+            let listener = tokio::net::UnixListener::bind(&socket_path)?;
+            tasks.push(tokio::spawn(accept_unix(listener, state)));
+            Ok(Self {
+                addr,
+                socket_path,
+                _socket_dir: socket_dir,
+                tasks,
+            })
+        }
+
+        #[cfg(not(unix))]
+        {
+            drop(state);
+            Ok(Self { addr, tasks })
+        }
+    }
+
+    pub(crate) fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Points the proxy variables of a command environment at this proxy.
+    pub(crate) fn apply_to_env(&self, env: &mut HashMap<String, String>) {
+        #[cfg(unix)]
+        let socket_path = Some(self.socket_path.as_path());
+        #[cfg(not(unix))]
+        let socket_path = None;
+        set_network_proxy_env(env, self.addr(), socket_path);
+    }
+}
+
+impl Drop for NetworkProxy {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+async fn accept_tcp(listener: TcpListener, state: Arc<ProxyState>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, &state).await {
+                        debug!("network proxy connection failed: {e}");
+                    }
+                });
+            }
+            Err(e) => {
+                debug!("network proxy accept failed: {e}");
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+async fn accept_unix(listener: tokio::net::UnixListener, state: Arc<ProxyState>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, &state).await {
+                        debug!("network proxy connection failed: {e}");
+                    }
+                });
+            }
+            Err(e) => {
+                debug!("network proxy accept failed: {e}");
+                return;
+            }
+        }
+    }
+}
+
+async fn handle_connection<S>(mut client: S, state: &ProxyState) -> io::Result<()>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let Some((head, rest)) = read_request_head(&mut client).await? else {
+        return respond(&mut client, "400 Bad Request", "malformed proxy request").await;
+    };
+    let Some(target) = parse_request_target(&head) else {
+        return respond(&mut client, "400 Bad Request", "malformed proxy request").await;
+    };
+
+    let ProxyTarget { host, port, tunnel } = target;
+    let allowed = is_allowed_host(&host, &state.allowed_domains);
+    info!("network proxy: {host}:{port} allowed={allowed}");
+    (state.on_request)(NetworkRequest {
+        host: host.clone(),
+        port,
+        allowed,
+    });
+    if !allowed {
+        let message = format!("{host} is not in the sandbox's allowed_domains");
+        return respond(&mut client, "403 Forbidden", &message).await;
+    }
+
+    let mut upstream = match TcpStream::connect((host.as_str(), port)).await {
+        Ok(upstream) => upstream,
+        Err(e) => {
+            let message = format!("failed to connect to {host}:{port}: {e}");
+            return respond(&mut client, "502 Bad Gateway", &message).await;
+        }
+    };
+    if tunnel {
+        client
+            .write_all(b"HTTP/1.1 200 Connection Established\r\n\r\n")
+            .await?;
+    } else {
+        // Origin servers must accept the absolute-form request target, so the
+        // request is forwarded unchanged.
+        upstream.write_all(&head).await?;
+    }
+    upstream.write_all(&rest).await?;
+    tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+    Ok(())
+}
+
+/// Reads up to the end of the request header. Returns the header and whatever
+/// was read past it, or `None` if the client closed the connection early or
+/// the header is too large.
+async fn read_request_head<S>(client: &mut S) -> io::Result<Option<(Vec<u8>, Vec<u8>)>>
+where
+    S: AsyncRead + Unpin,
+{
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let n = client.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        let search_from = buf.len().saturating_sub(3);
+        buf.extend_from_slice(&chunk[..n]);
+        if let Some(pos) = buf[search_from..]
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+        {
+            let end = search_from + pos + 4;
+            let rest = buf.split_off(end);
+            return Ok(Some((buf, rest)));
+        }
+        if buf.len() > MAX_REQUEST_HEAD_BYTES {
+            return Ok(None);
+        }
+    }
+}
+
+async fn respond<S>(client: &mut S, status: &str, message: &str) -> io::Result<()>
+where
+    S: AsyncWrite + Unpin,
+{
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{message}",
+        message.len()
+    );
+    client.write_all(response.as_bytes()).await?;
+    client.shutdown().await
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct ProxyTarget {
+    host: String,
+    port: u16,
+    /// `true` for `CONNECT` requests, which open a tunnel.
+    tunnel: bool,
+}
+
+/// Extracts the destination from the request line of a proxy request.
+fn parse_request_target(head: &[u8]) -> Option<ProxyTarget> {
+    let head = std::str::from_utf8(head).ok()?;
+    let request_line = head.lines().next()?;
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next()?;
+    let target = parts.next()?;
+
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let (host, port) = split_authority(target, None)?;
+        return Some(ProxyTarget {
+            host,
+            port,
+            tunnel: true,
+        });
+    }
+
+    let scheme_end = target.find("://")?;
+    if !target[..scheme_end].eq_ignore_ascii_case("http") {
+        return None;
+    }
+    let rest = &target[scheme_end + 3..];
+    let authority = rest.split(['/', '?', '#']).next()?;
+    let (host, port) = split_authority(authority, Some(80))?;
+    Some(ProxyTarget {
+        host,
+        port,
+        tunnel: false,
+    })
+}
+
+/// Splits `host[:port]` (with optional user info and bracketed IPv6 hosts)
+/// into a normalized host name and a port.
+fn split_authority(authority: &str, default_port: Option<u16>) -> Option<(String, u16)> {
+    let authority = authority.rsplit('@').next()?;
+    let (host, port) = if let Some(bracketed) = authority.strip_prefix('[') {
+        let (host, rest) = bracketed.split_once(']')?;
+        (host, rest.strip_prefix(':'))
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    let port = match port {
+        Some(port) => port.parse().ok()?,
+        None => default_port?,
+    };
+    let host = host.trim_end_matches('.').to_ascii_lowercase();
+    if host.is_empty() {
+        return None;
+    }
+    Some((host, port))
+}
+
+/// Returns whether `host` is one of `allowed_domains` or a subdomain of one.
+/// A leading `*.` in an entry is accepted and means the same thing.
+fn is_allowed_host(host: &str, allowed_domains: &[String]) -> bool {
+    allowed_domains.iter().any(|domain| {
+        let domain = domain
+            .trim()
+            .trim_start_matches("*.")
+            .trim_end_matches('.')
+            .to_ascii_lowercase();
+        !domain.is_empty()
+            && (host == domain
+                || host
+                    .strip_suffix(domain.as_str())
+                    .is_some_and(|prefix| prefix.ends_with('.')))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::sync::Mutex;
+
+    fn domains(domains: &[&str]) -> Vec<String> {
+        domains.iter().map(|domain| (*domain).to_string()).collect()
+    }
+
+    #[test]
+    fn allowlist_matches_domains_and_subdomains() {
+        let allowed = domains(&["crates.io", "*.npmjs.org", "GitHub.com."]);
+        assert!(is_allowed_host("crates.io", &allowed));
+        assert!(is_allowed_host("static.crates.io", &allowed));
+        assert!(is_allowed_host("registry.npmjs.org", &allowed));
+        assert!(is_allowed_host("github.com", &allowed));
+        assert!(!is_allowed_host("evilcrates.io", &allowed));
+        assert!(!is_allowed_host("crates.io.evil.com", &allowed));
+        assert!(!is_allowed_host("example.com", &allowed));
+        assert!(!is_allowed_host("example.com", &domains(&["", "*."])));
+    }
+
+    #[test]
+    fn request_targets_are_parsed() {
+        assert_eq!(
+            parse_request_target(b"CONNECT Crates.IO:443 HTTP/1.1\r\n\r\n"),
+            Some(ProxyTarget {
+                host: "crates.io".to_string(),
+                port: 443,
+                tunnel: true,
+            })
+        );
+        assert_eq!(
+            parse_request_target(b"GET http://user@example.com/a?b HTTP/1.1\r\n\r\n"),
+            Some(ProxyTarget {
+                host: "example.com".to_string(),
+                port: 80,
+                tunnel: false,
+            })
+        );
+        assert_eq!(
+            parse_request_target(b"CONNECT [::1]:8443 HTTP/1.1\r\n\r\n"),
+            Some(ProxyTarget {
+                host: "::1".to_string(),
+                port: 8443,
+                tunnel: true,
+            })
+        );
+        assert_eq!(
+            parse_request_target(b"CONNECT example.com HTTP/1.1\r\n\r\n"),
+            None
+        );
+        assert_eq!(
+            parse_request_target(b"GET /index.html HTTP/1.1\r\n\r\n"),
+            None
+        );
+        assert_eq!(
+            parse_request_target(b"GET ftp://example.com/ HTTP/1.1\r\n\r\n"),
+            None
+        );
+    }
+
+    async fn read_to_string(stream: &mut TcpStream) -> String {
+        let mut response = String::new();
+        stream
+            .read_to_string(&mut response)
+            .await
+            .expect("read response");
+        response
+    }
+
+    #[tokio::test]
+    async fn proxy_tunnels_allowed_hosts_and_rejects_others() {
+        let upstream = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+            .await
+            .expect("bind upstream");
+        let upstream_port = upstream.local_addr().expect("upstream addr").port();
+        tokio::spawn(async move {
+            let (mut stream, _) = upstream.accept().await.expect("accept");
+            let mut buf = [0u8; 4];
+            stream.read_exact(&mut buf).await.expect("read ping");
+            stream.write_all(b"pong").await.expect("write pong");
+        });
+
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        let proxy = NetworkProxy::start(domains(&["127.0.0.1"]), move |request| {
+            recorded
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                .push(request);
+        })
+        .await
+        .expect("start proxy");
+
+        let mut client = TcpStream::connect(proxy.addr()).await.expect("connect");
+        client
+            .write_all(format!("CONNECT 127.0.0.1:{upstream_port} HTTP/1.1\r\n\r\nping").as_bytes())
+            .await
+            .expect("send connect");
+        let established = b"HTTP/1.1 200 Connection Established\r\n\r\n";
+        let mut buf = vec![0u8; established.len() + 4];
+        client.read_exact(&mut buf).await.expect("read tunnel");
+        assert_eq!(&buf[..established.len()], established);
+        assert_eq!(&buf[established.len()..], b"pong");
+
+        let mut denied = TcpStream::connect(proxy.addr()).await.expect("connect");
+        denied
+            .write_all(b"GET http://example.com/ HTTP/1.1\r\nHost: example.com\r\n\r\n")
+            .await
+            .expect("send request");
+        let response = read_to_string(&mut denied).await;
+        assert!(response.starts_with("HTTP/1.1 403 Forbidden"), "{response}");
+
+        let requests = requests
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone();
+        assert_eq!(
+            requests,
+            vec![
+                NetworkRequest {
+                    host: "127.0.0.1".to_string(),
+                    port: upstream_port,
+                    allowed: true,
+                },
+                NetworkRequest {
+                    host: "example.com".to_string(),
+                    port: 80,
+                    allowed: false,
+                },
+            ]
+        );
+    }
+}
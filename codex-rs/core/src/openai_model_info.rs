@@ -1,30 +1,63 @@
+use std::collections::HashMap;
+
+use crate::config_types::ModelConfig;
+use crate::config_types::ModelPricing;
 use crate::model_family::ModelFamily;
+use crate::protocol::TokenUsage;
 
 /// Metadata about a model, particularly OpenAI models.
-/// We may want to consider including details like the pricing for
-/// input tokens, output tokens, etc., though users will need to be able to
-/// override this in config.toml, as this information can get out of date.
-/// Though this would help present more accurate pricing information in the UI.
+/// The built-in pricing can get out of date, so users can override it per
+/// model with `[model_pricing.<slug>]` in config.toml.
 #[derive(Debug)]
 pub(crate) struct ModelInfo {
     /// Size of the context window in tokens.
     pub(crate) context_window: u64,
 
     /// Maximum number of output tokens that can be generated for the model.
-    pub(crate) max_output_tokens: u64,
+    pub(crate) max_output_tokens: Option<u64>,
 
     /// Token threshold where we should automatically compact conversation history.
     pub(crate) auto_compact_token_limit: Option<i64>,
+
+    /// List price of the model's tokens, if known.
+    pub(crate) pricing: Option<ModelPricing>,
 }
 
 impl ModelInfo {
     const fn new(context_window: u64, max_output_tokens: u64) -> Self {
         Self {
             context_window,
-            max_output_tokens,
+            max_output_tokens: Some(max_output_tokens),
             auto_compact_token_limit: None,
+            pricing: None,
         }
     }
+
+    /// Sets the price in US dollars per million input, cached input and
+    /// output tokens. Reasoning tokens are billed as output.
+    const fn with_pricing(mut self, input: f64, cached_input: f64, output: f64) -> Self {
+        self.pricing = Some(ModelPricing {
+            input,
+            cached_input: Some(cached_input),
+            output,
+            reasoning_output: None,
+        });
+        self
+    }
+}
+
+/// Estimated cost in US dollars of the tokens in `usage` at `pricing`.
+pub(crate) fn estimate_cost_usd(pricing: &ModelPricing, usage: &TokenUsage) -> f64 {
+    let cached_input = pricing.cached_input.unwrap_or(pricing.input);
+    let reasoning_output = pricing.reasoning_output.unwrap_or(pricing.output);
+    let non_reasoning_output = usage
+        .output_tokens
+        .saturating_sub(usage.reasoning_output_tokens);
+    let micro_dollars = usage.non_cached_input() as f64 * pricing.input
+        + usage.cached_input() as f64 * cached_input
+        + non_reasoning_output as f64 * pricing.output
+        + usage.reasoning_output_tokens as f64 * reasoning_output;
+    micro_dollars / 1_000_000.0
 }
 
 pub(crate) fn get_model_info(model_family: &ModelFamily) -> Option<ModelInfo> {
@@ -36,41 +69,129 @@ pub(crate) fn get_model_info(model_family: &ModelFamily) -> Option<ModelInfo> {
         "gpt-oss-20b" => Some(ModelInfo::new(96_000, 32_000)),
         "gpt-oss-120b" => Some(ModelInfo::new(96_000, 32_000)),
         // https://platform.openai.com/docs/models/o3
-        "o3" => Some(ModelInfo::new(200_000, 100_000)),
+        "o3" => Some(ModelInfo::new(200_000, 100_000).with_pricing(2.0, 0.5, 8.0)),
 
         // https://platform.openai.com/docs/models/o4-mini
-        "o4-mini" => Some(ModelInfo::new(200_000, 100_000)),
+        "o4-mini" => Some(ModelInfo::new(200_000, 100_000).with_pricing(1.1, 0.275, 4.4)),
 
         // https://platform.openai.com/docs/models/codex-mini-latest
-        "codex-mini-latest" => Some(ModelInfo::new(200_000, 100_000)),
+        "codex-mini-latest" => Some(ModelInfo::new(200_000, 100_000).with_pricing(1.5, 0.375, 6.0)),
 
         // As of Jun 25, 2025, gpt-4.1 defaults to gpt-4.1-2025-04-14.
         // https://platform.openai.com/docs/models/gpt-4.1
-        "gpt-4.1" | "gpt-4.1-2025-04-14" => Some(ModelInfo::new(1_047_576, 32_768)),
+        "gpt-4.1" | "gpt-4.1-2025-04-14" => {
+            Some(ModelInfo::new(1_047_576, 32_768).with_pricing(2.0, 0.5, 8.0))
+        }
 
         // As of Jun 25, 2025, gpt-4o defaults to gpt-4o-2024-08-06.
         // https://platform.openai.com/docs/models/gpt-4o
-        "gpt-4o" | "gpt-4o-2024-08-06" => Some(ModelInfo::new(128_000, 16_384)),
+        "gpt-4o" | "gpt-4o-2024-08-06" => {
+            Some(ModelInfo::new(128_000, 16_384).with_pricing(2.5, 1.25, 10.0))
+        }
 
         // https://platform.openai.com/docs/models/gpt-4o?snapshot=gpt-4o-2024-05-13
-        "gpt-4o-2024-05-13" => Some(ModelInfo::new(128_000, 4_096)),
+        "gpt-4o-2024-05-13" => Some(ModelInfo::new(128_000, 4_096).with_pricing(5.0, 5.0, 15.0)),
 
         // https://platform.openai.com/docs/models/gpt-4o?snapshot=gpt-4o-2024-11-20
-        "gpt-4o-2024-11-20" => Some(ModelInfo::new(128_000, 16_384)),
+        "gpt-4o-2024-11-20" => Some(ModelInfo::new(128_000, 16_384).with_pricing(2.5, 1.25, 10.0)),
 
         // https://platform.openai.com/docs/models/gpt-3.5-turbo
-        "gpt-3.5-turbo" => Some(ModelInfo::new(16_385, 4_096)),
+        "gpt-3.5-turbo" => Some(ModelInfo::new(16_385, 4_096).with_pricing(0.5, 0.5, 1.5)),
 
         _ if slug.starts_with("gpt-5-codex") => Some(ModelInfo {
             context_window: 272_000,
-            max_output_tokens: 128_000,
+            max_output_tokens: Some(128_000),
             auto_compact_token_limit: Some(250_000),
+            pricing: Some(ModelPricing {
+                input: 1.25,
+                cached_input: Some(0.125),
+                output: 10.0,
+                reasoning_output: None,
+            }),
         }),
 
-        _ if slug.starts_with("gpt-5") => Some(ModelInfo::new(272_000, 128_000)),
+        _ if slug.starts_with("gpt-5") => {
+            Some(ModelInfo::new(272_000, 128_000).with_pricing(1.25, 0.125, 10.0))
+        }
 
         _ if slug.starts_with("codex-") => Some(ModelInfo::new(272_000, 128_000)),
 
         _ => None,
     }
 }
+
+/// Like [`get_model_info`], but with the `[models.<slug>]` entry from
+/// config.toml applied. Models unknown to Codex need at least a
+/// `context_window` there.
+pub(crate) fn get_configured_model_info(
+    model_family: &ModelFamily,
+    models: &HashMap<String, ModelConfig>,
+) -> Option<ModelInfo> {
+    let info = get_model_info(model_family);
+    let Some(model) = models.get(&model_family.slug) else {
+        return info;
+    };
+    let mut info = match (info, model.context_window) {
+        (Some(info), _) => info,
+        (None, Some(context_window)) => ModelInfo {
+            context_window,
+            max_output_tokens: None,
+            auto_compact_token_limit: None,
+            pricing: None,
+        },
+        (None, None) => return None,
+    };
+    if let Some(context_window) = model.context_window {
+        info.context_window = context_window;
+    }
+    if let Some(max_output_tokens) = model.max_output_tokens {
+        info.max_output_tokens = Some(max_output_tokens);
+    }
+    if let Some(auto_compact_token_limit) = model.auto_compact_token_limit {
+        info.auto_compact_token_limit = Some(auto_compact_token_limit);
+    }
+    Some(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn cost_uses_cached_and_reasoning_prices() {
+        let pricing = ModelPricing {
+            input: 2.0,
+            cached_input: Some(0.5),
+            output: 8.0,
+            reasoning_output: Some(4.0),
+        };
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            cached_input_tokens: 500_000,
+            output_tokens: 300_000,
+            reasoning_output_tokens: 100_000,
+            total_tokens: 1_300_000,
+        };
+        // 0.5M * $2 + 0.5M * $0.5 + 0.2M * $8 + 0.1M * $4
+        assert_eq!(estimate_cost_usd(&pricing, &usage), 3.25);
+    }
+
+    #[test]
+    fn cost_defaults_cached_and_reasoning_to_base_prices() {
+        let pricing = ModelPricing {
+            input: 1.0,
+            cached_input: None,
+            output: 10.0,
+            reasoning_output: None,
+        };
+        let usage = TokenUsage {
+            input_tokens: 2_000_000,
+            cached_input_tokens: 1_000_000,
+            output_tokens: 100_000,
+            reasoning_output_tokens: 50_000,
+            total_tokens: 2_100_000,
+        };
+        assert_eq!(estimate_cost_usd(&pricing, &usage), 3.0);
+    }
+}
@@ -5,7 +5,9 @@ use serde_json::json;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 
+use crate::delegate_tool::DELEGATE_TOOL;
 use crate::model_family::ModelFamily;
+use crate::output_store::READ_OUTPUT_TOOL;
 use crate::plan_tool::PLAN_TOOL;
 use crate::tool_apply_patch::ApplyPatchToolType;
 use crate::tool_apply_patch::create_apply_patch_freeform_tool;
@@ -68,6 +70,7 @@ pub(crate) struct ToolsConfig {
     pub web_search_request: bool,
     pub include_view_image_tool: bool,
     pub experimental_unified_exec_tool: bool,
+    pub delegate_tool: bool,
 }
 
 pub(crate) struct ToolsConfigParams<'a> {
@@ -78,6 +81,7 @@ pub(crate) struct ToolsConfigParams<'a> {
     pub(crate) use_streamable_shell_tool: bool,
     pub(crate) include_view_image_tool: bool,
     pub(crate) experimental_unified_exec_tool: bool,
+    pub(crate) include_delegate_tool: bool,
 }
 
 impl ToolsConfig {
@@ -90,6 +94,7 @@ impl ToolsConfig {
             use_streamable_shell_tool,
             include_view_image_tool,
             experimental_unified_exec_tool,
+            include_delegate_tool,
         } = params;
         let shell_type = if *use_streamable_shell_tool {
             ConfigShellToolType::Streamable
@@ -118,6 +123,7 @@ impl ToolsConfig {
             web_search_request: *include_web_search_request,
             include_view_image_tool: *include_view_image_tool,
             experimental_unified_exec_tool: *experimental_unified_exec_tool,
+            delegate_tool: *include_delegate_tool,
         }
     }
 }
@@ -488,9 +494,11 @@ pub(crate) fn get_openai_tools(
         match &config.shell_type {
             ConfigShellToolType::Default => {
                 tools.push(create_shell_tool());
+                tools.push(READ_OUTPUT_TOOL.clone());
             }
             ConfigShellToolType::Local => {
                 tools.push(OpenAiTool::LocalShell {});
+                tools.push(READ_OUTPUT_TOOL.clone());
             }
             ConfigShellToolType::Streamable => {
                 tools.push(OpenAiTool::Function(
@@ -526,6 +534,11 @@ pub(crate) fn get_openai_tools(
     if config.include_view_image_tool {
         tools.push(create_view_image_tool());
     }
+
+    if config.delegate_tool {
+        tools.push(DELEGATE_TOOL.clone());
+    }
+
     if let Some(mcp_tools) = mcp_tools {
         // Ensure deterministic ordering to maximize prompt cache hits.
         let mut entries: Vec<(String, mcp_types::Tool)> = mcp_tools.into_iter().collect();
@@ -588,6 +601,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -608,6 +622,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -617,6 +632,42 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_openai_tools_delegate_tool() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: true,
+            experimental_unified_exec_tool: true,
+            include_delegate_tool: true,
+        });
+        let tools = get_openai_tools(&config, Some(HashMap::new()));
+
+        assert_eq_tool_names(&tools, &["unified_exec", "view_image", "delegate_task"]);
+    }
+
+    #[test]
+    fn test_get_openai_tools_read_output_follows_shell() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            include_plan_tool: false,
+            include_apply_patch_tool: false,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: false,
+            experimental_unified_exec_tool: false,
+            include_delegate_tool: false,
+        });
+        let tools = get_openai_tools(&config, Some(HashMap::new()));
+
+        assert_eq_tool_names(&tools, &["shell", "read_output"]);
+    }
+
     #[test]
     fn test_get_openai_tools_mcp_tools() {
         let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
@@ -628,6 +679,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
         });
         let tools = get_openai_tools(
             &config,
@@ -732,6 +784,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
         });
 
         // Intentionally construct a map with keys that would sort alphabetically.
@@ -808,6 +861,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -869,6 +923,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -925,6 +980,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
         });
 
         let tools = get_openai_tools(
@@ -984,6 +1040,7 @@ mod tests {
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
             experimental_unified_exec_tool: true,
+            include_delegate_tool: false,
         });
 
         let tools = get_openai_tools(
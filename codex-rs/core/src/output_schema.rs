@@ -0,0 +1,125 @@
+//! Local validation of the final message of a turn that was given an output
+//! schema (`codex exec --output-schema`).
+//!
+//! The schema is forwarded to the model, but not every provider enforces it.
+//! The final agent message is therefore checked against the schema locally
+//! and, when it does not match, the errors are sent back to the model so it
+//! can repair its answer.
+
+use serde_json::Value;
+
+/// At most this many validation errors are reported for a single message so
+/// that a badly malformed answer does not flood the follow-up prompt.
+const MAX_REPORTED_ERRORS: usize = 20;
+
+/// An output schema compiled for validating final messages.
+pub struct OutputSchema {
+    validator: jsonschema::Validator,
+}
+
+/// Checks that `schema` is a JSON Schema that can be used to validate the
+/// final message, and compiles it.
+pub fn check_output_schema(schema: &Value) -> Result<OutputSchema, String> {
+    jsonschema::validator_for(schema)
+        .map(|validator| OutputSchema { validator })
+        .map_err(|err| err.to_string())
+}
+
+impl OutputSchema {
+    /// Parses `message` as JSON and validates it against the schema. Returns
+    /// the parsed value, or a description of each way the message does not
+    /// match.
+    pub fn validate_final_output(&self, message: Option<&str>) -> Result<Value, Vec<String>> {
+        let Some(message) = message.filter(|message| !message.trim().is_empty()) else {
+            return Err(vec!["the final message is empty".to_string()]);
+        };
+        let value = serde_json::from_str::<Value>(message.trim())
+            .map_err(|err| vec![format!("the final message is not valid JSON: {err}")])?;
+
+        let errors: Vec<String> = self
+            .validator
+            .iter_errors(&value)
+            .take(MAX_REPORTED_ERRORS)
+            .map(|err| {
+                let path = err.instance_path.to_string();
+                if path.is_empty() {
+                    err.to_string()
+                } else {
+                    format!("{path}: {err}")
+                }
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(value)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// The follow-up message that asks the model to fix a final message that did
+/// not match the output schema.
+pub(crate) fn repair_prompt(errors: &[String]) -> String {
+    let mut prompt = "Your final message does not match the required output schema:\n".to_string();
+    for error in errors {
+        prompt.push_str("- ");
+        prompt.push_str(error);
+        prompt.push('\n');
+    }
+    prompt.push_str("Reply again with only a JSON value that matches the schema.");
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn schema() -> OutputSchema {
+        check_output_schema(&json!({
+            "type": "object",
+            "properties": {
+                "answer": { "type": "string" },
+                "confidence": { "type": "number" }
+            },
+            "required": ["answer"],
+            "additionalProperties": false
+        }))
+        .expect("valid schema")
+    }
+
+    #[test]
+    fn matching_message_is_parsed() {
+        let value = schema().validate_final_output(Some(r#"{"answer": "42"}"#));
+        assert_eq!(value, Ok(json!({"answer": "42"})));
+    }
+
+    #[test]
+    fn mismatches_are_reported_with_their_location() {
+        let errors = schema()
+            .validate_final_output(Some(r#"{"answer": 42}"#))
+            .expect_err("answer should be a string");
+        assert_eq!(errors, vec![r#"/answer: 42 is not of type "string""#]);
+
+        let errors = schema()
+            .validate_final_output(Some(r#"{"confidence": 1}"#))
+            .expect_err("answer is required");
+        assert_eq!(errors, vec![r#""answer" is a required property"#]);
+    }
+
+    #[test]
+    fn non_json_and_empty_messages_are_rejected() {
+        let errors = schema()
+            .validate_final_output(Some("The answer is 42."))
+            .expect_err("prose is not JSON");
+        assert!(
+            errors[0].starts_with("the final message is not valid JSON"),
+            "{errors:?}"
+        );
+        assert_eq!(
+            schema().validate_final_output(None),
+            Err(vec!["the final message is empty".to_string()])
+        );
+    }
+}
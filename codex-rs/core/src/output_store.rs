@@ -0,0 +1,498 @@
+//! Full output of the commands run in a session.
+//!
+//! The output of a command is truncated before it is shown to the model (see
+//! `format_exec_output_str`), so the middle of a long build log is lost. Every
+//! command's aggregated output is therefore also written to a scratch
+//! directory of the session, and the truncated output names a handle that the
+//! model can pass to the `read_output` tool to page through the rest, or to
+//! grep it, without running the command again. Once the saved outputs
+//! exceed [`MAX_STORED_OUTPUT_BYTES`], the least recently used ones are
+//! removed.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+use std::sync::Mutex;
+
+use codex_protocol::models::FunctionCallOutputPayload;
+use codex_protocol::models::ResponseInputItem;
+use regex_lite::Regex;
+use serde::Deserialize;
+use tempfile::TempDir;
+use tracing::warn;
+
+use crate::codex::MODEL_FORMAT_MAX_BYTES;
+use crate::codex::MODEL_FORMAT_MAX_LINES;
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+
+pub(crate) const READ_OUTPUT_TOOL_NAME: &str = "read_output";
+
+/// At most this many bytes of output are kept for one session.
+const MAX_STORED_OUTPUT_BYTES: usize = 64 * 1024 * 1024;
+
+pub(crate) static READ_OUTPUT_TOOL: LazyLock<OpenAiTool> = LazyLock::new(|| {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "handle".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "The handle named where the command's output was truncated, e.g. `output-3`."
+                    .to_string(),
+            ),
+        },
+    );
+    properties.insert(
+        "start_line".to_string(),
+        JsonSchema::Number {
+            description: Some("First line to return, counting from 1. Defaults to 1.".to_string()),
+        },
+    );
+    properties.insert(
+        "end_line".to_string(),
+        JsonSchema::Number {
+            description: Some(format!(
+                "Last line to return. At most {MODEL_FORMAT_MAX_LINES} lines are returned at once."
+            )),
+        },
+    );
+    properties.insert(
+        "pattern".to_string(),
+        JsonSchema::String {
+            description: Some(
+                "Regular expression. When set, only the matching lines are returned, starting at start_line."
+                    .to_string(),
+            ),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: READ_OUTPUT_TOOL_NAME.to_string(),
+        description: r#"Reads the full output of an earlier command whose output was truncated.
+Returns the requested lines, or the lines matching a pattern, prefixed with their line numbers.
+"#
+        .to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["handle".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+});
+
+#[derive(Debug, Deserialize)]
+struct ReadOutputArgs {
+    handle: String,
+    #[serde(default)]
+    start_line: Option<usize>,
+    #[serde(default)]
+    end_line: Option<usize>,
+    #[serde(default)]
+    pattern: Option<String>,
+}
+
+/// Answers a `read_output` tool call from the session's stored outputs.
+pub(crate) fn handle_read_output(
+    store: &OutputStore,
+    arguments: String,
+    call_id: String,
+) -> ResponseInputItem {
+    let result = serde_json::from_str::<ReadOutputArgs>(&arguments)
+        .map_err(|e| format!("failed to parse function arguments: {e}"))
+        .and_then(|args| {
+            let query = match args.pattern {
+                Some(pattern) => {
+                    let pattern = Regex::new(&pattern)
+                        .map_err(|e| format!("invalid pattern `{pattern}`: {e}"))?;
+                    OutputQuery::Grep {
+                        pattern,
+                        start_line: args.start_line.unwrap_or(1),
+                    }
+                }
+                None => OutputQuery::Lines {
+                    start_line: args.start_line.unwrap_or(1),
+                    end_line: args.end_line,
+                },
+            };
+            store.read(&args.handle, &query)
+        });
+    let (content, success) = match result {
+        Ok(content) => (content, true),
+        Err(content) => (content, false),
+    };
+    ResponseInputItem::FunctionCallOutput {
+        call_id,
+        output: FunctionCallOutputPayload {
+            content,
+            success: Some(success),
+        },
+    }
+}
+
+/// What to read from a stored output. Line numbers count from 1.
+pub(crate) enum OutputQuery {
+    /// The lines from `start_line` through `end_line`, or as many as fit.
+    Lines {
+        start_line: usize,
+        end_line: Option<usize>,
+    },
+    /// The lines from `start_line` on that match `pattern`.
+    Grep { pattern: Regex, start_line: usize },
+}
+
+/// The stored outputs of one session. The scratch directory is created with
+/// the first output and removed when the session ends.
+pub(crate) struct OutputStore {
+    max_bytes: usize,
+    inner: Mutex<OutputStoreInner>,
+}
+
+impl Default for OutputStore {
+    fn default() -> Self {
+        Self::with_max_bytes(MAX_STORED_OUTPUT_BYTES)
+    }
+}
+
+#[derive(Default)]
+struct OutputStoreInner {
+    dir: Option<TempDir>,
+    next_id: u64,
+    files: HashMap<String, StoredOutput>,
+    /// Handles of the stored outputs, least recently used first.
+    recently_used: VecDeque<String>,
+    total_bytes: usize,
+    evicted: HashSet<String>,
+    handles_by_call_id: HashMap<String, String>,
+}
+
+struct StoredOutput {
+    path: PathBuf,
+    bytes: usize,
+}
+
+impl OutputStoreInner {
+    fn touch(&mut self, handle: &str) {
+        self.recently_used.retain(|used| used != handle);
+        self.recently_used.push_back(handle.to_string());
+    }
+
+    /// Removes the least recently used outputs until `bytes` more fit.
+    fn make_room(&mut self, bytes: usize, max_bytes: usize) {
+        while self.total_bytes + bytes > max_bytes {
+            let Some(handle) = self.recently_used.pop_front() else {
+                break;
+            };
+            if let Some(stored) = self.files.remove(&handle) {
+                self.total_bytes -= stored.bytes;
+                if let Err(e) = std::fs::remove_file(&stored.path) {
+                    warn!("failed to remove {}: {e}", stored.path.display());
+                }
+            }
+            self.handles_by_call_id.retain(|_, saved| *saved != handle);
+            self.evicted.insert(handle);
+        }
+    }
+}
+
+impl OutputStore {
+    /// A store that keeps at most `max_bytes` of output.
+    fn with_max_bytes(max_bytes: usize) -> Self {
+        Self {
+            max_bytes,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Writes the output of the command run for `call_id` to the scratch
+    /// directory and returns its handle, removing the least recently used
+    /// outputs to make room. An output larger than the whole store is not
+    /// saved. A command that is retried without the sandbox gets a new
+    /// handle for the output of the retry.
+    pub(crate) fn save(&self, call_id: &str, output: &str) -> Option<String> {
+        if output.len() > self.max_bytes {
+            return None;
+        }
+        let Ok(mut inner) = self.inner.lock() else {
+            return None;
+        };
+        if inner.dir.is_none() {
+            match tempfile::Builder::new().prefix("codex-output-").tempdir() {
+                Ok(dir) => inner.dir = Some(dir),
+                Err(e) => {
+                    warn!("failed to create the command output directory: {e}");
+                    return None;
+                }
+            }
+        }
+        let dir = inner.dir.as_ref()?.path().to_path_buf();
+
+        inner.make_room(output.len(), self.max_bytes);
+        inner.next_id += 1;
+        let handle = format!("output-{}", inner.next_id);
+        let path = dir.join(format!("{handle}.txt"));
+        if let Err(e) = std::fs::write(&path, output) {
+            warn!("failed to save command output to {}: {e}", path.display());
+            return None;
+        }
+        inner.files.insert(
+            handle.clone(),
+            StoredOutput {
+                path,
+                bytes: output.len(),
+            },
+        );
+        inner.total_bytes += output.len();
+        inner.touch(&handle);
+        inner
+            .handles_by_call_id
+            .insert(call_id.to_string(), handle.clone());
+        Some(handle)
+    }
+
+    /// The handle of the latest output saved for `call_id`.
+    pub(crate) fn handle_for_call(&self, call_id: &str) -> Option<String> {
+        let inner = self.inner.lock().ok()?;
+        inner.handles_by_call_id.get(call_id).cloned()
+    }
+
+    /// Answers `query` from the output stored under `handle`. The answer is
+    /// capped like the command output shown to the model and says where to
+    /// continue when there is more.
+    pub(crate) fn read(&self, handle: &str, query: &OutputQuery) -> Result<String, String> {
+        let path = {
+            let mut inner = self
+                .inner
+                .lock()
+                .map_err(|_| format!("failed to read `{handle}`"))?;
+            match inner.files.get(handle) {
+                Some(stored) => {
+                    let path = stored.path.clone();
+                    inner.touch(handle);
+                    path
+                }
+                None if inner.evicted.contains(handle) => {
+                    return Err(format!(
+                        "`{handle}` is no longer available: the oldest outputs are removed once the session's outputs exceed {} MiB. Run the command again if you need its output.",
+                        self.max_bytes / (1024 * 1024)
+                    ));
+                }
+                None => return Err(format!("unknown output handle `{handle}`")),
+            }
+        };
+        let bytes = std::fs::read(&path).map_err(|e| format!("failed to read `{handle}`: {e}"))?;
+        let text = String::from_utf8_lossy(&bytes);
+        let lines: Vec<&str> = text.lines().collect();
+        let total = lines.len();
+
+        let start_line = match query {
+            OutputQuery::Lines { start_line, .. } | OutputQuery::Grep { start_line, .. } => {
+                (*start_line).max(1)
+            }
+        };
+        if start_line > total {
+            return Err(format!(
+                "start_line {start_line} is past the end of `{handle}`, which has {total} lines"
+            ));
+        }
+
+        let mut page = Page::default();
+        match query {
+            OutputQuery::Lines { end_line, .. } => {
+                let end_line = end_line.unwrap_or(total).min(total);
+                for (index, line) in lines.iter().enumerate().take(end_line).skip(start_line - 1) {
+                    if !page.push(index + 1, line) {
+                        return Ok(page.finish(&format!(
+                            "[... stopped at line {} of {total}; continue with start_line {} ...]",
+                            index,
+                            index + 1
+                        )));
+                    }
+                }
+                if page.is_empty() {
+                    return Err(format!(
+                        "end_line {end_line} is before start_line {start_line}"
+                    ));
+                }
+                Ok(page.finish(""))
+            }
+            OutputQuery::Grep { pattern, .. } => {
+                let mut matches = lines
+                    .iter()
+                    .enumerate()
+                    .skip(start_line - 1)
+                    .filter(|(_, line)| pattern.is_match(line));
+                for (index, line) in matches.by_ref() {
+                    if !page.push(index + 1, line) {
+                        let more = matches.count() + 1;
+                        return Ok(page.finish(&format!(
+                            "[... {more} more matching lines; continue with start_line {} ...]",
+                            index + 1
+                        )));
+                    }
+                }
+                if page.is_empty() {
+                    return Ok(format!(
+                        "no lines of `{handle}` from line {start_line} on match `{}`",
+                        pattern.as_str()
+                    ));
+                }
+                Ok(page.finish(""))
+            }
+        }
+    }
+}
+
+/// Numbered lines within the limits of the command output shown to the model.
+#[derive(Default)]
+struct Page {
+    text: String,
+    lines: usize,
+}
+
+impl Page {
+    /// Adds a line, or returns false if it does not fit. The first line always
+    /// fits, cut short if need be, so every read makes progress.
+    fn push(&mut self, number: usize, line: &str) -> bool {
+        let numbered = format!("{number}: {line}\n");
+        if self.lines == 0 && numbered.len() > MODEL_FORMAT_MAX_BYTES {
+            let mut end = MODEL_FORMAT_MAX_BYTES;
+            while !numbered.is_char_boundary(end) {
+                end -= 1;
+            }
+            self.text.push_str(&numbered[..end]);
+            self.text.push('\n');
+            self.lines = 1;
+            return true;
+        }
+        if self.lines == MODEL_FORMAT_MAX_LINES
+            || self.text.len() + numbered.len() > MODEL_FORMAT_MAX_BYTES
+        {
+            return false;
+        }
+        self.text.push_str(&numbered);
+        self.lines += 1;
+        true
+    }
+
+    fn is_empty(&self) -> bool {
+        self.lines == 0
+    }
+
+    fn finish(mut self, note: &str) -> String {
+        self.text.push_str(note);
+        if note.is_empty() {
+            self.text.pop();
+        }
+        self.text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn store_with(output: &str) -> (OutputStore, String) {
+        let store = OutputStore::default();
+        let handle = store.save("call-1", output).unwrap();
+        (store, handle)
+    }
+
+    #[test]
+    fn reads_line_ranges_and_pages_through_long_outputs() {
+        let output = (1..=400)
+            .map(|i| format!("line{i}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (store, handle) = store_with(&output);
+        assert_eq!(handle, "output-1");
+        assert_eq!(store.handle_for_call("call-1"), Some(handle.clone()));
+
+        let range = store
+            .read(
+                &handle,
+                &OutputQuery::Lines {
+                    start_line: 199,
+                    end_line: Some(201),
+                },
+            )
+            .unwrap();
+        assert_eq!(range, "199: line199\n200: line200\n201: line201");
+
+        let page = store
+            .read(
+                &handle,
+                &OutputQuery::Lines {
+                    start_line: 1,
+                    end_line: None,
+                },
+            )
+            .unwrap();
+        assert!(page.starts_with("1: line1\n"));
+        assert!(page.ends_with(&format!(
+            "{MODEL_FORMAT_MAX_LINES}: line{MODEL_FORMAT_MAX_LINES}\n[... stopped at line {MODEL_FORMAT_MAX_LINES} of 400; continue with start_line {} ...]",
+            MODEL_FORMAT_MAX_LINES + 1
+        )));
+    }
+
+    #[test]
+    fn greps_from_start_line() {
+        let (store, handle) = store_with("ok\nerror: one\nok\nerror: two\n");
+
+        let matches = store
+            .read(
+                &handle,
+                &OutputQuery::Grep {
+                    pattern: Regex::new("^error").unwrap(),
+                    start_line: 3,
+                },
+            )
+            .unwrap();
+        assert_eq!(matches, "4: error: two");
+
+        let retried = store.save("call-1", "retried").unwrap();
+        assert_eq!(store.handle_for_call("call-1"), Some(retried));
+        assert_eq!(
+            store.read(
+                "output-9",
+                &OutputQuery::Lines {
+                    start_line: 1,
+                    end_line: None
+                }
+            ),
+            Err("unknown output handle `output-9`".to_string())
+        );
+    }
+
+    #[test]
+    fn evicts_least_recently_used_outputs_beyond_the_cap() {
+        let store = OutputStore::with_max_bytes(3 * 1024 * 1024);
+        let output = "x".repeat(1024 * 1024);
+        let first = store.save("call-1", &output).unwrap();
+        let second = store.save("call-2", &output).unwrap();
+        let third = store.save("call-3", &output).unwrap();
+        let all_lines = OutputQuery::Lines {
+            start_line: 1,
+            end_line: None,
+        };
+        store.read(&first, &all_lines).unwrap();
+
+        let fourth = store.save("call-4", &output).unwrap();
+        assert_eq!(
+            store.read(&second, &all_lines),
+            Err("`output-2` is no longer available: the oldest outputs are removed once the session's outputs exceed 3 MiB. Run the command again if you need its output.".to_string())
+        );
+        assert_eq!(store.handle_for_call("call-2"), None);
+        for handle in [&first, &third, &fourth] {
+            assert!(store.read(handle, &all_lines).is_ok());
+        }
+
+        assert_eq!(store.save("call-5", &"x".repeat(4 * 1024 * 1024)), None);
+        assert!(store.read(&first, &all_lines).is_ok());
+    }
+}
@@ -0,0 +1,433 @@
+//! Disposable overlay file-system for [`SandboxType::LinuxOverlay`].
+//!
+//! Codex creates an empty directory for every command and passes it to the
+//! Linux sandbox helper, which mounts an overlay over each writable root of
+//! the sandbox policy. For the root with index `i` the helper creates:
+//!
+//! - `<dir>/<i>/lower`: a symlink to the root in the real file-system,
+//! - `<dir>/<i>/upper`: the upper directory, which receives every write,
+//! - `<dir>/<i>/work`: the work directory overlayfs needs.
+//!
+//! Once the command has exited, the upper directories describe everything it
+//! changed: new and modified files are copied up whole, deleted entries are
+//! whiteouts (character devices 0/0) and directories that replaced a
+//! directory of the same name are marked opaque. [`OverlayChanges`] turns
+//! them into the same [`FileChange`]s `apply_patch` produces so they can be
+//! reviewed, and copies them into the real tree once approved.
+//!
+//! [`SandboxType::LinuxOverlay`]: crate::exec::SandboxType::LinuxOverlay
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+use similar::TextDiff;
+use tempfile::TempDir;
+
+use crate::protocol::FileChange;
+use crate::spawn::CODEX_SANDBOX_OVERLAY_DIR_ENV_VAR;
+
+/// Entries that are never copied back. The command may well have run git,
+/// but its objects and index are not something to review as a diff.
+const SKIPPED_NAMES: &[&str] = &[".git"];
+
+/// The overlay directory of one command. It is removed when dropped, so the
+/// changes must be applied before that.
+pub(crate) struct OverlaySandbox {
+    dir: TempDir,
+}
+
+impl OverlaySandbox {
+    pub(crate) fn new() -> io::Result<Self> {
+        let dir = tempfile::Builder::new()
+            .prefix("codex-overlay-")
+            .tempdir()?;
+        Ok(Self { dir })
+    }
+
+    /// Tells the sandbox helper where to keep the overlay.
+    pub(crate) fn apply_to_env(&self, env: &mut HashMap<String, String>) {
+        env.insert(
+            CODEX_SANDBOX_OVERLAY_DIR_ENV_VAR.to_string(),
+            self.dir.path().to_string_lossy().to_string(),
+        );
+    }
+
+    /// Reads what the command changed under each overlaid root.
+    pub(crate) fn collect_changes(&self) -> io::Result<OverlayChanges> {
+        let mut changes = OverlayChanges::default();
+        for entry in fs::read_dir(self.dir.path())? {
+            let layer = entry?.path();
+            let root = fs::read_link(layer.join("lower"))?;
+            changes.collect_dir(&layer.join("upper"), &root, false)?;
+        }
+        Ok(changes)
+    }
+}
+
+impl Drop for OverlaySandbox {
+    fn drop(&mut self) {
+        // overlayfs leaves `work/work` without any permissions, which would
+        // stop `TempDir` from removing it.
+        #[cfg(unix)]
+        if let Ok(entries) = fs::read_dir(self.dir.path()) {
+            use std::os::unix::fs::PermissionsExt;
+
+            for entry in entries.flatten() {
+                let work = entry.path().join("work").join("work");
+                let _ = fs::set_permissions(work, fs::Permissions::from_mode(0o700));
+            }
+        }
+    }
+}
+
+/// The changes a command made in the overlay, by their path in the real tree.
+#[derive(Debug, Default)]
+pub(crate) struct OverlayChanges {
+    /// Directories whose original contents the command removed or replaced
+    /// as a whole.
+    cleared_dirs: Vec<PathBuf>,
+    /// Directories the command created, including empty ones, which no file
+    /// change would bring back.
+    created_dirs: Vec<PathBuf>,
+    files: BTreeMap<PathBuf, FileOp>,
+}
+
+#[derive(Debug)]
+enum FileOp {
+    Delete,
+    /// Replace the file with this one from the upper directory.
+    Write(PathBuf),
+}
+
+impl OverlayChanges {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.cleared_dirs.is_empty() && self.created_dirs.is_empty() && self.files.is_empty()
+    }
+
+    /// Walks `upper` and records how it differs from `real`. Inside a cleared
+    /// directory the real tree no longer counts, so every file is new.
+    fn collect_dir(&mut self, upper: &Path, real: &Path, cleared: bool) -> io::Result<()> {
+        for entry in fs::read_dir(upper)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if SKIPPED_NAMES.iter().any(|skipped| name == *skipped) {
+                continue;
+            }
+            let upper_path = entry.path();
+            let real_path = real.join(&name);
+            let file_type = entry.file_type()?;
+            let real_type = if cleared {
+                None
+            } else {
+                fs::symlink_metadata(&real_path)
+                    .ok()
+                    .map(|metadata| metadata.file_type())
+            };
+
+            if is_whiteout(&upper_path)? {
+                match real_type {
+                    Some(real_type) if real_type.is_dir() => self.cleared_dirs.push(real_path),
+                    Some(_) => {
+                        self.files.insert(real_path, FileOp::Delete);
+                    }
+                    None => {}
+                }
+            } else if file_type.is_dir() {
+                let cleared = match real_type {
+                    Some(real_type) if real_type.is_dir() => {
+                        let opaque = is_opaque(&upper_path);
+                        if opaque {
+                            self.cleared_dirs.push(real_path.clone());
+                        }
+                        opaque
+                    }
+                    Some(_) => {
+                        self.files.insert(real_path.clone(), FileOp::Delete);
+                        true
+                    }
+                    None => true,
+                };
+                if cleared {
+                    self.created_dirs.push(real_path.clone());
+                }
+                self.collect_dir(&upper_path, &real_path, cleared)?;
+            } else {
+                match real_type {
+                    Some(real_type) if real_type.is_dir() => {
+                        self.cleared_dirs.push(real_path.clone());
+                    }
+                    // overlayfs also copies a file up when only its metadata
+                    // changes, e.g. on `touch`.
+                    Some(_) if is_unchanged(&upper_path, &real_path)? => continue,
+                    _ => {}
+                }
+                self.files.insert(real_path, FileOp::Write(upper_path));
+            }
+        }
+        Ok(())
+    }
+
+    fn is_cleared(&self, path: &Path) -> bool {
+        self.cleared_dirs.iter().any(|dir| path.starts_with(dir))
+    }
+
+    /// The changes as they are shown for review.
+    pub(crate) fn file_changes(&self) -> io::Result<HashMap<PathBuf, FileChange>> {
+        let mut changes = HashMap::new();
+        for dir in &self.cleared_dirs {
+            if dir.is_dir() {
+                for path in files_under(dir)? {
+                    let content = display_contents(&path)?;
+                    changes.insert(path, FileChange::Delete { content });
+                }
+            }
+        }
+        for (path, op) in &self.files {
+            let before = if self.is_cleared(path) {
+                None
+            } else {
+                match fs::symlink_metadata(path) {
+                    Ok(metadata) if !metadata.is_dir() => Some(display_contents(path)?),
+                    _ => None,
+                }
+            };
+            let change = match (op, before) {
+                (FileOp::Delete, Some(content)) => FileChange::Delete { content },
+                (FileOp::Delete, None) => continue,
+                (FileOp::Write(upper), None) => FileChange::Add {
+                    content: display_contents(upper)?,
+                },
+                (FileOp::Write(upper), Some(before)) => {
+                    let after = display_contents(upper)?;
+                    FileChange::Update {
+                        unified_diff: TextDiff::from_lines(&before, &after)
+                            .unified_diff()
+                            .to_string(),
+                        move_path: None,
+                    }
+                }
+            };
+            changes.insert(path.clone(), change);
+        }
+        Ok(changes)
+    }
+
+    /// Copies the changes into the real tree.
+    pub(crate) fn apply(&self) -> io::Result<()> {
+        for dir in &self.cleared_dirs {
+            ignore_not_found(fs::remove_dir_all(dir))?;
+        }
+        for (path, op) in &self.files {
+            if let FileOp::Delete = op {
+                ignore_not_found(fs::remove_file(path))?;
+            }
+        }
+        for dir in &self.created_dirs {
+            fs::create_dir_all(dir)?;
+        }
+        for (path, op) in &self.files {
+            let FileOp::Write(upper) = op else {
+                continue;
+            };
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            // Replace the file rather than writing to it, so hard links to it
+            // from outside the workspace are left alone.
+            ignore_not_found(fs::remove_file(path))?;
+            copy_entry(upper, path)?;
+        }
+        Ok(())
+    }
+}
+
+fn ignore_not_found(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// Whether `upper` has the same type, permissions and contents as `real`.
+fn is_unchanged(upper: &Path, real: &Path) -> io::Result<bool> {
+    let upper_metadata = fs::symlink_metadata(upper)?;
+    let real_metadata = fs::symlink_metadata(real)?;
+    if upper_metadata.file_type() != real_metadata.file_type()
+        || upper_metadata.permissions() != real_metadata.permissions()
+    {
+        return Ok(false);
+    }
+    if upper_metadata.file_type().is_symlink() {
+        return Ok(fs::read_link(upper)? == fs::read_link(real)?);
+    }
+    Ok(upper_metadata.len() == real_metadata.len() && fs::read(upper)? == fs::read(real)?)
+}
+
+/// All files below `dir`, without following symlinks.
+fn files_under(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            files.extend(files_under(&entry.path())?);
+        } else {
+            files.push(entry.path());
+        }
+    }
+    Ok(files)
+}
+
+/// The contents of a file as shown for review. Symlinks show their target and
+/// binary files a placeholder.
+fn display_contents(path: &Path) -> io::Result<String> {
+    let bytes = if fs::symlink_metadata(path)?.file_type().is_symlink() {
+        fs::read_link(path)?.into_os_string().into_encoded_bytes()
+    } else {
+        fs::read(path)?
+    };
+    Ok(String::from_utf8(bytes)
+        .unwrap_or_else(|e| format!("<binary file, {} bytes>\n", e.as_bytes().len())))
+}
+
+#[cfg(unix)]
+fn copy_entry(from: &Path, to: &Path) -> io::Result<()> {
+    if fs::symlink_metadata(from)?.file_type().is_symlink() {
+        std::os::unix::fs::symlink(fs::read_link(from)?, to)
+    } else {
+        fs::copy(from, to).map(|_| ())
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_entry(from: &Path, to: &Path) -> io::Result<()> {
+    fs::copy(from, to).map(|_| ())
+}
+
+/// overlayfs records a deleted entry as a character device with device
+/// number 0/0.
+#[cfg(unix)]
+fn is_whiteout(path: &Path) -> io::Result<bool> {
+    use std::os::unix::fs::FileTypeExt;
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = fs::symlink_metadata(path)?;
+    Ok(metadata.file_type().is_char_device() && metadata.rdev() == 0)
+}
+
+#[cfg(not(unix))]
+fn is_whiteout(_path: &Path) -> io::Result<bool> {
+    Ok(false)
+}
+
+/// The helper mounts the overlay with `userxattr`, so opaque directories are
+/// marked with `user.overlay.opaque`.
+#[cfg(target_os = "linux")]
+fn is_opaque(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    let mut value = [0u8; 1];
+    let len = unsafe {
+        libc::lgetxattr(
+            path.as_ptr(),
+            c"user.overlay.opaque".as_ptr(),
+            value.as_mut_ptr().cast(),
+            value.len(),
+        )
+    };
+    len == 1 && value[0] == b'y'
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_opaque(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Lays out an overlay for `root` by hand, as the helper would leave it
+    /// after the command ran.
+    fn overlay_for(root: &Path) -> (OverlaySandbox, PathBuf) {
+        let overlay = OverlaySandbox::new().unwrap();
+        let layer = overlay.dir.path().join("0");
+        let upper = layer.join("upper");
+        fs::create_dir_all(&upper).unwrap();
+        fs::create_dir_all(layer.join("work")).unwrap();
+        std::os::unix::fs::symlink(root, layer.join("lower")).unwrap();
+        (overlay, upper)
+    }
+
+    #[test]
+    fn reports_and_applies_added_and_updated_files() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("kept.txt"), "same\n").unwrap();
+        fs::write(root.path().join("edited.txt"), "old\n").unwrap();
+        let (overlay, upper) = overlay_for(root.path());
+        fs::copy(root.path().join("kept.txt"), upper.join("kept.txt")).unwrap();
+        fs::write(upper.join("edited.txt"), "new\n").unwrap();
+        fs::create_dir_all(upper.join("src")).unwrap();
+        fs::write(upper.join("src/added.txt"), "hello\n").unwrap();
+        fs::create_dir_all(upper.join(".git")).unwrap();
+        fs::write(upper.join(".git/index"), "ignored").unwrap();
+
+        let changes = overlay.collect_changes().unwrap();
+        let file_changes = changes.file_changes().unwrap();
+
+        assert_eq!(file_changes.len(), 2);
+        let Some(FileChange::Update { unified_diff, .. }) =
+            file_changes.get(&root.path().join("edited.txt"))
+        else {
+            panic!("expected an update of edited.txt, got {file_changes:?}");
+        };
+        assert_eq!(unified_diff, "@@ -1 +1 @@\n-old\n+new\n");
+        let Some(FileChange::Add { content }) =
+            file_changes.get(&root.path().join("src/added.txt"))
+        else {
+            panic!("expected src/added.txt to be added, got {file_changes:?}");
+        };
+        assert_eq!(content, "hello\n");
+
+        changes.apply().unwrap();
+        assert_eq!(
+            fs::read_to_string(root.path().join("edited.txt")).unwrap(),
+            "new\n"
+        );
+        assert_eq!(
+            fs::read_to_string(root.path().join("src/added.txt")).unwrap(),
+            "hello\n"
+        );
+        assert!(!root.path().join(".git").exists());
+    }
+
+    #[test]
+    fn applies_new_empty_directories() {
+        let root = tempfile::tempdir().unwrap();
+        let (overlay, upper) = overlay_for(root.path());
+        fs::create_dir_all(upper.join("build/cache")).unwrap();
+
+        let changes = overlay.collect_changes().unwrap();
+        assert!(!changes.is_empty());
+        assert!(changes.file_changes().unwrap().is_empty());
+
+        changes.apply().unwrap();
+        assert!(root.path().join("build/cache").is_dir());
+    }
+
+    #[test]
+    fn no_changes_when_upper_is_empty() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("file.txt"), "contents\n").unwrap();
+        let (overlay, _upper) = overlay_for(root.path());
+
+        assert!(overlay.collect_changes().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,482 @@
+//! Health of model providers across requests.
+//!
+//! The retry loops of the wire API clients only see a single request. A
+//! [`ProviderHealth`] is shared by every request to the same provider from
+//! the conversations of one [`ProviderHealthRegistry`], which is owned by the
+//! `ConversationManager`: it records latency, failures and `Retry-After` hints, opens a
+//! circuit after [`FAILURE_THRESHOLD`] consecutive failures so that requests
+//! fail fast instead of exhausting `request_max_retries`, and delays requests
+//! while the rate limits reported by the provider are nearly used up.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::time::Duration;
+use std::time::Instant;
+
+use codex_protocol::protocol::RateLimitSnapshot;
+use reqwest::StatusCode;
+
+use crate::ModelProviderInfo;
+use crate::error::CodexErr;
+use crate::error::ProviderUnavailableError;
+use crate::error::Result;
+use crate::util::backoff;
+
+/// Consecutive failed requests after which the circuit opens.
+const FAILURE_THRESHOLD: u32 = 5;
+
+/// How long the circuit stays open the first time it trips. Each time it
+/// trips again without a successful request in between, the duration
+/// doubles up to [`MAX_OPEN_DURATION`].
+const OPEN_DURATION: Duration = Duration::from_secs(30);
+const MAX_OPEN_DURATION: Duration = Duration::from_secs(300);
+
+/// Requests are delayed once a rate limit window is this full.
+const RATE_LIMIT_THROTTLE_PERCENT: f64 = 95.0;
+
+/// Delay before a request when a rate limit window is completely used up.
+/// The delay grows linearly from zero at [`RATE_LIMIT_THROTTLE_PERCENT`].
+const MAX_RATE_LIMIT_DELAY: Duration = Duration::from_secs(30);
+
+/// Weight of the latest request in the moving average latency.
+const LATENCY_SMOOTHING: f64 = 0.2;
+
+/// How long a half-open probe may go without an outcome before another
+/// request may probe instead, e.g. when the probe was cancelled.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The health of the providers used by the conversations of one
+/// `ConversationManager`, keyed by provider id and base URL.
+#[derive(Debug, Default)]
+pub struct ProviderHealthRegistry {
+    providers: Mutex<HashMap<(String, Option<String>), Arc<ProviderHealth>>>,
+}
+
+impl ProviderHealthRegistry {
+    /// The health tracker shared by all requests to the provider registered
+    /// as `provider_id`.
+    pub(crate) fn for_provider(
+        &self,
+        provider_id: &str,
+        provider: &ModelProviderInfo,
+    ) -> Arc<ProviderHealth> {
+        let key = (provider_id.to_string(), provider.base_url.clone());
+        let mut providers = self
+            .providers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(
+            providers
+                .entry(key)
+                .or_insert_with(|| Arc::new(ProviderHealth::new(provider.name.clone()))),
+        )
+    }
+
+    /// The health of the provider registered as `provider_id`, if any request
+    /// has been sent to it.
+    pub fn snapshot(
+        &self,
+        provider_id: &str,
+        provider: &ModelProviderInfo,
+    ) -> Option<ProviderHealthSnapshot> {
+        let key = (provider_id.to_string(), provider.base_url.clone());
+        let providers = self
+            .providers
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        providers
+            .get(&key)
+            .map(|health| health.snapshot())
+            .filter(|snapshot| snapshot.requests > 0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent normally.
+    Closed,
+    /// Requests fail fast until the circuit half-opens.
+    Open,
+    /// A single request probes whether the provider has recovered; the
+    /// others fail fast until it has an outcome.
+    HalfOpen,
+}
+
+/// Point-in-time view of a provider's health.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderHealthSnapshot {
+    pub state: CircuitState,
+    pub requests: u64,
+    pub failures: u64,
+    pub consecutive_failures: u32,
+    /// Moving average of the time until the response headers arrived.
+    pub average_latency: Option<Duration>,
+    /// Time until an open circuit half-opens or, when closed, until requests
+    /// are no longer delayed by a `Retry-After` hint or the rate limits.
+    pub retry_in: Option<Duration>,
+}
+
+impl ProviderHealthSnapshot {
+    /// Fraction of requests that failed, between 0 and 1.
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.failures as f64 / self.requests as f64
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct ProviderHealth {
+    name: String,
+    state: Mutex<HealthState>,
+}
+
+#[derive(Debug, Default)]
+struct HealthState {
+    requests: u64,
+    failures: u64,
+    consecutive_failures: u32,
+    average_latency: Option<Duration>,
+    /// Set while the circuit is open.
+    open_until: Option<Instant>,
+    /// Times the circuit tripped since the last successful request.
+    trips: u32,
+    /// When the request probing a half-open circuit was sent, until it
+    /// succeeds or fails.
+    probe_started: Option<Instant>,
+    /// Requests are delayed until this instant.
+    not_before: Option<Instant>,
+    /// State changes not yet reported to the user.
+    notices: Vec<String>,
+}
+
+impl HealthState {
+    fn circuit_state(&self, now: Instant) -> CircuitState {
+        match self.open_until {
+            Some(open_until) if now < open_until => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+            None => CircuitState::Closed,
+        }
+    }
+}
+
+impl ProviderHealth {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            state: Mutex::new(HealthState::default()),
+        }
+    }
+
+    /// Waits until a request may be sent to the provider. Fails fast with
+    /// [`CodexErr::ProviderUnavailable`] while the circuit is open, or while
+    /// another request probes the half-open circuit.
+    pub(crate) async fn before_request(&self) -> Result<()> {
+        let delay = self.request_delay(Instant::now())?;
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+        Ok(())
+    }
+
+    fn request_delay(&self, now: Instant) -> Result<Option<Duration>> {
+        let mut state = self.lock();
+        let retry_in = match state.circuit_state(now) {
+            CircuitState::Closed => None,
+            CircuitState::Open => state.open_until.map(|open_until| open_until - now),
+            CircuitState::HalfOpen => match state.probe_started {
+                Some(probe_started) if now < probe_started + PROBE_TIMEOUT => {
+                    Some(probe_started + PROBE_TIMEOUT - now)
+                }
+                _ => {
+                    state.probe_started = Some(now);
+                    None
+                }
+            },
+        };
+        if let Some(retry_in) = retry_in {
+            return Err(CodexErr::ProviderUnavailable(ProviderUnavailableError {
+                provider: self.name.clone(),
+                consecutive_failures: state.consecutive_failures,
+                retry_in,
+            }));
+        }
+        Ok(state
+            .not_before
+            .and_then(|not_before| not_before.checked_duration_since(now)))
+    }
+
+    /// Records a response with a successful status.
+    pub(crate) fn record_success(&self, latency: Duration) {
+        let mut state = self.lock();
+        state.requests += 1;
+        state.consecutive_failures = 0;
+        state.trips = 0;
+        state.probe_started = None;
+        state.average_latency = Some(match state.average_latency {
+            Some(average) => {
+                average.mul_f64(1.0 - LATENCY_SMOOTHING) + latency.mul_f64(LATENCY_SMOOTHING)
+            }
+            None => latency,
+        });
+        if state.open_until.take().is_some() {
+            let notice = format!("{} recovered; sending requests again", self.name);
+            state.notices.push(notice);
+        }
+    }
+
+    /// Records a response with a client error status other than 429. The
+    /// provider answered, so the circuit closes as after a success, but the
+    /// request counts towards the error rate.
+    pub(crate) fn record_rejected(&self, latency: Duration) {
+        self.record_success(latency);
+        self.lock().failures += 1;
+    }
+
+    /// Records a request that failed with a transport error, a rate limit or
+    /// a server error, with the provider's `Retry-After` hint if any.
+    pub(crate) fn record_failure(&self, retry_after: Option<Duration>) {
+        self.record_failure_at(Instant::now(), retry_after);
+    }
+
+    fn record_failure_at(&self, now: Instant, retry_after: Option<Duration>) {
+        let mut state = self.lock();
+        state.requests += 1;
+        state.failures += 1;
+        state.consecutive_failures += 1;
+        if let Some(retry_after) = retry_after {
+            state.not_before = state.not_before.max(Some(now + retry_after));
+        }
+
+        let circuit_state = state.circuit_state(now);
+        if circuit_state == CircuitState::Open {
+            return;
+        }
+        let probe_failed = circuit_state == CircuitState::HalfOpen;
+        if !probe_failed && state.consecutive_failures < FAILURE_THRESHOLD {
+            return;
+        }
+        let open_for = OPEN_DURATION
+            .saturating_mul(2u32.saturating_pow(state.trips))
+            .min(MAX_OPEN_DURATION)
+            .max(retry_after.unwrap_or_default());
+        state.trips += 1;
+        state.open_until = Some(now + open_for);
+        state.probe_started = None;
+        let notice = format!(
+            "{} failed {} requests in a row; pausing requests for {}s",
+            self.name,
+            state.consecutive_failures,
+            open_for.as_secs()
+        );
+        state.notices.push(notice);
+    }
+
+    /// Delays the next requests when a rate limit window is nearly used up.
+    pub(crate) fn record_rate_limits(&self, snapshot: &RateLimitSnapshot) {
+        let used_percent = snapshot
+            .primary_used_percent
+            .max(snapshot.secondary_used_percent);
+        let Some(delay) = rate_limit_delay(used_percent) else {
+            return;
+        };
+        let mut state = self.lock();
+        let now = Instant::now();
+        state.not_before = state.not_before.max(Some(now + delay));
+    }
+
+    pub fn snapshot(&self) -> ProviderHealthSnapshot {
+        let now = Instant::now();
+        let state = self.lock();
+        let retry_in = state
+            .open_until
+            .or(state.not_before)
+            .and_then(|until| until.checked_duration_since(now))
+            .filter(|retry_in| !retry_in.is_zero());
+        ProviderHealthSnapshot {
+            state: state.circuit_state(now),
+            requests: state.requests,
+            failures: state.failures,
+            consecutive_failures: state.consecutive_failures,
+            average_latency: state.average_latency,
+            retry_in,
+        }
+    }
+
+    /// Returns the circuit state changes since the last call.
+    pub(crate) fn take_notices(&self) -> Vec<String> {
+        std::mem::take(&mut self.lock().notices)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HealthState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Sends the request built by `build_request` to `provider`, retrying rate
+/// limits, server errors and transport errors up to `request_max_retries`
+/// times, and records the outcome of every attempt in `health`. Returns the
+/// first response with a successful status.
+pub(crate) async fn send_with_retries(
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    health: &ProviderHealth,
+    build_request: impl Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+) -> Result<reqwest::Response> {
+    let mut attempt = 0;
+    let max_retries = provider.request_max_retries();
+    loop {
+        attempt += 1;
+        health.before_request().await?;
+
+        let req_builder = build_request(provider.create_request_builder(client, &None).await?);
+
+        let request_start = Instant::now();
+        match req_builder.send().await {
+            Ok(resp) if resp.status().is_success() => {
+                health.record_success(request_start.elapsed());
+                return Ok(resp);
+            }
+            Ok(res) => {
+                let status = res.status();
+                // Overloaded errors (e.g. Anthropic's 529) are server errors and
+                // retried too.
+                if !(status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()) {
+                    health.record_rejected(request_start.elapsed());
+                    let body = res.text().await.unwrap_or_default();
+                    return Err(CodexErr::UnexpectedStatus(status, body));
+                }
+
+                let retry_after = res
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .map(Duration::from_secs);
+                health.record_failure(retry_after);
+
+                if attempt > max_retries {
+                    return Err(CodexErr::RetryLimit(status));
+                }
+
+                let delay = retry_after.unwrap_or_else(|| backoff(attempt));
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => {
+                health.record_failure(None);
+                if attempt > max_retries {
+                    return Err(e.into());
+                }
+                let delay = backoff(attempt);
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+fn rate_limit_delay(used_percent: f64) -> Option<Duration> {
+    if used_percent < RATE_LIMIT_THROTTLE_PERCENT {
+        return None;
+    }
+    let fraction = ((used_percent - RATE_LIMIT_THROTTLE_PERCENT)
+        / (100.0 - RATE_LIMIT_THROTTLE_PERCENT))
+        .clamp(0.0, 1.0);
+    Some(MAX_RATE_LIMIT_DELAY.mul_f64(fraction))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn circuit_opens_after_consecutive_failures_and_recovers() {
+        let health = ProviderHealth::new("Example".to_string());
+        let start = Instant::now();
+        for _ in 0..FAILURE_THRESHOLD - 1 {
+            health.record_failure_at(start, None);
+        }
+        assert!(matches!(health.request_delay(start), Ok(None)));
+
+        health.record_failure_at(start, None);
+        let err = health
+            .request_delay(start)
+            .expect_err("circuit should be open");
+        assert_eq!(
+            err.to_string(),
+            "Example failed 5 requests in a row; not sending requests for 30s"
+        );
+
+        // Once the circuit half-opens, a single request probes it and a
+        // failed probe reopens it for longer.
+        let half_open = start + OPEN_DURATION;
+        assert!(matches!(health.request_delay(half_open), Ok(None)));
+        let err = health
+            .request_delay(half_open)
+            .expect_err("only one request should probe");
+        assert!(err.to_string().ends_with("for 60s"), "{err}");
+        health.record_failure_at(half_open, None);
+        let err = health
+            .request_delay(half_open)
+            .expect_err("circuit should reopen");
+        assert!(err.to_string().ends_with("for 60s"), "{err}");
+
+        health.record_success(Duration::from_millis(200));
+        assert_eq!(health.snapshot().state, CircuitState::Closed);
+        assert_eq!(health.snapshot().error_rate(), 6.0 / 7.0);
+        assert_eq!(
+            health.take_notices(),
+            vec![
+                "Example failed 5 requests in a row; pausing requests for 30s",
+                "Example failed 6 requests in a row; pausing requests for 60s",
+                "Example recovered; sending requests again",
+            ]
+        );
+    }
+
+    #[test]
+    fn retry_after_delays_the_next_request() {
+        let health = ProviderHealth::new("Example".to_string());
+        let start = Instant::now();
+        health.record_failure_at(start, Some(Duration::from_secs(7)));
+        assert!(matches!(
+            health.request_delay(start + Duration::from_secs(2)),
+            Ok(Some(delay)) if delay == Duration::from_secs(5)
+        ));
+        assert!(matches!(
+            health.request_delay(start + Duration::from_secs(8)),
+            Ok(None)
+        ));
+    }
+
+    #[test]
+    fn rejected_probe_closes_the_circuit() {
+        let health = ProviderHealth::new("Example".to_string());
+        let start = Instant::now();
+        for _ in 0..FAILURE_THRESHOLD {
+            health.record_failure_at(start, None);
+        }
+        assert!(matches!(
+            health.request_delay(start + OPEN_DURATION),
+            Ok(None)
+        ));
+
+        health.record_rejected(Duration::from_millis(200));
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.state, CircuitState::Closed);
+        assert_eq!(snapshot.failures, 6);
+        assert_eq!(snapshot.consecutive_failures, 0);
+    }
+
+    #[test]
+    fn nearly_exhausted_rate_limits_delay_requests() {
+        assert_eq!(rate_limit_delay(80.0), None);
+        assert_eq!(rate_limit_delay(95.0), Some(Duration::ZERO));
+        assert_eq!(rate_limit_delay(97.5), Some(Duration::from_secs(15)));
+        assert_eq!(rate_limit_delay(120.0), Some(MAX_RATE_LIMIT_DELAY));
+    }
+}
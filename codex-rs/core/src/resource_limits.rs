@@ -0,0 +1,542 @@
+//! Resource limits for the commands run by the agent, configured in the
+//! `[resource_limits]` table of `config.toml`.
+//!
+//! The limits are applied with `setrlimit(2)` in the child right before it
+//! execs, so they are inherited by the sandbox wrappers (`sandbox-exec`,
+//! `codex-linux-sandbox`) and by everything the command starts. On Linux the
+//! command can also be moved into its own cgroup v2 sub-tree, which bounds the
+//! memory and process count of the whole process tree instead of each process.
+//! The process count is only limited that way: `RLIMIT_NPROC` counts every
+//! process of the user, so it would fail commands because of processes that
+//! have nothing to do with them.
+
+use std::fmt;
+
+use tokio::process::Command;
+
+use crate::config_types::ResourceLimits;
+use crate::error::CodexErr;
+use crate::error::Result;
+use crate::error::SandboxErr;
+use crate::exec::ExecToolCallOutput;
+
+const BYTES_PER_MB: u64 = 1024 * 1024;
+
+/// Messages printed by common runtimes when an allocation fails.
+const OUT_OF_MEMORY_MESSAGES: &[&str] = &[
+    "Cannot allocate memory",
+    "out of memory",
+    "memory allocation of",
+    "MemoryError",
+    "std::bad_alloc",
+];
+
+/// `strerror(EMFILE)`.
+const TOO_MANY_OPEN_FILES_MESSAGE: &str = "Too many open files";
+
+/// A limit from [`ResourceLimits`] that a command ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceLimit {
+    CpuTime { seconds: u64 },
+    Memory { megabytes: u64 },
+    Processes { max: u64 },
+    OpenFiles { max: u64 },
+    FileSize { megabytes: u64 },
+    Output { megabytes: u64 },
+}
+
+impl ResourceLimit {
+    /// The key in the `[resource_limits]` table that sets this limit.
+    pub fn config_key(&self) -> &'static str {
+        match self {
+            ResourceLimit::CpuTime { .. } => "cpu_time_secs",
+            ResourceLimit::Memory { .. } => "memory_mb",
+            ResourceLimit::Processes { .. } => "max_processes",
+            ResourceLimit::OpenFiles { .. } => "max_open_files",
+            ResourceLimit::FileSize { .. } => "max_file_size_mb",
+            ResourceLimit::Output { .. } => "max_output_mb",
+        }
+    }
+
+    /// Tells the model why the command failed and how it can stay within the
+    /// limit.
+    pub fn explanation(&self) -> String {
+        let hint = match self {
+            ResourceLimit::CpuTime { .. } => {
+                "Do less work per command, e.g. run a subset of the tests."
+            }
+            ResourceLimit::Memory { .. } | ResourceLimit::Processes { .. } => {
+                "Use fewer parallel jobs, e.g. `cargo test -j 2` or `make -j 2`."
+            }
+            ResourceLimit::OpenFiles { .. } => "Keep fewer files open at the same time.",
+            ResourceLimit::FileSize { .. } => "Write smaller files.",
+            ResourceLimit::Output { .. } => {
+                "Print less, e.g. filter the output with `grep`, `head` or `tail`."
+            }
+        };
+        format!(
+            "The command was stopped because it exceeded the {self} set by `resource_limits.{}`. {hint} If the limit is too low for the task, ask the user to raise it.",
+            self.config_key()
+        )
+    }
+}
+
+impl fmt::Display for ResourceLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceLimit::CpuTime { seconds } => write!(f, "CPU time limit of {seconds}s"),
+            ResourceLimit::Memory { megabytes } => write!(f, "memory limit of {megabytes} MB"),
+            ResourceLimit::Processes { max } => write!(f, "limit of {max} processes"),
+            ResourceLimit::OpenFiles { max } => write!(f, "limit of {max} open files"),
+            ResourceLimit::FileSize { megabytes } => {
+                write!(f, "file size limit of {megabytes} MB")
+            }
+            ResourceLimit::Output { megabytes } => write!(f, "output limit of {megabytes} MB"),
+        }
+    }
+}
+
+/// Checks that `limits` can be enforced on this machine: `max_processes`
+/// requires a cgroup v2 sub-tree delegated to the user with the `pids`
+/// controller enabled.
+pub fn check_resource_limits(limits: &ResourceLimits) -> std::io::Result<()> {
+    if limits.max_processes.is_none() {
+        return Ok(());
+    }
+    #[cfg(target_os = "linux")]
+    let result = cgroup::ExecCgroup::try_create(limits).map(drop);
+    #[cfg(not(target_os = "linux"))]
+    let result: std::io::Result<()> =
+        Err(std::io::Error::other("cgroups are only available on Linux"));
+    result.map_err(|e| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "`resource_limits.max_processes` requires a delegated cgroup v2 with the pids controller: {e}"
+            ),
+        )
+    })
+}
+
+/// Applies [`ResourceLimits`] to a single command and works out afterwards
+/// whether the command ran into one of them.
+#[derive(Debug, Default)]
+pub struct ResourceLimiter {
+    limits: ResourceLimits,
+    #[cfg(target_os = "linux")]
+    cgroup: Option<cgroup::ExecCgroup>,
+}
+
+impl ResourceLimiter {
+    /// Fails when `max_processes` is set but the command's cgroup cannot be
+    /// created, rather than running the command without the limit.
+    pub fn new(limits: &ResourceLimits) -> Result<Self> {
+        #[cfg(target_os = "linux")]
+        let cgroup = if limits.max_processes.is_some() {
+            let cgroup = cgroup::ExecCgroup::try_create(limits)
+                .map_err(|e| CodexErr::Sandbox(SandboxErr::ProcessLimit(e.to_string())))?;
+            Some(cgroup)
+        } else if limits.cgroup {
+            cgroup::ExecCgroup::create(limits)
+        } else {
+            None
+        };
+        #[cfg(not(target_os = "linux"))]
+        if limits.max_processes.is_some() {
+            return Err(CodexErr::Sandbox(SandboxErr::ProcessLimit(
+                "cgroups are only available on Linux".to_string(),
+            )));
+        }
+
+        Ok(Self {
+            limits: *limits,
+            #[cfg(target_os = "linux")]
+            cgroup,
+        })
+    }
+
+    /// Maximum number of bytes the command may write to stdout and stderr
+    /// combined.
+    pub(crate) fn max_output_bytes(&self) -> Option<usize> {
+        self.limits
+            .max_output_mb
+            .map(|mb| usize::try_from(mb.saturating_mul(BYTES_PER_MB)).unwrap_or(usize::MAX))
+    }
+
+    /// Arranges for the limits to be applied in the child process before it
+    /// execs.
+    #[cfg(unix)]
+    pub(crate) fn apply_to_command(&self, cmd: &mut Command) {
+        let rlimits = self.rlimits();
+        #[cfg(target_os = "linux")]
+        let cgroup_procs_fd = self.cgroup.as_ref().map(cgroup::ExecCgroup::procs_fd);
+        #[cfg(not(target_os = "linux"))]
+        let cgroup_procs_fd: Option<std::os::fd::RawFd> = None;
+        if rlimits.is_empty() && cgroup_procs_fd.is_none() {
+            return;
+        }
+
+        unsafe {
+            cmd.pre_exec(move || {
+                // Writing "0" to `cgroup.procs` moves the writing process. If
+                // it fails, the rlimits below still apply.
+                if let Some(fd) = cgroup_procs_fd {
+                    libc::write(fd, b"0".as_ptr().cast(), 1);
+                }
+                for &(resource, soft, hard) in &rlimits {
+                    set_rlimit(resource, soft, hard)?;
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn apply_to_command(&self, _cmd: &mut Command) {}
+
+    /// Works out whether the command ended because of one of the limits.
+    /// `signal` is the signal that terminated the command, if any, and
+    /// `output_limit_exceeded` is set when the command was stopped because
+    /// its output went over `max_output_mb`.
+    pub(crate) fn exceeded_limit(
+        &self,
+        signal: Option<i32>,
+        output_limit_exceeded: bool,
+        output: &ExecToolCallOutput,
+    ) -> Option<ResourceLimit> {
+        let limits = &self.limits;
+        if output_limit_exceeded && let Some(megabytes) = limits.max_output_mb {
+            return Some(ResourceLimit::Output { megabytes });
+        }
+
+        #[cfg(unix)]
+        match (signal, limits.cpu_time_secs, limits.max_file_size_mb) {
+            (Some(libc::SIGXCPU), Some(seconds), _) => {
+                return Some(ResourceLimit::CpuTime { seconds });
+            }
+            (Some(libc::SIGXFSZ), _, Some(megabytes)) => {
+                return Some(ResourceLimit::FileSize { megabytes });
+            }
+            _ => {}
+        }
+        #[cfg(not(unix))]
+        let _ = signal;
+
+        #[cfg(target_os = "linux")]
+        if let Some(cgroup) = &self.cgroup {
+            if let Some(megabytes) = limits.memory_mb
+                && cgroup.oom_killed()
+            {
+                return Some(ResourceLimit::Memory { megabytes });
+            }
+            if let Some(max) = limits.max_processes
+                && output.exit_code != 0
+                && cgroup.hit_pids_max()
+            {
+                return Some(ResourceLimit::Processes { max });
+            }
+        }
+
+        // Without a cgroup, running out of memory or file descriptors only
+        // shows up as failing system calls, so look for the messages
+        // programs print when that happens.
+        if output.exit_code == 0 {
+            return None;
+        }
+        let text = &output.aggregated_output.text;
+        if let Some(megabytes) = limits.memory_mb
+            && OUT_OF_MEMORY_MESSAGES
+                .iter()
+                .any(|message| text.contains(message))
+        {
+            return Some(ResourceLimit::Memory { megabytes });
+        }
+        if let Some(max) = limits.max_open_files
+            && text.contains(TOO_MANY_OPEN_FILES_MESSAGE)
+        {
+            return Some(ResourceLimit::OpenFiles { max });
+        }
+        None
+    }
+
+    /// The `(resource, soft, hard)` triples to pass to `setrlimit(2)`.
+    #[cfg(unix)]
+    fn rlimits(&self) -> Vec<(RlimitResource, u64, u64)> {
+        let limits = &self.limits;
+        let mut rlimits = Vec::new();
+        if let Some(seconds) = limits.cpu_time_secs {
+            // The soft limit sends SIGXCPU, the hard limit SIGKILL in case
+            // the command ignores it.
+            rlimits.push((libc::RLIMIT_CPU, seconds, seconds.saturating_add(1)));
+        }
+        if let Some(mb) = limits.memory_mb {
+            let bytes = mb.saturating_mul(BYTES_PER_MB);
+            rlimits.push((libc::RLIMIT_AS, bytes, bytes));
+        }
+        if let Some(max) = limits.max_open_files {
+            rlimits.push((libc::RLIMIT_NOFILE, max, max));
+        }
+        if let Some(mb) = limits.max_file_size_mb {
+            let bytes = mb.saturating_mul(BYTES_PER_MB);
+            rlimits.push((libc::RLIMIT_FSIZE, bytes, bytes));
+        }
+        rlimits
+    }
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu"))]
+type RlimitResource = libc::__rlimit_resource_t;
+#[cfg(all(unix, not(all(target_os = "linux", target_env = "gnu"))))]
+type RlimitResource = libc::c_int;
+
+/// Lowers `resource` to `soft`/`hard`. Only called between `fork` and `exec`,
+/// so it must not allocate.
+#[cfg(unix)]
+fn set_rlimit(resource: RlimitResource, soft: u64, hard: u64) -> std::io::Result<()> {
+    let mut current = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    if unsafe { libc::getrlimit(resource, &mut current) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    // An unprivileged process cannot raise its hard limit, so a configured
+    // limit above it is clamped.
+    let hard = hard.min(current.rlim_max);
+    let limit = libc::rlimit {
+        rlim_cur: soft.min(hard),
+        rlim_max: hard,
+    };
+    if unsafe { libc::setrlimit(resource, &limit) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+mod cgroup {
+    use std::fs;
+    use std::fs::File;
+    use std::io;
+    use std::os::fd::AsRawFd;
+    use std::os::fd::RawFd;
+    use std::path::Path;
+    use std::path::PathBuf;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::Ordering;
+
+    use tracing::debug;
+
+    use super::BYTES_PER_MB;
+    use crate::config_types::ResourceLimits;
+
+    const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+    static NEXT_CGROUP_ID: AtomicU64 = AtomicU64::new(0);
+
+    /// A cgroup v2 sub-tree created for a single command below the cgroup of
+    /// the Codex process. Removed on drop.
+    #[derive(Debug)]
+    pub(super) struct ExecCgroup {
+        path: PathBuf,
+        procs: File,
+    }
+
+    impl ExecCgroup {
+        /// Returns `None` when cgroup v2 is not mounted, the cgroup of the
+        /// Codex process is not delegated to the user, or the memory
+        /// controller is not enabled for its children. Only the per-process
+        /// limits apply then.
+        pub(super) fn create(limits: &ResourceLimits) -> Option<Self> {
+            match Self::try_create(limits) {
+                Ok(cgroup) => Some(cgroup),
+                Err(e) => {
+                    debug!("not using a cgroup for resource limits: {e}");
+                    None
+                }
+            }
+        }
+
+        pub(super) fn try_create(limits: &ResourceLimits) -> io::Result<Self> {
+            let own_cgroup = fs::read_to_string("/proc/self/cgroup")?;
+            let relative = own_cgroup
+                .lines()
+                .find_map(|line| line.strip_prefix("0::"))
+                .ok_or_else(|| io::Error::other("cgroup v2 is not in use"))?;
+            let path = Path::new(CGROUP_ROOT)
+                .join(relative.trim_start_matches('/'))
+                .join(format!(
+                    "codex-exec-{}-{}",
+                    std::process::id(),
+                    NEXT_CGROUP_ID.fetch_add(1, Ordering::Relaxed)
+                ));
+            fs::create_dir(&path)?;
+            let procs = match File::options().write(true).open(path.join("cgroup.procs")) {
+                Ok(procs) => procs,
+                Err(e) => {
+                    let _ = fs::remove_dir(&path);
+                    return Err(e);
+                }
+            };
+            let cgroup = Self { path, procs };
+
+            if let Some(mb) = limits.memory_mb {
+                cgroup.write("memory.max", &mb.saturating_mul(BYTES_PER_MB).to_string())?;
+            }
+            if let Some(max) = limits.max_processes {
+                cgroup.write("pids.max", &max.to_string())?;
+            }
+            Ok(cgroup)
+        }
+
+        /// File descriptor of `cgroup.procs`, for the child to join the
+        /// cgroup before it execs.
+        pub(super) fn procs_fd(&self) -> RawFd {
+            self.procs.as_raw_fd()
+        }
+
+        pub(super) fn oom_killed(&self) -> bool {
+            self.event_count("memory.events", "oom_kill") > 0
+        }
+
+        pub(super) fn hit_pids_max(&self) -> bool {
+            self.event_count("pids.events", "max") > 0
+        }
+
+        fn write(&self, file: &str, value: &str) -> io::Result<()> {
+            fs::write(self.path.join(file), value)
+        }
+
+        /// Reads the counter for `key` from one of the `*.events` files.
+        fn event_count(&self, file: &str, key: &str) -> u64 {
+            fs::read_to_string(self.path.join(file))
+                .ok()
+                .and_then(|events| {
+                    events.lines().find_map(|line| {
+                        let (name, count) = line.split_once(' ')?;
+                        if name == key {
+                            count.trim().parse().ok()
+                        } else {
+                            None
+                        }
+                    })
+                })
+                .unwrap_or(0)
+        }
+    }
+
+    impl Drop for ExecCgroup {
+        fn drop(&mut self) {
+            // Fails while processes the command left behind are still
+            // running; the kernel then keeps the empty cgroup until logout.
+            let _ = fs::remove_dir(&self.path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::StreamOutput;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+
+    fn failed_output(text: &str) -> ExecToolCallOutput {
+        ExecToolCallOutput {
+            exit_code: 1,
+            stdout: StreamOutput::new(String::new()),
+            stderr: StreamOutput::new(text.to_string()),
+            aggregated_output: StreamOutput::new(text.to_string()),
+            duration: Duration::from_secs(1),
+            timed_out: false,
+        }
+    }
+
+    fn limiter(limits: ResourceLimits) -> ResourceLimiter {
+        ResourceLimiter {
+            limits,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn output_limit_takes_precedence() {
+        let limiter = limiter(ResourceLimits {
+            max_output_mb: Some(4),
+            memory_mb: Some(512),
+            ..Default::default()
+        });
+        assert_eq!(
+            limiter.exceeded_limit(None, true, &failed_output("out of memory")),
+            Some(ResourceLimit::Output { megabytes: 4 })
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn signals_are_mapped_to_configured_limits() {
+        let limiter = limiter(ResourceLimits {
+            cpu_time_secs: Some(30),
+            max_file_size_mb: Some(100),
+            ..Default::default()
+        });
+        let output = failed_output("");
+        assert_eq!(
+            limiter.exceeded_limit(Some(libc::SIGXCPU), false, &output),
+            Some(ResourceLimit::CpuTime { seconds: 30 })
+        );
+        assert_eq!(
+            limiter.exceeded_limit(Some(libc::SIGXFSZ), false, &output),
+            Some(ResourceLimit::FileSize { megabytes: 100 })
+        );
+        assert_eq!(
+            limiter.exceeded_limit(Some(libc::SIGSEGV), false, &output),
+            None
+        );
+    }
+
+    #[test]
+    fn error_messages_are_only_attributed_to_configured_limits() {
+        let unlimited = limiter(ResourceLimits::default());
+        let limited = limiter(ResourceLimits {
+            memory_mb: Some(512),
+            max_processes: Some(64),
+            max_open_files: Some(256),
+            ..Default::default()
+        });
+
+        let cases = [
+            (
+                "memory allocation of 1073741824 bytes failed",
+                ResourceLimit::Memory { megabytes: 512 },
+            ),
+            (
+                "error: Too many open files (os error 24)",
+                ResourceLimit::OpenFiles { max: 256 },
+            ),
+        ];
+        for (message, expected) in cases {
+            let output = failed_output(message);
+            assert_eq!(limited.exceeded_limit(None, false, &output), Some(expected));
+            assert_eq!(unlimited.exceeded_limit(None, false, &output), None);
+        }
+
+        // The process limit is only enforced with a cgroup, so a failed fork
+        // is not blamed on it.
+        let output = failed_output("bash: fork: retry: Resource temporarily unavailable");
+        assert_eq!(limited.exceeded_limit(None, false, &output), None);
+
+        // A successful command is never reported, whatever it printed.
+        let mut output = failed_output("out of memory");
+        output.exit_code = 0;
+        assert_eq!(limited.exceeded_limit(None, false, &output), None);
+    }
+
+    #[test]
+    fn explanation_names_the_config_key() {
+        assert_eq!(
+            ResourceLimit::Memory { megabytes: 2048 }.explanation(),
+            "The command was stopped because it exceeded the memory limit of 2048 MB set by `resource_limits.memory_mb`. Use fewer parallel jobs, e.g. `cargo test -j 2` or `make -j 2`. If the limit is too low for the task, ask the user to raise it."
+        );
+    }
+}
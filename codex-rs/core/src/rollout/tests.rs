@@ -12,11 +12,17 @@ use time::format_description::FormatItem;
 use time::macros::format_description;
 use uuid::Uuid;
 
+use codex_protocol::protocol::CompactedItem;
+use codex_protocol::protocol::InitialHistory;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+
 use crate::rollout::list::ConversationItem;
 use crate::rollout::list::ConversationsPage;
 use crate::rollout::list::Cursor;
 use crate::rollout::list::get_conversation;
 use crate::rollout::list::get_conversations;
+use crate::rollout::recorder::RolloutRecorder;
 
 fn write_session_file(
     root: &Path,
@@ -443,3 +449,61 @@ async fn test_stable_ordering_same_second_pagination() {
     };
     assert_eq!(page2, expected_page2);
 }
+
+#[tokio::test]
+async fn compacted_items_round_trip_through_a_rollout_file() {
+    let temp = TempDir::new().unwrap();
+    let home = temp.path();
+
+    let uuid = Uuid::new_v4();
+    let ts = "2025-05-01T09-00-00";
+    write_session_file(home, ts, uuid, 0).unwrap();
+    let path = home
+        .join("sessions")
+        .join("2025")
+        .join("05")
+        .join("01")
+        .join(format!("rollout-{ts}-{uuid}.jsonl"));
+
+    let compacted = vec![
+        CompactedItem::ElideToolOutputs {
+            keep_recent_turns: 2,
+        },
+        CompactedItem::Summary {
+            message: "summary of the earlier turns".to_string(),
+        },
+    ];
+    let mut file = fs::OpenOptions::new().append(true).open(&path).unwrap();
+    for item in &compacted {
+        let line = RolloutLine {
+            timestamp: ts.to_string(),
+            item: RolloutItem::Compacted(item.clone()),
+        };
+        writeln!(file, "{}", serde_json::to_string(&line).unwrap()).unwrap();
+    }
+    drop(file);
+
+    // The summary keeps the untagged shape written by older versions.
+    let text = fs::read_to_string(&path).unwrap();
+    let summary_line: serde_json::Value =
+        serde_json::from_str(text.lines().last().unwrap()).unwrap();
+    assert_eq!(
+        summary_line["payload"],
+        serde_json::json!({"message": "summary of the earlier turns"})
+    );
+
+    let InitialHistory::Resumed(resumed) =
+        RolloutRecorder::get_rollout_history(&path).await.unwrap()
+    else {
+        panic!("expected a resumed history");
+    };
+    let read_back: Vec<CompactedItem> = resumed
+        .history
+        .into_iter()
+        .filter_map(|item| match item {
+            RolloutItem::Compacted(item) => Some(item),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(read_back, compacted);
+}
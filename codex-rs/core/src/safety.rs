@@ -253,6 +253,7 @@ fn is_write_patch_constrained_to_writable_paths(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::protocol::NetworkAccess;
     use tempfile::TempDir;
 
     #[test]
@@ -273,9 +274,11 @@ mod tests {
         // only `cwd` is writable by default.
         let policy_workspace_only = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
-            network_access: false,
+            network_access: NetworkAccess::Disabled,
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
+            readable_roots: None,
+            allowed_domains: vec![],
         };
 
         assert!(is_write_patch_constrained_to_writable_paths(
@@ -294,9 +297,11 @@ mod tests {
         // outside write should be permitted.
         let policy_with_parent = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![parent],
-            network_access: false,
+            network_access: NetworkAccess::Disabled,
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
+            readable_roots: None,
+            allowed_domains: vec![],
         };
         assert!(is_write_patch_constrained_to_writable_paths(
             &add_outside,
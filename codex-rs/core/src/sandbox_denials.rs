@@ -0,0 +1,238 @@
+//! Operations the Linux sandbox blocked while running a command.
+//!
+//! Codex lets the sandbox helper inherit the write end of a pipe and passes
+//! its descriptor in [`CODEX_SANDBOX_DENIALS_FD_ENV_VAR`]. The helper writes
+//! one JSON line per blocked operation, and once the command has exited Codex
+//! reads them back to tell the user and the model exactly what the command
+//! tried to do, e.g. "tried to write /home/u/.cargo/registry", instead of
+//! guessing from the exit code. Unlike a file, the pipe cannot be reached by
+//! the command, so it cannot make up denials.
+
+use std::fmt;
+use std::io;
+use std::io::PipeReader;
+use std::io::PipeWriter;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::process::Command;
+
+#[cfg(unix)]
+use crate::spawn::CODEX_SANDBOX_DENIALS_FD_ENV_VAR;
+
+/// At most this many denials are reported for one command; a build that
+/// cannot write its cache would otherwise list every file.
+const MAX_REPORTED_DENIALS: usize = 10;
+
+/// The kind of change a blocked file-system operation tried to make.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum FileOperation {
+    Write,
+    CreateDirectory,
+    Remove,
+    Rename,
+    Link,
+}
+
+/// One operation the sandbox blocked.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SandboxDenial {
+    /// A change to a path outside the writable roots.
+    FileSystem {
+        operation: FileOperation,
+        path: PathBuf,
+    },
+    /// A network system call, e.g. `connect`.
+    Network { syscall: String },
+    /// Any other blocked system call, e.g. `ptrace`.
+    Syscall { name: String },
+}
+
+impl SandboxDenial {
+    /// Appends the denial to the log as a single JSON line.
+    pub fn append_to(&self, log: &mut impl Write) -> io::Result<()> {
+        let mut line = serde_json::to_vec(self).map_err(io::Error::other)?;
+        line.push(b'\n');
+        log.write_all(&line)
+    }
+}
+
+impl fmt::Display for SandboxDenial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SandboxDenial::FileSystem { operation, path } => {
+                let verb = match operation {
+                    FileOperation::Write => "write",
+                    FileOperation::CreateDirectory => "create the directory",
+                    FileOperation::Remove => "remove",
+                    FileOperation::Rename => "rename",
+                    FileOperation::Link => "create a link at",
+                };
+                write!(f, "tried to {verb} {}", path.display())
+            }
+            SandboxDenial::Network { syscall } => {
+                write!(f, "tried to access the network (`{syscall}`)")
+            }
+            SandboxDenial::Syscall { name } => write!(f, "tried to call `{name}`"),
+        }
+    }
+}
+
+/// Tells the model what the sandbox blocked and how it can get the command to
+/// work. `can_escalate` is set when the model may ask to run the command
+/// outside the sandbox itself.
+pub fn explain_denials(denials: &[SandboxDenial], can_escalate: bool) -> String {
+    let mut explanation = String::from("The sandbox blocked the command:");
+    for denial in denials {
+        explanation.push_str(&format!("\n- {denial}"));
+    }
+
+    let mut dirs: Vec<&Path> = Vec::new();
+    for denial in denials {
+        if let SandboxDenial::FileSystem { path, .. } = denial {
+            let dir = path.parent().unwrap_or(path);
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    if !dirs.is_empty() {
+        let dirs = dirs
+            .iter()
+            .map(|dir| format!("`{}`", dir.display()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        explanation.push_str(&format!(
+            "\nOnly the workspace and the configured writable roots can be changed. Keep the writes inside the workspace, e.g. by pointing the tool's cache at a directory in it, or ask the user to add {dirs} to `sandbox_workspace_write.writable_roots`."
+        ));
+    }
+    if denials
+        .iter()
+        .any(|denial| matches!(denial, SandboxDenial::Network { .. }))
+    {
+        explanation.push_str("\nSandboxed commands have no network access. Ask the user to set `sandbox_workspace_write.network_access`, or to add the hosts the command needs to `sandbox_workspace_write.allowed_domains`.");
+    }
+    if can_escalate {
+        explanation.push_str("\nIf the command has to run as is, rerun it with `with_escalated_permissions` to ask the user to run it outside the sandbox.");
+    }
+    explanation
+}
+
+/// The denial log of one command: a pipe whose write end only the sandbox
+/// helper inherits.
+pub struct SandboxDenialLog {
+    reader: PipeReader,
+    #[cfg_attr(not(unix), allow(dead_code))]
+    writer: PipeWriter,
+}
+
+impl SandboxDenialLog {
+    #[cfg(unix)]
+    pub(crate) fn new() -> io::Result<Self> {
+        use std::os::fd::AsRawFd;
+
+        // Both ends are close-on-exec. The read end does not block, so Codex
+        // picks up what the helper wrote without waiting for the processes
+        // the command left behind, which may hold on to the write end.
+        let (reader, writer) = io::pipe()?;
+        let fd = reader.as_raw_fd();
+        let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+        if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self { reader, writer })
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn new() -> io::Result<Self> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    /// Lets the child inherit the write end and tells it where to find it.
+    #[cfg(unix)]
+    pub(crate) fn apply_to_command(&self, cmd: &mut Command) {
+        use std::os::fd::AsRawFd;
+
+        let fd = self.writer.as_raw_fd();
+        cmd.env(CODEX_SANDBOX_DENIALS_FD_ENV_VAR, fd.to_string());
+        unsafe {
+            cmd.pre_exec(move || {
+                if libc::fcntl(fd, libc::F_SETFD, 0) < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn apply_to_command(&self, _cmd: &mut Command) {}
+
+    /// The distinct denials recorded so far, in the order they happened.
+    /// Lines that do not parse are skipped.
+    pub(crate) fn read(&self) -> Vec<SandboxDenial> {
+        let mut contents = Vec::new();
+        // Reading stops with `WouldBlock` once the pipe is empty; what was
+        // read until then is kept.
+        let _ = (&self.reader).read_to_end(&mut contents);
+        let contents = String::from_utf8_lossy(&contents);
+        let mut denials = Vec::new();
+        for line in contents.lines() {
+            let Ok(denial) = serde_json::from_str::<SandboxDenial>(line) else {
+                continue;
+            };
+            if !denials.contains(&denial) {
+                denials.push(denial);
+            }
+            if denials.len() == MAX_REPORTED_DENIALS {
+                break;
+            }
+        }
+        denials
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[cfg(unix)]
+    #[test]
+    fn log_round_trips_distinct_denials() {
+        let log = SandboxDenialLog::new().unwrap();
+        let write = SandboxDenial::FileSystem {
+            operation: FileOperation::Write,
+            path: PathBuf::from("/home/u/.cargo/registry/index"),
+        };
+        let connect = SandboxDenial::Network {
+            syscall: "connect".to_string(),
+        };
+        let mut writer = &log.writer;
+        write.append_to(&mut writer).unwrap();
+        writer.write_all(b"not json\n").unwrap();
+        connect.append_to(&mut writer).unwrap();
+        write.append_to(&mut writer).unwrap();
+
+        assert_eq!(log.read(), vec![write, connect]);
+    }
+
+    #[test]
+    fn explanation_suggests_writable_roots_and_escalation() {
+        let denials = vec![SandboxDenial::FileSystem {
+            operation: FileOperation::Write,
+            path: PathBuf::from("/home/u/.cargo/registry"),
+        }];
+
+        assert_eq!(
+            explain_denials(&denials, true),
+            "The sandbox blocked the command:\n- tried to write /home/u/.cargo/registry\nOnly the workspace and the configured writable roots can be changed. Keep the writes inside the workspace, e.g. by pointing the tool's cache at a directory in it, or ask the user to add `/home/u/.cargo` to `sandbox_workspace_write.writable_roots`.\nIf the command has to run as is, rerun it with `with_escalated_permissions` to ask the user to run it outside the sandbox."
+        );
+    }
+}
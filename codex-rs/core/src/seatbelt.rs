@@ -3,7 +3,10 @@ use std::path::Path;
 use std::path::PathBuf;
 use tokio::process::Child;
 
+use crate::protocol::NetworkAccess;
 use crate::protocol::SandboxPolicy;
+use crate::resource_limits::ResourceLimiter;
+use crate::spawn::CODEX_NETWORK_PROXY_ENV_VAR;
 use crate::spawn::CODEX_SANDBOX_ENV_VAR;
 use crate::spawn::StdioPolicy;
 use crate::spawn::spawn_child_async;
@@ -23,8 +26,15 @@ pub async fn spawn_command_under_seatbelt(
     sandbox_policy_cwd: &Path,
     stdio_policy: StdioPolicy,
     mut env: HashMap<String, String>,
+    resource_limiter: &ResourceLimiter,
 ) -> std::io::Result<Child> {
-    let args = create_seatbelt_command_args(command, sandbox_policy, sandbox_policy_cwd);
+    let network_proxy = env.get(CODEX_NETWORK_PROXY_ENV_VAR).cloned();
+    let args = create_seatbelt_command_args(
+        command,
+        sandbox_policy,
+        sandbox_policy_cwd,
+        network_proxy.as_deref(),
+    );
     let arg0 = None;
     env.insert(CODEX_SANDBOX_ENV_VAR.to_string(), "seatbelt".to_string());
     spawn_child_async(
@@ -35,14 +45,19 @@ pub async fn spawn_command_under_seatbelt(
         sandbox_policy,
         stdio_policy,
         env,
+        resource_limiter,
+        None,
     )
     .await
 }
 
+/// `network_proxy` is the `host:port` of the Codex network proxy, which the
+/// command may connect to when the policy has `allowed_domains`.
 fn create_seatbelt_command_args(
     command: Vec<String>,
     sandbox_policy: &SandboxPolicy,
     sandbox_policy_cwd: &Path,
+    network_proxy: Option<&str>,
 ) -> Vec<String> {
     let (file_write_policy, extra_cli_args) = {
         if sandbox_policy.has_full_disk_write_access() {
@@ -74,7 +89,13 @@ fn create_seatbelt_command_args(
                     let mut require_parts: Vec<String> = Vec::new();
                     require_parts.push(format!("(subpath (param \"{root_param}\"))"));
                     for (subpath_index, ro) in wr.read_only_subpaths.iter().enumerate() {
-                        let canonical_ro = ro.canonicalize().unwrap_or_else(|_| ro.clone());
+                        // A read-only subpath need not exist yet; resolve it
+                        // against the canonical root so it still matches.
+                        let canonical_ro = ro.canonicalize().unwrap_or_else(|_| {
+                            ro.strip_prefix(&wr.root)
+                                .map(|relative| canonical_root.join(relative))
+                                .unwrap_or_else(|_| ro.clone())
+                        });
                         let ro_param = format!("WRITABLE_ROOT_{index}_RO_{subpath_index}");
                         cli_args.push(format!("-D{ro_param}={}", canonical_ro.to_string_lossy()));
                         require_parts
@@ -97,17 +118,49 @@ fn create_seatbelt_command_args(
         }
     };
 
-    let file_read_policy = if sandbox_policy.has_full_disk_read_access() {
-        "; allow read-only file operations\n(allow file-read*)"
-    } else {
-        ""
+    let (file_read_policy, read_cli_args) = match sandbox_policy
+        .get_readable_roots_with_cwd(sandbox_policy_cwd)
+    {
+        None => (
+            "; allow read-only file operations\n(allow file-read*)".to_string(),
+            Vec::<String>::new(),
+        ),
+        Some(readable_roots) => {
+            let mut readable_folder_policies: Vec<String> = Vec::new();
+            let mut cli_args: Vec<String> = Vec::new();
+            for (index, root) in readable_roots.iter().enumerate() {
+                let canonical_root = root.canonicalize().unwrap_or_else(|_| root.clone());
+                let root_param = format!("READABLE_ROOT_{index}");
+                cli_args.push(format!(
+                    "-D{root_param}={}",
+                    canonical_root.to_string_lossy()
+                ));
+                readable_folder_policies.push(format!("(subpath (param \"{root_param}\"))"));
+            }
+            // Metadata stays readable everywhere so that path lookups
+            // through the parents of the readable roots still work.
+            let file_read_policy = format!(
+                "; allow reads under the readable roots only\n(allow file-read-metadata)\n(allow file-read*\n{}\n)",
+                readable_folder_policies.join(" ")
+            );
+            (file_read_policy, cli_args)
+        }
     };
 
     // TODO(mbolin): apply_patch calls must also honor the SandboxPolicy.
+    let proxy_port = network_proxy
+        .filter(|_| !sandbox_policy.allowed_domains().is_empty())
+        .and_then(|proxy| proxy.rsplit_once(':'))
+        .and_then(|(_, port)| port.parse::<u16>().ok());
     let network_policy = if sandbox_policy.has_full_network_access() {
-        "(allow network-outbound)\n(allow network-inbound)\n(allow system-socket)"
+        "(allow network-outbound)\n(allow network-inbound)\n(allow system-socket)".to_string()
+    } else if sandbox_policy.network_access() == NetworkAccess::Loopback {
+        // Loopback also covers the proxy port, if there is one.
+        "(allow network-outbound (remote ip \"localhost:*\"))\n(allow network-inbound (local ip \"localhost:*\"))\n(allow network-bind (local ip \"localhost:*\"))\n(allow system-socket)".to_string()
+    } else if let Some(port) = proxy_port {
+        format!("(allow network-outbound (remote ip \"localhost:{port}\"))\n(allow system-socket)")
     } else {
-        ""
+        "".to_string()
     };
 
     let full_policy = format!(
@@ -116,6 +169,7 @@ fn create_seatbelt_command_args(
 
     let mut seatbelt_args: Vec<String> = vec!["-p".to_string(), full_policy];
     seatbelt_args.extend(extra_cli_args);
+    seatbelt_args.extend(read_cli_args);
     seatbelt_args.push("--".to_string());
     seatbelt_args.extend(command);
     seatbelt_args
@@ -125,6 +179,7 @@ fn create_seatbelt_command_args(
 mod tests {
     use super::MACOS_SEATBELT_BASE_POLICY;
     use super::create_seatbelt_command_args;
+    use crate::protocol::NetworkAccess;
     use crate::protocol::SandboxPolicy;
     use pretty_assertions::assert_eq;
     use std::fs;
@@ -155,28 +210,32 @@ mod tests {
         // does not automatically include defaults TMPDIR or /tmp.
         let policy = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![root_with_git, root_without_git],
-            network_access: false,
+            network_access: NetworkAccess::Disabled,
             exclude_tmpdir_env_var: true,
             exclude_slash_tmp: true,
+            readable_roots: None,
+            allowed_domains: vec![],
         };
 
         let args = create_seatbelt_command_args(
             vec!["/bin/echo".to_string(), "hello".to_string()],
             &policy,
             &cwd,
+            None,
         );
 
         // Build the expected policy text using a raw string for readability.
         // Note that the policy includes:
         // - the base policy,
         // - read-only access to the filesystem,
-        // - write access to WRITABLE_ROOT_0 (but not its .git) and WRITABLE_ROOT_1.
+        // - write access to WRITABLE_ROOT_0 (but not its .git or .codex),
+        //   WRITABLE_ROOT_1 and WRITABLE_ROOT_2 (but not their .codex).
         let expected_policy = format!(
             r#"{MACOS_SEATBELT_BASE_POLICY}
 ; allow read-only file operations
 (allow file-read*)
 (allow file-write*
-(require-all (subpath (param "WRITABLE_ROOT_0")) (require-not (subpath (param "WRITABLE_ROOT_0_RO_0"))) ) (subpath (param "WRITABLE_ROOT_1")) (subpath (param "WRITABLE_ROOT_2"))
+(require-all (subpath (param "WRITABLE_ROOT_0")) (require-not (subpath (param "WRITABLE_ROOT_0_RO_0"))) (require-not (subpath (param "WRITABLE_ROOT_0_RO_1"))) ) (require-all (subpath (param "WRITABLE_ROOT_1")) (require-not (subpath (param "WRITABLE_ROOT_1_RO_0"))) ) (require-all (subpath (param "WRITABLE_ROOT_2")) (require-not (subpath (param "WRITABLE_ROOT_2_RO_0"))) )
 )
 "#,
         );
@@ -192,11 +251,23 @@ mod tests {
                 "-DWRITABLE_ROOT_0_RO_0={}",
                 root_with_git_git_canon.to_string_lossy()
             ),
+            format!(
+                "-DWRITABLE_ROOT_0_RO_1={}",
+                root_with_git_canon.join(".codex").to_string_lossy()
+            ),
             format!(
                 "-DWRITABLE_ROOT_1={}",
                 root_without_git_canon.to_string_lossy()
             ),
+            format!(
+                "-DWRITABLE_ROOT_1_RO_0={}",
+                root_without_git_canon.join(".codex").to_string_lossy()
+            ),
             format!("-DWRITABLE_ROOT_2={}", cwd.to_string_lossy()),
+            format!(
+                "-DWRITABLE_ROOT_2_RO_0={}",
+                cwd.join(".codex").to_string_lossy()
+            ),
         ];
 
         expected_args.extend(vec![
@@ -230,15 +301,18 @@ mod tests {
         // is done properly for cwd.
         let policy = SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
-            network_access: false,
+            network_access: NetworkAccess::Disabled,
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
+            readable_roots: None,
+            allowed_domains: vec![],
         };
 
         let args = create_seatbelt_command_args(
             vec!["/bin/echo".to_string(), "hello".to_string()],
             &policy,
             root_with_git.as_path(),
+            None,
         );
 
         let tmpdir_env_var = std::env::var("TMPDIR")
@@ -248,7 +322,7 @@ mod tests {
             .map(|p| p.to_string_lossy().to_string());
 
         let tempdir_policy_entry = if tmpdir_env_var.is_some() {
-            r#" (subpath (param "WRITABLE_ROOT_2"))"#
+            r#" (require-all (subpath (param "WRITABLE_ROOT_2")) (require-not (subpath (param "WRITABLE_ROOT_2_RO_0"))) )"#
         } else {
             ""
         };
@@ -257,17 +331,21 @@ mod tests {
         // Note that the policy includes:
         // - the base policy,
         // - read-only access to the filesystem,
-        // - write access to WRITABLE_ROOT_0 (but not its .git) and WRITABLE_ROOT_1.
+        // - write access to WRITABLE_ROOT_0 (but not its .git or .codex) and
+        //   the temporary directories (but not their .codex).
         let expected_policy = format!(
             r#"{MACOS_SEATBELT_BASE_POLICY}
 ; allow read-only file operations
 (allow file-read*)
 (allow file-write*
-(require-all (subpath (param "WRITABLE_ROOT_0")) (require-not (subpath (param "WRITABLE_ROOT_0_RO_0"))) ) (subpath (param "WRITABLE_ROOT_1")){tempdir_policy_entry}
+(require-all (subpath (param "WRITABLE_ROOT_0")) (require-not (subpath (param "WRITABLE_ROOT_0_RO_0"))) (require-not (subpath (param "WRITABLE_ROOT_0_RO_1"))) ) (require-all (subpath (param "WRITABLE_ROOT_1")) (require-not (subpath (param "WRITABLE_ROOT_1_RO_0"))) ){tempdir_policy_entry}
 )
 "#,
         );
 
+        let slash_tmp = PathBuf::from("/tmp")
+            .canonicalize()
+            .expect("canonicalize /tmp");
         let mut expected_args = vec![
             "-p".to_string(),
             expected_policy,
@@ -280,16 +358,19 @@ mod tests {
                 root_with_git_git_canon.to_string_lossy()
             ),
             format!(
-                "-DWRITABLE_ROOT_1={}",
-                PathBuf::from("/tmp")
-                    .canonicalize()
-                    .expect("canonicalize /tmp")
-                    .to_string_lossy()
+                "-DWRITABLE_ROOT_0_RO_1={}",
+                root_with_git_canon.join(".codex").to_string_lossy()
+            ),
+            format!("-DWRITABLE_ROOT_1={}", slash_tmp.to_string_lossy()),
+            format!(
+                "-DWRITABLE_ROOT_1_RO_0={}",
+                slash_tmp.join(".codex").to_string_lossy()
             ),
         ];
 
         if let Some(p) = tmpdir_env_var {
             expected_args.push(format!("-DWRITABLE_ROOT_2={p}"));
+            expected_args.push(format!("-DWRITABLE_ROOT_2_RO_0={p}/.codex"));
         }
 
         expected_args.extend(vec![
@@ -301,6 +382,132 @@ mod tests {
         assert_eq!(expected_args, args);
     }
 
+    #[test]
+    fn create_seatbelt_args_with_restricted_reads() {
+        if cfg!(target_os = "windows") {
+            // /tmp does not exist on Windows, so skip this test.
+            return;
+        }
+
+        let tmp = TempDir::new().expect("tempdir");
+        let cwd = tmp.path().join("cwd");
+        let data = tmp.path().join("data");
+        fs::create_dir_all(&cwd).expect("create cwd");
+        fs::create_dir_all(&data).expect("create data");
+
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: NetworkAccess::Disabled,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            readable_roots: Some(vec![data.clone()]),
+            allowed_domains: vec![],
+        };
+
+        let args = create_seatbelt_command_args(
+            vec!["/bin/echo".to_string(), "hello".to_string()],
+            &policy,
+            &cwd,
+            None,
+        );
+
+        let full_policy = &args[1];
+        assert!(
+            !full_policy.contains("(allow file-read*)\n"),
+            "{full_policy}"
+        );
+        assert!(
+            full_policy.contains("(allow file-read-metadata)"),
+            "{full_policy}"
+        );
+        assert!(
+            full_policy.contains(
+                r#"(subpath (param "READABLE_ROOT_0")) (subpath (param "READABLE_ROOT_1"))"#
+            ),
+            "{full_policy}"
+        );
+
+        let cwd_canon = cwd.canonicalize().expect("canonicalize cwd");
+        let data_canon = data.canonicalize().expect("canonicalize data");
+        let read_args: Vec<&String> = args
+            .iter()
+            .filter(|arg| arg.starts_with("-DREADABLE_ROOT_"))
+            .collect();
+        assert_eq!(
+            read_args[..2],
+            [
+                &format!("-DREADABLE_ROOT_0={}", cwd_canon.to_string_lossy()),
+                &format!("-DREADABLE_ROOT_1={}", data_canon.to_string_lossy()),
+            ]
+        );
+    }
+
+    #[test]
+    fn create_seatbelt_args_with_network_proxy() {
+        let cwd = TempDir::new().expect("tempdir");
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: NetworkAccess::Disabled,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            readable_roots: None,
+            allowed_domains: vec!["crates.io".to_string()],
+        };
+
+        let args = create_seatbelt_command_args(
+            vec!["/bin/echo".to_string(), "hello".to_string()],
+            &policy,
+            cwd.path(),
+            Some("127.0.0.1:4128"),
+        );
+        assert!(
+            args[1].contains("\n(allow network-outbound (remote ip \"localhost:4128\"))\n"),
+            "{}",
+            args[1]
+        );
+
+        // Without an allowlist the proxy address is ignored.
+        let args = create_seatbelt_command_args(
+            vec!["/bin/echo".to_string(), "hello".to_string()],
+            &SandboxPolicy::new_workspace_write_policy(),
+            cwd.path(),
+            Some("127.0.0.1:4128"),
+        );
+        assert!(!args[1].contains("network-outbound"), "{}", args[1]);
+    }
+
+    #[test]
+    fn create_seatbelt_args_with_loopback_network() {
+        let cwd = TempDir::new().expect("tempdir");
+        let policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: NetworkAccess::Loopback,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+            readable_roots: None,
+            allowed_domains: vec![],
+        };
+
+        let args = create_seatbelt_command_args(
+            vec!["/bin/echo".to_string(), "hello".to_string()],
+            &policy,
+            cwd.path(),
+            None,
+        );
+        assert!(
+            args[1].ends_with(
+                "\n(allow network-outbound (remote ip \"localhost:*\"))\n(allow network-inbound (local ip \"localhost:*\"))\n(allow network-bind (local ip \"localhost:*\"))\n(allow system-socket)"
+            ),
+            "{}",
+            args[1]
+        );
+        assert!(
+            !args[1].contains("(allow network-outbound)\n"),
+            "{}",
+            args[1]
+        );
+    }
+
     struct PopulatedTmp {
         root_with_git: PathBuf,
         root_without_git: PathBuf,
@@ -305,6 +305,7 @@ mod tests {
         for (input, expected_cmd, expected_output) in cases {
             use std::collections::HashMap;
 
+            use crate::config_types::ResourceLimits;
             use crate::exec::ExecParams;
             use crate::exec::SandboxType;
             use crate::exec::process_exec_tool_call;
@@ -352,6 +353,7 @@ mod tests {
                 &SandboxPolicy::DangerFullAccess,
                 temp_home.path(),
                 &None,
+                &ResourceLimits::default(),
                 None,
             )
             .await
@@ -412,6 +414,7 @@ mod macos_tests {
             use std::collections::HashMap;
             use std::path::PathBuf;
 
+            use crate::config_types::ResourceLimits;
             use crate::exec::ExecParams;
             use crate::exec::SandboxType;
             use crate::exec::process_exec_tool_call;
@@ -460,6 +463,7 @@ mod macos_tests {
                 &SandboxPolicy::DangerFullAccess,
                 temp_home.path(),
                 &None,
+                &ResourceLimits::default(),
                 None,
             )
             .await
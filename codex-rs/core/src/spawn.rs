@@ -6,6 +6,8 @@ use tokio::process::Command;
 use tracing::trace;
 
 use crate::protocol::SandboxPolicy;
+use crate::resource_limits::ResourceLimiter;
+use crate::sandbox_denials::SandboxDenialLog;
 
 /// Experimental environment variable that will be set to some non-empty value
 /// if both of the following are true:
@@ -22,6 +24,30 @@ pub const CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR: &str = "CODEX_SANDBOX_NETWORK_
 /// accommodate sandboxing configuration and other sandboxing mechanisms.
 pub const CODEX_SANDBOX_ENV_VAR: &str = "CODEX_SANDBOX";
 
+/// Set to the loopback address of the Codex network proxy when a sandboxed
+/// command may reach the hosts in the policy's `allowed_domains`. The sandbox
+/// only lets the command connect to this address.
+pub const CODEX_NETWORK_PROXY_ENV_VAR: &str = "CODEX_NETWORK_PROXY";
+
+/// Set alongside [`CODEX_NETWORK_PROXY_ENV_VAR`] to the path of the Unix
+/// socket the proxy also listens on. On Linux the command runs in its own
+/// network namespace, and the sandbox helper forwards connections to the proxy
+/// address inside that namespace to this socket.
+pub const CODEX_NETWORK_PROXY_SOCKET_ENV_VAR: &str = "CODEX_NETWORK_PROXY_SOCKET";
+
+/// Set for [`crate::exec::SandboxType::LinuxOverlay`] to a directory owned by
+/// Codex. The Linux sandbox helper keeps the upper and work directories of
+/// the overlay mounts in it, from where Codex collects the command's writes.
+/// The helper removes it from the environment before running the command.
+pub const CODEX_SANDBOX_OVERLAY_DIR_ENV_VAR: &str = "CODEX_SANDBOX_OVERLAY_DIR";
+
+/// Set for the Linux sandbox helper to the write end of a pipe it inherits
+/// from Codex. The helper writes a JSON line for each operation the sandbox
+/// blocked (see [`crate::sandbox_denials::SandboxDenial`]), so Codex can say
+/// exactly what a failed command tried to do. The helper removes it from the
+/// environment and closes the pipe before running the command.
+pub const CODEX_SANDBOX_DENIALS_FD_ENV_VAR: &str = "CODEX_SANDBOX_DENIALS_FD";
+
 #[derive(Debug, Clone, Copy)]
 pub enum StdioPolicy {
     RedirectForShellTool,
@@ -35,6 +61,11 @@ pub enum StdioPolicy {
 /// For now, we take `SandboxPolicy` as a parameter to spawn_child() because
 /// we need to determine whether to set the
 /// `CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR` environment variable.
+///
+/// The limits of `resource_limiter` are applied to the child before it execs,
+/// so a sandbox wrapper passes them on to the command it runs. With a
+/// `denial_log`, the child inherits its write end.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn spawn_child_async(
     program: PathBuf,
     args: Vec<String>,
@@ -43,6 +74,8 @@ pub(crate) async fn spawn_child_async(
     sandbox_policy: &SandboxPolicy,
     stdio_policy: StdioPolicy,
     env: HashMap<String, String>,
+    resource_limiter: &ResourceLimiter,
+    denial_log: Option<&SandboxDenialLog>,
 ) -> std::io::Result<Child> {
     trace!(
         "spawn_child_async: {program:?} {args:?} {arg0:?} {cwd:?} {sandbox_policy:?} {stdio_policy:?} {env:?}"
@@ -85,6 +118,11 @@ pub(crate) async fn spawn_child_async(
         });
     }
 
+    resource_limiter.apply_to_command(&mut cmd);
+    if let Some(denial_log) = denial_log {
+        denial_log.apply_to_command(&mut cmd);
+    }
+
     match stdio_policy {
         StdioPolicy::RedirectForShellTool => {
             // Do not create a file descriptor for stdin because otherwise some
@@ -0,0 +1,215 @@
+//! Local token counting, used to estimate the size of a prompt before it is
+//! sent so that context limits can be enforced up front rather than after the
+//! server reports usage.
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ReasoningItemContent;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::models::WebSearchAction;
+
+use crate::model_family::ModelFamily;
+use crate::truncate::truncate_middle;
+
+/// Rough per-item overhead for role markers and item framing.
+const TOKENS_PER_ITEM: u64 = 4;
+
+/// Flat estimate for an image input at high detail.
+const TOKENS_PER_IMAGE: u64 = 765;
+
+/// Counts tokens the way a given model family's tokenizer would.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TokenEstimator {
+    /// Exact BPE encoding used by GPT-4o, GPT-4.1, GPT-5 and the o-series.
+    O200kBase,
+    /// Exact BPE encoding used by GPT-3.5 and GPT-4.
+    Cl100kBase,
+    /// Approximation of 4 bytes per token for models whose tokenizer is not
+    /// known.
+    Heuristic,
+}
+
+impl TokenEstimator {
+    pub(crate) fn for_model_family(model_family: &ModelFamily) -> Self {
+        let family = model_family.family.as_str();
+        if family.starts_with("gpt-4o")
+            || family.starts_with("gpt-4.1")
+            || family.starts_with("gpt-5")
+            || family.starts_with("gpt-oss")
+            || family.starts_with("codex-")
+            || family.starts_with("o3")
+            || family.starts_with("o4")
+        {
+            Self::O200kBase
+        } else if family.starts_with("gpt-3.5") || family.starts_with("gpt-4") {
+            Self::Cl100kBase
+        } else {
+            Self::Heuristic
+        }
+    }
+
+    /// Number of tokens in `text`.
+    pub(crate) fn count(self, text: &str) -> u64 {
+        match self {
+            Self::O200kBase => tiktoken_rs::o200k_base_singleton()
+                .encode_ordinary(text)
+                .len() as u64,
+            Self::Cl100kBase => tiktoken_rs::cl100k_base_singleton()
+                .encode_ordinary(text)
+                .len() as u64,
+            Self::Heuristic => (text.len() as u64).div_ceil(4),
+        }
+    }
+
+    /// Estimated number of tokens `items` will take up in the model's context
+    /// window. Encrypted reasoning is not counted because the server drops
+    /// prior reasoning from the context.
+    pub(crate) fn count_items<'a>(self, items: impl IntoIterator<Item = &'a ResponseItem>) -> u64 {
+        items.into_iter().map(|item| self.count_item(item)).sum()
+    }
+
+    fn count_item(self, item: &ResponseItem) -> u64 {
+        let content = match item {
+            ResponseItem::Message { content, .. } => content
+                .iter()
+                .map(|c| match c {
+                    ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                        self.count(text)
+                    }
+                    ContentItem::InputImage { .. } => TOKENS_PER_IMAGE,
+                })
+                .sum(),
+            ResponseItem::Reasoning {
+                summary, content, ..
+            } => {
+                let summary: u64 = summary
+                    .iter()
+                    .map(|ReasoningItemReasoningSummary::SummaryText { text }| self.count(text))
+                    .sum();
+                let content: u64 = content
+                    .iter()
+                    .flatten()
+                    .map(|c| match c {
+                        ReasoningItemContent::ReasoningText { text }
+                        | ReasoningItemContent::Text { text } => self.count(text),
+                    })
+                    .sum();
+                summary + content
+            }
+            ResponseItem::LocalShellCall {
+                action: LocalShellAction::Exec(exec),
+                ..
+            } => self.count(&exec.command.join(" ")),
+            ResponseItem::FunctionCall {
+                name, arguments, ..
+            } => self.count(name) + self.count(arguments),
+            ResponseItem::FunctionCallOutput { output, .. } => self.count(&output.content),
+            ResponseItem::CustomToolCall { name, input, .. } => {
+                self.count(name) + self.count(input)
+            }
+            ResponseItem::CustomToolCallOutput { output, .. } => self.count(output),
+            ResponseItem::WebSearchCall {
+                action: WebSearchAction::Search { query },
+                ..
+            } => self.count(query),
+            ResponseItem::WebSearchCall { .. } | ResponseItem::Other => 0,
+        };
+        content + TOKENS_PER_ITEM
+    }
+
+    /// Token-bounded counterpart of [`truncate_middle`]: keeps the beginning
+    /// and end of `s` so that the result fits in `max_tokens`. Returns the
+    /// possibly truncated string and `Some(original_token_count)` if
+    /// truncation occurred.
+    pub(crate) fn truncate_middle(self, s: &str, max_tokens: u64) -> (String, Option<u64>) {
+        let tokens = self.count(s);
+        if tokens <= max_tokens {
+            return (s.to_string(), None);
+        }
+
+        // Start from the average number of bytes per token in `s` and shrink
+        // the byte budget until the truncated output fits.
+        let mut max_bytes =
+            (s.len() as u128 * u128::from(max_tokens) / u128::from(tokens)) as usize;
+        for _ in 0..8 {
+            let (out, _) = truncate_middle(s, max_bytes);
+            if max_bytes == 0 || self.count(&out) <= max_tokens {
+                return (out, Some(tokens));
+            }
+            max_bytes = max_bytes * 9 / 10;
+        }
+        (truncate_middle(s, max_bytes).0, Some(tokens))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_family::find_family_for_model;
+    use codex_protocol::models::FunctionCallOutputPayload;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn picks_encoding_from_model_family() {
+        let estimator_for = |slug: &str| {
+            TokenEstimator::for_model_family(&find_family_for_model(slug).expect("known model"))
+        };
+        assert_eq!(estimator_for("gpt-5-codex"), TokenEstimator::O200kBase);
+        assert_eq!(estimator_for("gpt-4.1"), TokenEstimator::O200kBase);
+        assert_eq!(estimator_for("o3"), TokenEstimator::O200kBase);
+        assert_eq!(estimator_for("gpt-3.5-turbo"), TokenEstimator::Cl100kBase);
+    }
+
+    #[test]
+    fn counts_with_bpe_and_heuristic() {
+        assert_eq!(TokenEstimator::O200kBase.count("hello world"), 2);
+        assert_eq!(TokenEstimator::Cl100kBase.count("hello world"), 2);
+        assert_eq!(TokenEstimator::Heuristic.count("hello world"), 3);
+    }
+
+    #[test]
+    fn counts_items_with_overhead() {
+        let items = vec![
+            ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputText {
+                    text: "hello world".to_string(),
+                }],
+            },
+            ResponseItem::FunctionCallOutput {
+                call_id: "call-1".to_string(),
+                output: FunctionCallOutputPayload {
+                    content: "hello world".to_string(),
+                    success: Some(true),
+                },
+            },
+        ];
+        assert_eq!(
+            TokenEstimator::O200kBase.count_items(&items),
+            2 * (2 + TOKENS_PER_ITEM)
+        );
+    }
+
+    #[test]
+    fn truncates_to_token_budget() {
+        let estimator = TokenEstimator::O200kBase;
+        let s = (0..2_000)
+            .map(|i| format!("line {i}: some output\n"))
+            .collect::<String>();
+        let original = estimator.count(&s);
+
+        let (out, original_token_count) = estimator.truncate_middle(&s, 200);
+        assert_eq!(original_token_count, Some(original));
+        assert!(estimator.count(&out) <= 200, "too many tokens: {out}");
+        assert!(out.starts_with("line 0: "));
+        assert!(out.ends_with("line 1999: some output\n"));
+        assert!(out.contains("tokens truncated"));
+
+        assert_eq!(
+            estimator.truncate_middle("short", 200),
+            ("short".to_string(), None)
+        );
+    }
+}
@@ -15,6 +15,7 @@ use codex_protocol::mcp_protocol::ConversationId;
 use core_test_support::load_default_config_for_test;
 use futures::StreamExt;
 use serde_json::Value;
+use serde_json::json;
 use tempfile::TempDir;
 use wiremock::Mock;
 use wiremock::MockServer;
@@ -27,6 +28,10 @@ fn network_disabled() -> bool {
 }
 
 async fn run_request(input: Vec<ResponseItem>) -> Value {
+    run_request_with_images(input, false).await
+}
+
+async fn run_request_with_images(input: Vec<ResponseItem>, supports_images: bool) -> Value {
     let server = MockServer::start().await;
 
     let template = ResponseTemplate::new(200)
@@ -56,6 +61,9 @@ async fn run_request(input: Vec<ResponseItem>) -> Value {
         stream_max_retries: Some(0),
         stream_idle_timeout_ms: Some(5_000),
         requires_openai_auth: false,
+        supports_reasoning: None,
+        supports_images: Some(supports_images),
+        interpolate: false,
     };
 
     let codex_home = match TempDir::new() {
@@ -77,6 +85,7 @@ async fn run_request(input: Vec<ResponseItem>) -> Value {
         effort,
         summary,
         ConversationId::new(),
+        Arc::default(),
     );
 
     let mut prompt = Prompt::default();
@@ -133,6 +142,21 @@ fn reasoning_item(text: &str) -> ResponseItem {
     }
 }
 
+fn user_message_with_image(text: &str, image_url: &str) -> ResponseItem {
+    ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![
+            ContentItem::InputText {
+                text: text.to_string(),
+            },
+            ContentItem::InputImage {
+                image_url: image_url.to_string(),
+            },
+        ],
+    }
+}
+
 fn function_call() -> ResponseItem {
     ResponseItem::FunctionCall {
         id: None,
@@ -343,3 +367,56 @@ async fn suppresses_duplicate_assistant_messages() {
         Value::String("dup".into())
     );
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn sends_images_as_image_url_parts_when_supported() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let image_url = "data:image/png;base64,AAAA";
+    let body = run_request_with_images(
+        vec![user_message_with_image("what is this?", image_url)],
+        true,
+    )
+    .await;
+    let messages = messages_from(&body);
+    let user = match messages.iter().find(|msg| msg["role"] == "user") {
+        Some(v) => v,
+        None => panic!("user message not present"),
+    };
+
+    assert_eq!(
+        user["content"],
+        json!([
+            {"type": "text", "text": "what is this?"},
+            {"type": "image_url", "image_url": {"url": image_url}},
+        ])
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn omits_images_when_provider_does_not_support_them() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let body = run_request(vec![user_message_with_image(
+        "what is this?",
+        "data:image/png;base64,AAAA",
+    )])
+    .await;
+    let messages = messages_from(&body);
+    let user = match messages.iter().find(|msg| msg["role"] == "user") {
+        Some(v) => v,
+        None => panic!("user message not present"),
+    };
+
+    assert_eq!(user["content"], json!("what is this?"));
+}
@@ -23,6 +23,13 @@ fn network_disabled() -> bool {
 }
 
 async fn run_stream(sse_body: &str) -> Vec<ResponseEvent> {
+    run_stream_with_reasoning(sse_body, None).await
+}
+
+async fn run_stream_with_reasoning(
+    sse_body: &str,
+    supports_reasoning: Option<bool>,
+) -> Vec<ResponseEvent> {
     let server = MockServer::start().await;
 
     let template = ResponseTemplate::new(200)
@@ -49,6 +56,9 @@ async fn run_stream(sse_body: &str) -> Vec<ResponseEvent> {
         stream_max_retries: Some(0),
         stream_idle_timeout_ms: Some(5_000),
         requires_openai_auth: false,
+        supports_reasoning,
+        supports_images: None,
+        interpolate: false,
     };
 
     let codex_home = match TempDir::new() {
@@ -70,6 +80,7 @@ async fn run_stream(sse_body: &str) -> Vec<ResponseEvent> {
         effort,
         summary,
         ConversationId::new(),
+        Arc::default(),
     );
 
     let mut prompt = Prompt::default();
@@ -318,3 +329,75 @@ async fn streams_reasoning_before_tool_call() {
 
     assert!(matches!(events[3], ResponseEvent::Completed { .. }));
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn streams_reasoning_from_reasoning_content_delta() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let sse = concat!(
+        "data: {\"choices\":[{\"delta\":{\"reasoning_content\":\"step1\"}}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{\"reasoning_content\":\" step2\",\"content\":null}}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{} ,\"finish_reason\":\"stop\"}]}\n\n",
+    );
+
+    let events = run_stream(sse).await;
+    assert_eq!(events.len(), 6, "unexpected events: {events:?}");
+
+    match (&events[0], &events[1]) {
+        (
+            ResponseEvent::ReasoningContentDelta(first),
+            ResponseEvent::ReasoningContentDelta(second),
+        ) => {
+            assert_eq!(first, "step1");
+            assert_eq!(second, " step2");
+        }
+        other => panic!("expected reasoning deltas, got {other:?}"),
+    }
+
+    match &events[3] {
+        ResponseEvent::OutputItemDone(item) => assert_reasoning(item, "step1 step2"),
+        other => panic!("expected reasoning item, got {other:?}"),
+    }
+
+    match &events[4] {
+        ResponseEvent::OutputItemDone(item) => assert_message(item, "ok"),
+        other => panic!("expected message item, got {other:?}"),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn ignores_reasoning_when_provider_disables_it() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let sse = concat!(
+        "data: {\"choices\":[{\"delta\":{\"reasoning_content\":\"hidden\"}}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{\"content\":\"ok\"}}]}\n\n",
+        "data: {\"choices\":[{\"delta\":{} ,\"finish_reason\":\"stop\"}]}\n\n",
+    );
+
+    let events = run_stream_with_reasoning(sse, Some(false)).await;
+    assert_eq!(events.len(), 3, "unexpected events: {events:?}");
+
+    match &events[0] {
+        ResponseEvent::OutputTextDelta(text) => assert_eq!(text, "ok"),
+        other => panic!("expected text delta, got {other:?}"),
+    }
+
+    match &events[1] {
+        ResponseEvent::OutputItemDone(item) => assert_message(item, "ok"),
+        other => panic!("expected message item, got {other:?}"),
+    }
+
+    assert!(matches!(events[2], ResponseEvent::Completed { .. }));
+}
@@ -0,0 +1,307 @@
+use std::sync::Arc;
+
+use codex_core::ContentItem;
+use codex_core::ModelClient;
+use codex_core::ModelProviderInfo;
+use codex_core::Prompt;
+use codex_core::ReasoningItemContent;
+use codex_core::ResponseEvent;
+use codex_core::ResponseItem;
+use codex_core::WireApi;
+use codex_core::spawn::CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR;
+use codex_protocol::mcp_protocol::ConversationId;
+use core_test_support::load_default_config_for_test;
+use futures::StreamExt;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use tempfile::TempDir;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::header;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+fn network_disabled() -> bool {
+    std::env::var(CODEX_SANDBOX_NETWORK_DISABLED_ENV_VAR).is_ok()
+}
+
+/// A Messages API provider pointing at `server` that does not retry.
+fn messages_provider(server: &MockServer) -> ModelProviderInfo {
+    ModelProviderInfo {
+        name: "mock".into(),
+        base_url: Some(format!("{}/v1", server.uri())),
+        env_key: None,
+        env_key_instructions: None,
+        wire_api: WireApi::Messages,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(5_000),
+        requires_openai_auth: false,
+        supports_reasoning: None,
+        supports_images: None,
+        interpolate: false,
+    }
+}
+
+/// Streams one turn from a mock Messages API server that replies with
+/// `sse_body` and returns the events together with the request body it
+/// received.
+async fn run_stream(sse_body: &str) -> (Vec<ResponseEvent>, Value) {
+    let server = MockServer::start().await;
+
+    let template = ResponseTemplate::new(200)
+        .insert_header("content-type", "text/event-stream")
+        .set_body_raw(sse_body.to_string(), "text/event-stream");
+
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(header("anthropic-version", "2023-06-01"))
+        .respond_with(template)
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = messages_provider(&server);
+
+    let codex_home = match TempDir::new() {
+        Ok(dir) => dir,
+        Err(e) => panic!("failed to create TempDir: {e}"),
+    };
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider_id = provider.name.clone();
+    config.model_provider = provider.clone();
+    config.model_max_output_tokens = Some(4_096);
+    let effort = config.model_reasoning_effort;
+    let summary = config.model_reasoning_summary;
+    let config = Arc::new(config);
+
+    let client = ModelClient::new(
+        Arc::clone(&config),
+        None,
+        provider,
+        effort,
+        summary,
+        ConversationId::new(),
+        Arc::default(),
+    );
+
+    let mut prompt = Prompt::default();
+    prompt.input = vec![ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText {
+            text: "hello".to_string(),
+        }],
+    }];
+
+    let mut stream = match client.stream(&prompt).await {
+        Ok(s) => s,
+        Err(e) => panic!("stream messages failed: {e}"),
+    };
+    let mut events = Vec::new();
+    while let Some(event) = stream.next().await {
+        match event {
+            Ok(ev) => events.push(ev),
+            Err(e) => panic!("stream event error: {e}"),
+        }
+    }
+
+    let requests = match server.received_requests().await {
+        Some(reqs) => reqs,
+        None => panic!("request not made"),
+    };
+    match requests[0].body_json() {
+        Ok(payload) => (events, payload),
+        Err(e) => panic!("invalid json body: {e}"),
+    }
+}
+
+fn sse(events: &[Value]) -> String {
+    events
+        .iter()
+        .map(|event| {
+            format!(
+                "event: {}\ndata: {event}\n\n",
+                event["type"].as_str().unwrap_or("")
+            )
+        })
+        .collect()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn maps_content_blocks_and_usage() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let body = sse(&[
+        serde_json::json!({"type": "message_start", "message": {
+            "id": "msg_1",
+            "usage": {"input_tokens": 10, "cache_read_input_tokens": 90, "output_tokens": 1},
+        }}),
+        serde_json::json!({"type": "content_block_start", "index": 0, "content_block": {
+            "type": "thinking", "thinking": "",
+        }}),
+        serde_json::json!({"type": "content_block_delta", "index": 0, "delta": {
+            "type": "thinking_delta", "thinking": "Need ls.",
+        }}),
+        serde_json::json!({"type": "content_block_delta", "index": 0, "delta": {
+            "type": "signature_delta", "signature": "sig-1",
+        }}),
+        serde_json::json!({"type": "content_block_stop", "index": 0}),
+        serde_json::json!({"type": "content_block_start", "index": 1, "content_block": {
+            "type": "text", "text": "",
+        }}),
+        serde_json::json!({"type": "ping"}),
+        serde_json::json!({"type": "content_block_delta", "index": 1, "delta": {
+            "type": "text_delta", "text": "Listing",
+        }}),
+        serde_json::json!({"type": "content_block_delta", "index": 1, "delta": {
+            "type": "text_delta", "text": " files.",
+        }}),
+        serde_json::json!({"type": "content_block_stop", "index": 1}),
+        serde_json::json!({"type": "content_block_start", "index": 2, "content_block": {
+            "type": "tool_use", "id": "toolu_1", "name": "shell", "input": {},
+        }}),
+        serde_json::json!({"type": "content_block_delta", "index": 2, "delta": {
+            "type": "input_json_delta", "partial_json": "{\"command\":",
+        }}),
+        serde_json::json!({"type": "content_block_delta", "index": 2, "delta": {
+            "type": "input_json_delta", "partial_json": "[\"ls\"]}",
+        }}),
+        serde_json::json!({"type": "content_block_stop", "index": 2}),
+        serde_json::json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"},
+            "usage": {"output_tokens": 25}}),
+        serde_json::json!({"type": "message_stop"}),
+    ]);
+
+    let (events, payload) = run_stream(&body).await;
+
+    let mut items = Vec::new();
+    let mut text_deltas = String::new();
+    let mut reasoning_deltas = String::new();
+    let mut completed = None;
+    for event in events {
+        match event {
+            ResponseEvent::OutputItemDone(item) => items.push(item),
+            ResponseEvent::OutputTextDelta(delta) => text_deltas.push_str(&delta),
+            ResponseEvent::ReasoningContentDelta(delta) => reasoning_deltas.push_str(&delta),
+            ResponseEvent::Completed {
+                response_id,
+                token_usage,
+            } => completed = Some((response_id, token_usage)),
+            _ => {}
+        }
+    }
+
+    assert_eq!(text_deltas, "Listing files.");
+    assert_eq!(reasoning_deltas, "Need ls.");
+    assert_eq!(
+        items,
+        vec![
+            ResponseItem::Reasoning {
+                id: String::new(),
+                summary: Vec::new(),
+                content: Some(vec![ReasoningItemContent::ReasoningText {
+                    text: "Need ls.".to_string(),
+                }]),
+                encrypted_content: Some("sig-1".to_string()),
+            },
+            ResponseItem::Message {
+                id: None,
+                role: "assistant".to_string(),
+                content: vec![ContentItem::OutputText {
+                    text: "Listing files.".to_string(),
+                }],
+            },
+            ResponseItem::FunctionCall {
+                id: None,
+                name: "shell".to_string(),
+                arguments: "{\"command\":[\"ls\"]}".to_string(),
+                call_id: "toolu_1".to_string(),
+            },
+        ]
+    );
+    let Some((response_id, Some(usage))) = completed else {
+        panic!("expected Completed with token usage, got {completed:?}");
+    };
+    assert_eq!(response_id, "msg_1");
+    assert_eq!(usage.input_tokens, 100);
+    assert_eq!(usage.cached_input_tokens, 90);
+    assert_eq!(usage.output_tokens, 25);
+    assert_eq!(usage.total_tokens, 125);
+
+    assert_eq!(payload["max_tokens"], 4_096);
+    assert_eq!(payload["stream"], true);
+    assert!(payload["system"].as_str().is_some_and(|s| !s.is_empty()));
+    assert_eq!(
+        payload["messages"],
+        serde_json::json!([{"role": "user", "content": [{"type": "text", "text": "hello"}]}])
+    );
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn stream_error_event_fails_the_turn() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    let body = sse(&[
+        serde_json::json!({"type": "message_start", "message": {"id": "msg_1", "usage": {}}}),
+        serde_json::json!({"type": "error", "error": {
+            "type": "overloaded_error", "message": "Overloaded",
+        }}),
+    ]);
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(body, "text/event-stream"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = messages_provider(&server);
+    let codex_home = match TempDir::new() {
+        Ok(dir) => dir,
+        Err(e) => panic!("failed to create TempDir: {e}"),
+    };
+    let config = Arc::new(load_default_config_for_test(&codex_home));
+    let client = ModelClient::new(
+        Arc::clone(&config),
+        None,
+        provider,
+        None,
+        config.model_reasoning_summary,
+        ConversationId::new(),
+        Arc::default(),
+    );
+
+    let mut stream = match client.stream(&Prompt::default()).await {
+        Ok(s) => s,
+        Err(e) => panic!("stream messages failed: {e}"),
+    };
+    let mut error = None;
+    while let Some(event) = stream.next().await {
+        if let Err(e) = event {
+            error = Some(e.to_string());
+        }
+    }
+    assert!(
+        error.as_deref().is_some_and(|e| e.contains("Overloaded")),
+        "unexpected error: {error:?}"
+    );
+}
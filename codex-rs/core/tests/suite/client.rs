@@ -652,6 +652,9 @@ async fn azure_responses_request_includes_store_and_reasoning_ids() {
         stream_max_retries: Some(0),
         stream_idle_timeout_ms: Some(5_000),
         requires_openai_auth: false,
+        supports_reasoning: None,
+        supports_images: None,
+        interpolate: false,
     };
 
     let codex_home = TempDir::new().unwrap();
@@ -669,6 +672,7 @@ async fn azure_responses_request_includes_store_and_reasoning_ids() {
         effort,
         summary,
         ConversationId::new(),
+        Arc::default(),
     );
 
     let mut prompt = Prompt::default();
@@ -850,7 +854,9 @@ async fn token_count_includes_rate_limits_snapshot() {
                     "total_tokens": 123
                 },
                 // Default model is gpt-5-codex in tests → 272000 context window
-                "model_context_window": 272000
+                "model_context_window": 272000,
+                // 123 input tokens at $1.25 per million
+                "total_cost_usd": 0.00015375
             },
             "rate_limits": {
                 "primary_used_percent": 12.5,
@@ -997,6 +1003,9 @@ async fn azure_overrides_assign_properties_used_for_responses_url() {
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
+        supports_reasoning: None,
+        supports_images: None,
+        interpolate: false,
     };
 
     // Init session
@@ -1073,6 +1082,9 @@ async fn env_var_overrides_loaded_auth() {
         stream_max_retries: None,
         stream_idle_timeout_ms: None,
         requires_openai_auth: false,
+        supports_reasoning: None,
+        supports_images: None,
+        interpolate: false,
     };
 
     // Init session
@@ -3,6 +3,7 @@ use codex_core::ConversationManager;
 use codex_core::ModelProviderInfo;
 use codex_core::NewConversation;
 use codex_core::built_in_model_providers;
+use codex_core::protocol::CompactedItem;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
@@ -50,6 +51,8 @@ const SECOND_AUTO_SUMMARY: &str = "SECOND_AUTO_SUMMARY";
 const FINAL_REPLY: &str = "FINAL_REPLY";
 const DUMMY_FUNCTION_NAME: &str = "unsupported_tool";
 const DUMMY_CALL_ID: &str = "call-multi-auto";
+const PRE_FLIGHT_FIRST_MSG: &str = "pre-flight start";
+const PRE_FLIGHT_LARGE_MSG: &str = "pre-flight large input";
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
 async fn summarize_context_three_requests_and_instructions() {
@@ -247,8 +250,8 @@ async fn summarize_context_three_requests_and_instructions() {
             RolloutItem::TurnContext(_) => {
                 api_turn_count += 1;
             }
-            RolloutItem::Compacted(ci) => {
-                if ci.message == SUMMARY_TEXT {
+            RolloutItem::Compacted(CompactedItem::Summary { message }) => {
+                if message == SUMMARY_TEXT {
                     saw_compacted_summary = true;
                 }
             }
@@ -828,3 +831,100 @@ async fn auto_compact_allows_multiple_attempts_when_interleaved_with_other_turn_
         "second auto compact request should include the summarization prompt"
     );
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn auto_compact_runs_before_request_when_new_input_exceeds_limit() {
+    non_sandbox_test!();
+
+    let server = start_mock_server().await;
+
+    let sse1 = sse(vec![
+        ev_assistant_message("m1", FIRST_REPLY),
+        ev_completed_with_tokens("r1", 150),
+    ]);
+    let sse2 = sse(vec![
+        ev_assistant_message("m2", SUMMARY_TEXT),
+        ev_completed_with_tokens("r2", 50),
+    ]);
+    let sse3 = sse(vec![
+        ev_assistant_message("m3", FINAL_REPLY),
+        ev_completed_with_tokens("r3", 120),
+    ]);
+
+    let first_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains(PRE_FLIGHT_FIRST_MSG) && !body.contains(PRE_FLIGHT_LARGE_MSG)
+    };
+    mount_sse_once(&server, first_matcher, sse1).await;
+
+    let summarize_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains("You have exceeded the maximum number of tokens")
+    };
+    mount_sse_once(&server, summarize_matcher, sse2).await;
+
+    let follow_up_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains(SUMMARY_TEXT)
+            && !body.contains("You have exceeded the maximum number of tokens")
+    };
+    mount_sse_once(&server, follow_up_matcher, sse3).await;
+
+    let model_provider = ModelProviderInfo {
+        base_url: Some(format!("{}/v1", server.uri())),
+        ..built_in_model_providers()["openai"].clone()
+    };
+
+    let home = TempDir::new().unwrap();
+    let mut config = load_default_config_for_test(&home);
+    config.model_provider = model_provider;
+    config.model_auto_compact_token_limit = Some(200);
+    let conversation_manager = ConversationManager::with_auth(CodexAuth::from_api_key("dummy"));
+    let codex = conversation_manager
+        .new_conversation(config)
+        .await
+        .unwrap()
+        .conversation;
+
+    // The first turn fits, and the server reports a size below the limit.
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: PRE_FLIGHT_FIRST_MSG.into(),
+            }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    // The reported size plus this message goes over the limit, so the
+    // history is compacted before the message is sent to the model.
+    let large_msg = format!("{PRE_FLIGHT_LARGE_MSG} {}", "filler ".repeat(400));
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text { text: large_msg }],
+        })
+        .await
+        .unwrap();
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+
+    let requests = server.received_requests().await.unwrap();
+    let bodies: Vec<String> = requests
+        .iter()
+        .map(|req| String::from_utf8(req.body.clone()).unwrap_or_default())
+        .collect();
+    assert_eq!(bodies.len(), 3, "expected a compaction between the turns");
+    assert!(
+        !bodies[0].contains("You have exceeded the maximum number of tokens"),
+        "the first turn should not be compacted"
+    );
+    assert!(
+        bodies[1].contains("You have exceeded the maximum number of tokens")
+            && bodies[1].contains(PRE_FLIGHT_LARGE_MSG),
+        "the history should be compacted before the large input is sent"
+    );
+    assert!(
+        bodies[2].contains(SUMMARY_TEXT),
+        "the follow-up request should build on the summary"
+    );
+}
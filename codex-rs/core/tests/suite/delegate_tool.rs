@@ -0,0 +1,138 @@
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::SubAgentEventEvent;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+
+use responses::ev_assistant_message;
+use responses::ev_completed;
+use responses::ev_function_call;
+use responses::mount_sse_once;
+use responses::sse;
+use responses::start_mock_server;
+
+const CHILD_PROMPT: &str = "Summarize the README";
+
+/// A `delegate_task` call runs the prompt in a child conversation, forwards
+/// the child's events on the parent stream as `SubAgentEvent`s, and returns
+/// the child's last agent message to the model as the tool output.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn delegate_task_runs_child_and_returns_its_last_message() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+
+    let delegate_args = serde_json::json!({ "prompt": CHILD_PROMPT }).to_string();
+    let parent_first = sse(vec![
+        ev_function_call("call-1", "delegate_task", &delegate_args),
+        ev_completed("r1"),
+    ]);
+    let child = sse(vec![
+        ev_assistant_message("m-child", "The README explains how to build."),
+        ev_completed("r-child"),
+    ]);
+    let parent_second = sse(vec![ev_assistant_message("m2", "Done"), ev_completed("r2")]);
+
+    let parent_first_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        !body.contains(CHILD_PROMPT)
+    };
+    mount_sse_once(&server, parent_first_matcher, parent_first).await;
+
+    // The child's only input is the delegated prompt; the parent's follow-up
+    // also carries it in the call arguments, next to the call output.
+    let child_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains(CHILD_PROMPT) && !body.contains("function_call_output")
+    };
+    mount_sse_once(&server, child_matcher, child).await;
+
+    let parent_second_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains("function_call_output")
+    };
+    mount_sse_once(&server, parent_second_matcher, parent_second).await;
+
+    let TestCodex {
+        codex,
+        session_configured,
+        ..
+    } = test_codex()
+        .with_config(|config| {
+            config.include_delegate_tool = true;
+            config.approval_policy = AskForApproval::Never;
+        })
+        .build(&server)
+        .await?;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "delegate the summary".into(),
+            }],
+        })
+        .await?;
+
+    let mut forwarded = Vec::new();
+    loop {
+        match wait_for_event(&codex, |_| true).await {
+            EventMsg::SubAgentEvent(SubAgentEventEvent {
+                call_id,
+                conversation_id,
+                msg,
+            }) => {
+                assert_eq!(call_id, "call-1");
+                assert_ne!(conversation_id, session_configured.session_id);
+                forwarded.push(*msg);
+            }
+            EventMsg::TaskComplete(_) => break,
+            _ => {}
+        }
+    }
+
+    assert!(
+        forwarded.iter().any(|msg| matches!(
+            msg,
+            EventMsg::AgentMessage(ev) if ev.message == "The README explains how to build."
+        )),
+        "child agent message was not forwarded: {forwarded:?}"
+    );
+    match forwarded.last() {
+        Some(EventMsg::TaskComplete(ev)) => assert_eq!(
+            ev.last_agent_message.as_deref(),
+            Some("The README explains how to build.")
+        ),
+        other => panic!("expected the child's TaskComplete last, got {other:?}"),
+    }
+
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert_eq!(
+        requests.len(),
+        3,
+        "expected parent, child and parent requests"
+    );
+    let body = requests[2].body_json::<Value>()?;
+    let output = body["input"]
+        .as_array()
+        .and_then(|items| {
+            items
+                .iter()
+                .find(|item| item["type"] == "function_call_output" && item["call_id"] == "call-1")
+        })
+        .map(|item| item["output"].clone());
+    assert_eq!(
+        output,
+        Some(Value::String(
+            "The README explains how to build.".to_string()
+        ))
+    );
+
+    Ok(())
+}
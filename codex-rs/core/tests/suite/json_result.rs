@@ -15,7 +15,13 @@ use pretty_assertions::assert_eq;
 use responses::ev_assistant_message;
 use responses::ev_completed;
 use responses::sse;
+use responses::sse_response;
 use responses::start_mock_server;
+use serde_json::Value;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
 
 const SCHEMA: &str = r#"
 {
@@ -95,3 +101,112 @@ async fn codex_returns_json_result() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Mounts one response per final message, served in order.
+async fn mount_final_messages(server: &MockServer, messages: &[&str]) {
+    for (i, message) in messages.iter().enumerate() {
+        let body = sse(vec![
+            ev_assistant_message(&format!("m{i}"), message),
+            ev_completed(&format!("r{i}")),
+        ]);
+        Mock::given(method("POST"))
+            .and(path("/v1/responses"))
+            .respond_with(sse_response(body))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+}
+
+fn user_turn(cwd: &std::path::Path) -> anyhow::Result<Op> {
+    Ok(Op::UserTurn {
+        items: vec![InputItem::Text {
+            text: "hello world".into(),
+        }],
+        final_output_json_schema: Some(serde_json::from_str(SCHEMA)?),
+        cwd: cwd.to_path_buf(),
+        approval_policy: AskForApproval::Never,
+        sandbox_policy: SandboxPolicy::DangerFullAccess,
+        model: "gpt-5".to_string(),
+        effort: None,
+        summary: ReasoningSummary::Auto,
+    })
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn json_result_that_does_not_match_the_schema_is_repaired() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    let valid = r#"{"explanation": "explanation", "final_answer": "final_answer"}"#;
+    mount_final_messages(&server, &[r#"{"explanation": "explanation"}"#, valid]).await;
+
+    let TestCodex { codex, cwd, .. } = test_codex().build(&server).await?;
+    codex.submit(user_turn(cwd.path())?).await?;
+
+    let complete = wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    let EventMsg::TaskComplete(complete) = complete else {
+        unreachable!();
+    };
+    assert_eq!(complete.last_agent_message.as_deref(), Some(valid));
+
+    // The second request asks the model to fix the missing property.
+    let requests = server.received_requests().await.unwrap_or_default();
+    assert_eq!(requests.len(), 2);
+    let body = requests[1].body_json::<Value>()?;
+    let repair_request = body["input"]
+        .as_array()
+        .and_then(|input| input.last())
+        .cloned()
+        .unwrap_or_default();
+    assert_eq!(repair_request["role"], "user");
+    let text = repair_request["content"][0]["text"]
+        .as_str()
+        .unwrap_or_default();
+    assert!(
+        text.contains(r#""final_answer" is a required property"#),
+        "unexpected repair request: {text}"
+    );
+
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn json_result_that_cannot_be_repaired_is_reported() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+    mount_final_messages(&server, &["not json", "still not json"]).await;
+
+    let TestCodex { codex, cwd, .. } = test_codex()
+        .with_config(|config| config.output_schema_repair_attempts = 1)
+        .build(&server)
+        .await?;
+    codex.submit(user_turn(cwd.path())?).await?;
+
+    let failed = wait_for_event(&codex, |ev| {
+        matches!(ev, EventMsg::OutputSchemaValidationFailed(_))
+    })
+    .await;
+    let EventMsg::OutputSchemaValidationFailed(failed) = failed else {
+        unreachable!();
+    };
+    assert_eq!(failed.errors.len(), 1);
+    assert!(
+        failed.errors[0].starts_with("the final message is not valid JSON"),
+        "unexpected errors: {:?}",
+        failed.errors
+    );
+
+    let complete = wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    let EventMsg::TaskComplete(complete) = complete else {
+        unreachable!();
+    };
+    assert_eq!(
+        complete.last_agent_message.as_deref(),
+        Some("still not json")
+    );
+
+    Ok(())
+}
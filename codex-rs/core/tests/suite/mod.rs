@@ -4,13 +4,18 @@ mod cli_stream;
 mod client;
 mod compact;
 mod compact_resume_fork;
+mod delegate_tool;
 mod exec;
 mod exec_stream_events;
 mod fork_conversation;
 mod json_result;
 mod live_cli;
+mod model_fallback;
 mod model_overrides;
+mod parallel_tool_calls;
 mod prompt_caching;
+mod provider_health;
+mod resource_limits;
 mod review;
 mod rollout_list_find;
 mod seatbelt;
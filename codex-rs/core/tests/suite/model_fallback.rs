@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use codex_core::ModelProviderInfo;
+use codex_core::WireApi;
+use codex_core::config_types::ModelFallback;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use core_test_support::load_sse_fixture_with_id;
+use core_test_support::non_sandbox_test;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event_with_timeout;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::body_string_contains;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+fn sse_completed(id: &str) -> String {
+    load_sse_fixture_with_id("tests/fixtures/completed_template.json", id)
+}
+
+fn mock_provider(server: &MockServer) -> ModelProviderInfo {
+    ModelProviderInfo {
+        name: "mock-openai".into(),
+        base_url: Some(format!("{}/v1", server.uri())),
+        env_key: Some("PATH".into()),
+        env_key_instructions: None,
+        wire_api: WireApi::Responses,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(2_000),
+        requires_openai_auth: false,
+        supports_reasoning: None,
+        supports_images: None,
+        interpolate: false,
+    }
+}
+
+/// A server error from the primary model retries the same prompt on the
+/// next entry of the `fallback` chain and tells the user about the switch.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn server_error_falls_back_to_next_model() {
+    non_sandbox_test!();
+
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains(r#""model":"gpt-5""#))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(body_string_contains(r#""model":"gpt-4.1""#))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(sse_completed("resp_fallback"), "text/event-stream"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let provider = mock_provider(&server);
+    let TestCodex { codex, .. } = test_codex()
+        .with_config(move |config| {
+            config.model = "gpt-5".to_string();
+            config.model_provider = provider.clone();
+            config
+                .model_providers
+                .insert("backup".to_string(), provider);
+            config.fallback = vec![ModelFallback {
+                model_provider: "backup".to_string(),
+                model: "gpt-4.1".to_string(),
+            }];
+        })
+        .build(&server)
+        .await
+        .unwrap();
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "hello".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let background = wait_for_event_with_timeout(
+        &codex,
+        |ev| matches!(ev, EventMsg::BackgroundEvent(_)),
+        Duration::from_secs(5),
+    )
+    .await;
+    let EventMsg::BackgroundEvent(background) = background else {
+        unreachable!();
+    };
+    assert!(
+        background.message.contains("gpt-4.1"),
+        "unexpected message: {}",
+        background.message
+    );
+
+    wait_for_event_with_timeout(
+        &codex,
+        |ev| matches!(ev, EventMsg::TaskComplete(_)),
+        Duration::from_secs(5),
+    )
+    .await;
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2);
+    let first = requests[0].body_json::<Value>().unwrap();
+    let second = requests[1].body_json::<Value>().unwrap();
+    assert_eq!(first["model"], "gpt-5");
+    assert_eq!(second["model"], "gpt-4.1");
+    assert_eq!(first["input"], second["input"]);
+}
@@ -0,0 +1,134 @@
+#![cfg(not(target_os = "windows"))]
+
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use core_test_support::non_sandbox_test;
+use core_test_support::responses;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event;
+use pretty_assertions::assert_eq;
+use serde_json::Value;
+use std::fs::OpenOptions;
+use std::io::Write;
+
+use responses::ev_assistant_message;
+use responses::ev_completed;
+use responses::ev_function_call;
+use responses::mount_sse_once;
+use responses::sse;
+use responses::start_mock_server;
+
+/// Two read-only shell calls in one response run concurrently, and their
+/// outputs are still sent back to the model in the order they were issued.
+///
+/// Each call reads a FIFO that is only written once both calls have opened
+/// theirs, so the first call cannot finish before the second one started.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn parallel_safe_calls_overlap_and_keep_output_order() -> anyhow::Result<()> {
+    non_sandbox_test!(result);
+
+    let server = start_mock_server().await;
+
+    let fifo_dir = tempfile::tempdir()?;
+    let first_fifo = fifo_dir.path().join("first");
+    let second_fifo = fifo_dir.path().join("second");
+    let status = std::process::Command::new("mkfifo")
+        .arg(&first_fifo)
+        .arg(&second_fifo)
+        .status()?;
+    assert!(status.success(), "mkfifo failed");
+    let writer = {
+        let first_fifo = first_fifo.clone();
+        let second_fifo = second_fifo.clone();
+        std::thread::spawn(move || -> std::io::Result<()> {
+            // Opening a FIFO for writing blocks until a reader opens it.
+            let mut first = OpenOptions::new().write(true).open(&first_fifo)?;
+            let mut second = OpenOptions::new().write(true).open(&second_fifo)?;
+            first.write_all(b"first\n")?;
+            second.write_all(b"second\n")?;
+            Ok(())
+        })
+    };
+
+    let first_args = serde_json::json!({
+        "command": ["cat", first_fifo],
+        "timeout_ms": 3_000,
+    })
+    .to_string();
+    let second_args = serde_json::json!({
+        "command": ["cat", second_fifo],
+        "timeout_ms": 3_000,
+    })
+    .to_string();
+    let sse1 = sse(vec![
+        ev_function_call("call-1", "shell", &first_args),
+        ev_function_call("call-2", "shell", &second_args),
+        ev_completed("r1"),
+    ]);
+    let sse2 = sse(vec![ev_assistant_message("m1", "Done"), ev_completed("r2")]);
+
+    let first_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        !body.contains("function_call_output")
+    };
+    mount_sse_once(&server, first_matcher, sse1).await;
+
+    let second_matcher = |req: &wiremock::Request| {
+        let body = std::str::from_utf8(&req.body).unwrap_or("");
+        body.contains("function_call_output")
+    };
+    mount_sse_once(&server, second_matcher, sse2).await;
+
+    let TestCodex { codex, .. } = test_codex()
+        .with_config(|cfg| cfg.parallel_tool_calls = true)
+        .build(&server)
+        .await?;
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "run both".into(),
+            }],
+        })
+        .await?;
+    wait_for_event(&codex, |ev| matches!(ev, EventMsg::TaskComplete(_))).await;
+    writer
+        .join()
+        .expect("writer thread panicked")
+        .expect("both calls should have opened their FIFO at the same time");
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 2, "expected exactly two requests");
+
+    let first_body = requests[0].body_json::<Value>()?;
+    assert_eq!(first_body["parallel_tool_calls"], Value::Bool(true));
+
+    let second_body = requests[1].body_json::<Value>()?;
+    let outputs: Vec<(String, String)> = second_body["input"]
+        .as_array()
+        .expect("input array")
+        .iter()
+        .filter(|item| item["type"] == "function_call_output")
+        .map(|item| {
+            (
+                item["call_id"].as_str().unwrap_or_default().to_string(),
+                item["output"].as_str().unwrap_or_default().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(outputs.len(), 2);
+    assert_eq!(outputs[0].0, "call-1");
+    assert!(
+        outputs[0].1.contains("first"),
+        "unexpected output: {outputs:?}"
+    );
+    assert_eq!(outputs[1].0, "call-2");
+    assert!(
+        outputs[1].1.contains("second"),
+        "unexpected output: {outputs:?}"
+    );
+
+    Ok(())
+}
@@ -8,6 +8,7 @@ use codex_core::model_family::find_family_for_model;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
+use codex_core::protocol::NetworkAccess;
 use codex_core::protocol::Op;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol_config_types::ReasoningEffort;
@@ -216,7 +217,13 @@ async fn prompt_tools_are_consistent_across_requests() {
 
     // our internal implementation is responsible for keeping tools in sync
     // with the OpenAI schema, so we just verify the tool presence here
-    let expected_tools_names: &[&str] = &["shell", "update_plan", "apply_patch", "view_image"];
+    let expected_tools_names: &[&str] = &[
+        "shell",
+        "read_output",
+        "update_plan",
+        "apply_patch",
+        "view_image",
+    ];
     let body0 = requests[0].body_json::<serde_json::Value>().unwrap();
     assert_eq!(
         body0["instructions"],
@@ -407,9 +414,11 @@ async fn overrides_turn_context_but_keeps_cached_prefix_and_key_constant() {
             approval_policy: Some(AskForApproval::Never),
             sandbox_policy: Some(SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![writable.path().to_path_buf()],
-                network_access: true,
+                network_access: NetworkAccess::Enabled,
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
+                readable_roots: None,
+                allowed_domains: vec![],
             }),
             model: Some("o3".to_string()),
             effort: Some(Some(ReasoningEffort::High)),
@@ -539,9 +548,11 @@ async fn per_turn_overrides_keep_cached_prefix_and_key_constant() {
             approval_policy: AskForApproval::Never,
             sandbox_policy: SandboxPolicy::WorkspaceWrite {
                 writable_roots: vec![writable.path().to_path_buf()],
-                network_access: true,
+                network_access: NetworkAccess::Enabled,
                 exclude_tmpdir_env_var: true,
                 exclude_slash_tmp: true,
+                readable_roots: None,
+                allowed_domains: vec![],
             },
             model: "o3".to_string(),
             effort: Some(ReasoningEffort::High),
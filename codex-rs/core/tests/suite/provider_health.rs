@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+use codex_core::ModelProviderInfo;
+use codex_core::WireApi;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use core_test_support::non_sandbox_test;
+use core_test_support::test_codex::TestCodex;
+use core_test_support::test_codex::test_codex;
+use core_test_support::wait_for_event_with_timeout;
+use pretty_assertions::assert_eq;
+use wiremock::Mock;
+use wiremock::MockServer;
+use wiremock::ResponseTemplate;
+use wiremock::matchers::method;
+use wiremock::matchers::path;
+
+/// Repeated server errors open the provider's circuit, which stops the
+/// request retries early and tells the user why.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn repeated_failures_open_the_circuit() {
+    non_sandbox_test!();
+
+    let server = MockServer::start().await;
+
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .respond_with(ResponseTemplate::new(500).insert_header("retry-after", "0"))
+        .expect(5)
+        .mount(&server)
+        .await;
+
+    let provider = ModelProviderInfo {
+        name: "mock-openai".into(),
+        base_url: Some(format!("{}/v1", server.uri())),
+        env_key: Some("PATH".into()),
+        env_key_instructions: None,
+        wire_api: WireApi::Responses,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: Some(10),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(2_000),
+        requires_openai_auth: false,
+        supports_reasoning: None,
+        supports_images: None,
+        interpolate: false,
+    };
+    let TestCodex { codex, .. } = test_codex()
+        .with_config(move |config| {
+            config.model_provider = provider;
+        })
+        .build(&server)
+        .await
+        .unwrap();
+
+    codex
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: "hello".into(),
+            }],
+        })
+        .await
+        .unwrap();
+
+    let background = wait_for_event_with_timeout(
+        &codex,
+        |ev| matches!(ev, EventMsg::BackgroundEvent(_)),
+        Duration::from_secs(10),
+    )
+    .await;
+    let EventMsg::BackgroundEvent(background) = background else {
+        unreachable!();
+    };
+    assert_eq!(
+        background.message,
+        "mock-openai failed 5 requests in a row; pausing requests for 30s"
+    );
+
+    let error = wait_for_event_with_timeout(
+        &codex,
+        |ev| matches!(ev, EventMsg::Error(_)),
+        Duration::from_secs(5),
+    )
+    .await;
+    let EventMsg::Error(error) = error else {
+        unreachable!();
+    };
+    assert!(
+        error.message.contains("not sending requests"),
+        "unexpected error: {}",
+        error.message
+    );
+
+    let requests = server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 5);
+}
@@ -0,0 +1,82 @@
+#![cfg(unix)]
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use codex_core::config_types::ResourceLimits;
+use codex_core::error::CodexErr;
+use codex_core::error::Result;
+use codex_core::error::SandboxErr;
+use codex_core::exec::ExecParams;
+use codex_core::exec::ExecToolCallOutput;
+use codex_core::exec::SandboxType;
+use codex_core::exec::process_exec_tool_call;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::resource_limits::ResourceLimit;
+
+async fn run_with_limits(script: &str, limits: ResourceLimits) -> Result<ExecToolCallOutput> {
+    let cwd = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let params = ExecParams {
+        command: vec!["/bin/sh".to_string(), "-c".to_string(), script.to_string()],
+        cwd: cwd.clone(),
+        timeout_ms: Some(10_000),
+        env: HashMap::new(),
+        with_escalated_permissions: None,
+        justification: None,
+    };
+
+    process_exec_tool_call(
+        params,
+        SandboxType::None,
+        &SandboxPolicy::DangerFullAccess,
+        cwd.as_path(),
+        &None,
+        &limits,
+        None,
+    )
+    .await
+}
+
+#[tokio::test]
+async fn rlimits_are_applied_to_the_command() {
+    let limits = ResourceLimits {
+        max_open_files: Some(64),
+        ..Default::default()
+    };
+
+    let output = run_with_limits("ulimit -n", limits).await.unwrap();
+
+    assert_eq!(output.exit_code, 0);
+    assert_eq!(output.stdout.text, "64\n");
+}
+
+#[tokio::test]
+async fn command_is_stopped_when_output_limit_is_exceeded() {
+    let limits = ResourceLimits {
+        max_output_mb: Some(1),
+        ..Default::default()
+    };
+
+    let result = run_with_limits("yes", limits).await;
+
+    let Err(CodexErr::Sandbox(SandboxErr::ResourceLimitExceeded { limit, output })) = result else {
+        panic!("expected the output limit to be exceeded, got {result:?}");
+    };
+    assert_eq!(limit, ResourceLimit::Output { megabytes: 1 });
+    assert_eq!(output.stdout.text.len(), 1024 * 1024);
+}
+
+#[tokio::test]
+async fn cpu_time_limit_is_reported() {
+    let limits = ResourceLimits {
+        cpu_time_secs: Some(1),
+        ..Default::default()
+    };
+
+    let result = run_with_limits("while :; do :; done", limits).await;
+
+    let Err(CodexErr::Sandbox(SandboxErr::ResourceLimitExceeded { limit, .. })) = result else {
+        panic!("expected the CPU time limit to be exceeded, got {result:?}");
+    };
+    assert_eq!(limit, ResourceLimit::CpuTime { seconds: 1 });
+}
@@ -7,7 +7,9 @@ use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
 
+use codex_core::protocol::NetworkAccess;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::resource_limits::ResourceLimiter;
 use codex_core::seatbelt::spawn_command_under_seatbelt;
 use codex_core::spawn::CODEX_SANDBOX_ENV_VAR;
 use codex_core::spawn::StdioPolicy;
@@ -77,9 +79,11 @@ async fn if_parent_of_repo_is_writable_then_dot_git_folder_is_writable() {
     let test_scenario = create_test_scenario(&tmp);
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: vec![test_scenario.repo_parent.clone()],
-        network_access: false,
+        network_access: NetworkAccess::Disabled,
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
+        readable_roots: None,
+        allowed_domains: vec![],
     };
 
     test_scenario
@@ -103,9 +107,11 @@ async fn if_git_repo_is_writable_root_then_dot_git_folder_is_read_only() {
     let test_scenario = create_test_scenario(&tmp);
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: vec![test_scenario.repo_root.clone()],
-        network_access: false,
+        network_access: NetworkAccess::Disabled,
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
+        readable_roots: None,
+        allowed_domains: vec![],
     };
 
     test_scenario
@@ -186,6 +192,7 @@ async fn python_getpwuid_works_under_seatbelt() {
         sandbox_cwd.as_path(),
         StdioPolicy::RedirectForShellTool,
         HashMap::new(),
+        &ResourceLimiter::default(),
     )
     .await
     .expect("should be able to spawn python under seatbelt");
@@ -231,6 +238,7 @@ async fn touch(path: &Path, policy: &SandboxPolicy) -> bool {
         sandbox_cwd.as_path(),
         StdioPolicy::RedirectForShellTool,
         HashMap::new(),
+        &ResourceLimiter::default(),
     )
     .await
     .expect("should be able to spawn command under seatbelt");
@@ -56,6 +56,28 @@ pub struct Cli {
     #[arg(long = "output-schema", value_name = "FILE")]
     pub output_schema: Option<PathBuf>,
 
+    /// Number of follow-up turns that ask the model to fix a final response
+    /// that does not match `--output-schema` (default 2).
+    #[arg(
+        long = "output-schema-repair-attempts",
+        value_name = "N",
+        requires = "output_schema"
+    )]
+    pub output_schema_repair_attempts: Option<u32>,
+
+    /// Abort the task instead of making more than this many tool calls.
+    #[arg(long = "max-turn-tool-calls", value_name = "N")]
+    pub max_turn_tool_calls: Option<u64>,
+
+    /// Abort the task once the model has generated more than this many output tokens.
+    #[arg(long = "max-task-tokens", value_name = "N")]
+    pub max_task_tokens: Option<u64>,
+
+    /// Abort the task once it has run for more than this many seconds; a running
+    /// command is allowed to finish.
+    #[arg(long = "max-task-duration", value_name = "SECONDS")]
+    pub max_task_duration: Option<u64>,
+
     #[clap(skip)]
     pub config_overrides: CliConfigOverrides,
 
@@ -17,10 +17,13 @@ use codex_core::protocol::FileChange;
 use codex_core::protocol::McpInvocation;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
+use codex_core::protocol::NetworkAccessEvent;
+use codex_core::protocol::OutputSchemaValidationFailedEvent;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::PatchApplyEndEvent;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::StreamErrorEvent;
+use codex_core::protocol::SubAgentEventEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TurnAbortReason;
 use codex_core::protocol::TurnDiffEvent;
@@ -190,13 +193,29 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 }
                 return CodexStatus::InitiateShutdown;
             }
+            EventMsg::OutputSchemaValidationFailed(OutputSchemaValidationFailedEvent {
+                errors,
+            }) => {
+                let prefix = "ERROR:".style(self.red);
+                ts_println!(
+                    self,
+                    "{prefix} final message does not match the output schema"
+                );
+                for error in errors {
+                    ts_println!(self, "  - {error}");
+                }
+            }
             EventMsg::TokenCount(ev) => {
                 if let Some(usage_info) = ev.info {
-                    ts_println!(
-                        self,
-                        "tokens used: {}",
-                        format_with_separators(usage_info.total_token_usage.blended_total())
-                    );
+                    let tokens_used =
+                        format_with_separators(usage_info.total_token_usage.blended_total());
+                    match usage_info.total_cost_usd {
+                        Some(cost_usd) => ts_println!(
+                            self,
+                            "tokens used: {tokens_used} (estimated cost: ${cost_usd:.4})"
+                        ),
+                        None => ts_println!(self, "tokens used: {tokens_used}"),
+                    }
                 }
             }
             EventMsg::AgentMessageDelta(AgentMessageDeltaEvent { delta }) => {
@@ -329,6 +348,22 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 }
                 println!("{}", truncated_output.style(self.dimmed));
             }
+            EventMsg::NetworkAccess(NetworkAccessEvent {
+                host,
+                port,
+                allowed,
+                ..
+            }) => {
+                if allowed {
+                    ts_println!(self, "{} {host}:{port}", "network".style(self.magenta));
+                } else {
+                    ts_println!(
+                        self,
+                        "{} {host}:{port} blocked: not in allowed_domains",
+                        "network".style(self.red)
+                    );
+                }
+            }
             EventMsg::McpToolCallBegin(McpToolCallBeginEvent {
                 call_id: _,
                 invocation,
@@ -590,12 +625,42 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 TurnAbortReason::ReviewEnded => {
                     ts_println!(self, "task aborted: review ended");
                 }
+                TurnAbortReason::BudgetExceeded(budget) => {
+                    ts_println!(self, "task aborted: exceeded the {budget} budget");
+                    return CodexStatus::InitiateShutdown;
+                }
             },
             EventMsg::ShutdownComplete => return CodexStatus::Shutdown,
             EventMsg::ConversationPath(_) => {}
             EventMsg::UserMessage(_) => {}
             EventMsg::EnteredReviewMode(_) => {}
             EventMsg::ExitedReviewMode(_) => {}
+            EventMsg::SubAgentEvent(SubAgentEventEvent { msg, .. }) => match *msg {
+                EventMsg::AgentMessage(AgentMessageEvent { message }) => {
+                    ts_println!(
+                        self,
+                        "{}\n{}",
+                        "sub-agent".style(self.italic).style(self.magenta),
+                        message.style(self.dimmed),
+                    );
+                }
+                EventMsg::ExecCommandBegin(ExecCommandBeginEvent { command, cwd, .. }) => {
+                    ts_println!(
+                        self,
+                        "{} {} in {}",
+                        "sub-agent exec".style(self.magenta),
+                        escape_command(&command).style(self.bold),
+                        cwd.to_string_lossy(),
+                    );
+                }
+                EventMsg::Error(ErrorEvent { message }) => {
+                    let prefix = "sub-agent ERROR:".style(self.red);
+                    ts_println!(self, "{prefix} {message}");
+                }
+                _ => {
+                    // Other sub-agent events are not surfaced in exec output.
+                }
+            },
         }
         CodexStatus::Running
     }
@@ -6,6 +6,7 @@ mod event_processor_with_json_output;
 use std::io::IsTerminal;
 use std::io::Read;
 use std::path::PathBuf;
+use std::time::Duration;
 
 pub use cli::Cli;
 use codex_core::AuthManager;
@@ -15,12 +16,16 @@ use codex_core::NewConversation;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
 use codex_core::git_info::get_git_repo_root;
+use codex_core::output_schema::check_output_schema;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
+use codex_core::protocol::OutputSchemaValidationFailedEvent;
 use codex_core::protocol::TaskCompleteEvent;
+use codex_core::protocol::TurnAbortReason;
+use codex_core::protocol::TurnAbortedEvent;
 use codex_ollama::DEFAULT_OSS_MODEL;
 use codex_protocol::config_types::SandboxMode;
 use event_processor_with_human_output::EventProcessorWithHumanOutput;
@@ -36,7 +41,16 @@ use crate::event_processor::CodexStatus;
 use crate::event_processor::EventProcessor;
 use codex_core::find_conversation_path_by_id_str;
 
-pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()> {
+/// Exit code used when the task was aborted because it exceeded one of its
+/// budgets (`--max-turn-tool-calls`, `--max-task-tokens`, `--max-task-duration`).
+pub const BUDGET_EXCEEDED_EXIT_CODE: i32 = 3;
+
+/// Exit code used when the final message still did not match
+/// `--output-schema` after the configured repair attempts.
+pub const OUTPUT_SCHEMA_MISMATCH_EXIT_CODE: i32 = 4;
+
+/// Runs the task and returns the exit code the process should exit with.
+pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<i32> {
     let Cli {
         command,
         images,
@@ -53,6 +67,10 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         sandbox_mode: sandbox_mode_cli_arg,
         prompt,
         output_schema: output_schema_path,
+        output_schema_repair_attempts,
+        max_turn_tool_calls,
+        max_task_tokens,
+        max_task_duration,
         include_plan_tool,
         config_overrides,
     } = cli;
@@ -167,6 +185,10 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         include_view_image_tool: None,
         show_raw_agent_reasoning: oss.then_some(true),
         tools_web_search_request: None,
+        max_turn_tool_calls,
+        max_task_tokens,
+        max_task_duration: max_task_duration.map(Duration::from_secs),
+        output_schema_repair_attempts,
     };
     // Parse `-c` overrides.
     let cli_kv_overrides = match config_overrides.parse_overrides() {
@@ -315,7 +337,18 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
     info!("Sent prompt with event ID: {initial_prompt_task_id}");
 
     // Run the loop until the task is complete.
+    let mut budget_exceeded = false;
+    let mut output_schema_errors: Option<Vec<String>> = None;
     while let Some(event) = rx.recv().await {
+        match &event.msg {
+            EventMsg::TurnAborted(TurnAbortedEvent {
+                reason: TurnAbortReason::BudgetExceeded(_),
+            }) => budget_exceeded = true,
+            EventMsg::OutputSchemaValidationFailed(OutputSchemaValidationFailedEvent {
+                errors,
+            }) => output_schema_errors = Some(errors.clone()),
+            _ => {}
+        }
         let shutdown: CodexStatus = event_processor.process_event(event);
         match shutdown {
             CodexStatus::Running => continue,
@@ -328,7 +361,21 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         }
     }
 
-    Ok(())
+    if budget_exceeded {
+        return Ok(BUDGET_EXCEEDED_EXIT_CODE);
+    }
+    if let Some(errors) = output_schema_errors {
+        // Report the mismatch on stderr as a single JSON object so that
+        // callers can tell it apart from the agent's own output.
+        let error = serde_json::json!({
+            "type": "output_schema_validation_failed",
+            "errors": errors,
+        });
+        eprintln!("{error}");
+        return Ok(OUTPUT_SCHEMA_MISMATCH_EXIT_CODE);
+    }
+
+    Ok(0)
 }
 
 async fn resolve_resume_path(
@@ -365,8 +412,8 @@ fn load_output_schema(path: Option<PathBuf>) -> Option<Value> {
         }
     };
 
-    match serde_json::from_str::<Value>(&schema_str) {
-        Ok(value) => Some(value),
+    let schema = match serde_json::from_str::<Value>(&schema_str) {
+        Ok(value) => value,
         Err(err) => {
             eprintln!(
                 "Output schema file {} is not valid JSON: {err}",
@@ -374,5 +421,14 @@ fn load_output_schema(path: Option<PathBuf>) -> Option<Value> {
             );
             std::process::exit(1);
         }
+    };
+
+    if let Err(err) = check_output_schema(&schema) {
+        eprintln!(
+            "Output schema file {} is not a valid JSON Schema: {err}",
+            path.display()
+        );
+        std::process::exit(1);
     }
+    Some(schema)
 }
@@ -34,7 +34,10 @@ fn main() -> anyhow::Result<()> {
             .raw_overrides
             .splice(0..0, top_cli.config_overrides.raw_overrides);
 
-        run_main(inner, codex_linux_sandbox_exe).await?;
+        let exit_code = run_main(inner, codex_linux_sandbox_exe).await?;
+        if exit_code != 0 {
+            std::process::exit(exit_code);
+        }
         Ok(())
     })
 }
@@ -8,19 +8,23 @@ use std::process::Command;
 use tempfile::TempDir;
 use wiremock::matchers::any;
 
-#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
-async fn exec_includes_output_schema_in_request() -> anyhow::Result<()> {
-    let home = TempDir::new()?;
-    let workspace = TempDir::new()?;
-
-    let schema_contents = serde_json::json!({
+fn answer_schema() -> Value {
+    serde_json::json!({
         "type": "object",
         "properties": {
             "answer": { "type": "string" }
         },
         "required": ["answer"],
         "additionalProperties": false
-    });
+    })
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn exec_includes_output_schema_in_request() -> anyhow::Result<()> {
+    let home = TempDir::new()?;
+    let workspace = TempDir::new()?;
+
+    let schema_contents = answer_schema();
     let schema_path = workspace.path().join("schema.json");
     std::fs::write(&schema_path, serde_json::to_vec_pretty(&schema_contents)?)?;
     let expected_schema: Value = schema_contents;
@@ -31,7 +35,7 @@ async fn exec_includes_output_schema_in_request() -> anyhow::Result<()> {
             "type": "response.created",
             "response": {"id": "resp1"}
         }),
-        responses::ev_assistant_message("m1", "fixture hello"),
+        responses::ev_assistant_message("m1", r#"{"answer": "fixture hello"}"#),
         responses::ev_completed("resp1"),
     ]);
     responses::mount_sse_once(&server, any(), body).await;
@@ -74,3 +78,63 @@ async fn exec_includes_output_schema_in_request() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn exec_fails_when_output_does_not_match_schema() -> anyhow::Result<()> {
+    let home = TempDir::new()?;
+    let workspace = TempDir::new()?;
+
+    let schema_path = workspace.path().join("schema.json");
+    std::fs::write(&schema_path, serde_json::to_vec_pretty(&answer_schema())?)?;
+
+    let server = responses::start_mock_server().await;
+    let body = responses::sse(vec![
+        serde_json::json!({
+            "type": "response.created",
+            "response": {"id": "resp1"}
+        }),
+        responses::ev_assistant_message("m1", r#"{"answer": 42}"#),
+        responses::ev_completed("resp1"),
+    ]);
+    responses::mount_sse_once(&server, any(), body).await;
+
+    let output = Command::cargo_bin("codex-exec")?
+        .current_dir(workspace.path())
+        .env("CODEX_HOME", home.path())
+        .env("OPENAI_API_KEY", "dummy")
+        .env("OPENAI_BASE_URL", format!("{}/v1", server.uri()))
+        .arg("--skip-git-repo-check")
+        .arg("-C")
+        .arg(workspace.path())
+        .arg("--output-schema")
+        .arg(&schema_path)
+        .arg("--output-schema-repair-attempts")
+        .arg("1")
+        .arg("-m")
+        .arg("gpt-5")
+        .arg("tell me a joke")
+        .output()?;
+
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8(output.stderr)?;
+    let error = stderr
+        .lines()
+        .find_map(|line| serde_json::from_str::<Value>(line).ok())
+        .expect("stderr should contain a JSON error");
+    assert_eq!(
+        error,
+        serde_json::json!({
+            "type": "output_schema_validation_failed",
+            "errors": [r#"/answer: 42 is not of type "string""#],
+        })
+    );
+
+    // The first answer and one repair attempt.
+    let requests = server
+        .received_requests()
+        .await
+        .expect("failed to capture requests");
+    assert_eq!(requests.len(), 2);
+
+    Ok(())
+}
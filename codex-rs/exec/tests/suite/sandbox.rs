@@ -1,5 +1,7 @@
 #![cfg(unix)]
+use codex_core::protocol::NetworkAccess;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::resource_limits::ResourceLimiter;
 use codex_core::spawn::StdioPolicy;
 use std::collections::HashMap;
 use std::future::Future;
@@ -27,6 +29,7 @@ async fn spawn_command_under_sandbox(
         sandbox_cwd,
         stdio_policy,
         env,
+        &ResourceLimiter::default(),
     )
     .await
 }
@@ -50,6 +53,8 @@ async fn spawn_command_under_sandbox(
         sandbox_cwd,
         stdio_policy,
         env,
+        &ResourceLimiter::default(),
+        None,
     )
     .await
 }
@@ -68,9 +73,11 @@ async fn python_multiprocessing_lock_works_under_sandbox() {
 
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots,
-        network_access: false,
+        network_access: NetworkAccess::Disabled,
         exclude_tmpdir_env_var: false,
         exclude_slash_tmp: false,
+        readable_roots: None,
+        allowed_domains: vec![],
     };
 
     let python_code = r#"import multiprocessing
@@ -127,9 +134,11 @@ async fn sandbox_distinguishes_command_and_policy_cwds() {
     // is under a writable root.
     let policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: vec![],
-        network_access: false,
+        network_access: NetworkAccess::Disabled,
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
+        readable_roots: None,
+        allowed_domains: vec![],
     };
 
     // Attempt to write inside the command cwd, which is outside of the sandbox policy cwd.
@@ -0,0 +1,744 @@
+//! Records what the sandbox blocked, for Codex to report.
+//!
+//! When Codex passes a pipe in [`CODEX_SANDBOX_DENIALS_FD_ENV_VAR`], the
+//! seccomp filter does not fail the blocked system calls by itself but hands
+//! them to a supervisor through a seccomp user notification listener
+//! (`SECCOMP_RET_USER_NOTIF`). The supervisor writes a [`SandboxDenial`] to
+//! the pipe for each of them and answers:
+//!
+//! - network system calls (and `ptrace`) fail with `EPERM`, exactly as they
+//!   would without the supervisor;
+//! - system calls that change the file-system are also routed through the
+//!   supervisor while Landlock restricts writes. They are recorded when they
+//!   target a path outside the writable roots and then carry on, so Landlock
+//!   still makes the decision.
+//!
+//! The supervisor is the helper process itself. It forks the process that
+//! goes on to exec the command, which lets it read the paths from the
+//! command's memory even when Yama restricts `ptrace` to ancestors, and exits
+//! with the command's status. Only helper processes hold the pipe: the
+//! command does not inherit it, and the supervisor is not dumpable, so the
+//! command cannot reopen it through `/proc` either. Where the
+//! kernel lacks user notifications or `pidfd_getfd(2)`, the plain filter is
+//! installed and nothing is recorded.
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::fd::FromRawFd;
+use std::os::fd::OwnedFd;
+use std::os::fd::RawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::error::Result;
+use codex_core::error::SandboxErr;
+use codex_core::sandbox_denials::FileOperation;
+use codex_core::sandbox_denials::SandboxDenial;
+use codex_core::spawn::CODEX_SANDBOX_DENIALS_FD_ENV_VAR;
+use seccompiler::SeccompCmpArgLen;
+use seccompiler::SeccompCmpOp;
+use seccompiler::SeccompCondition;
+use seccompiler::SeccompRule;
+
+use crate::landlock::compile_seccomp_filter;
+use crate::landlock::install_seccomp_filter_on_current_thread;
+use crate::process::wait_and_exit;
+
+// Not every libc version exposes the seccomp user notification API yet; these
+// come from <linux/seccomp.h>.
+const SECCOMP_SET_MODE_FILTER: libc::c_ulong = 1;
+const SECCOMP_FILTER_FLAG_NEW_LISTENER: libc::c_ulong = 1 << 3;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+const SECCOMP_RET_USER_NOTIF: u32 = 0x7fc0_0000;
+const SECCOMP_USER_NOTIF_FLAG_CONTINUE: u32 = 1;
+const SECCOMP_IOCTL_NOTIF_RECV: libc::c_ulong = 0xc050_2100;
+const SECCOMP_IOCTL_NOTIF_SEND: libc::c_ulong = 0xc018_2101;
+
+/// `BPF_RET | BPF_K`, a BPF instruction returning a constant.
+const BPF_RET_K: u16 = 0x06;
+
+/// Stop recording after this many distinct denials.
+const MAX_RECORDED_DENIALS: usize = 64;
+
+/// Pseudo file-systems that programs probe all the time, e.g. bash opening
+/// `/dev/tty`, without failing when they cannot write to them.
+const IGNORED_DIRS: &[&str] = &["/dev", "/proc", "/sys"];
+
+#[repr(C)]
+#[derive(Default)]
+struct SeccompData {
+    nr: libc::c_int,
+    arch: u32,
+    instruction_pointer: u64,
+    args: [u64; 6],
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct SeccompNotif {
+    id: u64,
+    pid: u32,
+    flags: u32,
+    data: SeccompData,
+}
+
+#[repr(C)]
+struct SeccompNotifResp {
+    id: u64,
+    val: i64,
+    error: i32,
+    flags: u32,
+}
+
+/// A system call that can change the file-system.
+struct WatchedSyscall {
+    nr: i64,
+    operation: FileOperation,
+    /// The arguments holding the directory file descriptor, if any, and the
+    /// path of each path the system call changes.
+    paths: &'static [(Option<usize>, usize)],
+    /// The argument holding the `open(2)` flags, for system calls that only
+    /// write with some of them.
+    flags: Option<u8>,
+}
+
+const fn watched(
+    nr: i64,
+    operation: FileOperation,
+    paths: &'static [(Option<usize>, usize)],
+    flags: Option<u8>,
+) -> WatchedSyscall {
+    WatchedSyscall {
+        nr,
+        operation,
+        paths,
+        flags,
+    }
+}
+
+fn watched_syscalls() -> Vec<WatchedSyscall> {
+    #[allow(unused_mut)]
+    let mut syscalls = vec![
+        watched(
+            libc::SYS_openat,
+            FileOperation::Write,
+            &[(Some(0), 1)],
+            Some(2),
+        ),
+        watched(libc::SYS_truncate, FileOperation::Write, &[(None, 0)], None),
+        watched(
+            libc::SYS_mknodat,
+            FileOperation::Write,
+            &[(Some(0), 1)],
+            None,
+        ),
+        watched(
+            libc::SYS_mkdirat,
+            FileOperation::CreateDirectory,
+            &[(Some(0), 1)],
+            None,
+        ),
+        watched(
+            libc::SYS_unlinkat,
+            FileOperation::Remove,
+            &[(Some(0), 1)],
+            None,
+        ),
+        watched(
+            libc::SYS_renameat,
+            FileOperation::Rename,
+            &[(Some(0), 1), (Some(2), 3)],
+            None,
+        ),
+        watched(
+            libc::SYS_renameat2,
+            FileOperation::Rename,
+            &[(Some(0), 1), (Some(2), 3)],
+            None,
+        ),
+        watched(libc::SYS_linkat, FileOperation::Link, &[(Some(2), 3)], None),
+        watched(
+            libc::SYS_symlinkat,
+            FileOperation::Link,
+            &[(Some(1), 2)],
+            None,
+        ),
+    ];
+    #[cfg(target_arch = "x86_64")]
+    syscalls.extend([
+        watched(libc::SYS_open, FileOperation::Write, &[(None, 0)], Some(1)),
+        watched(libc::SYS_creat, FileOperation::Write, &[(None, 0)], None),
+        watched(libc::SYS_mknod, FileOperation::Write, &[(None, 0)], None),
+        watched(
+            libc::SYS_mkdir,
+            FileOperation::CreateDirectory,
+            &[(None, 0)],
+            None,
+        ),
+        watched(libc::SYS_unlink, FileOperation::Remove, &[(None, 0)], None),
+        watched(libc::SYS_rmdir, FileOperation::Remove, &[(None, 0)], None),
+        watched(
+            libc::SYS_rename,
+            FileOperation::Rename,
+            &[(None, 0), (None, 1)],
+            None,
+        ),
+        watched(libc::SYS_link, FileOperation::Link, &[(None, 1)], None),
+        watched(libc::SYS_symlink, FileOperation::Link, &[(None, 1)], None),
+    ]);
+    syscalls
+}
+
+/// The denial for a system call the network filter blocked.
+fn blocked_syscall_denial(nr: i64) -> SandboxDenial {
+    let name = match nr {
+        libc::SYS_connect => "connect",
+        libc::SYS_accept => "accept",
+        libc::SYS_accept4 => "accept4",
+        libc::SYS_bind => "bind",
+        libc::SYS_listen => "listen",
+        libc::SYS_getpeername => "getpeername",
+        libc::SYS_getsockname => "getsockname",
+        libc::SYS_shutdown => "shutdown",
+        libc::SYS_sendto => "sendto",
+        libc::SYS_sendmsg => "sendmsg",
+        libc::SYS_sendmmsg => "sendmmsg",
+        libc::SYS_recvmsg => "recvmsg",
+        libc::SYS_recvmmsg => "recvmmsg",
+        libc::SYS_getsockopt => "getsockopt",
+        libc::SYS_setsockopt => "setsockopt",
+        libc::SYS_socket => "socket",
+        libc::SYS_socketpair => "socketpair",
+        libc::SYS_ptrace => {
+            return SandboxDenial::Syscall {
+                name: "ptrace".to_string(),
+            };
+        }
+        _ => {
+            return SandboxDenial::Syscall {
+                name: format!("syscall {nr}"),
+            };
+        }
+    };
+    SandboxDenial::Network {
+        syscall: name.to_string(),
+    }
+}
+
+/// The pipe Codex asked the helper to record denials in.
+pub(crate) struct DenialRecorder {
+    log: File,
+}
+
+impl DenialRecorder {
+    /// Takes the pipe Codex put in the environment, and removes it from the
+    /// environment so the command does not see it. The pipe is closed when
+    /// the command is executed, and does not block: when Codex has not kept
+    /// up, denials are dropped rather than holding up the command.
+    pub(crate) fn from_env() -> Option<Self> {
+        let fd = std::env::var_os(CODEX_SANDBOX_DENIALS_FD_ENV_VAR)?;
+        // The helper is single threaded, so nothing reads the environment
+        // concurrently.
+        unsafe {
+            std::env::remove_var(CODEX_SANDBOX_DENIALS_FD_ENV_VAR);
+        }
+        match take_log(&fd) {
+            Ok(log) => Some(Self { log }),
+            Err(e) => {
+                eprintln!(
+                    "codex-linux-sandbox: cannot use the denial log, denials are not reported: {e}"
+                );
+                None
+            }
+        }
+    }
+}
+
+fn take_log(fd: &OsStr) -> io::Result<File> {
+    let fd = fd
+        .to_str()
+        .and_then(|fd| fd.parse::<RawFd>().ok())
+        .filter(|&fd| fd > 2)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file descriptor"))?;
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let log = unsafe { File::from_raw_fd(fd) };
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFL) };
+    if flags < 0 || unsafe { libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(log)
+}
+
+/// Installs `network_rules` as a seccomp filter whose blocked system calls
+/// are recorded by a supervisor, as described in the module documentation.
+/// With `writable_roots`, writes outside of them are recorded as well. Like
+/// [`crate::overlay::enter_overlay_sandbox`], this forks and only returns in
+/// the child, so it must be called while the process is still single
+/// threaded.
+pub(crate) fn install_supervised_seccomp_filter(
+    network_rules: BTreeMap<i64, Vec<SeccompRule>>,
+    writable_roots: Option<Vec<PathBuf>>,
+    recorder: DenialRecorder,
+) -> Result<()> {
+    if network_rules.is_empty() && writable_roots.is_none() {
+        return Ok(());
+    }
+
+    // Plain pipes rather than a socket pair: the filter blocks most socket
+    // system calls, and the child keeps talking to the supervisor after
+    // installing it.
+    let (from_child, to_supervisor) = pipe()?;
+    let (from_supervisor, to_child) = pipe()?;
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error().into()),
+        0 => {
+            unsafe {
+                libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+            }
+            drop((from_child, to_child, recorder));
+            let watch_writes = writable_roots.is_some();
+            install_filter_in_child(network_rules, watch_writes, from_supervisor, to_supervisor)
+        }
+        child => {
+            // Keep the command, which runs as the same user, out of
+            // /proc/<pid>/fd of the supervisor and so away from the log.
+            unsafe {
+                libc::prctl(libc::PR_SET_DUMPABLE, 0);
+            }
+            let child_fd = to_supervisor.as_raw_fd();
+            drop((from_supervisor, to_supervisor));
+            if let Some((listener, pidfd)) = take_listener(child, child_fd, from_child, to_child) {
+                Supervisor::new(recorder, writable_roots).serve(child, listener, pidfd);
+            }
+            wait_and_exit(child)
+        }
+    }
+}
+
+/// The child's side of the handshake: install the notifying filter if the
+/// supervisor can take over its listener, the plain filter otherwise.
+fn install_filter_in_child(
+    network_rules: BTreeMap<i64, Vec<SeccompRule>>,
+    watch_writes: bool,
+    mut from_supervisor: File,
+    mut to_supervisor: File,
+) -> Result<()> {
+    let mut supervised = [0u8];
+    from_supervisor.read_exact(&mut supervised)?;
+    if supervised[0] == 0 {
+        return install_plain_filter(network_rules);
+    }
+
+    let mut rules = network_rules.clone();
+    if watch_writes {
+        for syscall in watched_syscalls() {
+            let rule = match syscall.flags {
+                Some(arg) => write_flag_rules(arg)?,
+                None => vec![],
+            };
+            rules.insert(syscall.nr, rule);
+        }
+    }
+    let listener = match install_notifying_filter(rules) {
+        Ok(listener) => listener,
+        // E.g. a kernel without user notifications: the command runs as
+        // before, only without reports.
+        Err(_) => {
+            to_supervisor.write_all(&u32::MAX.to_ne_bytes())?;
+            return install_plain_filter(network_rules);
+        }
+    };
+    to_supervisor.write_all(&(listener.as_raw_fd() as u32).to_ne_bytes())?;
+
+    // Keep the listener open until the supervisor has its own copy; without a
+    // listener the filtered system calls fail with `ENOSYS`.
+    from_supervisor.read_exact(&mut supervised)?;
+    if supervised[0] == 0 {
+        return Err(io::Error::other("the supervisor cannot receive sandbox denials").into());
+    }
+    Ok(())
+}
+
+fn install_plain_filter(network_rules: BTreeMap<i64, Vec<SeccompRule>>) -> Result<()> {
+    if !network_rules.is_empty() {
+        install_seccomp_filter_on_current_thread(network_rules)?;
+    }
+    Ok(())
+}
+
+/// Rules matching `open(2)` flags that can change the file.
+fn write_flag_rules(arg: u8) -> std::result::Result<Vec<SeccompRule>, SandboxErr> {
+    let mut rules = Vec::new();
+    for flag in [libc::O_WRONLY, libc::O_RDWR, libc::O_CREAT, libc::O_TRUNC] {
+        rules.push(SeccompRule::new(vec![SeccompCondition::new(
+            arg,
+            SeccompCmpArgLen::Dword,
+            SeccompCmpOp::MaskedEq(flag as u64),
+            flag as u64,
+        )?])?);
+    }
+    Ok(rules)
+}
+
+/// seccompiler has no action for user notifications, so the filter is built
+/// to return `EPERM` and those returns are swapped afterwards.
+fn install_notifying_filter(rules: BTreeMap<i64, Vec<SeccompRule>>) -> Result<OwnedFd> {
+    let mut program = compile_seccomp_filter(rules)?;
+    for instruction in &mut program {
+        if instruction.code == BPF_RET_K && instruction.k == SECCOMP_RET_ERRNO | libc::EPERM as u32
+        {
+            instruction.k = SECCOMP_RET_USER_NOTIF;
+        }
+    }
+    let fprog = libc::sock_fprog {
+        len: program.len() as libc::c_ushort,
+        filter: program.as_mut_ptr().cast(),
+    };
+    let listener = unsafe {
+        if libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        libc::syscall(
+            libc::SYS_seccomp,
+            SECCOMP_SET_MODE_FILTER,
+            SECCOMP_FILTER_FLAG_NEW_LISTENER,
+            &fprog as *const libc::sock_fprog,
+        )
+    };
+    if listener < 0 {
+        return Err(io::Error::last_os_error().into());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(listener as RawFd) })
+}
+
+/// The supervisor's side of the handshake. Returns the listener and a pidfd
+/// for the child when the supervisor got hold of the listener.
+fn take_listener(
+    child: libc::pid_t,
+    child_fd: RawFd,
+    mut from_child: File,
+    mut to_child: File,
+) -> Option<(OwnedFd, OwnedFd)> {
+    let pidfd = pidfd_open(child).ok();
+    // Taking a file descriptor the child is known to have shows whether the
+    // kernel and its ptrace restrictions allow taking the listener later.
+    let supervised = pidfd
+        .as_ref()
+        .is_some_and(|pidfd| pidfd_getfd(pidfd, child_fd).is_ok());
+    to_child.write_all(&[u8::from(supervised)]).ok()?;
+    let pidfd = pidfd.filter(|_| supervised)?;
+
+    let mut listener_fd = [0u8; 4];
+    from_child.read_exact(&mut listener_fd).ok()?;
+    let listener_fd = u32::from_ne_bytes(listener_fd);
+    if listener_fd == u32::MAX {
+        return None;
+    }
+    let listener = pidfd_getfd(&pidfd, listener_fd as RawFd).ok();
+    to_child.write_all(&[u8::from(listener.is_some())]).ok()?;
+    Some((listener?, pidfd))
+}
+
+struct Supervisor {
+    recorder: DenialRecorder,
+    /// The writable roots, as given and canonicalized, when writes are
+    /// watched.
+    writable_roots: Vec<PathBuf>,
+    watched: Vec<WatchedSyscall>,
+    recorded: HashSet<SandboxDenial>,
+}
+
+impl Supervisor {
+    fn new(recorder: DenialRecorder, writable_roots: Option<Vec<PathBuf>>) -> Self {
+        let watched = if writable_roots.is_some() {
+            watched_syscalls()
+        } else {
+            Vec::new()
+        };
+        let mut roots = writable_roots.unwrap_or_default();
+        let canonical: Vec<PathBuf> = roots
+            .iter()
+            .filter_map(|root| root.canonicalize().ok())
+            .collect();
+        roots.extend(canonical);
+        Self {
+            recorder,
+            writable_roots: roots,
+            watched,
+            recorded: HashSet::new(),
+        }
+    }
+
+    /// Answers notifications until the child exits, then exits like it.
+    /// Processes the command left running in the background keep using the
+    /// filter, so a detached process keeps answering for them until they are
+    /// gone too; without a listener their system calls would fail with
+    /// `ENOSYS`.
+    fn serve(mut self, child: libc::pid_t, listener: OwnedFd, pidfd: OwnedFd) -> ! {
+        let mut fds = [
+            libc::pollfd {
+                fd: listener.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+            libc::pollfd {
+                fd: pidfd.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            },
+        ];
+        loop {
+            if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } < 0 {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                break;
+            }
+            if fds[0].revents & libc::POLLIN != 0 {
+                self.answer(&listener);
+                continue;
+            }
+            if fds[0].revents & (libc::POLLHUP | libc::POLLERR) != 0 {
+                // Nothing uses the filter any more.
+                break;
+            }
+            if fds[1].revents & libc::POLLIN != 0 {
+                if unsafe { libc::fork() } == 0 {
+                    self.serve_background(&listener);
+                }
+                break;
+            }
+        }
+        wait_and_exit(child)
+    }
+
+    /// Body of the detached process started by [`Self::serve`]. Nothing it
+    /// records is read any more, the point is to let the system calls through.
+    fn serve_background(&mut self, listener: &OwnedFd) -> ! {
+        // Let go of the pipes Codex reads the output from.
+        if let Ok(dev_null) = File::options().read(true).write(true).open("/dev/null") {
+            for fd in 0..3 {
+                unsafe {
+                    libc::dup2(dev_null.as_raw_fd(), fd);
+                }
+            }
+        }
+        let mut fds = [libc::pollfd {
+            fd: listener.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        loop {
+            if unsafe { libc::poll(fds.as_mut_ptr(), 1, -1) } < 0 {
+                if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                break;
+            }
+            if fds[0].revents & libc::POLLIN != 0 {
+                self.answer(listener);
+            } else if fds[0].revents & (libc::POLLHUP | libc::POLLERR) != 0 {
+                break;
+            }
+        }
+        unsafe { libc::_exit(0) }
+    }
+
+    /// Receives one notification, records the denial it stands for, if any,
+    /// and answers it.
+    fn answer(&mut self, listener: &OwnedFd) {
+        let mut notif = SeccompNotif::default();
+        if unsafe {
+            libc::ioctl(
+                listener.as_raw_fd(),
+                SECCOMP_IOCTL_NOTIF_RECV as _,
+                &mut notif as *mut SeccompNotif,
+            )
+        } != 0
+        {
+            // The target was interrupted or killed in the meantime.
+            return;
+        }
+
+        let mut response = SeccompNotifResp {
+            id: notif.id,
+            val: 0,
+            error: 0,
+            flags: 0,
+        };
+        let nr = i64::from(notif.data.nr);
+        match self.watched.iter().find(|syscall| syscall.nr == nr) {
+            Some(syscall) => {
+                if let Some(path) = self.blocked_path(&notif, syscall) {
+                    self.record(SandboxDenial::FileSystem {
+                        operation: syscall.operation,
+                        path,
+                    });
+                }
+                response.flags = SECCOMP_USER_NOTIF_FLAG_CONTINUE;
+            }
+            None => {
+                self.record(blocked_syscall_denial(nr));
+                response.error = -libc::EPERM;
+            }
+        }
+        // Fails if the target is gone, which is fine.
+        unsafe {
+            libc::ioctl(
+                listener.as_raw_fd(),
+                SECCOMP_IOCTL_NOTIF_SEND as _,
+                &mut response as *mut SeccompNotifResp,
+            );
+        }
+    }
+
+    /// The first path outside the writable roots that the system call would
+    /// change, if any.
+    fn blocked_path(&self, notif: &SeccompNotif, syscall: &WatchedSyscall) -> Option<PathBuf> {
+        let args = &notif.data.args;
+        for (dirfd_arg, path_arg) in syscall.paths {
+            let path = read_path(notif.pid, args[*path_arg])?;
+            let dirfd = dirfd_arg.map_or(libc::AT_FDCWD, |arg| args[arg] as libc::c_int);
+            let path = resolve(notif.pid, dirfd, &path)?;
+            if IGNORED_DIRS.iter().any(|dir| path.starts_with(dir)) || self.is_writable(&path) {
+                continue;
+            }
+            let exists = path.symlink_metadata().is_ok();
+            let changes = match (syscall.operation, syscall.flags) {
+                // Opening an existing file read-only with `O_CREAT` leaves it
+                // alone.
+                (FileOperation::Write, Some(arg)) => {
+                    let flags = args[usize::from(arg)] as libc::c_int;
+                    !exists
+                        || flags & libc::O_ACCMODE != libc::O_RDONLY
+                        || flags & libc::O_TRUNC != 0
+                }
+                // These fail with `EEXIST` or `ENOENT` before the sandbox is
+                // asked, e.g. for every parent in `mkdir -p`.
+                (FileOperation::CreateDirectory | FileOperation::Link, _) => !exists,
+                (FileOperation::Remove, _) => exists,
+                _ => true,
+            };
+            if changes {
+                return Some(path);
+            }
+        }
+        None
+    }
+
+    fn is_writable(&self, path: &Path) -> bool {
+        let canonical = path
+            .parent()
+            .and_then(|parent| parent.canonicalize().ok())
+            .zip(path.file_name())
+            .map(|(parent, name)| parent.join(name));
+        [Some(path.to_path_buf()), canonical]
+            .into_iter()
+            .flatten()
+            .any(|path| {
+                self.writable_roots
+                    .iter()
+                    .any(|root| path.starts_with(root))
+            })
+    }
+
+    fn record(&mut self, denial: SandboxDenial) {
+        if self.recorded.len() < MAX_RECORDED_DENIALS && self.recorded.insert(denial.clone()) {
+            // Best effort: the command runs the same either way.
+            let _ = denial.append_to(&mut self.recorder.log);
+        }
+    }
+}
+
+/// Reads a NUL-terminated path from the memory of `pid`.
+fn read_path(pid: u32, address: u64) -> Option<PathBuf> {
+    const PAGE_SIZE: usize = 4096;
+    let mut buf = vec![0u8; libc::PATH_MAX as usize];
+    let mut read = 0;
+    while read < buf.len() {
+        // Stay within one page per read: the path may end right before an
+        // unmapped page.
+        let remote = address.checked_add(read as u64)? as usize;
+        let len = (PAGE_SIZE - remote % PAGE_SIZE).min(buf.len() - read);
+        let local_iov = libc::iovec {
+            iov_base: buf[read..].as_mut_ptr().cast(),
+            iov_len: len,
+        };
+        let remote_iov = libc::iovec {
+            iov_base: remote as *mut libc::c_void,
+            iov_len: len,
+        };
+        let n =
+            unsafe { libc::process_vm_readv(pid as libc::pid_t, &local_iov, 1, &remote_iov, 1, 0) };
+        if n <= 0 {
+            return None;
+        }
+        let n = n as usize;
+        if let Some(end) = buf[read..read + n].iter().position(|byte| *byte == 0) {
+            buf.truncate(read + end);
+            return Some(PathBuf::from(OsStr::from_bytes(&buf)));
+        }
+        read += n;
+    }
+    None
+}
+
+/// Makes `path` absolute the way the kernel would for `pid`, and removes `.`
+/// and `..` components.
+fn resolve(pid: u32, dirfd: libc::c_int, path: &Path) -> Option<PathBuf> {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        let dir = if dirfd == libc::AT_FDCWD {
+            format!("/proc/{pid}/cwd")
+        } else {
+            format!("/proc/{pid}/fd/{dirfd}")
+        };
+        std::fs::read_link(dir).ok()?.join(path)
+    };
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    Some(resolved)
+}
+
+fn pipe() -> io::Result<(File, File)> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) })
+}
+
+fn pidfd_open(pid: libc::pid_t) -> io::Result<OwnedFd> {
+    let pidfd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid, 0) };
+    if pidfd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(pidfd as RawFd) })
+}
+
+fn pidfd_getfd(pidfd: &OwnedFd, fd: RawFd) -> io::Result<OwnedFd> {
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_getfd, pidfd.as_raw_fd(), fd, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as RawFd) })
+}
@@ -7,6 +7,13 @@ use codex_core::error::Result;
 use codex_core::error::SandboxErr;
 use codex_core::protocol::SandboxPolicy;
 
+use codex_core::protocol::NetworkAccess;
+
+use crate::denials::DenialRecorder;
+use crate::denials::install_supervised_seccomp_filter;
+use crate::network_namespace::ProxyBridge;
+use crate::network_namespace::enter_loopback_only_network_namespace;
+
 use landlock::ABI;
 use landlock::Access;
 use landlock::AccessFs;
@@ -26,48 +33,93 @@ use seccompiler::TargetArch;
 use seccompiler::apply_filter;
 
 /// Apply sandbox policies inside this thread so only the child inherits
-/// them, not the entire CLI process.
+/// them, not the entire CLI process. File-system access is only restricted
+/// when `restrict_filesystem` is set. With a `denial_recorder`, blocked
+/// operations are recorded by a supervisor process, see [`crate::denials`].
 pub(crate) fn apply_sandbox_policy_to_current_thread(
     sandbox_policy: &SandboxPolicy,
     cwd: &Path,
+    restrict_filesystem: bool,
+    denial_recorder: Option<DenialRecorder>,
 ) -> Result<()> {
+    let mut network_rules = BTreeMap::new();
     if !sandbox_policy.has_full_network_access() {
-        install_network_seccomp_filter_on_current_thread()?;
+        // In loopback mode, or with an allowlist served by the Codex network
+        // proxy, the command gets its own network namespace, and the command
+        // does not run if the namespace cannot be created. Otherwise all
+        // network access is blocked, including when the proxy did not start,
+        // which Codex reports to the user.
+        let proxy_bridge = if sandbox_policy.allowed_domains().is_empty() {
+            None
+        } else {
+            ProxyBridge::from_env()
+        };
+        let loopback = sandbox_policy.network_access() == NetworkAccess::Loopback;
+        if loopback || proxy_bridge.is_some() {
+            enter_loopback_only_network_namespace(proxy_bridge.as_ref())
+                .map_err(|e| CodexErr::Sandbox(SandboxErr::NetworkNamespace(e.to_string())))?;
+        } else {
+            network_rules = network_seccomp_rules()?;
+        }
     }
 
-    if !sandbox_policy.has_full_disk_write_access() {
-        let writable_roots = sandbox_policy
-            .get_writable_roots_with_cwd(cwd)
-            .into_iter()
-            .map(|writable_root| writable_root.root)
-            .collect();
-        install_filesystem_landlock_rules_on_current_thread(writable_roots)?;
+    let restrict_filesystem = restrict_filesystem && !sandbox_policy.has_full_disk_write_access();
+    // Landlock rules grant access to whole trees and cannot carve a read-only
+    // subpath out of a writable root, so `read_only_subpaths` (`.git`,
+    // `.codex`) are not enforced here; docs/config.md says so.
+    let writable_roots: Vec<PathBuf> = sandbox_policy
+        .get_writable_roots_with_cwd(cwd)
+        .into_iter()
+        .map(|writable_root| writable_root.root)
+        .collect();
+
+    match denial_recorder {
+        Some(denial_recorder) => install_supervised_seccomp_filter(
+            network_rules,
+            restrict_filesystem.then(|| writable_roots.clone()),
+            denial_recorder,
+        )?,
+        None if !network_rules.is_empty() => {
+            install_seccomp_filter_on_current_thread(network_rules)?;
+        }
+        None => {}
     }
 
-    // TODO(ragona): Add appropriate restrictions if
-    // `sandbox_policy.has_full_disk_read_access()` is `false`.
+    if restrict_filesystem {
+        let readable_roots = sandbox_policy.get_readable_roots_with_cwd(cwd);
+        install_filesystem_landlock_rules_on_current_thread(writable_roots, readable_roots)?;
+    }
 
     Ok(())
 }
 
-/// Installs Landlock file-system rules on the current thread allowing read
-/// access to the entire file-system while restricting write access to
-/// `/dev/null` and the provided list of `writable_roots`.
+/// Installs Landlock file-system rules on the current thread restricting
+/// write access to `/dev/null` and the provided list of `writable_roots`.
+/// Read access is granted to the entire file-system, or only to
+/// `readable_roots` when the policy restricts reads.
 ///
 /// # Errors
 /// Returns [`CodexErr::Sandbox`] variants when the ruleset fails to apply.
-fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathBuf>) -> Result<()> {
+fn install_filesystem_landlock_rules_on_current_thread(
+    writable_roots: Vec<PathBuf>,
+    readable_roots: Option<Vec<PathBuf>>,
+) -> Result<()> {
     let abi = ABI::V5;
     let access_rw = AccessFs::from_all(abi);
     let access_ro = AccessFs::from_read(abi);
 
-    let mut ruleset = Ruleset::default()
+    let ruleset = Ruleset::default()
         .set_compatibility(CompatLevel::BestEffort)
         .handle_access(access_rw)?
-        .create()?
-        .add_rules(landlock::path_beneath_rules(&["/"], access_ro))?
-        .add_rules(landlock::path_beneath_rules(&["/dev/null"], access_rw))?
-        .set_no_new_privs(true);
+        .create()?;
+    let mut ruleset = match readable_roots {
+        Some(readable_roots) => {
+            ruleset.add_rules(landlock::path_beneath_rules(&readable_roots, access_ro))?
+        }
+        None => ruleset.add_rules(landlock::path_beneath_rules(&["/"], access_ro))?,
+    }
+    .add_rules(landlock::path_beneath_rules(&["/dev/null"], access_rw))?
+    .set_no_new_privs(true);
 
     if !writable_roots.is_empty() {
         ruleset = ruleset.add_rules(landlock::path_beneath_rules(&writable_roots, access_rw))?;
@@ -82,9 +134,9 @@ fn install_filesystem_landlock_rules_on_current_thread(writable_roots: Vec<PathB
     Ok(())
 }
 
-/// Installs a seccomp filter that blocks outbound network access except for
-/// AF_UNIX domain sockets.
-fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(), SandboxErr> {
+/// Seccomp rules that block outbound network access except for AF_UNIX
+/// domain sockets.
+fn network_seccomp_rules() -> std::result::Result<BTreeMap<i64, Vec<SeccompRule>>, SandboxErr> {
     // Build rule map.
     let mut rules: BTreeMap<i64, Vec<SeccompRule>> = BTreeMap::new();
 
@@ -124,6 +176,14 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
     rules.insert(libc::SYS_socket, vec![unix_only_rule.clone()]);
     rules.insert(libc::SYS_socketpair, vec![unix_only_rule]); // always deny (Unix can use socketpair but fine, keep open?)
 
+    Ok(rules)
+}
+
+/// Compiles `rules` into a filter that fails the matching system calls with
+/// `EPERM` and allows everything else.
+pub(crate) fn compile_seccomp_filter(
+    rules: BTreeMap<i64, Vec<SeccompRule>>,
+) -> std::result::Result<BpfProgram, SandboxErr> {
     let filter = SeccompFilter::new(
         rules,
         SeccompAction::Allow,                     // default – allow
@@ -139,6 +199,15 @@ fn install_network_seccomp_filter_on_current_thread() -> std::result::Result<(),
 
     let prog: BpfProgram = filter.try_into()?;
 
+    Ok(prog)
+}
+
+/// Installs a seccomp filter built from `rules` on the current thread.
+pub(crate) fn install_seccomp_filter_on_current_thread(
+    rules: BTreeMap<i64, Vec<SeccompRule>>,
+) -> std::result::Result<(), SandboxErr> {
+    let prog = compile_seccomp_filter(rules)?;
+
     apply_filter(&prog)?;
 
     Ok(())
@@ -2,7 +2,10 @@ use clap::Parser;
 use std::ffi::CString;
 use std::path::PathBuf;
 
+use crate::denials::DenialRecorder;
 use crate::landlock::apply_sandbox_policy_to_current_thread;
+use crate::overlay::enter_overlay_sandbox;
+use crate::overlay::overlay_dir_from_env;
 
 #[derive(Debug, Parser)]
 pub struct LandlockCommand {
@@ -24,7 +27,24 @@ pub fn run_main() -> ! {
         command,
     } = LandlockCommand::parse();
 
-    if let Err(e) = apply_sandbox_policy_to_current_thread(&sandbox_policy, &sandbox_policy_cwd) {
+    // Take the denial log out of the environment before anything else runs.
+    let denial_recorder = DenialRecorder::from_env();
+
+    // With an overlay the command cannot change the real file-system, so
+    // Landlock is not needed.
+    let overlay_dir = overlay_dir_from_env();
+    if let Some(overlay_dir) = &overlay_dir
+        && let Err(e) = enter_overlay_sandbox(&sandbox_policy, &sandbox_policy_cwd, overlay_dir)
+    {
+        panic!("error setting up the overlay sandbox: {e:?}");
+    }
+
+    if let Err(e) = apply_sandbox_policy_to_current_thread(
+        &sandbox_policy,
+        &sandbox_policy_cwd,
+        overlay_dir.is_none(),
+        denial_recorder,
+    ) {
         panic!("error running landlock: {e:?}");
     }
 
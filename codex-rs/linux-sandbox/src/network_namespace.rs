@@ -0,0 +1,146 @@
+//! Network isolation that still allows some connections.
+//!
+//! The command runs in a new network namespace whose only interface is the
+//! loopback device, so it cannot open connections to the outside at all but
+//! servers and clients inside the sandbox can talk to each other.
+//!
+//! When the command may also reach the hosts allowed by the Codex network
+//! proxy, a bridge process listens on the proxy address inside the namespace
+//! and forwards every connection to the proxy's Unix socket, which lives on
+//! the file-system and is therefore reachable from any network namespace.
+
+use std::io;
+use std::net::SocketAddr;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::error::Result;
+use codex_core::spawn::CODEX_NETWORK_PROXY_ENV_VAR;
+use codex_core::spawn::CODEX_NETWORK_PROXY_SOCKET_ENV_VAR;
+
+use crate::user_namespace::unshare_as_current_user;
+
+/// Where the command expects the proxy and where the proxy really listens.
+pub(crate) struct ProxyBridge {
+    listen_addr: SocketAddr,
+    socket_path: PathBuf,
+}
+
+impl ProxyBridge {
+    /// Reads the proxy addresses that Codex put in the environment of the
+    /// command.
+    pub(crate) fn from_env() -> Option<Self> {
+        let listen_addr = std::env::var(CODEX_NETWORK_PROXY_ENV_VAR)
+            .ok()?
+            .parse()
+            .ok()?;
+        let socket_path = PathBuf::from(std::env::var_os(CODEX_NETWORK_PROXY_SOCKET_ENV_VAR)?);
+        Some(Self {
+            listen_addr,
+            socket_path,
+        })
+    }
+}
+
+/// Moves the current process into a new user and network namespace with only
+/// the loopback device up, and starts the bridge to the proxy if there is one.
+/// Must be called while the process is still single threaded, before the
+/// command is executed.
+pub(crate) fn enter_loopback_only_network_namespace(
+    proxy_bridge: Option<&ProxyBridge>,
+) -> Result<()> {
+    unshare_as_current_user(libc::CLONE_NEWNET)?;
+    bring_up_loopback()?;
+
+    let Some(bridge) = proxy_bridge else {
+        return Ok(());
+    };
+    let listener = TcpListener::bind(bridge.listen_addr)?;
+    let parent = unsafe { libc::getpid() };
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error().into()),
+        0 => run_bridge(listener, &bridge.socket_path, parent),
+        _ => Ok(()),
+    }
+}
+
+/// A new network namespace starts with its loopback device down.
+fn bring_up_loopback() -> io::Result<()> {
+    let fd = unsafe { libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0) };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut request: libc::ifreq = unsafe { std::mem::zeroed() };
+    for (dst, src) in request.ifr_name.iter_mut().zip(b"lo\0") {
+        *dst = *src as libc::c_char;
+    }
+    let result = unsafe {
+        if libc::ioctl(fd, libc::SIOCGIFFLAGS, &mut request) < 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            request.ifr_ifru.ifru_flags |= libc::IFF_UP as libc::c_short;
+            if libc::ioctl(fd, libc::SIOCSIFFLAGS, &request) < 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        }
+    };
+    unsafe {
+        libc::close(fd);
+    }
+    result
+}
+
+/// Body of the bridge process. It exits together with the command.
+fn run_bridge(listener: TcpListener, socket_path: &Path, parent: libc::pid_t) -> ! {
+    unsafe {
+        // The command inherits the pid of the parent through `execvp`, so
+        // this delivers SIGKILL once the command exits.
+        libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+        // The bridge holds the descriptors the helper inherited, e.g. the
+        // denial log; keep the command out of its /proc/<pid>/fd.
+        libc::prctl(libc::PR_SET_DUMPABLE, 0);
+        if libc::getppid() != parent {
+            libc::_exit(0);
+        }
+        // Do not hold on to the command's output pipes, otherwise readers
+        // would not see EOF until the bridge exits.
+        let dev_null = libc::open(c"/dev/null".as_ptr(), libc::O_RDWR);
+        if dev_null >= 0 {
+            for fd in 0..3 {
+                libc::dup2(dev_null, fd);
+            }
+        }
+    }
+
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let socket_path = socket_path.to_path_buf();
+        std::thread::spawn(move || {
+            let _ = forward(stream, &socket_path);
+        });
+    }
+    unsafe { libc::_exit(0) }
+}
+
+fn forward(client: TcpStream, socket_path: &Path) -> io::Result<()> {
+    let proxy = UnixStream::connect(socket_path)?;
+    let mut client_reader = client.try_clone()?;
+    let mut proxy_writer = proxy.try_clone()?;
+    let upload = std::thread::spawn(move || {
+        let _ = io::copy(&mut client_reader, &mut proxy_writer);
+        let _ = proxy_writer.shutdown(std::net::Shutdown::Write);
+    });
+    let mut proxy_reader = proxy;
+    let mut client_writer = client;
+    let _ = io::copy(&mut proxy_reader, &mut client_writer);
+    let _ = client_writer.shutdown(std::net::Shutdown::Write);
+    let _ = upload.join();
+    Ok(())
+}
@@ -0,0 +1,250 @@
+//! Runs the command against a disposable overlay of the workspace.
+//!
+//! The command gets its own user, mount and pid namespaces. Each writable
+//! root of the sandbox policy (the working directory for read-only policies)
+//! is covered by an overlay mount whose upper directory lives in the
+//! directory Codex passed in [`CODEX_SANDBOX_OVERLAY_DIR_ENV_VAR`], so the
+//! command can change the workspace as it likes while the real files stay
+//! untouched. Codex then reads the upper directories and shows the changes
+//! for approval. For the root with index `i` the layout is:
+//!
+//! - `<dir>/<i>/lower`: a symlink to the root,
+//! - `<dir>/<i>/upper`: the upper directory,
+//! - `<dir>/<i>/work`: the work directory overlayfs needs.
+//!
+//! `/tmp`, `$TMPDIR` and `/dev/shm` are replaced by empty tmpfs mounts that
+//! disappear with the command, and everything else is mounted read-only.
+//! Unlike Landlock, this needs no special kernel support beyond unprivileged
+//! user namespaces and overlayfs.
+
+use std::ffi::CString;
+use std::fs::File;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::error::Result;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::spawn::CODEX_SANDBOX_OVERLAY_DIR_ENV_VAR;
+
+use crate::process::wait_and_exit;
+use crate::user_namespace::unshare_as_current_user;
+
+/// Not every libc version exposes the `mount_setattr(2)` API yet.
+#[repr(C)]
+struct MountAttr {
+    attr_set: u64,
+    attr_clr: u64,
+    propagation: u64,
+    userns_fd: u64,
+}
+
+const MOUNT_ATTR_RDONLY: u64 = 0x1;
+const AT_RECURSIVE: libc::c_int = 0x8000;
+
+/// Reads the overlay directory Codex put in the environment, and removes it
+/// so the command does not see it.
+pub(crate) fn overlay_dir_from_env() -> Option<PathBuf> {
+    let overlay_dir = std::env::var_os(CODEX_SANDBOX_OVERLAY_DIR_ENV_VAR)?;
+    // The helper is single threaded, so nothing reads the environment
+    // concurrently.
+    unsafe {
+        std::env::remove_var(CODEX_SANDBOX_OVERLAY_DIR_ENV_VAR);
+    }
+    Some(PathBuf::from(overlay_dir))
+}
+
+/// Sets up the namespaces and mounts described in the module documentation.
+/// The current process stays outside the new pid namespace: it forks the
+/// process that goes on to exec the command, waits for it and exits with its
+/// status, so this only returns in the child. Must be called while the
+/// process is still single threaded.
+pub(crate) fn enter_overlay_sandbox(
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+    overlay_dir: &Path,
+) -> Result<()> {
+    let scratch_dirs = scratch_dirs();
+    let roots = overlaid_roots(sandbox_policy, cwd, &scratch_dirs);
+
+    // Open everything the overlays need up front: the tmpfs mounts may hide
+    // the overlay directory and even some of the roots.
+    let mut layers = Vec::with_capacity(roots.len());
+    for (index, root) in roots.into_iter().enumerate() {
+        let layer_dir = overlay_dir.join(index.to_string());
+        let upper = layer_dir.join("upper");
+        let work = layer_dir.join("work");
+        std::fs::create_dir_all(&upper)?;
+        std::fs::create_dir_all(&work)?;
+        std::os::unix::fs::symlink(&root, layer_dir.join("lower"))?;
+        let lower = File::open(&root)?;
+        layers.push((root, lower, File::open(&upper)?, File::open(&work)?));
+    }
+
+    unshare_as_current_user(libc::CLONE_NEWNS | libc::CLONE_NEWPID)?;
+    // Keep the mounts below from propagating back to the real mount
+    // namespace.
+    mount(
+        None,
+        Path::new("/"),
+        None,
+        libc::MS_REC | libc::MS_PRIVATE,
+        None,
+    )?;
+
+    let mut writable_mounts = Vec::new();
+    for dir in scratch_dirs.into_iter().chain([PathBuf::from("/dev/shm")]) {
+        if dir.is_dir() {
+            mount(Some("tmpfs"), &dir, Some("tmpfs"), 0, Some("mode=1777"))?;
+            writable_mounts.push(dir);
+        }
+    }
+    for (root, lower, upper, work) in &layers {
+        // A root below a scratch directory has just been hidden.
+        std::fs::create_dir_all(root)?;
+        let options = format!(
+            "lowerdir={},upperdir={},workdir={},userxattr",
+            fd_path(lower),
+            fd_path(upper),
+            fd_path(work),
+        );
+        mount(Some("overlay"), root, Some("overlay"), 0, Some(&options))?;
+        writable_mounts.push(root.clone());
+    }
+    drop(layers);
+
+    set_read_only(Path::new("/"), true, AT_RECURSIVE)?;
+    for dir in &writable_mounts {
+        set_read_only(dir, false, 0)?;
+    }
+
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error().into()),
+        0 => {
+            unsafe {
+                libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+                libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0);
+            }
+            // The child is the first process of the new pid namespace, so it
+            // needs a /proc that matches. Tools that do not look at /proc
+            // work without it.
+            if let Err(e) = mount(Some("proc"), Path::new("/proc"), Some("proc"), 0, None) {
+                eprintln!("codex-linux-sandbox: cannot mount /proc: {e}");
+            }
+            Ok(())
+        }
+        child => wait_and_exit(child),
+    }
+}
+
+/// Directories whose contents are thrown away with the command.
+fn scratch_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![PathBuf::from("/tmp")];
+    if let Some(tmpdir) = std::env::var_os("TMPDIR").filter(|tmpdir| !tmpdir.is_empty()) {
+        let tmpdir = PathBuf::from(tmpdir);
+        if !dirs.iter().any(|dir| tmpdir.starts_with(dir)) {
+            dirs.push(tmpdir);
+        }
+    }
+    dirs
+}
+
+/// The existing writable roots of the policy, or the working directory for a
+/// read-only policy. Roots inside another root are covered by its overlay.
+fn overlaid_roots(
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+    scratch_dirs: &[PathBuf],
+) -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = sandbox_policy
+        .get_writable_roots_with_cwd(cwd)
+        .into_iter()
+        .map(|writable_root| writable_root.root)
+        .filter(|root| !scratch_dirs.contains(root))
+        .collect();
+    if roots.is_empty() {
+        roots.push(cwd.to_path_buf());
+    }
+    roots.retain(|root| root.is_dir());
+    roots.sort();
+    roots.dedup_by(|root, outer| root.starts_with(outer));
+    roots
+}
+
+fn fd_path(file: &File) -> String {
+    format!("/proc/self/fd/{}", file.as_raw_fd())
+}
+
+fn cstring(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes()).map_err(io::Error::other)
+}
+
+fn mount(
+    source: Option<&str>,
+    target: &Path,
+    fstype: Option<&str>,
+    flags: libc::c_ulong,
+    data: Option<&str>,
+) -> io::Result<()> {
+    let source = source
+        .map(CString::new)
+        .transpose()
+        .map_err(io::Error::other)?;
+    let target = cstring(target)?;
+    let fstype = fstype
+        .map(CString::new)
+        .transpose()
+        .map_err(io::Error::other)?;
+    let data = data
+        .map(CString::new)
+        .transpose()
+        .map_err(io::Error::other)?;
+    let result = unsafe {
+        libc::mount(
+            source
+                .as_ref()
+                .map_or(std::ptr::null(), |source| source.as_ptr()),
+            target.as_ptr(),
+            fstype
+                .as_ref()
+                .map_or(std::ptr::null(), |fstype| fstype.as_ptr()),
+            flags,
+            data.as_ref()
+                .map_or(std::ptr::null(), |data| data.as_ptr().cast()),
+        )
+    };
+    if result != 0 {
+        let e = io::Error::last_os_error();
+        return Err(io::Error::new(
+            e.kind(),
+            format!("failed to mount {}: {e}", target.to_string_lossy()),
+        ));
+    }
+    Ok(())
+}
+
+fn set_read_only(path: &Path, read_only: bool, flags: libc::c_int) -> io::Result<()> {
+    let path = cstring(path)?;
+    let attr = MountAttr {
+        attr_set: if read_only { MOUNT_ATTR_RDONLY } else { 0 },
+        attr_clr: if read_only { 0 } else { MOUNT_ATTR_RDONLY },
+        propagation: 0,
+        userns_fd: 0,
+    };
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_mount_setattr,
+            libc::AT_FDCWD,
+            path.as_ptr(),
+            flags,
+            &attr as *const MountAttr,
+            std::mem::size_of::<MountAttr>(),
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
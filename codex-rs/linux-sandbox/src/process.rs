@@ -0,0 +1,25 @@
+use std::io;
+
+/// Body of a helper process that stays behind after forking the process that
+/// goes on to exec the command. It mirrors how the command ended so Codex
+/// sees the same exit code or signal.
+pub(crate) fn wait_and_exit(child: libc::pid_t) -> ! {
+    let mut status = 0;
+    loop {
+        if unsafe { libc::waitpid(child, &mut status, 0) } == child {
+            break;
+        }
+        if io::Error::last_os_error().kind() != io::ErrorKind::Interrupted {
+            unsafe { libc::_exit(1) }
+        }
+    }
+    unsafe {
+        if libc::WIFSIGNALED(status) {
+            let signal = libc::WTERMSIG(status);
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+            libc::_exit(128 + signal)
+        }
+        libc::_exit(libc::WEXITSTATUS(status))
+    }
+}
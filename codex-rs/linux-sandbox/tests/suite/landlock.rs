@@ -1,4 +1,5 @@
 #![cfg(target_os = "linux")]
+use codex_core::config_types::ResourceLimits;
 use codex_core::config_types::ShellEnvironmentPolicy;
 use codex_core::error::CodexErr;
 use codex_core::error::SandboxErr;
@@ -6,7 +7,12 @@ use codex_core::exec::ExecParams;
 use codex_core::exec::SandboxType;
 use codex_core::exec::process_exec_tool_call;
 use codex_core::exec_env::create_env;
+use codex_core::protocol::NetworkAccess;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::sandbox_denials::FileOperation;
+use codex_core::sandbox_denials::SandboxDenial;
+use codex_core::spawn::CODEX_NETWORK_PROXY_ENV_VAR;
+use codex_core::spawn::CODEX_NETWORK_PROXY_SOCKET_ENV_VAR;
 use std::collections::HashMap;
 use std::path::PathBuf;
 use tempfile::NamedTempFile;
@@ -33,8 +39,17 @@ fn create_env_from_core_vars() -> HashMap<String, String> {
     create_env(&policy)
 }
 
-#[expect(clippy::print_stdout, clippy::expect_used, clippy::unwrap_used)]
 async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
+    run_cmd_with_readable_roots(cmd, writable_roots, None, timeout_ms).await;
+}
+
+#[expect(clippy::print_stdout, clippy::expect_used, clippy::unwrap_used)]
+async fn run_cmd_with_readable_roots(
+    cmd: &[&str],
+    writable_roots: &[PathBuf],
+    readable_roots: Option<Vec<PathBuf>>,
+    timeout_ms: u64,
+) {
     let cwd = std::env::current_dir().expect("cwd should exist");
     let sandbox_cwd = cwd.clone();
     let params = ExecParams {
@@ -48,12 +63,14 @@ async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
 
     let sandbox_policy = SandboxPolicy::WorkspaceWrite {
         writable_roots: writable_roots.to_vec(),
-        network_access: false,
+        network_access: NetworkAccess::Disabled,
         // Exclude tmp-related folders from writable roots because we need a
         // folder that is writable by tests but that we intentionally disallow
         // writing to in the sandbox.
         exclude_tmpdir_env_var: true,
         exclude_slash_tmp: true,
+        readable_roots,
+        allowed_domains: vec![],
     };
     let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
     let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
@@ -63,6 +80,7 @@ async fn run_cmd(cmd: &[&str], writable_roots: &[PathBuf], timeout_ms: u64) {
         &sandbox_policy,
         sandbox_cwd.as_path(),
         &codex_linux_sandbox_exe,
+        &ResourceLimits::default(),
         None,
     )
     .await
@@ -123,6 +141,35 @@ async fn test_writable_root() {
     .await;
 }
 
+#[tokio::test]
+async fn test_readable_root() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let file_path = tmpdir.path().join("secret");
+    std::fs::write(&file_path, "blah").unwrap();
+    run_cmd_with_readable_roots(
+        &["cat", &file_path.to_string_lossy()],
+        &[],
+        Some(vec![tmpdir.path().to_path_buf()]),
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+}
+
+#[tokio::test]
+#[should_panic]
+async fn test_read_outside_readable_roots() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let file_path = tmpdir.path().join("secret");
+    std::fs::write(&file_path, "blah").unwrap();
+    run_cmd_with_readable_roots(
+        &["cat", &file_path.to_string_lossy()],
+        &[],
+        Some(Vec::new()),
+        LONG_TIMEOUT_MS,
+    )
+    .await;
+}
+
 #[tokio::test]
 #[should_panic(expected = "Sandbox(Timeout")]
 async fn test_timeout() {
@@ -133,8 +180,12 @@ async fn test_timeout() {
 /// does NOT succeed (i.e. returns a non‑zero exit code) **unless** the binary
 /// is missing in which case we silently treat it as an accepted skip so the
 /// suite remains green on leaner CI images.
-#[expect(clippy::expect_used)]
 async fn assert_network_blocked(cmd: &[&str]) {
+    assert_network_blocked_with_policy(cmd, SandboxPolicy::new_read_only_policy()).await;
+}
+
+#[expect(clippy::expect_used)]
+async fn assert_network_blocked_with_policy(cmd: &[&str], sandbox_policy: SandboxPolicy) {
     let cwd = std::env::current_dir().expect("cwd should exist");
     let sandbox_cwd = cwd.clone();
     let params = ExecParams {
@@ -148,7 +199,6 @@ async fn assert_network_blocked(cmd: &[&str]) {
         justification: None,
     };
 
-    let sandbox_policy = SandboxPolicy::new_read_only_policy();
     let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
     let codex_linux_sandbox_exe: Option<PathBuf> = Some(PathBuf::from(sandbox_program));
     let result = process_exec_tool_call(
@@ -157,13 +207,14 @@ async fn assert_network_blocked(cmd: &[&str]) {
         &sandbox_policy,
         sandbox_cwd.as_path(),
         &codex_linux_sandbox_exe,
+        &ResourceLimits::default(),
         None,
     )
     .await;
 
     let output = match result {
         Ok(output) => output,
-        Err(CodexErr::Sandbox(SandboxErr::Denied { output })) => *output,
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output, .. })) => *output,
         _ => {
             panic!("expected sandbox denied error, got: {result:?}");
         }
@@ -234,3 +285,203 @@ async fn sandbox_blocks_dev_tcp_redirection() {
     // all images ship bash, so we guard against 127 as well.
     assert_network_blocked(&["bash", "-c", "echo hi > /dev/tcp/127.0.0.1/80"]).await;
 }
+
+/// Commands that may reach the allowlisted hosts connect to the Codex network
+/// proxy through a bridge in their own network namespace. Skipped when the
+/// kernel does not allow unprivileged user namespaces.
+#[tokio::test]
+async fn sandbox_bridges_network_proxy() {
+    use std::io::Read;
+
+    let socket_dir = tempfile::tempdir().expect("tempdir");
+    let socket_path = socket_dir.path().join("proxy.sock");
+    let proxy = std::os::unix::net::UnixListener::bind(&socket_path).expect("bind proxy");
+    let received = std::thread::spawn(move || {
+        let (mut stream, _) = proxy.accept().expect("accept");
+        let mut request = String::new();
+        stream.read_to_string(&mut request).expect("read request");
+        request
+    });
+
+    let mut env = create_env_from_core_vars();
+    env.insert(
+        CODEX_NETWORK_PROXY_ENV_VAR.to_string(),
+        "127.0.0.1:3128".to_string(),
+    );
+    env.insert(
+        CODEX_NETWORK_PROXY_SOCKET_ENV_VAR.to_string(),
+        socket_path.to_string_lossy().to_string(),
+    );
+    let cwd = std::env::current_dir().expect("cwd should exist");
+    let params = ExecParams {
+        command: vec![
+            "bash".to_string(),
+            "-c".to_string(),
+            "exec 3<>/dev/tcp/127.0.0.1/3128 && printf ping >&3".to_string(),
+        ],
+        cwd: cwd.clone(),
+        timeout_ms: Some(NETWORK_TIMEOUT_MS),
+        env,
+        with_escalated_permissions: None,
+        justification: None,
+    };
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: vec![],
+        network_access: NetworkAccess::Disabled,
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+        readable_roots: None,
+        allowed_domains: vec!["example.com".to_string()],
+    };
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
+    let output = match process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        cwd.as_path(),
+        &codex_linux_sandbox_exe,
+        &ResourceLimits::default(),
+        None,
+    )
+    .await
+    {
+        Ok(output) => output,
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output, .. })) => *output,
+        Err(e) => panic!("unexpected error: {e:?}"),
+    };
+
+    if output.stderr.text.contains("NetworkNamespace") {
+        // User namespaces are unavailable; the command did not run.
+        assert_ne!(output.exit_code, 0);
+        assert_eq!(output.stdout.text, "");
+        return;
+    }
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr.text);
+    assert_eq!(received.join().expect("proxy thread"), "ping");
+}
+
+fn loopback_policy() -> SandboxPolicy {
+    SandboxPolicy::WorkspaceWrite {
+        writable_roots: vec![],
+        network_access: NetworkAccess::Loopback,
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+        readable_roots: None,
+        allowed_domains: vec![],
+    }
+}
+
+/// In loopback mode a server and a client started in the sandbox can talk to
+/// each other. Skipped when python3 is missing or the kernel does not allow
+/// unprivileged user namespaces.
+#[tokio::test]
+async fn sandbox_loopback_allows_local_connections() {
+    let script = "import socket\n\
+server = socket.socket()\n\
+server.bind(('127.0.0.1', 0))\n\
+server.listen()\n\
+client = socket.create_connection(server.getsockname())\n\
+conn, _ = server.accept()\n\
+client.sendall(b'ping')\n\
+assert conn.recv(4) == b'ping'\n";
+    let cwd = std::env::current_dir().expect("cwd should exist");
+    let params = ExecParams {
+        command: vec!["python3".to_string(), "-c".to_string(), script.to_string()],
+        cwd: cwd.clone(),
+        timeout_ms: Some(NETWORK_TIMEOUT_MS),
+        env: create_env_from_core_vars(),
+        with_escalated_permissions: None,
+        justification: None,
+    };
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
+    let output = match process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &loopback_policy(),
+        cwd.as_path(),
+        &codex_linux_sandbox_exe,
+        &ResourceLimits::default(),
+        None,
+    )
+    .await
+    {
+        Ok(output) => output,
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output, .. })) => *output,
+        Err(e) => panic!("unexpected error: {e:?}"),
+    };
+
+    if output.exit_code == 127 || output.stderr.text.contains("NetworkNamespace") {
+        return;
+    }
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr.text);
+}
+
+#[tokio::test]
+async fn sandbox_loopback_blocks_curl() {
+    assert_network_blocked_with_policy(&["curl", "-I", "http://openai.com"], loopback_policy())
+        .await;
+}
+
+#[tokio::test]
+async fn sandbox_loopback_blocks_ping() {
+    assert_network_blocked_with_policy(&["ping", "-c", "1", "8.8.8.8"], loopback_policy()).await;
+}
+
+#[tokio::test]
+async fn sandbox_reports_blocked_write() {
+    let tmpdir = tempfile::tempdir().unwrap();
+    let blocked = tmpdir.path().join("blocked.txt");
+    let cwd = std::env::current_dir().expect("cwd should exist");
+    let sandbox_cwd = cwd.clone();
+    let params = ExecParams {
+        command: vec![
+            "bash".to_string(),
+            "-c".to_string(),
+            format!("echo blah > {}", blocked.display()),
+        ],
+        cwd,
+        timeout_ms: Some(LONG_TIMEOUT_MS),
+        env: create_env_from_core_vars(),
+        with_escalated_permissions: None,
+        justification: None,
+    };
+    let sandbox_policy = SandboxPolicy::WorkspaceWrite {
+        writable_roots: vec![],
+        network_access: NetworkAccess::Disabled,
+        exclude_tmpdir_env_var: true,
+        exclude_slash_tmp: true,
+        readable_roots: None,
+        allowed_domains: vec![],
+    };
+
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
+    let result = process_exec_tool_call(
+        params,
+        SandboxType::LinuxSeccomp,
+        &sandbox_policy,
+        sandbox_cwd.as_path(),
+        &codex_linux_sandbox_exe,
+        &ResourceLimits::default(),
+        None,
+    )
+    .await;
+
+    let denials = match result {
+        Err(CodexErr::Sandbox(SandboxErr::Denied { denials, .. })) => denials,
+        _ => panic!("expected sandbox denied error, got: {result:?}"),
+    };
+    // Kernels without seccomp user notifications cannot report denials.
+    if denials.is_empty() {
+        return;
+    }
+    assert_eq!(
+        denials,
+        vec![SandboxDenial::FileSystem {
+            operation: FileOperation::Write,
+            path: blocked,
+        }]
+    );
+}
@@ -1,2 +1,3 @@
 // Aggregates all former standalone integration tests as modules.
 mod landlock;
+mod overlay;
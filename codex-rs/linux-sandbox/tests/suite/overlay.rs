@@ -0,0 +1,124 @@
+#![cfg(target_os = "linux")]
+use codex_core::config_types::ResourceLimits;
+use codex_core::config_types::ShellEnvironmentPolicy;
+use codex_core::error::CodexErr;
+use codex_core::error::SandboxErr;
+use codex_core::exec::ExecParams;
+use codex_core::exec::ExecToolCallOutput;
+use codex_core::exec::SandboxType;
+use codex_core::exec::process_exec_tool_call;
+use codex_core::exec_env::create_env;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::spawn::CODEX_SANDBOX_OVERLAY_DIR_ENV_VAR;
+use std::path::Path;
+use std::path::PathBuf;
+
+const TIMEOUT_MS: u64 = 5_000;
+
+/// Runs `script` under the overlay sandbox with `workspace` as the working
+/// directory. Returns `None` when the kernel does not allow unprivileged
+/// overlay mounts.
+async fn run_in_overlay(
+    script: &str,
+    workspace: &Path,
+    overlay_dir: &Path,
+) -> Option<ExecToolCallOutput> {
+    let mut env = create_env(&ShellEnvironmentPolicy::default());
+    env.insert(
+        CODEX_SANDBOX_OVERLAY_DIR_ENV_VAR.to_string(),
+        overlay_dir.to_string_lossy().to_string(),
+    );
+    let params = ExecParams {
+        command: vec!["bash".to_string(), "-c".to_string(), script.to_string()],
+        cwd: workspace.to_path_buf(),
+        timeout_ms: Some(TIMEOUT_MS),
+        env,
+        with_escalated_permissions: None,
+        justification: None,
+    };
+    let sandbox_program = env!("CARGO_BIN_EXE_codex-linux-sandbox");
+    let codex_linux_sandbox_exe = Some(PathBuf::from(sandbox_program));
+    let output = match process_exec_tool_call(
+        params,
+        SandboxType::LinuxOverlay,
+        &SandboxPolicy::new_workspace_write_policy(),
+        workspace,
+        &codex_linux_sandbox_exe,
+        &ResourceLimits::default(),
+        None,
+    )
+    .await
+    {
+        Ok(output) => output,
+        Err(CodexErr::Sandbox(SandboxErr::Denied { output, .. })) => *output,
+        Err(e) => panic!("unexpected error: {e:?}"),
+    };
+
+    if output
+        .stderr
+        .text
+        .contains("error setting up the overlay sandbox")
+    {
+        return None;
+    }
+    Some(output)
+}
+
+#[tokio::test]
+async fn overlay_keeps_writes_out_of_the_workspace() {
+    let workspace = tempfile::tempdir().unwrap();
+    let overlay_dir = tempfile::tempdir().unwrap();
+    std::fs::write(workspace.path().join("edited.txt"), "before\n").unwrap();
+
+    let Some(output) = run_in_overlay(
+        "echo after > edited.txt && echo new > added.txt && cat edited.txt",
+        workspace.path(),
+        overlay_dir.path(),
+    )
+    .await
+    else {
+        return;
+    };
+
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr.text);
+    assert_eq!(output.stdout.text, "after\n");
+    assert_eq!(
+        std::fs::read_to_string(workspace.path().join("edited.txt")).unwrap(),
+        "before\n"
+    );
+    assert!(!workspace.path().join("added.txt").exists());
+
+    let layer = overlay_dir.path().join("0");
+    assert_eq!(
+        std::fs::read_link(layer.join("lower")).unwrap(),
+        workspace.path()
+    );
+    assert_eq!(
+        std::fs::read_to_string(layer.join("upper/edited.txt")).unwrap(),
+        "after\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(layer.join("upper/added.txt")).unwrap(),
+        "new\n"
+    );
+}
+
+#[tokio::test]
+async fn overlay_discards_writes_to_tmp() {
+    let workspace = tempfile::tempdir().unwrap();
+    let overlay_dir = tempfile::tempdir().unwrap();
+    let scratch_file = PathBuf::from(format!("/tmp/codex-overlay-test-{}", std::process::id()));
+
+    let Some(output) = run_in_overlay(
+        &format!("echo blah > {}", scratch_file.display()),
+        workspace.path(),
+        overlay_dir.path(),
+    )
+    .await
+    else {
+        return;
+    };
+
+    assert_eq!(output.exit_code, 0, "stderr: {}", output.stderr.text);
+    assert!(!scratch_file.exists());
+}
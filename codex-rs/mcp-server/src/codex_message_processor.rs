@@ -587,6 +587,7 @@ impl CodexMessageProcessor {
         };
         tracing::debug!("Sandbox type: {sandbox_type:?}");
         let codex_linux_sandbox_exe = self.config.codex_linux_sandbox_exe.clone();
+        let resource_limits = self.config.resource_limits;
         let outgoing = self.outgoing.clone();
         let req_id = request_id;
         let sandbox_cwd = self.config.cwd.clone();
@@ -598,6 +599,7 @@ impl CodexMessageProcessor {
                 &effective_policy,
                 sandbox_cwd.as_path(),
                 &codex_linux_sandbox_exe,
+                &resource_limits,
                 None,
             )
             .await
@@ -1273,6 +1275,10 @@ fn derive_config_from_params(
         include_view_image_tool: None,
         show_raw_agent_reasoning: None,
         tools_web_search_request: None,
+        max_turn_tool_calls: None,
+        max_task_tokens: None,
+        max_task_duration: None,
+        output_schema_repair_attempts: None,
     };
 
     let cli_overrides = cli_overrides
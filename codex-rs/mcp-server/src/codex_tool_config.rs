@@ -1,5 +1,6 @@
 //! Configuration object accepted by the `codex` MCP tool-call.
 
+use codex_core::output_schema::check_output_schema;
 use codex_core::protocol::AskForApproval;
 use codex_protocol::config_types::SandboxMode;
 use mcp_types::Tool;
@@ -54,6 +55,16 @@ pub struct CodexToolCallParam {
     /// Whether to include the plan tool in the conversation.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub include_plan_tool: Option<bool>,
+
+    /// JSON Schema that the final message must match. The matching JSON is
+    /// returned as the structured content of the tool-call result.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema: Option<serde_json::Value>,
+
+    /// Number of follow-up turns that ask the model to fix a final message
+    /// that does not match `output-schema` (default 2).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_schema_repair_attempts: Option<u32>,
 }
 
 /// Custom enum mirroring [`AskForApproval`], but has an extra dependency on
@@ -132,7 +143,9 @@ pub(crate) fn create_tool_for_codex_tool_call_param() -> Tool {
 
 impl CodexToolCallParam {
     /// Returns the initial user prompt to start the Codex conversation and the
-    /// effective Config object generated from the supplied parameters.
+    /// effective Config object generated from the supplied parameters. The
+    /// `output_schema` is not part of the config; it is only checked to be a
+    /// valid JSON Schema here.
     pub fn into_config(
         self,
         codex_linux_sandbox_exe: Option<PathBuf>,
@@ -147,8 +160,19 @@ impl CodexToolCallParam {
             config: cli_overrides,
             base_instructions,
             include_plan_tool,
+            output_schema,
+            output_schema_repair_attempts,
         } = self;
 
+        if let Some(schema) = &output_schema {
+            check_output_schema(schema).map_err(|err| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("output-schema is not a valid JSON Schema: {err}"),
+                )
+            })?;
+        }
+
         // Build the `ConfigOverrides` recognized by codex-core.
         let overrides = codex_core::config::ConfigOverrides {
             model,
@@ -165,6 +189,10 @@ impl CodexToolCallParam {
             include_view_image_tool: None,
             show_raw_agent_reasoning: None,
             tools_web_search_request: None,
+            max_turn_tool_calls: None,
+            max_task_tokens: None,
+            max_task_duration: None,
+            output_schema_repair_attempts,
         };
 
         let cli_overrides = cli_overrides
@@ -296,6 +324,15 @@ mod tests {
                 "description": "The set of instructions to use instead of the default ones.",
                 "type": "string"
               },
+              "output-schema": {
+                "description": "JSON Schema that the final message must match. The matching JSON is returned as the structured content of the tool-call result."
+              },
+              "output-schema-repair-attempts": {
+                "description": "Number of follow-up turns that ask the model to fix a final message that does not match `output-schema` (default 2).",
+                "format": "uint32",
+                "minimum": 0.0,
+                "type": "integer"
+              },
             },
             "required": [
               "prompt"
@@ -20,6 +20,7 @@ use codex_core::protocol::EventMsg;
 use codex_core::protocol::ExecApprovalRequestEvent;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
+use codex_core::protocol::OutputSchemaValidationFailedEvent;
 use codex_core::protocol::Submission;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_protocol::mcp_protocol::ConversationId;
@@ -27,6 +28,7 @@ use mcp_types::CallToolResult;
 use mcp_types::ContentBlock;
 use mcp_types::RequestId;
 use mcp_types::TextContent;
+use serde_json::Value;
 use serde_json::json;
 use tokio::sync::Mutex;
 
@@ -39,11 +41,32 @@ pub(crate) const INVALID_PARAMS_ERROR_CODE: i64 = -32602;
 pub async fn run_codex_tool_session(
     id: RequestId,
     initial_prompt: String,
+    output_schema: Option<Value>,
     config: CodexConfig,
     outgoing: Arc<OutgoingMessageSender>,
     conversation_manager: Arc<ConversationManager>,
     running_requests_id_to_codex_uuid: Arc<Mutex<HashMap<RequestId, ConversationId>>>,
 ) {
+    // The output schema is per turn, so the prompt must be sent as a full
+    // `UserTurn` that repeats the session's settings.
+    let items = vec![InputItem::Text {
+        text: initial_prompt,
+    }];
+    let structured_output = output_schema.is_some();
+    let op = match output_schema {
+        Some(schema) => Op::UserTurn {
+            items,
+            cwd: config.cwd.clone(),
+            approval_policy: config.approval_policy,
+            sandbox_policy: config.sandbox_policy.clone(),
+            model: config.model.clone(),
+            effort: config.model_reasoning_effort,
+            summary: config.model_reasoning_summary,
+            final_output_json_schema: Some(schema),
+        },
+        None => Op::UserInput { items },
+    };
+
     let NewConversation {
         conversation_id,
         conversation,
@@ -90,11 +113,7 @@ pub async fn run_codex_tool_session(
         .insert(id.clone(), conversation_id);
     let submission = Submission {
         id: sub_id.clone(),
-        op: Op::UserInput {
-            items: vec![InputItem::Text {
-                text: initial_prompt.clone(),
-            }],
-        },
+        op,
     };
 
     if let Err(e) = conversation.submit_with_id(submission).await {
@@ -108,6 +127,7 @@ pub async fn run_codex_tool_session(
         conversation,
         outgoing,
         id,
+        structured_output,
         running_requests_id_to_codex_uuid,
     )
     .await;
@@ -144,21 +164,26 @@ pub async fn run_codex_tool_session_reply(
         conversation,
         outgoing,
         request_id,
+        false,
         running_requests_id_to_codex_uuid,
     )
     .await;
 }
 
+/// When `structured_output` is set, the final message was validated against
+/// an output schema and is also returned as the result's structured content.
 async fn run_codex_tool_session_inner(
     codex: Arc<CodexConversation>,
     outgoing: Arc<OutgoingMessageSender>,
     request_id: RequestId,
+    structured_output: bool,
     running_requests_id_to_codex_uuid: Arc<Mutex<HashMap<RequestId, ConversationId>>>,
 ) {
     let request_id_str = match &request_id {
         RequestId::String(s) => s.clone(),
         RequestId::Integer(n) => n.to_string(),
     };
+    let mut output_schema_errors: Option<Vec<String>> = None;
 
     // Stream events until the task needs to pause for user interaction or
     // completes.
@@ -220,19 +245,37 @@ async fn run_codex_tool_session_inner(
                         .await;
                         continue;
                     }
+                    EventMsg::OutputSchemaValidationFailed(OutputSchemaValidationFailedEvent {
+                        errors,
+                    }) => {
+                        output_schema_errors = Some(errors);
+                    }
                     EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
                         let text = match last_agent_message {
                             Some(msg) => msg,
                             None => "".to_string(),
                         };
+                        let (is_error, structured_content) = match output_schema_errors.take() {
+                            Some(errors) => (
+                                Some(true),
+                                Some(json!({
+                                    "error": "output_schema_validation_failed",
+                                    "errors": errors,
+                                })),
+                            ),
+                            None if structured_output => {
+                                (None, serde_json::from_str::<Value>(&text).ok())
+                            }
+                            None => (None, None),
+                        };
                         let result = CallToolResult {
                             content: vec![ContentBlock::TextContent(TextContent {
                                 r#type: "text".to_string(),
                                 text,
                                 annotations: None,
                             })],
-                            is_error: None,
-                            structured_content: None,
+                            is_error,
+                            structured_content,
                         };
                         outgoing.send_response(request_id.clone(), result).await;
                         // unregister the id so we don't keep it in the map
@@ -267,6 +310,7 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::ExecCommandBegin(_)
                     | EventMsg::ExecCommandOutputDelta(_)
                     | EventMsg::ExecCommandEnd(_)
+                    | EventMsg::NetworkAccess(_)
                     | EventMsg::BackgroundEvent(_)
                     | EventMsg::StreamError(_)
                     | EventMsg::PatchApplyBegin(_)
@@ -281,7 +325,8 @@ async fn run_codex_tool_session_inner(
                     | EventMsg::UserMessage(_)
                     | EventMsg::ShutdownComplete
                     | EventMsg::EnteredReviewMode(_)
-                    | EventMsg::ExitedReviewMode(_) => {
+                    | EventMsg::ExitedReviewMode(_)
+                    | EventMsg::SubAgentEvent(_) => {
                         // For now, we do not do anything extra for these
                         // events. Note that
                         // send(codex_event_to_notification(&event)) above has
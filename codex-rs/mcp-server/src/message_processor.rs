@@ -360,27 +360,30 @@ impl MessageProcessor {
         }
     }
     async fn handle_tool_call_codex(&self, id: RequestId, arguments: Option<serde_json::Value>) {
-        let (initial_prompt, config): (String, Config) = match arguments {
+        let (initial_prompt, output_schema, config) = match arguments {
             Some(json_val) => match serde_json::from_value::<CodexToolCallParam>(json_val) {
-                Ok(tool_cfg) => match tool_cfg.into_config(self.codex_linux_sandbox_exe.clone()) {
-                    Ok(cfg) => cfg,
-                    Err(e) => {
-                        let result = CallToolResult {
-                            content: vec![ContentBlock::TextContent(TextContent {
-                                r#type: "text".to_owned(),
-                                text: format!(
-                                    "Failed to load Codex configuration from overrides: {e}"
-                                ),
-                                annotations: None,
-                            })],
-                            is_error: Some(true),
-                            structured_content: None,
-                        };
-                        self.send_response::<mcp_types::CallToolRequest>(id, result)
-                            .await;
-                        return;
+                Ok(tool_cfg) => {
+                    let output_schema = tool_cfg.output_schema.clone();
+                    match tool_cfg.into_config(self.codex_linux_sandbox_exe.clone()) {
+                        Ok((prompt, cfg)) => (prompt, output_schema, cfg),
+                        Err(e) => {
+                            let result = CallToolResult {
+                                content: vec![ContentBlock::TextContent(TextContent {
+                                    r#type: "text".to_owned(),
+                                    text: format!(
+                                        "Failed to load Codex configuration from overrides: {e}"
+                                    ),
+                                    annotations: None,
+                                })],
+                                is_error: Some(true),
+                                structured_content: None,
+                            };
+                            self.send_response::<mcp_types::CallToolRequest>(id, result)
+                                .await;
+                            return;
+                        }
                     }
-                },
+                }
                 Err(e) => {
                     let result = CallToolResult {
                         content: vec![ContentBlock::TextContent(TextContent {
@@ -426,6 +429,7 @@ impl MessageProcessor {
             crate::codex_tool_runner::run_codex_tool_session(
                 id,
                 initial_prompt,
+                output_schema,
                 config,
                 outgoing,
                 conversation_manager,
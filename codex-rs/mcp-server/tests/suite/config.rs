@@ -2,6 +2,7 @@ use std::collections::HashMap;
 use std::path::Path;
 
 use codex_core::protocol::AskForApproval;
+use codex_core::protocol::NetworkAccess;
 use codex_protocol::config_types::ReasoningEffort;
 use codex_protocol::config_types::ReasoningSummary;
 use codex_protocol::config_types::SandboxMode;
@@ -88,7 +89,7 @@ async fn get_config_toml_parses_all_fields() {
             sandbox_mode: Some(SandboxMode::WorkspaceWrite),
             sandbox_settings: Some(SandboxSettings {
                 writable_roots: vec!["/tmp".into()],
-                network_access: Some(true),
+                network_access: Some(NetworkAccess::Enabled),
                 exclude_tmpdir_env_var: Some(true),
                 exclude_slash_tmp: Some(true),
             }),
@@ -9,6 +9,7 @@ use crate::config_types::Verbosity;
 use crate::protocol::AskForApproval;
 use crate::protocol::EventMsg;
 use crate::protocol::FileChange;
+use crate::protocol::NetworkAccess;
 use crate::protocol::ReviewDecision;
 use crate::protocol::SandboxPolicy;
 use crate::protocol::TurnAbortReason;
@@ -522,7 +523,8 @@ pub struct SandboxSettings {
     #[serde(default)]
     pub writable_roots: Vec<PathBuf>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub network_access: Option<bool>,
+    #[ts(type = "boolean | \"loopback\" | null")]
+    pub network_access: Option<NetworkAccess>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub exclude_tmpdir_env_var: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -4,6 +4,7 @@
 //! between user and agent.
 
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::fmt;
 use std::path::Path;
 use std::path::PathBuf;
@@ -15,13 +16,13 @@ use crate::config_types::ReasoningSummary as ReasoningSummaryConfig;
 use crate::custom_prompts::CustomPrompt;
 use crate::mcp_protocol::ConversationId;
 use crate::message_history::HistoryEntry;
-use crate::models::ContentItem;
 use crate::models::ResponseItem;
 use crate::num_format::format_with_separators;
 use crate::parse_command::ParsedCommand;
 use crate::plan_tool::UpdatePlanArgs;
 use mcp_types::CallToolResult;
 use mcp_types::Tool as McpTool;
+use schemars::JsonSchema;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::Value;
@@ -127,6 +128,10 @@ pub enum Op {
         summary: Option<ReasoningSummaryConfig>,
     },
 
+    /// Reload `config.toml` and apply the changes that do not require a
+    /// restart. A `BackgroundEvent` lists what changed.
+    ReloadConfig,
+
     /// Approve a command execution
     ExecApproval {
         /// The id of the submission we are approving
@@ -180,7 +185,20 @@ pub enum Op {
 
 /// Determines the conditions under which the user is consulted to approve
 /// running the command proposed by Codex.
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Display, TS)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    Display,
+    TS,
+    JsonSchema,
+)]
 #[serde(rename_all = "kebab-case")]
 #[strum(serialize_all = "kebab-case")]
 pub enum AskForApproval {
@@ -228,10 +246,11 @@ pub enum SandboxPolicy {
         #[serde(default, skip_serializing_if = "Vec::is_empty")]
         writable_roots: Vec<PathBuf>,
 
-        /// When set to `true`, outbound network access is allowed. `false` by
+        /// Network access for commands run in the sandbox. Disabled by
         /// default.
         #[serde(default)]
-        network_access: bool,
+        #[ts(type = "boolean | \"loopback\"")]
+        network_access: NetworkAccess,
 
         /// When set to `true`, will NOT include the per-user `TMPDIR`
         /// environment variable among the default writable roots. Defaults to
@@ -243,9 +262,118 @@ pub enum SandboxPolicy {
         /// writable roots on UNIX. Defaults to `false`.
         #[serde(default)]
         exclude_slash_tmp: bool,
+
+        /// When set, reads are limited to these folders in addition to the
+        /// writable roots, the toolchains in the user's home directory and the
+        /// system directories. `None` (the default) allows reading the entire
+        /// file-system.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        readable_roots: Option<Vec<PathBuf>>,
+
+        /// Hosts that commands may reach through the Codex network proxy when
+        /// `network_access` is not enabled. An entry also matches its
+        /// subdomains.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        allowed_domains: Vec<String>,
     },
 }
 
+/// Network access granted to sandboxed commands. Written as `false`, `true` or
+/// `"loopback"` in configuration files.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(from = "NetworkAccessRepr", into = "NetworkAccessRepr")]
+pub enum NetworkAccess {
+    /// No network access.
+    #[default]
+    Disabled,
+    /// Only the loopback interface, so that servers and clients started in
+    /// the sandbox can talk to each other.
+    Loopback,
+    /// Full network access.
+    Enabled,
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+enum NetworkAccessRepr {
+    Flag(bool),
+    Mode(NetworkAccessMode),
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "kebab-case")]
+enum NetworkAccessMode {
+    Loopback,
+}
+
+impl From<NetworkAccessRepr> for NetworkAccess {
+    fn from(repr: NetworkAccessRepr) -> Self {
+        match repr {
+            NetworkAccessRepr::Flag(false) => NetworkAccess::Disabled,
+            NetworkAccessRepr::Flag(true) => NetworkAccess::Enabled,
+            NetworkAccessRepr::Mode(NetworkAccessMode::Loopback) => NetworkAccess::Loopback,
+        }
+    }
+}
+
+impl From<NetworkAccess> for NetworkAccessRepr {
+    fn from(network_access: NetworkAccess) -> Self {
+        match network_access {
+            NetworkAccess::Disabled => NetworkAccessRepr::Flag(false),
+            NetworkAccess::Enabled => NetworkAccessRepr::Flag(true),
+            NetworkAccess::Loopback => NetworkAccessRepr::Mode(NetworkAccessMode::Loopback),
+        }
+    }
+}
+
+impl JsonSchema for NetworkAccess {
+    fn schema_name() -> String {
+        "NetworkAccess".to_string()
+    }
+
+    fn json_schema(generator: &mut schemars::r#gen::SchemaGenerator) -> schemars::schema::Schema {
+        NetworkAccessRepr::json_schema(generator)
+    }
+}
+
+/// System directories that stay readable when reads are restricted, so that
+/// programs, shared libraries and their configuration can still be loaded.
+#[cfg(target_os = "macos")]
+const SYSTEM_READABLE_ROOTS: &[&str] = &[
+    "/bin",
+    "/sbin",
+    "/usr",
+    "/opt",
+    "/System",
+    "/Library",
+    "/Applications",
+    "/private/etc",
+    "/private/var/db",
+    "/dev",
+];
+#[cfg(not(target_os = "macos"))]
+const SYSTEM_READABLE_ROOTS: &[&str] = &[
+    "/bin", "/sbin", "/usr", "/lib", "/lib32", "/lib64", "/opt", "/etc", "/nix", "/dev", "/proc",
+    "/sys", "/run",
+];
+
+/// Toolchain directories under the user's home directory that stay readable
+/// when reads are restricted.
+const HOME_READABLE_ROOTS: &[&str] = &[
+    ".cargo",
+    ".rustup",
+    ".nvm",
+    ".npm",
+    ".volta",
+    ".bun",
+    ".deno",
+    ".pyenv",
+    ".rbenv",
+    "go",
+    ".local/bin",
+    ".local/lib",
+];
+
 /// A writable root path accompanied by a list of subpaths that should remain
 /// read‑only even when the root is writable. This is primarily used to ensure
 /// top‑level VCS metadata directories (e.g. `.git`) under a writable root are
@@ -297,15 +425,20 @@ impl SandboxPolicy {
     pub fn new_workspace_write_policy() -> Self {
         SandboxPolicy::WorkspaceWrite {
             writable_roots: vec![],
-            network_access: false,
+            network_access: NetworkAccess::Disabled,
             exclude_tmpdir_env_var: false,
             exclude_slash_tmp: false,
+            readable_roots: None,
+            allowed_domains: vec![],
         }
     }
 
-    /// Always returns `true`; restricting read access is not supported.
     pub fn has_full_disk_read_access(&self) -> bool {
-        true
+        match self {
+            SandboxPolicy::DangerFullAccess => true,
+            SandboxPolicy::ReadOnly => true,
+            SandboxPolicy::WorkspaceWrite { readable_roots, .. } => readable_roots.is_none(),
+        }
     }
 
     pub fn has_full_disk_write_access(&self) -> bool {
@@ -320,10 +453,34 @@ impl SandboxPolicy {
         match self {
             SandboxPolicy::DangerFullAccess => true,
             SandboxPolicy::ReadOnly => false,
+            SandboxPolicy::WorkspaceWrite { network_access, .. } => {
+                *network_access == NetworkAccess::Enabled
+            }
+        }
+    }
+
+    /// Returns the network access granted to sandboxed commands.
+    pub fn network_access(&self) -> NetworkAccess {
+        match self {
+            SandboxPolicy::DangerFullAccess => NetworkAccess::Enabled,
+            SandboxPolicy::ReadOnly => NetworkAccess::Disabled,
             SandboxPolicy::WorkspaceWrite { network_access, .. } => *network_access,
         }
     }
 
+    /// Returns the hosts that commands may reach through the network proxy.
+    /// Empty when the policy has full network access or no allowlist.
+    pub fn allowed_domains(&self) -> &[String] {
+        match self {
+            SandboxPolicy::WorkspaceWrite {
+                network_access: NetworkAccess::Disabled | NetworkAccess::Loopback,
+                allowed_domains,
+                ..
+            } => allowed_domains,
+            _ => &[],
+        }
+    }
+
     /// Returns the list of writable roots (tailored to the current working
     /// directory) together with subpaths that should remain read‑only under
     /// each writable root.
@@ -336,6 +493,8 @@ impl SandboxPolicy {
                 exclude_tmpdir_env_var,
                 exclude_slash_tmp,
                 network_access: _,
+                readable_roots: _,
+                allowed_domains: _,
             } => {
                 // Start from explicitly configured writable roots.
                 let mut roots: Vec<PathBuf> = writable_roots.clone();
@@ -376,6 +535,10 @@ impl SandboxPolicy {
                         if top_level_git.is_dir() {
                             subpaths.push(top_level_git);
                         }
+                        // A project's `.codex/config.toml` is loaded into
+                        // later sessions, so commands must not be able to
+                        // create or change it, whether or not it exists yet.
+                        subpaths.push(writable_root.join(".codex"));
                         WritableRoot {
                             root: writable_root,
                             read_only_subpaths: subpaths,
@@ -385,6 +548,35 @@ impl SandboxPolicy {
             }
         }
     }
+
+    /// Returns the folders that may be read when read access is restricted:
+    /// the writable roots (including the current working directory), the
+    /// configured readable roots, and the system and toolchain directories that
+    /// exist. Returns `None` when the entire file-system is readable.
+    pub fn get_readable_roots_with_cwd(&self, cwd: &Path) -> Option<Vec<PathBuf>> {
+        let SandboxPolicy::WorkspaceWrite {
+            readable_roots: Some(readable_roots),
+            ..
+        } = self
+        else {
+            return None;
+        };
+
+        let mut roots: Vec<PathBuf> = self
+            .get_writable_roots_with_cwd(cwd)
+            .into_iter()
+            .map(|writable_root| writable_root.root)
+            .collect();
+        roots.extend(readable_roots.iter().cloned());
+        roots.extend(SYSTEM_READABLE_ROOTS.iter().map(PathBuf::from));
+        if let Some(home) = std::env::var_os("HOME").filter(|home| !home.is_empty()) {
+            let home = PathBuf::from(home);
+            roots.extend(HOME_READABLE_ROOTS.iter().map(|dir| home.join(dir)));
+        }
+        let mut seen = HashSet::new();
+        roots.retain(|root| root.exists() && seen.insert(root.clone()));
+        Some(roots)
+    }
 }
 
 /// User input
@@ -431,6 +623,10 @@ pub enum EventMsg {
     /// Agent has completed all actions
     TaskComplete(TaskCompleteEvent),
 
+    /// The final message of the task does not match the turn's output schema,
+    /// even after the configured repair attempts. Sent before `TaskComplete`.
+    OutputSchemaValidationFailed(OutputSchemaValidationFailedEvent),
+
     /// Usage update for the current session, including totals and last turn.
     /// Optional means unknown — UIs should not display when `None`.
     TokenCount(TokenCountEvent),
@@ -477,6 +673,9 @@ pub enum EventMsg {
 
     ExecCommandEnd(ExecCommandEndEvent),
 
+    /// A running command asked the network proxy to reach a host.
+    NetworkAccess(NetworkAccessEvent),
+
     ExecApprovalRequest(ExecApprovalRequestEvent),
 
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
@@ -519,6 +718,10 @@ pub enum EventMsg {
 
     /// Exited review mode with an optional final result to apply.
     ExitedReviewMode(ExitedReviewModeEvent),
+
+    /// Event emitted by a sub-agent spawned through the delegation tool,
+    /// forwarded so front-ends can render the child's progress inline.
+    SubAgentEvent(SubAgentEventEvent),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -538,6 +741,12 @@ pub struct TaskCompleteEvent {
     pub last_agent_message: Option<String>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct OutputSchemaValidationFailedEvent {
+    /// Each way in which the final message does not match the schema.
+    pub errors: Vec<String>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct TaskStartedEvent {
     pub model_context_window: Option<u64>,
@@ -557,6 +766,10 @@ pub struct TokenUsageInfo {
     pub total_token_usage: TokenUsage,
     pub last_token_usage: TokenUsage,
     pub model_context_window: Option<u64>,
+    /// Estimated cost of the session so far in US dollars. Only usage from
+    /// models with a known price is counted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_cost_usd: Option<f64>,
 }
 
 impl TokenUsageInfo {
@@ -575,6 +788,7 @@ impl TokenUsageInfo {
                 total_token_usage: TokenUsage::default(),
                 last_token_usage: TokenUsage::default(),
                 model_context_window,
+                total_cost_usd: None,
             },
         };
         if let Some(last) = last {
@@ -587,6 +801,10 @@ impl TokenUsageInfo {
         self.total_token_usage.add_assign(last);
         self.last_token_usage = last.clone();
     }
+
+    pub fn append_cost_usd(&mut self, cost_usd: f64) {
+        self.total_cost_usd = Some(self.total_cost_usd.unwrap_or(0.0) + cost_usd);
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -942,21 +1160,24 @@ pub enum RolloutItem {
     EventMsg(EventMsg),
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, TS)]
-pub struct CompactedItem {
-    pub message: String,
-}
-
-impl From<CompactedItem> for ResponseItem {
-    fn from(value: CompactedItem) -> Self {
-        ResponseItem::Message {
-            id: None,
-            role: "assistant".to_string(),
-            content: vec![ContentItem::OutputText {
-                text: value.message,
-            }],
-        }
-    }
+/// One compaction stage applied to the conversation history. Each variant
+/// records enough to replay the stage when the history is rebuilt from a
+/// rollout. Stages other than `Summary` only touch items that come before the
+/// last `keep_recent_turns` user messages.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, TS)]
+#[serde(tag = "stage", rename_all = "snake_case")]
+pub enum CompactedItem {
+    /// Tool call outputs were replaced with a short stub; the calls and their
+    /// ids were kept.
+    ElideToolOutputs { keep_recent_turns: usize },
+    /// Reasoning items were removed.
+    CollapseReasoning { keep_recent_turns: usize },
+    /// Image inputs were replaced with a text placeholder.
+    DropImages { keep_recent_turns: usize },
+    /// History was rebuilt from the user messages and a model-written
+    /// summary. Untagged so it keeps the shape written by older versions.
+    #[serde(untagged)]
+    Summary { message: String },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, TS)]
@@ -965,6 +1186,9 @@ pub struct TurnContextItem {
     pub approval_policy: AskForApproval,
     pub sandbox_policy: SandboxPolicy,
     pub model: String,
+    /// Key of the provider that served `model`. Absent in older rollouts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_provider: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub effort: Option<ReasoningEffortConfig>,
     pub summary: ReasoningSummaryConfig,
@@ -1072,6 +1296,18 @@ pub struct ExecCommandEndEvent {
     pub formatted_output: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
+pub struct NetworkAccessEvent {
+    /// Identifier for the ExecCommandBegin of the command that made the request.
+    pub call_id: String,
+    /// Host the command tried to reach.
+    pub host: String,
+    pub port: u16,
+    /// Whether the host is in `allowed_domains`. Denied requests are answered
+    /// with `403 Forbidden` and never leave the machine.
+    pub allowed: bool,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecOutputStream {
@@ -1123,6 +1359,16 @@ pub struct BackgroundEventEvent {
     pub message: String,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SubAgentEventEvent {
+    /// Responses API call id of the delegation tool call that spawned the sub-agent.
+    pub call_id: String,
+    /// Conversation id of the sub-agent.
+    pub conversation_id: ConversationId,
+    /// The event emitted by the sub-agent.
+    pub msg: Box<EventMsg>,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct StreamErrorEvent {
     pub message: String,
@@ -1259,6 +1505,23 @@ pub enum TurnAbortReason {
     Interrupted,
     Replaced,
     ReviewEnded,
+    /// The task used up one of its configured budgets.
+    BudgetExceeded(TaskBudget),
+}
+
+/// A budget configured in `config.toml` that can end a task. Displays as the
+/// name of the config key that sets it.
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, Display, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskBudget {
+    #[strum(serialize = "max_turn_tool_calls")]
+    ToolCalls,
+    #[strum(serialize = "max_task_tokens")]
+    Tokens,
+    #[strum(serialize = "max_task_duration")]
+    Duration,
+    #[strum(serialize = "max_cost_usd")]
+    Cost,
 }
 
 #[cfg(test)]
@@ -1268,6 +1531,31 @@ mod tests {
     use serde_json::json;
     use tempfile::NamedTempFile;
 
+    #[test]
+    fn network_access_accepts_booleans_and_loopback() -> Result<()> {
+        let policy: SandboxPolicy = serde_json::from_value(json!({
+            "mode": "workspace-write",
+            "network_access": "loopback",
+        }))?;
+        assert_eq!(policy.network_access(), NetworkAccess::Loopback);
+        assert!(!policy.has_full_network_access());
+        assert_eq!(
+            serde_json::to_value(&policy)?["network_access"],
+            json!("loopback")
+        );
+
+        for (value, expected) in [
+            (json!(true), NetworkAccess::Enabled),
+            (json!(false), NetworkAccess::Disabled),
+        ] {
+            let network_access: NetworkAccess = serde_json::from_value(value.clone())?;
+            assert_eq!(network_access, expected);
+            assert_eq!(serde_json::to_value(network_access)?, value);
+        }
+        assert!(serde_json::from_value::<NetworkAccess>(json!("internet")).is_err());
+        Ok(())
+    }
+
     /// Serialize Event to verify that its JSON representation has the expected
     /// amount of nesting.
     #[test]
@@ -1320,4 +1608,27 @@ mod tests {
         assert_eq!(deserialized, event);
         Ok(())
     }
+
+    #[test]
+    fn compacted_item_summary_keeps_legacy_shape() -> Result<()> {
+        let summary = CompactedItem::Summary {
+            message: "summary".to_string(),
+        };
+        assert_eq!(
+            json!({ "message": "summary" }),
+            serde_json::to_value(&summary)?
+        );
+        assert_eq!(
+            summary,
+            serde_json::from_value::<CompactedItem>(json!({ "message": "summary" }))?
+        );
+
+        let stage = CompactedItem::ElideToolOutputs {
+            keep_recent_turns: 2,
+        };
+        let value = json!({ "stage": "elide_tool_outputs", "keep_recent_turns": 2 });
+        assert_eq!(value, serde_json::to_value(&stage)?);
+        assert_eq!(stage, serde_json::from_value::<CompactedItem>(value)?);
+        Ok(())
+    }
 }
@@ -13,7 +13,7 @@ use codex_core::AuthManager;
 use codex_core::ConversationManager;
 use codex_core::config::Config;
 use codex_core::config::persist_model_selection;
-use codex_core::model_family::find_family_for_model;
+use codex_core::model_family::find_configured_family_for_model;
 use codex_core::protocol::TokenUsage;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_protocol::mcp_protocol::ConversationId;
@@ -96,6 +96,7 @@ impl App {
                     initial_images: initial_images.clone(),
                     enhanced_keys_supported,
                     auth_manager: auth_manager.clone(),
+                    provider_health: conversation_manager.provider_health(),
                 };
                 ChatWidget::new(init, conversation_manager.clone())
             }
@@ -118,6 +119,7 @@ impl App {
                     initial_images: initial_images.clone(),
                     enhanced_keys_supported,
                     auth_manager: auth_manager.clone(),
+                    provider_health: conversation_manager.provider_health(),
                 };
                 ChatWidget::new_from_existing(
                     init,
@@ -220,6 +222,7 @@ impl App {
                     initial_images: Vec::new(),
                     enhanced_keys_supported: self.enhanced_keys_supported,
                     auth_manager: self.auth_manager.clone(),
+                    provider_health: self.server.provider_health(),
                 };
                 self.chat_widget = ChatWidget::new(init, self.server.clone());
                 tui.frame_requester().schedule_frame();
@@ -298,6 +301,21 @@ impl App {
                 ));
                 tui.frame_requester().schedule_frame();
             }
+            AppEvent::ShowCommandOutput { command, output } => {
+                let _ = tui.enter_alt_screen();
+                let mut pager_lines: Vec<ratatui::text::Line<'static>> =
+                    vec![format!("$ {command}").dim().into()];
+                if output.trim().is_empty() {
+                    pager_lines.push("The command produced no output.".italic().into());
+                } else {
+                    pager_lines.extend(output.lines().map(ansi_escape_line));
+                }
+                self.overlay = Some(Overlay::new_static_with_title(
+                    pager_lines,
+                    "O U T P U T".to_string(),
+                ));
+                tui.frame_requester().schedule_frame();
+            }
             AppEvent::StartFileSearch(query) => {
                 if !query.is_empty() {
                     self.file_search.on_user_query(query);
@@ -312,7 +330,8 @@ impl App {
             AppEvent::UpdateModel(model) => {
                 self.chat_widget.set_model(&model);
                 self.config.model = model.clone();
-                if let Some(family) = find_family_for_model(&model) {
+                if let Some(family) = find_configured_family_for_model(&model, &self.config.models)
+                {
                     self.config.model_family = family;
                 }
             }
@@ -41,6 +41,12 @@ pub(crate) enum AppEvent {
     /// Result of computing a `/diff` command.
     DiffResult(String),
 
+    /// Open the full output of a finished command in the pager, for `/output`.
+    ShowCommandOutput {
+        command: String,
+        output: String,
+    },
+
     InsertHistoryCell(Box<dyn HistoryCell>),
 
     StartCommitAnimation,
@@ -28,11 +28,14 @@ use codex_core::protocol::ListCustomPromptsResponseEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
+use codex_core::protocol::NetworkAccessEvent;
 use codex_core::protocol::Op;
+use codex_core::protocol::OutputSchemaValidationFailedEvent;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::RateLimitSnapshot;
 use codex_core::protocol::ReviewRequest;
 use codex_core::protocol::StreamErrorEvent;
+use codex_core::protocol::SubAgentEventEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TokenUsage;
 use codex_core::protocol::TokenUsageInfo;
@@ -70,6 +73,7 @@ use crate::bottom_pane::custom_prompt_view::CustomPromptView;
 use crate::bottom_pane::popup_consts::STANDARD_POPUP_HINT_LINE;
 use crate::clipboard_paste::paste_image_to_temp_png;
 use crate::diff_render::display_path_for;
+use crate::exec_command::strip_bash_lc_and_escape;
 use crate::get_git_diff::get_git_diff;
 use crate::history_cell;
 use crate::history_cell::AgentMessageCell;
@@ -103,6 +107,7 @@ use codex_core::ConversationManager;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_core::provider_health::ProviderHealthRegistry;
 use codex_file_search::FileMatch;
 use codex_git_tooling::CreateGhostCommitOptions;
 use codex_git_tooling::GhostCommit;
@@ -177,6 +182,7 @@ pub(crate) struct ChatWidgetInit {
     pub(crate) initial_images: Vec<PathBuf>,
     pub(crate) enhanced_keys_supported: bool,
     pub(crate) auth_manager: Arc<AuthManager>,
+    pub(crate) provider_health: Arc<ProviderHealthRegistry>,
 }
 
 pub(crate) struct ChatWidget {
@@ -186,6 +192,7 @@ pub(crate) struct ChatWidget {
     active_exec_cell: Option<ExecCell>,
     config: Config,
     auth_manager: Arc<AuthManager>,
+    provider_health: Arc<ProviderHealthRegistry>,
     session_header: SessionHeader,
     initial_user_message: Option<UserMessage>,
     token_info: Option<TokenUsageInfo>,
@@ -194,6 +201,8 @@ pub(crate) struct ChatWidget {
     // Stream lifecycle controller
     stream_controller: Option<StreamController>,
     running_commands: HashMap<String, RunningCommand>,
+    // Command line and full output of the last finished command, for `/output`
+    last_command_output: Option<(String, String)>,
     task_complete_pending: bool,
     // Queue of interruptive UI events deferred during an active write cycle
     interrupts: InterruptManager,
@@ -532,6 +541,23 @@ impl ChatWidget {
         debug!("BackgroundEvent: {message}");
     }
 
+    fn on_network_access(&mut self, ev: NetworkAccessEvent) {
+        let NetworkAccessEvent {
+            host,
+            port,
+            allowed,
+            ..
+        } = ev;
+        if allowed {
+            debug!("NetworkAccess: allowed {host}:{port}");
+        } else {
+            self.add_to_history(history_cell::new_warning_event(format!(
+                "Blocked network request to {host}:{port} (not in allowed_domains)"
+            )));
+            self.request_redraw();
+        }
+    }
+
     fn on_stream_error(&mut self, message: String) {
         // Show stream errors in the transcript so users see retry/backoff info.
         self.add_to_history(history_cell::new_stream_error_event(message));
@@ -601,6 +627,10 @@ impl ChatWidget {
             Some(rc) => (rc.command, rc.parsed_cmd),
             None => (vec![ev.call_id.clone()], Vec::new()),
         };
+        self.last_command_output = Some((
+            strip_bash_lc_and_escape(&command),
+            ev.aggregated_output.clone(),
+        ));
 
         if self.active_exec_cell.is_none() {
             // This should have been created by handle_exec_begin_now, but in case it wasn't,
@@ -769,6 +799,7 @@ impl ChatWidget {
             initial_images,
             enhanced_keys_supported,
             auth_manager,
+            provider_health,
         } = common;
         let mut rng = rand::rng();
         let placeholder = EXAMPLE_PROMPTS[rng.random_range(0..EXAMPLE_PROMPTS.len())].to_string();
@@ -789,6 +820,7 @@ impl ChatWidget {
             active_exec_cell: None,
             config: config.clone(),
             auth_manager,
+            provider_health,
             session_header: SessionHeader::new(config.model),
             initial_user_message: create_initial_user_message(
                 initial_prompt.unwrap_or_default(),
@@ -799,6 +831,7 @@ impl ChatWidget {
             rate_limit_warnings: RateLimitWarningState::default(),
             stream_controller: None,
             running_commands: HashMap::new(),
+            last_command_output: None,
             task_complete_pending: false,
             interrupts: InterruptManager::new(),
             reasoning_buffer: String::new(),
@@ -828,6 +861,7 @@ impl ChatWidget {
             initial_images,
             enhanced_keys_supported,
             auth_manager,
+            provider_health,
         } = common;
         let mut rng = rand::rng();
         let placeholder = EXAMPLE_PROMPTS[rng.random_range(0..EXAMPLE_PROMPTS.len())].to_string();
@@ -850,6 +884,7 @@ impl ChatWidget {
             active_exec_cell: None,
             config: config.clone(),
             auth_manager,
+            provider_health,
             session_header: SessionHeader::new(config.model),
             initial_user_message: create_initial_user_message(
                 initial_prompt.unwrap_or_default(),
@@ -860,6 +895,7 @@ impl ChatWidget {
             rate_limit_warnings: RateLimitWarningState::default(),
             stream_controller: None,
             running_commands: HashMap::new(),
+            last_command_output: None,
             task_complete_pending: false,
             interrupts: InterruptManager::new(),
             reasoning_buffer: String::new(),
@@ -1024,6 +1060,17 @@ impl ChatWidget {
                     tx.send(AppEvent::DiffResult(text));
                 });
             }
+            SlashCommand::Output => match &self.last_command_output {
+                Some((command, output)) => {
+                    self.app_event_tx.send(AppEvent::ShowCommandOutput {
+                        command: command.clone(),
+                        output: output.clone(),
+                    });
+                }
+                None => {
+                    self.add_info_message("No command has finished yet.".to_string(), None);
+                }
+            },
             SlashCommand::Mention => {
                 self.insert_str("@");
             }
@@ -1260,6 +1307,14 @@ impl ChatWidget {
             EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
                 self.on_task_complete(last_agent_message)
             }
+            EventMsg::OutputSchemaValidationFailed(OutputSchemaValidationFailedEvent {
+                errors,
+            }) => {
+                self.add_to_history(history_cell::new_error_event(format!(
+                    "Final message does not match the output schema: {}",
+                    errors.join("; ")
+                )));
+            }
             EventMsg::TokenCount(ev) => {
                 self.set_token_info(ev.info);
                 self.on_rate_limit_snapshot(ev.rate_limits);
@@ -1275,6 +1330,9 @@ impl ChatWidget {
                 TurnAbortReason::ReviewEnded => {
                     self.on_interrupted_turn(ev.reason);
                 }
+                TurnAbortReason::BudgetExceeded(budget) => {
+                    self.on_error(format!("Turn aborted: exceeded the {budget} budget"))
+                }
             },
             EventMsg::PlanUpdate(update) => self.on_plan_update(update),
             EventMsg::ExecApprovalRequest(ev) => {
@@ -1289,6 +1347,7 @@ impl ChatWidget {
             EventMsg::PatchApplyBegin(ev) => self.on_patch_apply_begin(ev),
             EventMsg::PatchApplyEnd(ev) => self.on_patch_apply_end(ev),
             EventMsg::ExecCommandEnd(ev) => self.on_exec_command_end(ev),
+            EventMsg::NetworkAccess(ev) => self.on_network_access(ev),
             EventMsg::McpToolCallBegin(ev) => self.on_mcp_tool_call_begin(ev),
             EventMsg::McpToolCallEnd(ev) => self.on_mcp_tool_call_end(ev),
             EventMsg::WebSearchBegin(ev) => self.on_web_search_begin(ev),
@@ -1315,6 +1374,18 @@ impl ChatWidget {
                 self.on_entered_review_mode(review_request)
             }
             EventMsg::ExitedReviewMode(review) => self.on_exited_review_mode(review),
+            EventMsg::SubAgentEvent(SubAgentEventEvent { msg, .. }) => {
+                self.on_sub_agent_event(*msg)
+            }
+        }
+    }
+
+    fn on_sub_agent_event(&mut self, msg: EventMsg) {
+        // Only the sub-agent's final messages are surfaced; its streaming
+        // deltas would otherwise interleave with the parent's transcript.
+        if let EventMsg::AgentMessage(AgentMessageEvent { message }) = msg {
+            self.add_to_history(history_cell::new_sub_agent_message(message));
+            self.request_redraw();
         }
     }
 
@@ -1447,9 +1518,12 @@ impl ChatWidget {
             default_usage = TokenUsage::default();
             &default_usage
         };
+        let cost_usd = self.token_info.as_ref().and_then(|ti| ti.total_cost_usd);
         self.add_to_history(history_cell::new_status_output(
             &self.config,
+            &self.provider_health,
             usage_ref,
+            cost_usd,
             &self.conversation_id,
             self.rate_limit_snapshot.as_ref(),
         ));
@@ -285,6 +285,7 @@ async fn helpers_are_available_and_do_not_panic() {
         initial_images: Vec::new(),
         enhanced_keys_supported: false,
         auth_manager,
+        provider_health: conversation_manager.provider_health(),
     };
     let mut w = ChatWidget::new(init, conversation_manager);
     // Basic construction sanity.
@@ -317,6 +318,7 @@ fn make_chatwidget_manual() -> (
         active_exec_cell: None,
         config: cfg.clone(),
         auth_manager,
+        provider_health: Arc::default(),
         session_header: SessionHeader::new(cfg.model),
         initial_user_message: None,
         token_info: None,
@@ -324,6 +326,7 @@ fn make_chatwidget_manual() -> (
         rate_limit_warnings: RateLimitWarningState::default(),
         stream_controller: None,
         running_commands: HashMap::new(),
+        last_command_output: None,
         task_complete_pending: false,
         interrupts: InterruptManager::new(),
         reasoning_buffer: String::new(),
@@ -978,6 +981,30 @@ fn disabled_slash_command_while_task_running_snapshot() {
     assert_snapshot!(blob);
 }
 
+#[test]
+fn output_command_opens_last_command_output() {
+    let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
+    begin_exec(&mut chat, "call-1", "cargo build");
+    end_exec(&mut chat, "call-1", "Compiling a\n", "error: b\n", 101);
+    while rx.try_recv().is_ok() {}
+
+    chat.dispatch_command(SlashCommand::Output);
+
+    let mut shown = None;
+    while let Ok(ev) = rx.try_recv() {
+        if let AppEvent::ShowCommandOutput { command, output } = ev {
+            shown = Some((command, output));
+        }
+    }
+    assert_eq!(
+        shown,
+        Some((
+            "cargo build".to_string(),
+            "Compiling a\nerror: b\n".to_string()
+        ))
+    );
+}
+
 #[tokio::test(flavor = "current_thread")]
 async fn binary_size_transcript_snapshot() {
     let (mut chat, mut rx, _op_rx) = make_chatwidget_manual();
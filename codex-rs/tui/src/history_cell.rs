@@ -17,6 +17,7 @@ use codex_common::elapsed::format_duration;
 use codex_core::auth::get_auth_file;
 use codex_core::auth::try_read_auth_json;
 use codex_core::config::Config;
+use codex_core::config_layer::ConfigLayer;
 use codex_core::config_types::ReasoningSummaryFormat;
 use codex_core::plan_tool::PlanItemArg;
 use codex_core::plan_tool::StepStatus;
@@ -29,6 +30,9 @@ use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::TokenUsage;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
+use codex_core::provider_health::CircuitState;
+use codex_core::provider_health::ProviderHealthRegistry;
+use codex_core::provider_health::ProviderHealthSnapshot;
 use codex_protocol::mcp_protocol::ConversationId;
 use codex_protocol::num_format::format_with_separators;
 use codex_protocol::parse_command::ParsedCommand;
@@ -690,6 +694,15 @@ fn title_case(s: &str) -> String {
     first.to_uppercase().collect::<String>() + &rest
 }
 
+/// Dimmed note naming the layer a `/status` value came from, unless it came
+/// from the user's own config or the defaults.
+fn config_origin_span(layer: ConfigLayer) -> Option<Span<'static>> {
+    match layer {
+        ConfigLayer::Project | ConfigLayer::CommandLine => Some(format!(" ({layer})").dim()),
+        ConfigLayer::Default | ConfigLayer::User => None,
+    }
+}
+
 fn pretty_provider_name(id: &str) -> String {
     if id.eq_ignore_ascii_case("openai") {
         "OpenAI".to_string()
@@ -1080,7 +1093,9 @@ pub(crate) fn new_warning_event(message: String) -> PlainHistoryCell {
 
 pub(crate) fn new_status_output(
     config: &Config,
+    provider_health: &ProviderHealthRegistry,
     usage: &TokenUsage,
+    cost_usd: Option<f64>,
     session_id: &Option<ConversationId>,
     rate_limits: Option<&RateLimitSnapshot>,
 ) -> PlainHistoryCell {
@@ -1116,7 +1131,19 @@ pub(crate) fn new_status_output(
         SandboxPolicy::ReadOnly => "read-only",
         SandboxPolicy::WorkspaceWrite { .. } => "workspace-write",
     };
-    lines.push(vec!["  • Sandbox: ".into(), sandbox_name.into()].into());
+    let sandbox_origin = config
+        .value_origin("sandbox_mode")
+        .max(config.value_origin("sandbox_workspace_write"));
+    let mut sandbox_spans: Vec<Span<'static>> = vec!["  • Sandbox: ".into(), sandbox_name.into()];
+    sandbox_spans.extend(config_origin_span(sandbox_origin));
+    lines.push(sandbox_spans.into());
+    if let Some(project_config) = &config.config_origins.project_config {
+        let display = match project_config.strip_prefix(&config.cwd) {
+            Ok(relative) => relative.display().to_string(),
+            Err(_) => project_config.display().to_string(),
+        };
+        lines.push(vec!["  • Project Config: ".into(), display.into()].into());
+    }
 
     // AGENTS.md files discovered via core's project_doc logic
     let agents_list = {
@@ -1202,9 +1229,25 @@ pub(crate) fn new_status_output(
 
     // 🧠 Model
     lines.push(vec![padded_emoji("🧠").into(), "Model".bold()].into());
-    lines.push(vec!["  • Name: ".into(), config.model.clone().into()].into());
+    let mut name_spans: Vec<Span<'static>> = vec!["  • Name: ".into(), config.model.clone().into()];
+    name_spans.extend(config_origin_span(config.value_origin("model")));
+    lines.push(name_spans.into());
     let provider_disp = pretty_provider_name(&config.model_provider_id);
-    lines.push(vec!["  • Provider: ".into(), provider_disp.into()].into());
+    let mut provider_spans: Vec<Span<'static>> =
+        vec!["  • Provider: ".into(), provider_disp.into()];
+    provider_spans.extend(config_origin_span(config.value_origin("model_provider")));
+    lines.push(provider_spans.into());
+    if let Some(health) =
+        provider_health.snapshot(&config.model_provider_id, &config.model_provider)
+    {
+        lines.push(
+            vec![
+                "  • Provider Health: ".into(),
+                format_provider_health(&health),
+            ]
+            .into(),
+        );
+    }
     // Only show Reasoning fields if present in config summary
     let reff = lookup("reasoning effort");
     if !reff.is_empty() {
@@ -1248,6 +1291,13 @@ pub(crate) fn new_status_output(
         "  • Total: ".into(),
         format_with_separators(usage.blended_total()).into(),
     ]));
+    // Estimated Cost: $<cost>
+    if let Some(cost_usd) = cost_usd {
+        lines.push(Line::from(vec![
+            "  • Estimated Cost: ".into(),
+            format!("${cost_usd:.4}").into(),
+        ]));
+    }
 
     if is_chatgpt_auth {
         lines.push("".into());
@@ -1257,6 +1307,36 @@ pub(crate) fn new_status_output(
     PlainHistoryCell { lines }
 }
 
+fn format_provider_health(health: &ProviderHealthSnapshot) -> Span<'static> {
+    let mut details = vec![format!(
+        "{} requests, {:.0}% failed",
+        health.requests,
+        health.error_rate() * 100.0
+    )];
+    if let Some(latency) = health.average_latency {
+        details.push(format!("avg {:.1}s", latency.as_secs_f64()));
+    }
+    let retry_in = health
+        .retry_in
+        .map(|retry_in| retry_in.as_secs_f64().ceil());
+    match (health.state, retry_in) {
+        (CircuitState::Open, retry_in) => format!(
+            "Unavailable, retrying in {}s ({})",
+            retry_in.unwrap_or_default(),
+            details.join(", ")
+        )
+        .red(),
+        (CircuitState::HalfOpen, _) => format!("Recovering ({})", details.join(", ")).yellow(),
+        (CircuitState::Closed, Some(retry_in)) => {
+            format!("Delaying requests {retry_in}s ({})", details.join(", ")).yellow()
+        }
+        (CircuitState::Closed, None) if health.consecutive_failures > 0 => {
+            format!("Degraded ({})", details.join(", ")).yellow()
+        }
+        (CircuitState::Closed, None) => format!("Healthy ({})", details.join(", ")).into(),
+    }
+}
+
 /// Render a summary of configured MCP servers from the current `Config`.
 pub(crate) fn empty_mcp_output() -> PlainHistoryCell {
     let lines: Vec<Line<'static>> = vec![
@@ -1345,6 +1425,12 @@ pub(crate) fn new_stream_error_event(message: String) -> PlainHistoryCell {
     PlainHistoryCell { lines }
 }
 
+pub(crate) fn new_sub_agent_message(message: String) -> PlainHistoryCell {
+    let mut lines: Vec<Line<'static>> = vec!["sub-agent".magenta().italic().into()];
+    lines.extend(message.lines().map(|line| line.to_string().dim().into()));
+    PlainHistoryCell { lines }
+}
+
 /// Render a user‑friendly plan update styled like a checkbox todo list.
 pub(crate) fn new_plan_update(update: UpdatePlanArgs) -> PlanUpdateCell {
     let UpdatePlanArgs { explanation, plan } = update;
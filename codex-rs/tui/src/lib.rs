@@ -139,6 +139,10 @@ pub async fn run_main(
         include_view_image_tool: None,
         show_raw_agent_reasoning: cli.oss.then_some(true),
         tools_web_search_request: cli.web_search.then_some(true),
+        max_turn_tool_calls: None,
+        max_task_tokens: None,
+        max_task_duration: None,
+        output_schema_repair_attempts: None,
     };
     let raw_overrides = cli.config_overrides.raw_overrides.clone();
     let overrides_cli = codex_common::CliConfigOverrides { raw_overrides };
@@ -20,6 +20,7 @@ pub enum SlashCommand {
     Compact,
     Undo,
     Diff,
+    Output,
     Mention,
     Status,
     Mcp,
@@ -40,6 +41,7 @@ impl SlashCommand {
             SlashCommand::Undo => "restore the workspace to the last Codex snapshot",
             SlashCommand::Quit => "exit Codex",
             SlashCommand::Diff => "show git diff (including untracked files)",
+            SlashCommand::Output => "show the full output of the last command",
             SlashCommand::Mention => "mention a file",
             SlashCommand::Status => "show current session configuration and token usage",
             SlashCommand::Model => "choose what model and reasoning effort to use",
@@ -69,6 +71,7 @@ impl SlashCommand {
             | SlashCommand::Review
             | SlashCommand::Logout => false,
             SlashCommand::Diff
+            | SlashCommand::Output
             | SlashCommand::Mention
             | SlashCommand::Status
             | SlashCommand::Mcp